@@ -0,0 +1,135 @@
+//! Timings of the conversions between Rust types, values and text
+//!
+//! Run with `cargo bench`, optionally followed by a part of the names of
+//! the benchmarks to run, like `cargo bench -- parse`. The same records are
+//! also converted to and from JSON with serde_json and to and from RON with
+//! ron, for comparison, and parsed into a `CompactValue` next to each
+//! `Value::parse`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde_derive::{Deserialize, Serialize};
+use typed_format::value::{compact::CompactValue, Value};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+enum Kind {
+    Sensor,
+    Relay { channel: u8 },
+    Gateway(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Record {
+    id: u64,
+    name: String,
+    description: String,
+    score: f64,
+    enabled: bool,
+    tags: Vec<String>,
+    position: Option<(i32, i32)>,
+    kind: Kind,
+}
+
+fn record(id: u64) -> Record {
+    Record {
+        id,
+        name: format!("device-{}", id),
+        description: format!(
+            "A \"quoted\" description of device {},\nspread over lines",
+            id
+        ),
+        score: id as f64 * 0.37,
+        enabled: !id.is_multiple_of(3),
+        tags: vec!["indoor".to_string(), format!("floor-{}", id % 7)],
+        position: if id.is_multiple_of(2) {
+            Some((id as i32, -(id as i32)))
+        } else {
+            None
+        },
+        kind: match id % 3 {
+            0 => Kind::Sensor,
+            1 => Kind::Relay {
+                channel: (id % 16) as u8,
+            },
+            _ => Kind::Gateway(format!("10.0.{}.1", id % 256)),
+        },
+    }
+}
+
+fn formats(c: &mut Criterion) {
+    let payloads = [("small", 1), ("medium", 100), ("large", 20_000)];
+    for &(size, count) in &payloads {
+        let records = (0..count).map(record).collect::<Vec<_>>();
+        let value = Value::new(&records);
+        let pretty = value.to_string_pretty();
+        let compact = value.to_string_compact();
+        assert_eq!(value.deserialize::<Vec<Record>>().unwrap(), records);
+        assert_eq!(Value::parse(&pretty).unwrap(), value);
+        assert_eq!(CompactValue::parse(&pretty).unwrap().to_value(), value);
+
+        let mut group = c.benchmark_group(size);
+        if count > 100 {
+            group.sample_size(10);
+        }
+
+        group.bench_function("serialize", |b| b.iter(|| Value::new(&records)));
+        group.bench_function("deserialize", |b| {
+            b.iter(|| value.deserialize::<Vec<Record>>().unwrap())
+        });
+
+        group.throughput(Throughput::Bytes(pretty.len() as u64));
+        group.bench_function("print_pretty", |b| {
+            b.iter(|| value.to_string_pretty())
+        });
+        group.bench_function("parse_pretty", |b| {
+            b.iter(|| Value::parse(&pretty).unwrap())
+        });
+        group.bench_function("parse_pretty_compact_value", |b| {
+            b.iter(|| CompactValue::parse(&pretty).unwrap())
+        });
+        group.bench_function("from_str", |b| {
+            b.iter(|| typed_format::from_str::<Vec<Record>>(&pretty).unwrap())
+        });
+        group.bench_function("to_string_pretty", |b| {
+            b.iter(|| typed_format::to_string_pretty(&records).unwrap())
+        });
+
+        group.throughput(Throughput::Bytes(compact.len() as u64));
+        group.bench_function("print_compact", |b| {
+            b.iter(|| value.to_string_compact())
+        });
+        group.bench_function("parse_compact", |b| {
+            b.iter(|| Value::parse(&compact).unwrap())
+        });
+        group.bench_function("parse_compact_compact_value", |b| {
+            b.iter(|| CompactValue::parse(&compact).unwrap())
+        });
+
+        let json = serde_json::to_string_pretty(&records).unwrap();
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_function("serde_json/to_string", |b| {
+            b.iter(|| serde_json::to_string_pretty(&records).unwrap())
+        });
+        group.bench_function("serde_json/from_str", |b| {
+            b.iter(|| serde_json::from_str::<Vec<Record>>(&json).unwrap())
+        });
+
+        let pretty_config = ron::ser::PrettyConfig::default();
+        let ron = ron::ser::to_string_pretty(&records, pretty_config.clone())
+            .unwrap();
+        group.throughput(Throughput::Bytes(ron.len() as u64));
+        group.bench_function("ron/to_string", |b| {
+            b.iter(|| {
+                ron::ser::to_string_pretty(&records, pretty_config.clone())
+                    .unwrap()
+            })
+        });
+        group.bench_function("ron/from_str", |b| {
+            b.iter(|| ron::de::from_str::<Vec<Record>>(&ron).unwrap())
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, formats);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+use pest_meta::{
+    ast::{Expr, RuleType},
+    parser,
+};
+use std::{env, fmt::Write, fs, path::Path};
+
+const GRAMMAR: &str = "src/value/value.pest";
+
+/// Generates the rule table behind `typed_format::grammar::rules`
+fn main() {
+    println!("cargo:rerun-if-changed={}", GRAMMAR);
+
+    let grammar = fs::read_to_string(GRAMMAR).unwrap();
+    let pairs = parser::parse(parser::Rule::grammar_rules, &grammar)
+        .unwrap_or_else(|e| panic!("{}", e));
+    let rules = pest_meta::unwrap_or_report(parser::consume_rules(pairs));
+
+    let mut output = String::from("const RULES: &[GrammarRule] = &[\n");
+
+    for rule in &rules {
+        let kind = match rule.ty {
+            RuleType::Normal => "Normal",
+            RuleType::Silent => "Silent",
+            RuleType::Atomic => "Atomic",
+            RuleType::CompoundAtomic => "CompoundAtomic",
+            RuleType::NonAtomic => "NonAtomic",
+        };
+
+        let mut children: Vec<String> = Vec::new();
+        for expr in rule.expr.iter_top_down() {
+            if let Expr::Ident(name) = expr {
+                let defined = rules.iter().any(|r| r.name == name);
+                if defined && !children.contains(&name) {
+                    children.push(name);
+                }
+            }
+        }
+
+        writeln!(
+            output,
+            "    GrammarRule {{ name: {:?}, kind: RuleKind::{}, \
+             children: &{:?}, expression: {:?} }},",
+            rule.name,
+            kind,
+            children,
+            rule.expr.to_string(),
+        )
+        .unwrap();
+    }
+
+    output.push_str("];\n");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("grammar_rules.rs"), output).unwrap();
+}
@@ -0,0 +1,29 @@
+//! Arbitrary input either fails to parse or reads back the way it is
+//! printed, with any options
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use typed_format::value::{options::ParseOptions, Value};
+use typed_format_fuzz::assert_round_trip;
+
+fuzz_target!(|data: &[u8]| {
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+
+    if let Ok(value) = Value::parse(input) {
+        assert_round_trip(&value);
+    }
+
+    let lenient = ParseOptions {
+        dedup_strings: true,
+        allow_unknown_escapes: true,
+        allow_trailing_garbage: true,
+        ..Default::default()
+    };
+    if let Ok(value) = Value::parse_with(input, &lenient) {
+        assert_round_trip(&value);
+    }
+});
@@ -0,0 +1,11 @@
+//! Generated values read back the way they are printed
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use typed_format::value::Value;
+use typed_format_fuzz::assert_round_trip;
+
+fuzz_target!(|value: Value| {
+    assert_round_trip(&value);
+});
@@ -0,0 +1,32 @@
+//! Shared parts of the fuzz targets
+//!
+//! Run a target with `cargo +nightly fuzz run parse` or
+//! `cargo +nightly fuzz run round_trip` from the crate root.
+
+use typed_format::value::Value;
+
+/// Prints `value` in both modes and checks that it reads back unchanged
+pub fn assert_round_trip(value: &Value) {
+    for &pretty in &[true, false] {
+        let print = |value: &Value| {
+            if pretty {
+                value.to_string_pretty()
+            } else {
+                value.to_string_compact()
+            }
+        };
+
+        let text = print(value);
+        let parsed = Value::parse(&text).unwrap_or_else(|error| {
+            panic!("{}\n\nfor the value {:?}", error, value)
+        });
+        // NaN is not equal to itself, but prints the same
+        assert!(
+            parsed == *value || print(&parsed) == text,
+            "{}\n\nwas printed for {:?}\nand read as {:?}",
+            text,
+            value,
+            parsed
+        );
+    }
+}
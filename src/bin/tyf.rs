@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Context};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use typed_format::{
+    document::Document,
+    migrate::{migrate_tree, FileFormat},
+    value::{printer::ValuePrinter, Value},
+};
+
+const USAGE: &str = "\
+Usage:
+    tyf fmt [--compact | --fit] [--check] <file>...
+    tyf check <file>...
+    tyf convert [--from <format>] [--to <format>] [--out <file>] <file>
+    tyf migrate-tree --from <format> --to <format> [--out <dir>] <dir>
+
+A file of `-` is standard input, `fmt` then writes to standard output.
+Formats: json, tyf";
+
+fn read_input(path: &Path) -> anyhow::Result<String> {
+    if path == Path::new("-") {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Could not read standard input")?;
+        Ok(input)
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))
+    }
+}
+
+fn write_output(path: &Path, output: &str) -> anyhow::Result<()> {
+    if path == Path::new("-") {
+        std::io::stdout()
+            .write_all(output.as_bytes())
+            .context("Could not write standard output")
+    } else {
+        std::fs::write(path, output)
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+}
+
+/// `output` with values written as in `input`, see
+/// `Document::restore_notation`
+///
+/// Printing writes numbers, strings and aliases in their canonical form,
+/// which a formatter must not do to `0xFF`, `1_000` or `*name`. Fails if
+/// the text would no longer read as `value`, such as when sorting map
+/// entries puts an alias before its anchor.
+fn keep_notation(input: &str, output: &str, value: &Value) -> Option<String> {
+    let input = Document::parse(input).ok()?;
+    let mut output = Document::parse(output).ok()?;
+    output.restore_notation(&input);
+
+    let output = output.to_string();
+    match Value::parse(&output) {
+        Ok(parsed) if parsed == *value => Some(output),
+        _ => None,
+    }
+}
+
+/// Rewrites files with a layout, keeping their comments
+///
+/// Values keep their notation, such as a hexadecimal number or an alias.
+/// Files are left as they are if that is not possible.
+fn fmt_command(args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let mut printer = ValuePrinter::pretty as fn() -> ValuePrinter<'static>;
+    let mut check = false;
+    let mut files = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--compact" => printer = ValuePrinter::compact,
+            "--fit" => printer = ValuePrinter::fit,
+            "--check" => check = true,
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        return Err(anyhow!("Missing file"));
+    }
+
+    let mut success = true;
+    for path in files {
+        let input = read_input(&path)?;
+        let (value, comments) = match Value::parse_with_comments(&input) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                eprintln!("{}: {}", path.display(), error);
+                success = false;
+                continue;
+            },
+        };
+
+        let mut output = String::new();
+        printer()
+            .with_comments(&comments)
+            .write(&value, &mut output)?;
+        output.push('\n');
+
+        let output = match keep_notation(&input, &output, &value) {
+            Some(output) => output,
+            None => {
+                eprintln!(
+                    "{}: formatting would change a value, leaving the file as \
+                     it is",
+                    path.display(),
+                );
+                success = false;
+                continue;
+            },
+        };
+
+        if check {
+            if output != input {
+                println!("{} is not formatted", path.display());
+                success = false;
+            }
+        } else if output != input || path == Path::new("-") {
+            write_output(&path, &output)?;
+        }
+    }
+
+    Ok(success)
+}
+
+/// Parses files and reports syntax errors
+fn check_command(args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let files = args.map(PathBuf::from).collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(anyhow!("Missing file"));
+    }
+
+    let mut success = true;
+    for path in files {
+        if let Err(error) = Value::parse(&read_input(&path)?) {
+            eprintln!("{}: {}", path.display(), error);
+            success = false;
+        }
+    }
+
+    Ok(success)
+}
+
+/// Translates a file between the text format and JSON
+fn convert_command(
+    mut args: impl Iterator<Item = String>,
+) -> anyhow::Result<bool> {
+    let mut from = None;
+    let mut to = None;
+    let mut out = None;
+    let mut file = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("Missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--from" => from = Some(value()?.parse::<FileFormat>()?),
+            "--to" => to = Some(value()?.parse::<FileFormat>()?),
+            "--out" => out = Some(PathBuf::from(value()?)),
+            _ if file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => return Err(anyhow!("Unexpected argument {:?}", arg)),
+        }
+    }
+
+    let file = file.context("Missing file")?;
+    let from = match from {
+        Some(from) => from,
+        None => file
+            .extension()
+            .and_then(|e| e.to_str())
+            .context("Missing --from")?
+            .parse()?,
+    };
+    let to = to.unwrap_or(match from {
+        FileFormat::Json => FileFormat::Tyf,
+        FileFormat::Tyf => FileFormat::Json,
+    });
+
+    let value = from.read(&read_input(&file)?)?;
+    let mut output = to.write(&value);
+    output.push('\n');
+
+    write_output(out.as_deref().unwrap_or_else(|| Path::new("-")), &output)?;
+
+    Ok(true)
+}
+
+fn migrate_tree_command(
+    mut args: impl Iterator<Item = String>,
+) -> anyhow::Result<bool> {
+    let mut from = None;
+    let mut to = None;
+    let mut out = None;
+    let mut dir = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("Missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--from" => from = Some(value()?.parse::<FileFormat>()?),
+            "--to" => to = Some(value()?.parse::<FileFormat>()?),
+            "--out" => out = Some(PathBuf::from(value()?)),
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => return Err(anyhow!("Unexpected argument {:?}", arg)),
+        }
+    }
+
+    let from = from.context("Missing --from")?;
+    let to = to.context("Missing --to")?;
+    let dir = dir.context("Missing directory")?;
+    let out = out.unwrap_or_else(|| dir.clone());
+
+    let report = migrate_tree(&dir, &out, from, to)?;
+    println!("{}", report);
+
+    Ok(report.is_success())
+}
+
+fn run() -> anyhow::Result<bool> {
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        Some("fmt") => fmt_command(args),
+        Some("check") => check_command(args),
+        Some("convert") => convert_command(args),
+        Some("migrate-tree") => migrate_tree_command(args),
+        _ => Err(anyhow!("{}", USAGE)),
+    }
+}
+
+fn main() {
+    match run() {
+        Ok(true) => {},
+        Ok(false) => std::process::exit(1),
+        Err(error) => {
+            eprintln!("{:#}", error);
+            std::process::exit(2);
+        },
+    }
+}
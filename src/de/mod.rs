@@ -0,0 +1,1666 @@
+//! Deserializer reading the text format directly, without building a Value
+
+use crate::{
+    literals,
+    literals::Literals,
+    progress::{CancellationToken, Cancelled, Progress, ProgressReporter},
+    raw,
+    value::{
+        deserializer::{Transient, ValueDeserializer},
+        embed, number,
+        number::Number,
+        options::DEFAULT_MAX_DEPTH,
+        parser,
+        printer::{escape_bytes, escape_char, quote_string},
+        types::is_type_name,
+        Value,
+    },
+};
+use serde::de::{
+    value::StringDeserializer, DeserializeOwned, DeserializeSeed, EnumAccess,
+    IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+    io,
+    io::{BufReader, Read},
+};
+
+/// Deserializes a T from the text format in `reader`
+///
+/// The input is tokenized while it is read, so neither the text
+/// nor an intermediate Value is held in memory.
+/// Types written as values, such as `[u8; 4]`, are not supported, nor are
+/// anchors and aliases like `&name` and `*name`, which fail with
+/// `TextDeserializerError::Alias`. Read those with `from_str`.
+/// To bound the memory a single string literal takes, deserialize with
+/// `TextDeserializer::max_string_length`. Nesting is limited to
+/// `DEFAULT_MAX_DEPTH`, see `TextDeserializer::max_depth`.
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = TextDeserializer::new(reader);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    Ok(value)
+}
+
+/// Deserializes a T from `reader` like `from_reader`, reporting progress
+///
+/// Fails with `TextDeserializerError::Cancelled` if the token of
+/// `progress` was cancelled while reading.
+pub fn from_reader_with_progress<'progress, R, T>(
+    reader: R,
+    progress: ProgressReporter<'progress>,
+) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = TextDeserializer::new(reader).progress(progress);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    Ok(value)
+}
+
+/// Deserializes a T from `reader` like `from_reader` until `token` is
+/// cancelled
+///
+/// The token is checked before every item, a cancelled read fails with
+/// `TextDeserializerError::Cancelled`.
+pub fn from_reader_with_cancellation<R, T>(
+    reader: R,
+    token: CancellationToken,
+) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut deserializer = TextDeserializer::new(reader).cancellation(token);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    Ok(value)
+}
+
+#[derive(Debug)]
+pub enum TextDeserializerError {
+    Io(io::Error),
+    Syntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    Custom(String),
+    Cancelled,
+    /// An anchor like `&name` or an alias like `*name`, which would need
+    /// the anchored value to be kept in memory
+    Alias {
+        line: usize,
+        column: usize,
+    },
+}
+
+impl Display for TextDeserializerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextDeserializerError::Io(io) => Display::fmt(io, f),
+            TextDeserializerError::Syntax {
+                message,
+                line,
+                column,
+            } => write!(f, "{} at {}:{}", message, line, column),
+            TextDeserializerError::Custom(custom) => Display::fmt(custom, f),
+            TextDeserializerError::Cancelled => Display::fmt(&Cancelled, f),
+            TextDeserializerError::Alias { line, column } => write!(
+                f,
+                "Anchors and aliases are not supported when streaming, at \
+                 {}:{}",
+                line, column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextDeserializerError {}
+
+impl serde::de::Error for TextDeserializerError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        TextDeserializerError::Custom(msg.to_string())
+    }
+}
+
+impl From<io::Error> for TextDeserializerError {
+    fn from(e: io::Error) -> Self {
+        TextDeserializerError::Io(e)
+    }
+}
+
+impl From<Cancelled> for TextDeserializerError {
+    fn from(_: Cancelled) -> Self {
+        TextDeserializerError::Cancelled
+    }
+}
+
+type Result<T> = std::result::Result<T, TextDeserializerError>;
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Identifier(String),
+    /// `::` between the segments of a type identifier
+    PathSeparator,
+    String(String),
+    /// Part of a string longer than `Lexer::max_string_length`, followed
+    /// by more chunks and a `String` with its end
+    StringChunk(String),
+    Bytes(Vec<u8>),
+    Char(char),
+    Number(String),
+    Punctuation(char),
+    End,
+}
+
+/// Splits the input into tokens, reading it byte by byte
+struct Lexer<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    peeked: Option<u8>,
+    /// Number of bytes consumed
+    offset: usize,
+    line: usize,
+    column: usize,
+    /// Length in bytes above which strings are split into chunks
+    max_string_length: Option<usize>,
+    /// The string the last token was a chunk of
+    open_string: Option<StringKind>,
+    /// Chars of the open string that did not fit into the last chunk
+    carry: String,
+    /// Number of opening delimiters not closed yet
+    nesting: usize,
+    max_depth: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum StringKind {
+    Quoted,
+    /// A raw string with the given number of `#`
+    Raw(usize),
+}
+
+impl<R> Lexer<R>
+where
+    R: Read,
+{
+    fn error<T, M>(&self, message: M) -> Result<T>
+    where
+        M: Display,
+    {
+        Err(TextDeserializerError::Syntax {
+            message: message.to_string(),
+            line: self.line,
+            column: self.column,
+        })
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        if self.peeked.is_none() {
+            self.peeked = self.bytes.next().transpose()?;
+        }
+        Ok(self.peeked)
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        let byte = self.peek_byte()?;
+        self.peeked = None;
+
+        if byte.is_some() {
+            self.offset += 1;
+        }
+        match byte {
+            Some(b'\n') => {
+                self.line += 1;
+                self.column = 0;
+            },
+            // Continuation bytes do not start a new column
+            Some(b) if b & 0xC0 != 0x80 => self.column += 1,
+            _ => {},
+        }
+        Ok(byte)
+    }
+
+    fn expect_byte(&mut self) -> Result<u8> {
+        match self.next_byte()? {
+            Some(b) => Ok(b),
+            None => self.error("Unexpected end of input"),
+        }
+    }
+
+    fn eat_byte(&mut self, expected: u8) -> Result<bool> {
+        if self.peek_byte()? == Some(expected) {
+            self.next_byte()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Skips whitespace and comments, telling if there were any
+    fn skip_trivia(&mut self) -> Result<bool> {
+        let start = self.offset;
+        while let Some(b) = self.peek_byte()? {
+            if b.is_ascii_whitespace() {
+                self.next_byte()?;
+            } else if b == b'/' {
+                self.next_byte()?;
+                self.comment()?;
+            } else {
+                break;
+            }
+        }
+        Ok(self.offset > start)
+    }
+
+    /// Skips a comment after its leading `/`
+    fn comment(&mut self) -> Result<()> {
+        match self.expect_byte()? {
+            b'/' => {
+                while let Some(b) = self.next_byte()? {
+                    if b == b'\n' {
+                        break;
+                    }
+                }
+                Ok(())
+            },
+            b'*' => loop {
+                if self.expect_byte()? == b'*' && self.eat_byte(b'/')? {
+                    return Ok(());
+                }
+            },
+            other => self.error(format!("Unexpected {:?}", other as char)),
+        }
+    }
+
+    /// The next token, and whether whitespace or a comment precedes it
+    fn next_token(&mut self) -> Result<(Token, bool)> {
+        if let Some(kind) = self.open_string.take() {
+            return Ok((self.string(kind)?, false));
+        }
+
+        let spaced = self.skip_trivia()?;
+        Ok((self.token()?, spaced))
+    }
+
+    fn token(&mut self) -> Result<Token> {
+        let b = match self.next_byte()? {
+            Some(b) => b,
+            None => return Ok(Token::End),
+        };
+
+        Ok(match b {
+            b'"' => self.string(StringKind::Quoted)?,
+            b'\'' => Token::Char(self.char()?),
+            b'r' if matches!(self.peek_byte()?, Some(b'#') | Some(b'"')) => {
+                self.raw_string()?
+            },
+            b'b' if self.peek_byte()? == Some(b'"') => {
+                Token::Bytes(self.byte_string()?)
+            },
+            b'-' | b'0'..=b'9' => Token::Number(self.number(b)?),
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let mut identifier = String::new();
+                identifier.push(b as char);
+                while let Some(b) = self.peek_byte()? {
+                    if !(b.is_ascii_alphanumeric() || b == b'_') {
+                        break;
+                    }
+                    identifier.push(b as char);
+                    self.next_byte()?;
+                }
+                Token::Identifier(identifier)
+            },
+            b':' if self.eat_byte(b':')? => Token::PathSeparator,
+            // Angle brackets of types nest like the other delimiters
+            b'(' | b'[' | b'{' | b'<' => {
+                self.nesting += 1;
+                if let Some(max) = self.max_depth {
+                    if self.nesting > max {
+                        return self.error(format!(
+                            "Nesting exceeds the maximum depth of {}",
+                            max
+                        ));
+                    }
+                }
+                Token::Punctuation(b as char)
+            },
+            b')' | b']' | b'}' | b'>' => {
+                self.nesting = self.nesting.saturating_sub(1);
+                Token::Punctuation(b as char)
+            },
+            b',' | b':' | b';' => Token::Punctuation(b as char),
+            b'&' | b'*' => {
+                return Err(TextDeserializerError::Alias {
+                    line: self.line,
+                    column: self.column,
+                })
+            },
+            other => {
+                return self.error(format!("Unexpected {:?}", other as char))
+            },
+        })
+    }
+
+    fn number(&mut self, first: u8) -> Result<String> {
+        let mut number = String::new();
+        number.push(first as char);
+
+        if first == b'-' {
+            match self.peek_byte()? {
+                Some(b @ b'0'..=b'9') => {
+                    number.push(b as char);
+                    self.next_byte()?;
+                },
+                // `-inf`, the parser checks the name
+                Some(b) if b.is_ascii_alphabetic() => {
+                    while let Some(b) = self.peek_byte()? {
+                        if !(b.is_ascii_alphanumeric() || b == b'_') {
+                            break;
+                        }
+                        number.push(b as char);
+                        self.next_byte()?;
+                    }
+                    return Ok(number);
+                },
+                _ => {},
+            }
+        }
+
+        // Radix prefixes, as in `0xFF`, allow letters as digits
+        if number == "0" || number == "-0" {
+            if let Some(radix @ b'x') | Some(radix @ b'o')
+            | Some(radix @ b'b') = self.peek_byte()?
+            {
+                number.push(radix as char);
+                self.next_byte()?;
+                while let Some(b) = self.peek_byte()? {
+                    if !(b.is_ascii_hexdigit() || b == b'_') {
+                        break;
+                    }
+                    number.push(b as char);
+                    self.next_byte()?;
+                }
+                return Ok(number);
+            }
+        }
+
+        while let Some(b) = self.peek_byte()? {
+            match b {
+                b'0'..=b'9' | b'.' | b'_' => number.push(b as char),
+                b'e' | b'E' => {
+                    number.push(b as char);
+                    self.next_byte()?;
+                    if let Some(sign @ b'+') | Some(sign @ b'-') =
+                        self.peek_byte()?
+                    {
+                        number.push(sign as char);
+                    } else {
+                        continue;
+                    }
+                },
+                _ => break,
+            }
+            self.next_byte()?;
+        }
+
+        Ok(number)
+    }
+
+    /// Reads the remaining bytes of a UTF-8 encoded char
+    fn utf8_char(&mut self, first: u8) -> Result<char> {
+        let length = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _ => 4,
+        };
+
+        let mut bytes = vec![first];
+        for _ in 1..length {
+            bytes.push(self.expect_byte()?);
+        }
+
+        match std::str::from_utf8(&bytes) {
+            Ok(s) => Ok(s.chars().next().unwrap()),
+            Err(_) => self.error("Invalid UTF-8"),
+        }
+    }
+
+    /// Reads a char of a string or char literal, resolving escapes
+    fn literal_char(&mut self, first: u8) -> Result<char> {
+        if first != b'\\' {
+            return self.utf8_char(first);
+        }
+
+        Ok(match self.expect_byte()? {
+            b'\\' => '\\',
+
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+
+            b'0' => '\0',
+
+            b'"' => '"',
+            b'\'' => '\'',
+
+            b'u' => {
+                let mut code = 0;
+                for _ in 0..4 {
+                    let digit = (self.expect_byte()? as char).to_digit(16);
+                    code = code * 16
+                        + match digit {
+                            Some(digit) => digit,
+                            None => {
+                                return self.error("Invalid unicode escape")
+                            },
+                        };
+                }
+                match std::char::from_u32(code) {
+                    Some(c) => c,
+                    None => return self.error("Invalid unicode escape"),
+                }
+            },
+
+            other => {
+                return self.error(format!(
+                    "Unknown escape character {:?}",
+                    other as char
+                ))
+            },
+        })
+    }
+
+    /// Adds `c` to the chunk of a string being read, or carries it over
+    /// to the next chunk if this one is full
+    fn push_string_char(&mut self, chunk: &mut String, c: char) {
+        let full = match self.max_string_length {
+            Some(max) => {
+                !self.carry.is_empty()
+                    || (!chunk.is_empty() && chunk.len() + c.len_utf8() > max)
+            },
+            None => false,
+        };
+
+        if full {
+            self.carry.push(c);
+        } else {
+            chunk.push(c);
+        }
+    }
+
+    /// Reads a string after its opening quote, or the rest of the open one
+    ///
+    /// Strings longer than `max_string_length` are returned as chunks,
+    /// the lexer continues the string with the next token.
+    fn string(&mut self, kind: StringKind) -> Result<Token> {
+        let mut string = std::mem::take(&mut self.carry);
+        loop {
+            if !self.carry.is_empty() {
+                self.open_string = Some(kind);
+                return Ok(Token::StringChunk(string));
+            }
+
+            match (kind, self.expect_byte()?) {
+                (StringKind::Quoted, b'"') => return Ok(Token::String(string)),
+                (StringKind::Quoted, b) => {
+                    let c = self.literal_char(b)?;
+                    self.push_string_char(&mut string, c);
+                },
+
+                (StringKind::Raw(hashes), b'"') => {
+                    let mut found = 0;
+                    while found < hashes && self.eat_byte(b'#')? {
+                        found += 1;
+                    }
+                    if found == hashes {
+                        return Ok(Token::String(string));
+                    }
+                    self.push_string_char(&mut string, '"');
+                    for _ in 0..found {
+                        self.push_string_char(&mut string, '#');
+                    }
+                },
+                (StringKind::Raw(_), b) => {
+                    let c = self.utf8_char(b)?;
+                    self.push_string_char(&mut string, c);
+                },
+            }
+        }
+    }
+
+    fn char(&mut self) -> Result<char> {
+        let first = self.expect_byte()?;
+        let c = self.literal_char(first)?;
+
+        if self.expect_byte()? != b'\'' {
+            return self.error("Expected end of char literal");
+        }
+        Ok(c)
+    }
+
+    /// Reads a raw string after its leading `r`
+    fn raw_string(&mut self) -> Result<Token> {
+        let mut hashes = 0;
+        while self.eat_byte(b'#')? {
+            hashes += 1;
+        }
+        if self.expect_byte()? != b'"' {
+            return self.error("Expected '\"' in raw string");
+        }
+
+        self.string(StringKind::Raw(hashes))
+    }
+
+    /// Reads a byte string after its leading `b`
+    fn byte_string(&mut self) -> Result<Vec<u8>> {
+        self.expect_byte()?;
+
+        // Non ASCII bytes are kept as chars, so decoding rejects them
+        let mut text = String::new();
+        loop {
+            match self.expect_byte()? {
+                b'"' => break,
+                b'\\' => {
+                    text.push('\\');
+                    text.push(self.expect_byte()? as char);
+                },
+                b => text.push(b as char),
+            }
+        }
+
+        match parser::unescape_bytes(&text, false) {
+            Ok(bytes) => Ok(bytes),
+            Err(error) => self.error(error),
+        }
+    }
+}
+
+/// Streaming counterpart of `ValueDeserializer`
+pub struct TextDeserializer<'progress, R> {
+    lexer: Lexer<R>,
+    /// Tokens that were looked at, but not consumed yet, and whether
+    /// whitespace or a comment precedes them
+    tokens: VecDeque<(Token, bool)>,
+    progress: ProgressReporter<'progress>,
+    cancellation: Option<CancellationToken>,
+    /// Number of collections the current value is nested in
+    depth: usize,
+    /// Number of items and entries read so far
+    nodes: usize,
+    human_readable: bool,
+    implicit_some: bool,
+}
+
+impl<'progress, R> TextDeserializer<'progress, R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        TextDeserializer {
+            lexer: Lexer {
+                bytes: BufReader::new(reader).bytes(),
+                peeked: None,
+                offset: 0,
+                line: 1,
+                column: 0,
+                max_string_length: None,
+                open_string: None,
+                carry: String::new(),
+                nesting: 0,
+                max_depth: Some(DEFAULT_MAX_DEPTH),
+            },
+            tokens: VecDeque::new(),
+            progress: ProgressReporter::new(),
+            cancellation: None,
+            depth: 0,
+            nodes: 0,
+            human_readable: true,
+            implicit_some: false,
+        }
+    }
+
+    /// Reports progress after every element of the outermost collection
+    pub fn progress(mut self, progress: ProgressReporter<'progress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Stops reading before the next item once `token` is cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Whether the deserialized types expect human readable
+    /// representations, true by default, see
+    /// `TextSerializer::human_readable`
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+
+    /// Reads a value where an option is expected as `Some(value)`, see
+    /// `DeserializeOptions::implicit_some`
+    pub fn implicit_some(mut self, implicit_some: bool) -> Self {
+        self.implicit_some = implicit_some;
+        self
+    }
+
+    /// Delivers strings longer than `max` bytes in chunks of at most
+    /// `max` bytes, instead of reading them into a single allocation
+    ///
+    /// A chunked string is visited as a sequence of strings, so it can be
+    /// collected by a `Vec<String>` or a type processing the pieces as
+    /// they arrive. Where a whole string is needed, as for a `String`
+    /// field, a longer string fails like with
+    /// `ParseOptions::max_string_length`.
+    pub fn max_string_length(mut self, max: usize) -> Self {
+        self.lexer.max_string_length = Some(max);
+        self
+    }
+
+    /// Maximum number of nested parentheses, brackets and braces, see
+    /// `ParseOptions::max_depth`
+    ///
+    /// Nesting deeper than that fails instead of overflowing the stack.
+    pub fn max_depth(mut self, max: Option<usize>) -> Self {
+        self.lexer.max_depth = max;
+        self
+    }
+
+    /// Checks that nothing but whitespace and comments follows
+    pub fn end(&mut self) -> Result<()> {
+        match self.next()? {
+            Token::End => self.report(),
+            other => self.unexpected(&other, "end of input"),
+        }
+    }
+
+    fn report(&mut self) -> Result<()> {
+        let progress = Progress {
+            bytes: self.lexer.offset,
+            nodes: self.nodes,
+        };
+        Ok(self.progress.report(progress)?)
+    }
+
+    fn unexpected<T>(&self, found: &Token, expected: &str) -> Result<T> {
+        self.lexer
+            .error(format!("Expected {}, found {:?}", expected, found))
+    }
+
+    /// Fails for a chunked string where the whole string is needed
+    fn oversized<T>(&self) -> Result<T> {
+        self.lexer.error(format!(
+            "String exceeds the maximum length of {}",
+            self.lexer.max_string_length.unwrap_or_default()
+        ))
+    }
+
+    /// Visits the chunks of a string, after the first one
+    fn visit_chunks<'de, V>(
+        &mut self,
+        first: String,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut chunks = Chunks {
+            deserializer: self,
+            next: Some(first),
+            last: false,
+        };
+        let value = visitor.visit_seq(&mut chunks)?;
+        chunks.finish()?;
+        Ok(value)
+    }
+
+    fn peek_nth(&mut self, index: usize) -> Result<&Token> {
+        while self.tokens.len() <= index {
+            let token = self.lexer.next_token()?;
+            self.tokens.push_back(token);
+        }
+        Ok(&self.tokens[index].0)
+    }
+
+    fn peek(&mut self) -> Result<&Token> {
+        self.peek_nth(0)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        Ok(self.next_spaced()?.0)
+    }
+
+    /// The next token, and whether whitespace or a comment precedes it
+    fn next_spaced(&mut self) -> Result<(Token, bool)> {
+        match self.tokens.pop_front() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn eat(&mut self, punctuation: char) -> Result<bool> {
+        if *self.peek()? == Token::Punctuation(punctuation) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, punctuation: char) -> Result<()> {
+        match self.next()? {
+            Token::Punctuation(c) if c == punctuation => Ok(()),
+            other => self.unexpected(&other, &format!("{:?}", punctuation)),
+        }
+    }
+
+    /// Reads a type identifier after its first segment
+    ///
+    /// Generics are skipped, the name of the last segment is returned
+    fn type_identifier(&mut self, first: String) -> Result<String> {
+        Ok(self.path_identifier(first)?.0)
+    }
+
+    /// Like `type_identifier`, but also tells if the path names an enum
+    /// variant, see `TypeIdentifier::variant`
+    fn path_identifier(&mut self, first: String) -> Result<(String, bool)> {
+        let mut last = first;
+        let mut variant = false;
+        loop {
+            if self.eat('<')? {
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.next()? {
+                        Token::Punctuation('<') => depth += 1,
+                        Token::Punctuation('>') => depth -= 1,
+                        Token::End => {
+                            return self.unexpected(&Token::End, "'>'")
+                        },
+                        _ => {},
+                    }
+                }
+            }
+
+            if *self.peek()? != Token::PathSeparator {
+                return Ok((last, variant));
+            }
+            self.next()?;
+            variant = is_type_name(&last);
+
+            last = match self.next()? {
+                Token::Identifier(identifier) => identifier,
+                other => return self.unexpected(&other, "identifier"),
+            };
+        }
+    }
+
+    /// Whether the parenthesis just opened contains named fields
+    fn named_fields_follow(&mut self) -> Result<bool> {
+        let starts_with_name =
+            matches!(self.peek()?, Token::Identifier(_) | Token::String(_));
+        Ok(starts_with_name && *self.peek_nth(1)? == Token::Punctuation(':'))
+    }
+
+    /// Whether the next map key is an identifier read as a string, as in
+    /// `{ host: "localhost" }`, rather than a keyword or a value
+    fn bare_key_follows(&mut self) -> Result<bool> {
+        let bare = match self.peek()? {
+            Token::Identifier(key) => {
+                !matches!(key.as_str(), "true" | "false" | "None")
+                    && !is_special_float(key)
+            },
+            _ => false,
+        };
+        Ok(bare && *self.peek_nth(1)? == Token::Punctuation(':'))
+    }
+
+    /// Visits the items up to `close`, after the opening delimiter
+    fn visit_items<'de, V>(
+        &mut self,
+        close: char,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.depth += 1;
+        let mut compound = Compound {
+            deserializer: self,
+            close,
+            done: false,
+        };
+        let value = visitor.visit_seq(&mut compound)?;
+        compound.finish()?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// Visits map entries or named fields up to `close`
+    fn visit_entries<'de, V>(
+        &mut self,
+        close: char,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.depth += 1;
+        let mut compound = Compound {
+            deserializer: self,
+            close,
+            done: false,
+        };
+        let value = visitor.visit_map(&mut compound)?;
+        compound.finish()?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// Visits the content of a struct or tuple struct after its name
+    fn visit_struct<'de, V>(
+        &mut self,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.eat('(')? {
+            return visitor.visit_str(name);
+        }
+
+        if self.named_fields_follow()? {
+            self.visit_entries(')', visitor)
+        } else {
+            self.visit_items(')', visitor)
+        }
+    }
+
+    /// Visits an enum variant with contents as a map from its name to them,
+    /// like `ValueDeserializer::deserialize_any`
+    ///
+    /// Whether a variant is a newtype is only known at its closing
+    /// parenthesis, so the contents are buffered in a Value.
+    fn visit_variant<'de, V>(
+        &mut self,
+        name: String,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let text = self.capture_value()?;
+        let contents = match Value::parse(&text) {
+            // Newtype variants contain their only item, not a tuple of it
+            Ok(Value::Tuple(mut items)) if items.len() == 1 => items.remove(0),
+            Ok(contents) => contents,
+            Err(error) => return self.lexer.error(error.message),
+        };
+
+        visitor.visit_map(VariantEntry {
+            name: Some(name),
+            contents,
+        })
+    }
+
+    /// Reads the tokens of a single value and joins them into text
+    ///
+    /// Whitespace and comments between tokens become a single space, so
+    /// the text parses exactly like the input would, including its errors.
+    fn capture_value(&mut self) -> Result<String> {
+        let mut text = String::new();
+        let mut depth = 0usize;
+
+        loop {
+            let (token, spaced) = self.next_spaced()?;
+            if spaced && !text.is_empty() {
+                text.push(' ');
+            }
+            match token {
+                Token::Identifier(ref identifier) => text.push_str(identifier),
+                Token::PathSeparator => text.push_str("::"),
+                Token::String(ref s) => text.push_str(&quote_string(s)),
+                Token::StringChunk(_) => return self.oversized(),
+                Token::Bytes(ref b) => {
+                    text.push_str(&format!("b\"{}\"", escape_bytes(b)))
+                },
+                Token::Char(c) => {
+                    text.push_str(&format!("'{}'", escape_char(c)))
+                },
+                Token::Number(ref number) => text.push_str(number),
+                Token::Punctuation(c) => {
+                    match c {
+                        '(' | '[' | '{' | '<' => depth += 1,
+                        ')' | ']' | '}' | '>' if depth > 0 => depth -= 1,
+                        _ if depth > 0 => {},
+                        _ => return self.unexpected(&token, "value"),
+                    }
+                    text.push(c);
+                },
+                Token::End => return self.unexpected(&token, "value"),
+            }
+
+            if depth > 0 {
+                continue;
+            }
+
+            // Type identifiers continue with segments, generics or content
+            let continues = matches!(
+                (&token, self.peek()?),
+                (Token::PathSeparator, _)
+                    | (Token::Identifier(_), Token::PathSeparator)
+                    | (Token::Identifier(_), Token::Punctuation('('))
+                    | (Token::Identifier(_), Token::Punctuation('<'))
+                    | (Token::Punctuation('>'), Token::PathSeparator)
+                    | (Token::Punctuation('>'), Token::Punctuation('('))
+            );
+            if !continues {
+                return Ok(text);
+            }
+        }
+    }
+
+    /// The string of a `prefix"..."` literal, if a string follows the
+    /// prefix just read, see `Literals::common`
+    fn literal_string(&mut self, prefix: &str) -> Result<Option<String>> {
+        self.peek()?;
+        if !matches!(self.tokens.front(), Some((Token::String(_), false))) {
+            return Ok(None);
+        }
+        let contents = match self.next()? {
+            Token::String(contents) => contents,
+            _ => unreachable!(),
+        };
+
+        let value = match Literals::common_ref().parse(prefix, &contents) {
+            Ok(value) => value,
+            Err(error) => return self.lexer.error(error),
+        };
+        match literals::tagged_string(&value) {
+            Some(Value::String(string)) => Ok(Some(string.to_string())),
+            _ => self
+                .lexer
+                .error(format!("{}\"...\" is not a string", prefix)),
+        }
+    }
+
+    fn number(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Number(number) => Ok(number),
+            Token::Identifier(special) if is_special_float(&special) => {
+                Ok(special)
+            },
+            other => self.unexpected(&other, "number"),
+        }
+    }
+}
+
+/// Floats without digits, which the lexer reads as identifiers
+fn is_special_float(identifier: &str) -> bool {
+    matches!(identifier, "inf" | "NaN")
+}
+
+macro_rules! deserialize_number {
+    ($deserialize_function:ident, $visit_function:ident) => {
+        fn $deserialize_function<V>(self, visitor: V) -> Result<V::Value>
+        where
+            V: Visitor<'de>,
+        {
+            let number = self.number()?;
+            match number::parse_literal(&number) {
+                Some(v) => visitor.$visit_function(v),
+                None => self
+                    .lexer
+                    .error(format!("Failed to parse {:?} as number", number)),
+            }
+        }
+    };
+}
+
+impl<'de, R> serde::Deserializer<'de> for &mut TextDeserializer<'_, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Punctuation('(') => {
+                if self.eat(')')? {
+                    visitor.visit_unit()
+                } else if self.named_fields_follow()? {
+                    self.visit_entries(')', visitor)
+                } else {
+                    self.visit_items(')', visitor)
+                }
+            },
+            Token::Punctuation('[') => self.visit_items(']', visitor),
+            Token::Punctuation('{') => self.visit_entries('}', visitor),
+
+            Token::String(s) => visitor.visit_string(s),
+            Token::StringChunk(chunk) => self.visit_chunks(chunk, visitor),
+            Token::Bytes(b) => visitor.visit_byte_buf(b),
+            Token::Char(c) => visitor.visit_char(c),
+            Token::Number(number) => match Number::parse(&number) {
+                Some(Number::U64(v)) => visitor.visit_u64(v),
+                Some(Number::I64(v)) => visitor.visit_i64(v),
+                Some(Number::U128(v)) => visitor.visit_u128(v),
+                Some(Number::I128(v)) => visitor.visit_i128(v),
+                Some(Number::F64(v)) => visitor.visit_f64(v),
+                None => self
+                    .lexer
+                    .error(format!("Could not parse '{}' as a number", number)),
+            },
+
+            Token::Identifier(identifier) => match identifier.as_str() {
+                "inf" => visitor.visit_f64(f64::INFINITY),
+                "NaN" => visitor.visit_f64(f64::NAN),
+                "true" => visitor.visit_bool(true),
+                "false" => visitor.visit_bool(false),
+                "None" => visitor.visit_none(),
+                "Some" => {
+                    self.expect('(')?;
+                    let value = visitor.visit_some(&mut *self)?;
+                    self.expect(')')?;
+                    Ok(value)
+                },
+                _ => {
+                    if let Some(string) = self.literal_string(&identifier)? {
+                        return visitor.visit_string(string);
+                    }
+
+                    let (name, variant) = self.path_identifier(identifier)?;
+                    if variant && *self.peek()? == Token::Punctuation('(') {
+                        self.visit_variant(name, visitor)
+                    } else {
+                        self.visit_struct(&name, visitor)
+                    }
+                },
+            },
+
+            other => self.unexpected(&other, "value"),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Identifier(b) if b == "true" => visitor.visit_bool(true),
+            Token::Identifier(b) if b == "false" => visitor.visit_bool(false),
+            other => self.unexpected(&other, "bool"),
+        }
+    }
+
+    deserialize_number!(deserialize_i8, visit_i8);
+    deserialize_number!(deserialize_i16, visit_i16);
+    deserialize_number!(deserialize_i32, visit_i32);
+    deserialize_number!(deserialize_i64, visit_i64);
+    deserialize_number!(deserialize_i128, visit_i128);
+    deserialize_number!(deserialize_u8, visit_u8);
+    deserialize_number!(deserialize_u16, visit_u16);
+    deserialize_number!(deserialize_u32, visit_u32);
+    deserialize_number!(deserialize_u64, visit_u64);
+    deserialize_number!(deserialize_u128, visit_u128);
+    deserialize_number!(deserialize_f32, visit_f32);
+    deserialize_number!(deserialize_f64, visit_f64);
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Char(c) => visitor.visit_char(c),
+            other => self.unexpected(&other, "char"),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::String(s) => visitor.visit_string(s),
+            Token::StringChunk(_) => self.oversized(),
+            Token::Identifier(prefix) => match self.literal_string(&prefix)? {
+                Some(string) => visitor.visit_string(string),
+                None => self.unexpected(&Token::Identifier(prefix), "string"),
+            },
+            other => self.unexpected(&other, "string"),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.peek()? {
+            Token::Bytes(_) => match self.next()? {
+                Token::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+                _ => unreachable!(),
+            },
+            // Sequences of u8, as serde writes them by default
+            Token::Punctuation('[') => {
+                visitor.visit_byte_buf(serde::Deserialize::deserialize(self)?)
+            },
+            _ => {
+                let other = self.next()?;
+                self.unexpected(&other, "bytes")
+            },
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let written = matches!(
+            self.peek()?,
+            Token::Identifier(o) if o == "None" || o == "Some"
+        );
+        if self.implicit_some && !written {
+            return visitor.visit_some(self);
+        }
+
+        match self.next()? {
+            Token::Identifier(o) if o == "None" => visitor.visit_none(),
+            Token::Identifier(o) if o == "Some" => {
+                self.expect('(')?;
+                let value = visitor.visit_some(&mut *self)?;
+                self.expect(')')?;
+                Ok(value)
+            },
+            other => self.unexpected(&other, "option"),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Identifier(identifier) => {
+                self.type_identifier(identifier)?;
+                // Empty structs are printed as `Name()`
+                if self.eat('(')? {
+                    self.expect(')')?;
+                }
+                visitor.visit_unit()
+            },
+            Token::Punctuation('(') => {
+                self.expect(')')?;
+                visitor.visit_unit()
+            },
+            other => self.unexpected(&other, "unit struct"),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Identifier(identifier) => {
+                let variant = self.type_identifier(identifier)?;
+                visitor.visit_enum(Variant {
+                    deserializer: self,
+                    variant,
+                })
+            },
+            other => self.unexpected(&other, "enum variant"),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.next()? {
+            Token::Identifier(identifier) => {
+                visitor.visit_string(self.type_identifier(identifier)?)
+            },
+            Token::String(s) => visitor.visit_string(s),
+            Token::StringChunk(_) => self.oversized(),
+            other => self.unexpected(&other, "identifier"),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == raw::TOKEN {
+            let text = self.capture_value()?;
+            if let Err(error) = Value::parse(&text) {
+                return self.lexer.error(error.message);
+            }
+            visitor.visit_string(text)
+        } else if name == embed::TOKEN {
+            let text = self.capture_value()?;
+            embed::hand_over(Value::parse(&text).map_err(|e| {
+                TextDeserializerError::Custom(format!("{:#}", e))
+            })?);
+            let result = visitor.visit_unit();
+            embed::discard();
+            result
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        unit seq tuple tuple_struct map struct ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+}
+
+/// Items of a list or tuple, or entries of a map or struct
+struct Compound<'lt, 'progress, R> {
+    deserializer: &'lt mut TextDeserializer<'progress, R>,
+    close: char,
+    /// Whether the closing delimiter was consumed
+    done: bool,
+}
+
+impl<'lt, 'progress, R> Compound<'lt, 'progress, R>
+where
+    R: Read,
+{
+    /// Consumes the separator after an item, or the closing delimiter
+    fn after_item(&mut self) -> Result<()> {
+        if !self.deserializer.eat(',')? {
+            self.deserializer.expect(self.close)?;
+            self.done = true;
+        }
+
+        self.deserializer.nodes += 1;
+        if self.deserializer.depth == 1 {
+            self.deserializer.report()?;
+        }
+        Ok(())
+    }
+
+    /// Whether another item follows
+    fn has_next(&mut self) -> Result<bool> {
+        if let Some(token) = &self.deserializer.cancellation {
+            token.check()?;
+        }
+        if !self.done && self.deserializer.eat(self.close)? {
+            self.done = true;
+        }
+        Ok(!self.done)
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.done {
+            Ok(())
+        } else {
+            self.deserializer.expect(self.close)
+        }
+    }
+}
+
+impl<'de, 'lt, 'progress, R> SeqAccess<'de> for Compound<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        self.after_item()?;
+        Ok(Some(value))
+    }
+}
+
+impl<'de, 'lt, 'progress, R> MapAccess<'de> for Compound<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+
+        let key = if self.close == ')' {
+            // Field names, quoted or not
+            match self.deserializer.next()? {
+                Token::Identifier(name) | Token::String(name) => {
+                    let name: StringDeserializer<TextDeserializerError> =
+                        name.into_deserializer();
+                    seed.deserialize(name)?
+                },
+                Token::StringChunk(_) => return self.deserializer.oversized(),
+                other => return self.deserializer.unexpected(&other, "field"),
+            }
+        } else if self.deserializer.bare_key_follows()? {
+            seed.deserialize(BareKey {
+                deserializer: &mut *self.deserializer,
+            })?
+        } else {
+            seed.deserialize(&mut *self.deserializer)?
+        };
+
+        self.deserializer.expect(':')?;
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        self.after_item()?;
+        Ok(value)
+    }
+}
+
+/// A map key written as an identifier, as in `{ host: "localhost" }`
+///
+/// It reads as a string, or as the unit struct it is written as for
+/// everything else, like `Value` keys.
+struct BareKey<'lt, 'progress, R> {
+    deserializer: &'lt mut TextDeserializer<'progress, R>,
+}
+
+impl<'de, 'lt, 'progress, R> serde::Deserializer<'de>
+    for BareKey<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.deserializer.next()? {
+            Token::Identifier(key) => visitor.visit_string(key),
+            other => unreachable!("{:?} as a bare key", other),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.deserialize_identifier(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes
+        byte_buf option unit unit_struct seq tuple tuple_struct map struct
+        ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.human_readable
+    }
+}
+
+/// Pieces of a string longer than `max_string_length`, see
+/// `TextDeserializer::max_string_length`
+struct Chunks<'lt, 'progress, R> {
+    deserializer: &'lt mut TextDeserializer<'progress, R>,
+    /// The chunk to visit next, None once all were visited
+    next: Option<String>,
+    /// Whether `next` ends the string
+    last: bool,
+}
+
+impl<'lt, 'progress, R> Chunks<'lt, 'progress, R>
+where
+    R: Read,
+{
+    /// Reads the chunk after `next`
+    fn advance(&mut self) -> Result<()> {
+        self.next = if self.last {
+            None
+        } else {
+            match self.deserializer.next()? {
+                Token::StringChunk(chunk) => Some(chunk),
+                Token::String(end) => {
+                    self.last = true;
+                    Some(end)
+                },
+                other => unreachable!("{:?} within a string", other),
+            }
+        };
+        Ok(())
+    }
+
+    /// Skips the chunks the visitor did not ask for
+    fn finish(mut self) -> Result<()> {
+        while self.next.is_some() {
+            self.advance()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, 'lt, 'progress, R> SeqAccess<'de> for Chunks<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let chunk = match self.next.take() {
+            Some(chunk) => chunk,
+            None => return Ok(None),
+        };
+        self.advance()?;
+
+        let chunk: StringDeserializer<TextDeserializerError> =
+            chunk.into_deserializer();
+        seed.deserialize(chunk).map(Some)
+    }
+}
+
+/// An enum variant as a map with a single entry, see `visit_variant`
+struct VariantEntry {
+    /// Taken once the key was visited
+    name: Option<String>,
+    contents: Value,
+}
+
+impl<'de> MapAccess<'de> for VariantEntry {
+    type Error = TextDeserializerError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.name.take() {
+            None => Ok(None),
+            Some(name) => {
+                let name: StringDeserializer<TextDeserializerError> =
+                    name.into_deserializer();
+                Ok(Some(seed.deserialize(name)?))
+            },
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(ValueDeserializer::with_source(
+            &self.contents,
+            Transient,
+        ))
+        .map_err(|error| TextDeserializerError::Custom(error.to_string()))
+    }
+}
+
+struct Variant<'lt, 'progress, R> {
+    deserializer: &'lt mut TextDeserializer<'progress, R>,
+    variant: String,
+}
+
+impl<'de, 'lt, 'progress, R> EnumAccess<'de> for Variant<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant: StringDeserializer<TextDeserializerError> =
+            self.variant.clone().into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de, 'lt, 'progress, R> VariantAccess<'de> for Variant<'lt, 'progress, R>
+where
+    R: Read,
+{
+    type Error = TextDeserializerError;
+
+    fn unit_variant(self) -> Result<()> {
+        // `Baz()` is a unit variant as well
+        if self.deserializer.eat('(')? {
+            self.deserializer.expect(')')?;
+        }
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.deserializer.expect('(')?;
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        self.deserializer.eat(',')?;
+        self.deserializer.expect(')')?;
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.expect('(')?;
+        self.deserializer.visit_items(')', visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserializer.expect('(')?;
+        self.deserializer.visit_entries(')', visitor)
+    }
+}
@@ -0,0 +1,768 @@
+//! Lossless document model for rewriting files in place
+//!
+//! A `Document` keeps comments, whitespace and the order of entries,
+//! so a file only changes where it was edited.
+
+use crate::value::{
+    diagnostic::ParseError, printer::ValuePrinter, types::Identifier, Value,
+};
+use std::{
+    fmt,
+    fmt::{Display, Formatter, Write},
+    str::FromStr,
+};
+
+/// A parsed document that prints back to the exact text it came from
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Document {
+    /// Whitespace and comments before the root value
+    leading: String,
+    root: Item,
+    /// Whitespace and comments after the root value
+    trailing: String,
+}
+
+/// A value inside a document, together with its layout
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Item(Node);
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Node {
+    /// Text of a value without items, such as a number or unit struct
+    Scalar(String),
+    Collection(Collection),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Collection {
+    /// Everything up to and including the opening delimiter, as in `Name(`
+    /// or `&name [`
+    open: String,
+    close: char,
+    entries: Vec<Entry>,
+    /// Whitespace and comments before the closing delimiter
+    trailing: String,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Entry {
+    /// Whitespace and comments before the entry
+    leading: String,
+    /// Key with the text between it and the value, including the `:`
+    key: Option<(Item, String)>,
+    value: Item,
+    /// Whitespace and comments after the value, including its comma
+    /// and a comment on the same line
+    trailing: String,
+}
+
+impl Document {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        // The scanner below is lenient, so the input is validated first
+        Value::parse(input)?;
+
+        let mut scanner = Scanner { input, position: 0 };
+        let leading = scanner.trivia().to_string();
+        let root = scanner.item()?;
+        let trailing = scanner.trivia().to_string();
+        if scanner.position != input.len() {
+            return Err(scanner.error("Unexpected input"));
+        }
+
+        Ok(Document {
+            leading,
+            root,
+            trailing,
+        })
+    }
+
+    pub fn root(&self) -> &Item {
+        &self.root
+    }
+    pub fn root_mut(&mut self) -> &mut Item {
+        &mut self.root
+    }
+
+    pub fn to_value(&self) -> Result<Value, ParseError> {
+        self.root.to_value()
+    }
+
+    /// Source text of every value without items, keys included, and of
+    /// everything up to the opening delimiter of every collection, such
+    /// as `&name Point(`, in the order they appear
+    ///
+    /// Shows how a value was written, `0xFF` and `255` are the same value,
+    /// but different text.
+    pub fn tokens(&self) -> Vec<&str> {
+        let mut tokens = Vec::new();
+        self.root.tokens(&mut tokens);
+        tokens
+    }
+
+    /// Writes values as `source` writes them, for a document printed from
+    /// the value of `source`
+    ///
+    /// Printing writes `0xFF` as `255` and repeats the value of an alias
+    /// like `*name`. This brings back the text of `source` and keeps the
+    /// layout and comments of this document, which is how a formatter
+    /// keeps the notation of a file.
+    pub fn restore_notation(&mut self, source: &Document) {
+        self.root.restore_notation(&source.root);
+    }
+}
+
+impl FromStr for Document {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Document::parse(s)
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.leading, self.root, self.trailing)
+    }
+}
+
+impl Item {
+    /// An item for `value`, written on a single line
+    pub fn new(value: &Value) -> Self {
+        let mut text = String::new();
+        ValuePrinter::inline().write(value, &mut text).unwrap();
+
+        let mut scanner = Scanner {
+            input: &text,
+            position: 0,
+        };
+        scanner.item().expect("Printed values can be scanned")
+    }
+
+    pub fn to_value(&self) -> Result<Value, ParseError> {
+        Value::parse(&self.to_string())
+    }
+
+    /// Number of items or entries, zero for values without any
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Node::Scalar(_) => 0,
+            Node::Collection(collection) => collection.entries.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of the field or map entry named `key`
+    ///
+    /// Map keys match if they are a string equal to `key`.
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        let collection = self.collection().ok()?;
+        let index = collection.position(key)?;
+        Some(&collection.entries[index].value)
+    }
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Item> {
+        let collection = self.collection_mut().ok()?;
+        let index = collection.position(key)?;
+        Some(&mut collection.entries[index].value)
+    }
+
+    /// The item at `index` of a list or tuple
+    pub fn item(&self, index: usize) -> Option<&Item> {
+        let collection = self.collection().ok()?;
+        collection.entries.get(index).map(|entry| &entry.value)
+    }
+    pub fn item_mut(&mut self, index: usize) -> Option<&mut Item> {
+        let collection = self.collection_mut().ok()?;
+        collection
+            .entries
+            .get_mut(index)
+            .map(|entry| &mut entry.value)
+    }
+
+    /// Replaces this item, the layout around it is kept
+    pub fn set(&mut self, value: &Value) {
+        *self = Item::new(value);
+    }
+
+    /// Replaces the value of a field or map entry, or appends a new one
+    ///
+    /// A new entry follows the indentation and commas of the entry before it.
+    pub fn set_field(
+        &mut self,
+        key: &str,
+        value: &Value,
+    ) -> Result<(), EditError> {
+        let collection = self.collection_mut()?;
+
+        if let Some(index) = collection.position(key) {
+            collection.entries[index].value = Item::new(value);
+            return Ok(());
+        }
+
+        let key = match collection.close {
+            ')' if collection
+                .entries
+                .iter()
+                .any(|entry| entry.key.is_none()) =>
+            {
+                return Err(EditError::FieldNotAllowed {
+                    key: key.to_string(),
+                    collection: "tuple",
+                })
+            },
+            ')' if Identifier::from(key).is_valid() => {
+                Item(Node::Scalar(key.to_string()))
+            },
+            ')' | '}' => Item::new(&Value::String(key.into())),
+            _ => {
+                return Err(EditError::FieldNotAllowed {
+                    key: key.to_string(),
+                    collection: "list",
+                })
+            },
+        };
+        let separator = collection
+            .entries
+            .last()
+            .and_then(|entry| entry.key.as_ref())
+            .map_or(": ".to_string(), |(_, separator)| separator.clone());
+
+        collection.append(Some((key, separator)), Item::new(value));
+        Ok(())
+    }
+
+    /// Appends an item to a list or tuple
+    pub fn push(&mut self, value: &Value) -> Result<(), EditError> {
+        let collection = self.collection_mut()?;
+
+        if collection.close == '}'
+            || collection.entries.iter().any(|entry| entry.key.is_some())
+        {
+            return Err(EditError::KeyRequired);
+        }
+
+        collection.append(None, Item::new(value));
+        Ok(())
+    }
+
+    /// Removes a field or map entry together with its comments
+    pub fn remove_field(&mut self, key: &str) -> Option<Item> {
+        let collection = self.collection_mut().ok()?;
+        let index = collection.position(key)?;
+        Some(collection.entries.remove(index).value)
+    }
+
+    fn collection(&self) -> Result<&Collection, EditError> {
+        match &self.0 {
+            Node::Collection(collection) => Ok(collection),
+            Node::Scalar(text) => Err(EditError::NoEntries(text.clone())),
+        }
+    }
+    fn collection_mut(&mut self) -> Result<&mut Collection, EditError> {
+        match &mut self.0 {
+            Node::Collection(collection) => Ok(collection),
+            Node::Scalar(text) => Err(EditError::NoEntries(text.clone())),
+        }
+    }
+}
+
+/// Error of `Item::set_field` and `Item::push`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum EditError {
+    /// The item is a value without entries, with its text
+    NoEntries(String),
+    /// A named field can not be added to a tuple or list
+    FieldNotAllowed {
+        key: String,
+        collection: &'static str,
+    },
+    /// Items of a struct or map need a key
+    KeyRequired,
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NoEntries(text) => write!(f, "{} has no entries", text),
+            EditError::FieldNotAllowed { key, collection } => {
+                write!(f, "Cannot add field {:?} to a {}", key, collection)
+            },
+            EditError::KeyRequired => {
+                f.write_str("Cannot push an item without a key")
+            },
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Node::Scalar(text) => write!(f, "{}", text),
+            Node::Collection(collection) => {
+                write!(f, "{}", collection.open)?;
+                for entry in &collection.entries {
+                    write!(f, "{}", entry.leading)?;
+                    if let Some((key, separator)) = &entry.key {
+                        write!(f, "{}{}", key, separator)?;
+                    }
+                    write!(f, "{}{}", entry.value, entry.trailing)?;
+                }
+                write!(f, "{}", collection.trailing)?;
+                f.write_char(collection.close)
+            },
+        }
+    }
+}
+
+impl Item {
+    fn tokens<'lt>(&'lt self, tokens: &mut Vec<&'lt str>) {
+        match &self.0 {
+            Node::Scalar(text) => tokens.push(text),
+            Node::Collection(collection) => {
+                tokens.push(&collection.open);
+                for entry in &collection.entries {
+                    if let Some((key, _)) = &entry.key {
+                        key.tokens(tokens);
+                    }
+                    entry.value.tokens(tokens);
+                }
+            },
+        }
+    }
+}
+
+impl Item {
+    fn restore_notation(&mut self, source: &Item) {
+        // Whitespace inside of a token is layout as well
+        let normalize =
+            |token: &str| token.split_whitespace().collect::<String>();
+
+        match (&mut self.0, &source.0) {
+            (Node::Scalar(text), Node::Scalar(original)) => {
+                if normalize(text) != normalize(original) {
+                    *text = original.clone();
+                }
+            },
+            (Node::Collection(collection), Node::Collection(original)) => {
+                if normalize(&collection.open) != normalize(&original.open) {
+                    collection.open = original.open.clone();
+                }
+                collection.restore_notation(original);
+            },
+            // An alias that was printed as its value
+            _ => *self = source.clone(),
+        }
+    }
+}
+
+impl Collection {
+    /// See `Document::restore_notation`
+    ///
+    /// Entries with a key are matched by its value, as map entries may be
+    /// printed in another order, all others by their position.
+    fn restore_notation(&mut self, source: &Collection) {
+        let keys = source
+            .entries
+            .iter()
+            .map(|entry| {
+                entry.key.as_ref().and_then(|(key, _)| key.to_value().ok())
+            })
+            .collect::<Vec<_>>();
+
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            let original = match &mut entry.key {
+                Some((key, _)) => {
+                    let value = key.to_value().ok();
+                    let original = keys
+                        .iter()
+                        .position(|original| {
+                            original.is_some() && *original == value
+                        })
+                        .map(|index| &source.entries[index]);
+                    if let Some((original, _)) =
+                        original.and_then(|original| original.key.as_ref())
+                    {
+                        key.restore_notation(original);
+                    }
+                    original
+                },
+                None => source.entries.get(index),
+            };
+
+            if let Some(original) = original {
+                entry.value.restore_notation(&original.value);
+            }
+        }
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| match &entry.key {
+            Some((Item(Node::Scalar(text)), _)) => {
+                text == key
+                    || matches!(
+                        Value::parse(text),
+                        Ok(Value::String(string)) if &*string == key
+                    )
+            },
+            _ => false,
+        })
+    }
+
+    fn append(&mut self, key: Option<(Item, String)>, value: Item) {
+        let single = self.entries.len() == 1;
+        let (leading, trailing) = match self.entries.last_mut() {
+            Some(last) => {
+                let comma = last.trailing.contains(',');
+                if !comma {
+                    last.trailing.push(',');
+                }
+
+                // Indentation of the last entry, without its comments
+                let indentation =
+                    &last.leading[last.leading.trim_end().len()..];
+                let leading = match indentation {
+                    "" if single => " ",
+                    indentation => indentation,
+                };
+
+                let trailing = if comma { "," } else { "" };
+                (leading.to_string(), trailing.to_string())
+            },
+            None => (String::new(), String::new()),
+        };
+
+        self.entries.push(Entry {
+            leading,
+            key,
+            value,
+            trailing,
+        });
+    }
+}
+
+/// Splits text into items, keeping everything between them
+struct Scanner<'input> {
+    input: &'input str,
+    position: usize,
+}
+
+impl<'input> Scanner<'input> {
+    fn rest(&self) -> &'input str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn advance(&mut self, bytes: usize) -> &'input str {
+        let text = &self.rest()[..bytes];
+        self.position += bytes;
+        text
+    }
+
+    /// Consumes whitespace and comments
+    fn trivia(&mut self) -> &'input str {
+        let start = self.position;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("//") {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                self.advance(end);
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                // Past the opening `/*`, whose `*` could start `*/`
+                let end = comment.find("*/").map_or(rest.len(), |end| end + 4);
+                self.advance(end);
+            } else {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() => {
+                        self.advance(c.len_utf8());
+                    },
+                    _ => break,
+                }
+            }
+        }
+        &self.input[start..self.position]
+    }
+
+    /// Consumes a comment on the rest of the current line, if there is one
+    fn line_comment(&mut self) -> &'input str {
+        let start = self.position;
+        let spaces = self.rest().len()
+            - self.rest().trim_start_matches([' ', '\t']).len();
+        self.advance(spaces);
+
+        if self.rest().starts_with("//") {
+            let end = self.rest().find('\n').unwrap_or(self.rest().len());
+            self.advance(end);
+        } else {
+            self.position = start;
+        }
+        &self.input[start..self.position]
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(self.input, self.position, message)
+    }
+
+    fn item(&mut self) -> Result<Item, ParseError> {
+        match self.peek() {
+            Some('[') => {
+                // Array types such as `[u8; 4]` are not lists
+                let start = self.position;
+                match self.collection("[".to_string(), ']') {
+                    Ok(item) => Ok(item),
+                    Err(_) => {
+                        self.position = start;
+                        Ok(Item(Node::Scalar(self.balanced().to_string())))
+                    },
+                }
+            },
+            Some(open @ '(') | Some(open @ '{') => {
+                self.advance(1);
+                let close = if open == '(' { ')' } else { '}' };
+                self.collection(open.to_string(), close)
+            },
+            Some('&') => {
+                // `&name value`, the anchor is kept in front of the value
+                let start = self.position;
+                self.advance(1);
+                self.trivia();
+                self.atom()?;
+                self.trivia();
+                let anchor = &self.input[start..self.position];
+
+                Ok(match self.item()? {
+                    Item(Node::Scalar(text)) => {
+                        Item(Node::Scalar(format!("{}{}", anchor, text)))
+                    },
+                    Item(Node::Collection(mut collection)) => {
+                        collection.open.insert_str(0, anchor);
+                        Item(Node::Collection(collection))
+                    },
+                })
+            },
+            Some('*') => {
+                let start = self.position;
+                self.advance(1);
+                self.atom()?;
+                Ok(Item(Node::Scalar(
+                    self.input[start..self.position].to_string(),
+                )))
+            },
+            Some(quote @ '"') | Some(quote @ '\'') => {
+                Ok(Item(Node::Scalar(self.quoted(quote).to_string())))
+            },
+            Some('b') if self.rest()[1..].starts_with('"') => {
+                let start = self.position;
+                self.advance(1);
+                self.quoted('"');
+                Ok(Item(Node::Scalar(
+                    self.input[start..self.position].to_string(),
+                )))
+            },
+            Some('r')
+                if self.rest()[1..]
+                    .trim_start_matches('#')
+                    .starts_with('"') =>
+            {
+                Ok(Item(Node::Scalar(self.raw_string().to_string())))
+            },
+            _ => {
+                if let Some(literal) = self.prefixed_string() {
+                    return Ok(Item(Node::Scalar(literal.to_string())));
+                }
+                let atom = self.atom()?;
+
+                // A name followed by a parenthesis opens a struct
+                let start = self.position;
+                let trivia = self.trivia();
+                if self.peek() == Some('(') {
+                    self.advance(1);
+                    let open = format!("{}{}(", atom, trivia);
+                    self.collection(open, ')')
+                } else {
+                    self.position = start;
+                    Ok(Item(Node::Scalar(atom.to_string())))
+                }
+            },
+        }
+    }
+
+    /// Scans entries after the opening delimiter, which is part of `open`
+    fn collection(
+        &mut self,
+        open: String,
+        close: char,
+    ) -> Result<Item, ParseError> {
+        if open == "[" {
+            self.advance(1);
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            let leading = self.trivia().to_string();
+            if self.peek() == Some(close) {
+                self.advance(1);
+                return Ok(Item(Node::Collection(Collection {
+                    open,
+                    close,
+                    entries,
+                    trailing: leading,
+                })));
+            }
+
+            let mut value = self.item()?;
+            let mut key = None;
+
+            let before_separator = self.position;
+            let trivia = self.trivia();
+            if self.rest().starts_with(':') && !self.rest().starts_with("::") {
+                let separator =
+                    format!("{}{}{}", trivia, self.advance(1), self.trivia());
+                key = Some((value, separator));
+                value = self.item()?;
+            } else {
+                self.position = before_separator;
+            }
+
+            let before_comma = self.position;
+            let trivia = self.trivia();
+            let trailing = if self.peek() == Some(',') {
+                self.advance(1);
+                format!("{},{}", trivia, self.line_comment())
+            } else if self.peek() == Some(close) {
+                // Kept as trailing text of the collection
+                self.position = before_comma;
+                String::new()
+            } else {
+                return Err(self.error(&format!("Expected ',' or {:?}", close)));
+            };
+
+            entries.push(Entry {
+                leading,
+                key,
+                value,
+                trailing,
+            });
+        }
+    }
+
+    /// Consumes a name, number or path, including generics
+    fn atom(&mut self) -> Result<&'input str, ParseError> {
+        let start = self.position;
+        let mut depth = 0usize;
+
+        while let Some(c) = self.peek() {
+            let rest = self.rest();
+            match c {
+                '<' => depth += 1,
+                '>' if depth > 0 => depth -= 1,
+                ':' if rest.starts_with("::") => {
+                    self.advance(2);
+                    self.trivia();
+                    continue;
+                },
+                _ if depth > 0 => {},
+                c if c.is_alphanumeric() || "_.-+".contains(c) => {},
+                _ => {
+                    // Whitespace and comments may come before a `::` too
+                    let before = self.position;
+                    self.trivia();
+                    if self.position > before && self.rest().starts_with("::") {
+                        continue;
+                    }
+                    self.position = before;
+                    break;
+                },
+            }
+            self.advance(c.len_utf8());
+        }
+
+        if self.position == start {
+            return Err(self.error("Unexpected input"));
+        }
+        Ok(&self.input[start..self.position])
+    }
+
+    /// Consumes a literal like `datetime"..."`, see `Literals`, or one
+    /// with a raw string after its prefix like `prefixr#"..."#`
+    fn prefixed_string(&mut self) -> Option<&'input str> {
+        let start = self.position;
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+        let prefix = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let after = &rest[prefix..];
+
+        if after.starts_with('"') {
+            self.advance(prefix);
+            self.quoted('"');
+        } else if rest[..prefix].ends_with('r')
+            && after.starts_with('#')
+            && after.trim_start_matches('#').starts_with('"')
+        {
+            self.advance(prefix - 1);
+            self.raw_string();
+        } else {
+            return None;
+        }
+        Some(&self.input[start..self.position])
+    }
+
+    /// Consumes brackets up to the matching closing one
+    fn balanced(&mut self) -> &'input str {
+        let mut depth = 0usize;
+        let end = self
+            .rest()
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {},
+                }
+                depth == 0
+            })
+            .map_or(self.rest().len(), |(index, _)| index + 1);
+        self.advance(end)
+    }
+
+    fn quoted(&mut self, quote: char) -> &'input str {
+        let mut escaped = false;
+        let end = self
+            .rest()
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| match c {
+                _ if escaped => {
+                    escaped = false;
+                    false
+                },
+                '\\' => {
+                    escaped = true;
+                    false
+                },
+                c => *c == quote,
+            })
+            .map_or(self.rest().len(), |(index, _)| index + 1);
+        self.advance(end)
+    }
+
+    fn raw_string(&mut self) -> &'input str {
+        let rest = self.rest();
+        let hashes = rest[1..].len() - rest[1..].trim_start_matches('#').len();
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let content = hashes + 2;
+        let end = rest[content..]
+            .find(&terminator)
+            .map_or(rest.len(), |end| content + end + terminator.len());
+        self.advance(end)
+    }
+}
@@ -0,0 +1,199 @@
+//! The error of functions that combine several steps, see `Error`
+
+use crate::{
+    progress::Cancelled,
+    value::{
+        binary::DecodeError, deserializer::ValueDeserializerError,
+        diagnostic::ParseError, patch::PatchError, path::Path,
+        serializer::ValueSerializerError,
+    },
+};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    io,
+};
+
+/// Error of `from_str`, `Value::retag` and the other functions that parse,
+/// convert or decode in more than one step
+///
+/// Each variant holds the error of the step that failed, so callers can
+/// match on it. Like every error of this crate it converts into an
+/// `anyhow::Error` with `?`.
+#[derive(Debug)]
+pub enum Error {
+    /// The text is not valid
+    Parse(ParseError),
+    /// The value does not fit the type it is deserialized as
+    Deserialize(ValueDeserializerError),
+    /// The type could not be converted into a value
+    Serialize(ValueSerializerError),
+    /// The bytes are not a valid binary encoding, see `value::binary`
+    Decode(DecodeError),
+    /// An operation of a `ValuePatch` could not be applied
+    Patch(PatchError),
+    /// A value is of another kind than the function needs, such as a list
+    /// for `Value::into_map`
+    KindMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A value does not have the structure the function reads, such as a
+    /// patch operation without a path, `path` leads to the part that is
+    /// wrong
+    Invalid { path: Path, message: String },
+    /// The text is not valid JSON
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The text is not valid TOML
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The text is not valid YAML
+    #[cfg(feature = "yaml")]
+    Yaml(serde_yaml::Error),
+    /// The text is not valid RON
+    #[cfg(feature = "ron")]
+    Ron(ron::error::SpannedError),
+    /// A document of a `ValueStream` failed, with the line it starts at
+    Document { line: usize, error: Box<Error> },
+    /// A record of a `ValueLogReader` failed, with its line
+    Record { line: usize, error: Box<Error> },
+    /// Reading or writing failed
+    Io(io::Error),
+    /// The token of a `ProgressReporter` was cancelled
+    Cancelled,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => Display::fmt(error, f),
+            Error::Deserialize(error) => Display::fmt(error, f),
+            Error::Serialize(error) => Display::fmt(error, f),
+            Error::Decode(error) => Display::fmt(error, f),
+            Error::Patch(error) => Display::fmt(error, f),
+            Error::KindMismatch { expected, found } => {
+                write!(f, "Expected a {}, found a {}", expected, found)
+            },
+            Error::Invalid { path, message } if path.is_root() => {
+                f.write_str(message)
+            },
+            Error::Invalid { path, message } => {
+                write!(f, "At {}: {}", path, message)
+            },
+            #[cfg(feature = "json")]
+            Error::Json(error) => Display::fmt(error, f),
+            #[cfg(feature = "toml")]
+            Error::Toml(error) => Display::fmt(error, f),
+            #[cfg(feature = "yaml")]
+            Error::Yaml(error) => Display::fmt(error, f),
+            #[cfg(feature = "ron")]
+            Error::Ron(error) => Display::fmt(error, f),
+            Error::Document { line, error } => {
+                write!(f, "Document at line {}: {}", line, error)
+            },
+            Error::Record { line, error } => {
+                write!(f, "Record at line {}: {}", line, error)
+            },
+            Error::Io(error) => Display::fmt(error, f),
+            Error::Cancelled => Display::fmt(&Cancelled, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Deserialize(error) => Some(error),
+            Error::Serialize(error) => Some(error),
+            Error::Decode(error) => Some(error),
+            Error::Patch(error) => Some(error),
+            Error::Io(error) => Some(error),
+            #[cfg(feature = "json")]
+            Error::Json(error) => Some(error),
+            #[cfg(feature = "toml")]
+            Error::Toml(error) => Some(error),
+            #[cfg(feature = "yaml")]
+            Error::Yaml(error) => Some(error),
+            #[cfg(feature = "ron")]
+            Error::Ron(error) => Some(error),
+            Error::Document { error, .. } | Error::Record { error, .. } => {
+                Some(error.as_ref())
+            },
+            Error::KindMismatch { .. }
+            | Error::Invalid { .. }
+            | Error::Cancelled => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<ValueDeserializerError> for Error {
+    fn from(e: ValueDeserializerError) -> Self {
+        Error::Deserialize(e)
+    }
+}
+
+impl From<ValueSerializerError> for Error {
+    fn from(e: ValueSerializerError) -> Self {
+        Error::Serialize(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<PatchError> for Error {
+    fn from(e: PatchError) -> Self {
+        Error::Patch(e)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+#[cfg(feature = "ron")]
+impl From<ron::error::SpannedError> for Error {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Error::Ron(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Cancelled> for Error {
+    fn from(_: Cancelled) -> Self {
+        Error::Cancelled
+    }
+}
@@ -0,0 +1,76 @@
+//! Machine readable description of the text format grammar
+//!
+//! The rule table is generated from `value.pest` at build time,
+//! so it always matches the grammar the parser is built from.
+
+use crate::value::{map::OrderedMap, Value};
+
+/// How a rule treats whitespace and whether it produces tokens,
+/// mirroring the pest rule modifiers
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RuleKind {
+    /// `rule = { ... }`
+    Normal,
+    /// `rule = _{ ... }`, matches but produces no token
+    Silent,
+    /// `rule = @{ ... }`, no implicit whitespace and no inner tokens
+    Atomic,
+    /// `rule = ${ ... }`, no implicit whitespace, but inner tokens
+    CompoundAtomic,
+    /// `rule = !{ ... }`, cancels an enclosing atomic rule
+    NonAtomic,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct GrammarRule {
+    pub name: &'static str,
+    pub kind: RuleKind,
+    /// Names of the grammar rules referenced by this rule, in order of
+    /// first appearance. Builtin pest rules are not included.
+    pub children: &'static [&'static str],
+    /// The rule body in pest syntax
+    pub expression: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/grammar_rules.rs"));
+
+/// All rules of the grammar, in declaration order
+pub fn rules() -> &'static [GrammarRule] {
+    RULES
+}
+
+/// Looks up a rule by name
+pub fn rule(name: &str) -> Option<&'static GrammarRule> {
+    RULES.iter().find(|rule| rule.name == name)
+}
+
+/// The rule table as a value, for printing it as text or JSON
+pub fn rules_value() -> Value {
+    let string = |s: &str| Value::String(s.into());
+
+    Value::List(
+        RULES
+            .iter()
+            .map(|rule| {
+                let mut fields = OrderedMap::new();
+                fields.insert("name".into(), string(rule.name));
+                fields.insert(
+                    "kind".into(),
+                    Value::UnitStruct(
+                        ("RuleKind", format!("{:?}", rule.kind).as_str())
+                            .into(),
+                    ),
+                );
+                fields.insert(
+                    "children".into(),
+                    Value::List(
+                        rule.children.iter().map(|c| string(c)).collect(),
+                    ),
+                );
+                fields.insert("expression".into(), string(rule.expression));
+
+                Value::Struct("GrammarRule".into(), fields)
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,177 @@
+//! Round trip fidelity of values written through different formats
+//!
+//! `check` writes a value through the text format, the streaming
+//! serializer and, with the `json` feature, JSON, reads it back and reports
+//! where the result differs. This shows ahead of time which types lose information in which format,
+//! for example maps with non string keys in JSON.
+
+#[cfg(feature = "json")]
+use crate::value::json;
+use crate::value::Value;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+/// A way of writing and reading a value
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Format {
+    /// `to_string_pretty` and `from_str`
+    Text,
+    /// `to_writer` and `from_reader`
+    Stream,
+    /// `json::to_json` and `json::from_json`
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl Format {
+    pub const ALL: &'static [Format] = &[
+        Format::Text,
+        Format::Stream,
+        #[cfg(feature = "json")]
+        Format::Json,
+    ];
+
+    fn round_trip<T>(self, value: &T) -> anyhow::Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        Ok(match self {
+            Format::Text => crate::from_str(&crate::to_string_pretty(value)?)?,
+            Format::Stream => {
+                let mut buffer = Vec::new();
+                crate::to_writer(&mut buffer, value)?;
+                crate::from_reader(buffer.as_slice())?
+            },
+            #[cfg(feature = "json")]
+            Format::Json => {
+                let text = json::to_json(&Value::try_new(value)?, false);
+                json::from_json(&text)?.deserialize()?
+            },
+        })
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Text => "text",
+            Format::Stream => "stream",
+            #[cfg(feature = "json")]
+            Format::Json => "json",
+        })
+    }
+}
+
+/// What is left of a value after a round trip
+#[derive(Clone, PartialEq, Debug)]
+pub enum Fidelity {
+    /// The value read back is the same
+    Exact,
+    /// The value could be read back, but is different
+    Changed { before: Value, after: Value },
+    /// The value could not be written or read back
+    Failed(String),
+}
+
+impl Fidelity {
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Fidelity::Exact)
+    }
+}
+
+impl Display for Fidelity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Fidelity::Exact => write!(f, "exact"),
+            Fidelity::Changed { before, after } => write!(
+                f,
+                "changed from {} to {}",
+                before.to_string_compact(),
+                after.to_string_compact()
+            ),
+            Fidelity::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// Fidelity of one value in every format
+#[derive(Clone, PartialEq, Debug)]
+pub struct CompatibilityReport {
+    pub type_name: &'static str,
+    pub formats: Vec<(Format, Fidelity)>,
+}
+
+impl CompatibilityReport {
+    pub fn fidelity(&self, format: Format) -> Option<&Fidelity> {
+        self.formats
+            .iter()
+            .find(|(f, _)| *f == format)
+            .map(|(_, fidelity)| fidelity)
+    }
+
+    /// True if the value survives all formats unchanged
+    pub fn is_lossless(&self) -> bool {
+        self.formats.iter().all(|(_, fidelity)| fidelity.is_exact())
+    }
+
+    /// The formats the value does not survive unchanged
+    pub fn mismatches(&self) -> impl Iterator<Item = &(Format, Fidelity)> {
+        self.formats
+            .iter()
+            .filter(|(_, fidelity)| !fidelity.is_exact())
+    }
+}
+
+impl Display for CompatibilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.type_name)?;
+        for (format, fidelity) in &self.formats {
+            writeln!(f, "    {}: {}", format, fidelity)?;
+        }
+        Ok(())
+    }
+}
+
+/// Round trips `value` through every format
+///
+/// Values are compared in their serialized form, so T does not need
+/// to implement PartialEq.
+pub fn check<T>(value: &T) -> CompatibilityReport
+where
+    T: Serialize + DeserializeOwned,
+{
+    let before = Value::try_new(value);
+
+    let formats = Format::ALL
+        .iter()
+        .map(|&format| {
+            let before = match &before {
+                Ok(before) => before,
+                Err(error) => {
+                    return (format, Fidelity::Failed(error.to_string()))
+                },
+            };
+
+            let fidelity = match format
+                .round_trip(value)
+                .and_then(|after| Ok(Value::try_new(&after)?))
+            {
+                Ok(after) if after == *before => Fidelity::Exact,
+                Ok(after) => Fidelity::Changed {
+                    before: before.clone(),
+                    after,
+                },
+                Err(error) => Fidelity::Failed(format!("{:#}", error)),
+            };
+            (format, fidelity)
+        })
+        .collect();
+
+    CompatibilityReport {
+        type_name: std::any::type_name::<T>(),
+        formats,
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    loader::LoadError,
+    value::Value,
+    vfs::{StdFs, Vfs},
+};
+use serde::de::DeserializeOwned;
+use std::{fmt, path::PathBuf, sync::OnceLock};
+
+enum Source {
+    /// Read from a file system on first access
+    File(&'static dyn Vfs, &'static str),
+    /// Embedded into the binary, usually with `include_str!`
+    Embedded(&'static str),
+}
+
+/// A value that is loaded, parsed and deserialized on first access
+///
+/// Intended for statics, loading happens at most once even if multiple
+/// threads access it at the same time:
+///
+/// ```ignore
+/// static CONFIG: TyfLazy<Config> = TyfLazy::file("config.tyf");
+///
+/// fn main() {
+///     match CONFIG.try_get() {
+///         Ok(config) => { /* ... */ },
+///         Err(error) => eprintln!("{}", error),
+///     }
+/// }
+/// ```
+///
+/// A failed load is remembered as well, every access reports the same error.
+pub struct TyfLazy<T> {
+    source: Source,
+    cell: OnceLock<Result<T, LoadError>>,
+}
+
+impl<T> TyfLazy<T> {
+    /// Loads the file at `path`, relative to the working directory
+    pub const fn file(path: &'static str) -> Self {
+        TyfLazy::file_in(&StdFs, path)
+    }
+
+    /// Loads the file at `path` from `vfs`
+    pub const fn file_in(vfs: &'static dyn Vfs, path: &'static str) -> Self {
+        TyfLazy {
+            source: Source::File(vfs, path),
+            cell: OnceLock::new(),
+        }
+    }
+
+    /// Parses `content`, usually embedded with `include_str!`
+    pub const fn embedded(content: &'static str) -> Self {
+        TyfLazy {
+            source: Source::Embedded(content),
+            cell: OnceLock::new(),
+        }
+    }
+}
+
+impl<T> TyfLazy<T>
+where
+    T: DeserializeOwned,
+{
+    fn load(&self) -> Result<T, LoadError> {
+        let (content, origin) = match self.source {
+            Source::File(vfs, path) => (
+                vfs.read_to_string(path.as_ref()).map_err(|error| {
+                    LoadError::Io {
+                        file: PathBuf::from(path),
+                        error,
+                    }
+                })?,
+                path,
+            ),
+            Source::Embedded(content) => (content.to_string(), "<embedded>"),
+        };
+
+        let value =
+            Value::parse(&content).map_err(|error| LoadError::Parse {
+                file: PathBuf::from(origin),
+                error: Box::new(error),
+            })?;
+
+        value.deserialize().map_err(|error| LoadError::Deserialize {
+            file: PathBuf::from(origin),
+            error,
+        })
+    }
+
+    /// Gets the value, loading it if this is the first access
+    pub fn try_get(&self) -> Result<&T, &LoadError> {
+        self.cell.get_or_init(|| self.load()).as_ref()
+    }
+
+    /// Gets the value, loading it if this is the first access
+    ///
+    /// # Panics
+    ///
+    /// If loading failed, with the error as message
+    pub fn get(&self) -> &T {
+        match self.try_get() {
+            Ok(value) => value,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Gets the load error, loading the value if this is the first access
+    pub fn error(&self) -> Option<&LoadError> {
+        self.try_get().err()
+    }
+}
+
+impl<T> fmt::Debug for TyfLazy<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("TyfLazy");
+        match &self.source {
+            Source::File(_, path) => debug.field("file", path),
+            Source::Embedded(_) => debug.field("file", &"<embedded>"),
+        };
+        debug.field("value", &self.cell.get()).finish()
+    }
+}
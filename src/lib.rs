@@ -1 +1,138 @@
+pub mod de;
+pub mod document;
+pub mod error;
+pub mod grammar;
+pub mod interop;
+pub mod lazy;
+pub mod literals;
+pub mod loader;
+pub mod log;
+#[cfg(feature = "json")]
+pub mod migrate;
+pub mod progress;
+pub mod raw;
+pub mod schema;
+pub mod ser;
+pub mod typed;
+pub mod validate;
 pub mod value;
+pub mod vfs;
+
+pub use crate::{
+    de::from_reader,
+    error::Error,
+    raw::RawFragment,
+    ser::{to_writer, to_writer_pretty},
+    typed::Typed,
+};
+
+use crate::value::{
+    deserializer::ValueDeserializer, options::ParseOptions, parser,
+    serializer::ValueSerializerError, Value,
+};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed},
+    Deserialize, Serialize,
+};
+
+/// Serializes `value` to the pretty, multi line text format
+pub fn to_string_pretty<T>(value: &T) -> Result<String, ValueSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Value::try_new(value)?.to_string_pretty())
+}
+
+/// Serializes `value` to the compact, single line text format
+pub fn to_string_compact<T>(value: &T) -> Result<String, ValueSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Value::try_new(value)?.to_string_compact())
+}
+
+/// Serializes `value` with one leaf per line, so changes diff cleanly
+pub fn to_string_diff<T>(value: &T) -> Result<String, ValueSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Value::try_new(value)?.to_string_diff())
+}
+
+/// Serializes `value` with short collections on a single line,
+/// breaking up those that don't fit into 80 columns
+pub fn to_string_fit<T>(value: &T) -> Result<String, ValueSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Value::try_new(value)?.to_string_fit())
+}
+
+/// Serializes `value` to the binary encoding, see `value::binary`
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>, ValueSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Value::try_new(value)?.to_bytes())
+}
+
+/// Writes `value` in the binary encoding, see `value::binary`
+pub fn to_writer_binary<W, T>(writer: W, value: &T) -> Result<(), Error>
+where
+    W: std::io::Write,
+    T: Serialize + ?Sized,
+{
+    Ok(value::binary::to_writer(writer, &Value::try_new(value)?)?)
+}
+
+/// Decodes the binary encoding and deserializes it as T
+pub fn from_bytes<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    Ok(T::deserialize(Value::from_bytes(bytes)?)?)
+}
+
+/// Parses the text format and deserializes it as T
+pub fn from_str<T>(s: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    Ok(T::deserialize(Value::parse(s)?)?)
+}
+
+/// Parses the text format and deserializes it as T, borrowing strings
+/// from `s`
+///
+/// Only strings written without escape sequences can be borrowed, so
+/// `&str` fails on the others. `Cow<str>` with `#[serde(borrow)]`
+/// borrows whenever possible and allocates otherwise.
+pub fn from_str_borrowed<'de, T>(s: &'de str) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    let (value, strings) =
+        parser::parse_main_value_borrowable(s, &ParseOptions::default())?;
+    Ok(T::deserialize(ValueDeserializer::with_source(
+        &value, &strings,
+    ))?)
+}
+
+/// Parses the text format and deserializes it with `seed`, borrowing
+/// strings from `s` like `from_str_borrowed`
+pub fn from_str_seed<'de, S>(s: &'de str, seed: S) -> Result<S::Value, Error>
+where
+    S: DeserializeSeed<'de>,
+{
+    let (value, strings) =
+        parser::parse_main_value_borrowable(s, &ParseOptions::default())?;
+    Ok(seed.deserialize(ValueDeserializer::with_source(&value, &strings))?)
+}
+
+/// Parses the text format into a T that keeps the parsed Value, see `Typed`
+pub fn from_str_typed<T>(s: &str) -> Result<Typed<T>, Error>
+where
+    T: DeserializeOwned + Serialize,
+{
+    Typed::from_value(Value::parse(s)?)
+}
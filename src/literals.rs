@@ -0,0 +1,420 @@
+//! Custom literal syntaxes like `re"^[a-z]+$"` or `ip"10.0.0.1"`
+//!
+//! A `LiteralSyntax` turns the string after its prefix into a value and
+//! recognizes such values again when printing. `Literals` maps prefixes
+//! to syntaxes, it is passed to the parser with `ParseOptions::literals`
+//! and to the printer with `ValuePrinter::with_literals`.
+//!
+//! The string part is escaped like any other string, raw strings can not
+//! be prefixed. `b` and `r` are taken by byte and raw strings.
+//!
+//! The `datetime` and `uuid` features add syntaxes for the strings that
+//! chrono, time and uuid serialize their types as, see `Literals::common`.
+//! They are known to the parser and printers by default. Fields serialized
+//! with the `datetime` or `uuid` module are written with them:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Event {
+//!     #[serde(with = "typed_format::literals::datetime")]
+//!     at: chrono::DateTime<chrono::Utc>,
+//! }
+//! ```
+
+use crate::{
+    validate::FormatValidator,
+    value::{
+        types::{Identifier, TypeIdentifier},
+        Value,
+    },
+};
+use anyhow::anyhow;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fmt::{Debug, Display, Formatter},
+    sync::{Arc, OnceLock},
+};
+
+/// Prefixes the text format already uses for its own literals
+const RESERVED_PREFIXES: &[&str] = &["b", "r", "br"];
+
+/// Newtype struct `datetime"..."` literals are parsed into
+pub const DATETIME: &str = "DateTime";
+/// Newtype struct `uuid"..."` literals are parsed into
+pub const UUID: &str = "Uuid";
+
+/// Parsing and printing of a prefixed string literal
+pub trait LiteralSyntax: Send + Sync {
+    /// Converts the unescaped string of `prefix"..."` into a value,
+    /// describes the problem if it is not valid
+    fn parse(&self, contents: &str) -> Result<Value, String>;
+
+    /// The string to print for `value`, if it is written with this syntax
+    fn print(&self, value: &Value) -> Option<String>;
+}
+
+/// Custom literal syntaxes by prefix
+#[derive(Clone, Default)]
+pub struct Literals {
+    syntaxes: BTreeMap<String, Arc<dyn LiteralSyntax>>,
+}
+
+impl Debug for Literals {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.syntaxes.keys()).finish()
+    }
+}
+
+impl Literals {
+    pub fn new() -> Self {
+        Literals::default()
+    }
+
+    /// Adds or replaces the syntax of a prefix
+    ///
+    /// Fails if the prefix is not an identifier or is reserved.
+    pub fn with<P, L>(
+        mut self,
+        prefix: P,
+        syntax: L,
+    ) -> Result<Self, PrefixError>
+    where
+        P: Into<String>,
+        L: LiteralSyntax + 'static,
+    {
+        let prefix = prefix.into();
+        if !Identifier(prefix.clone()).is_valid() {
+            return Err(PrefixError::Invalid(prefix));
+        }
+        if RESERVED_PREFIXES.contains(&prefix.as_str()) {
+            return Err(PrefixError::Reserved(prefix));
+        }
+
+        self.syntaxes.insert(prefix, Arc::new(syntax));
+        Ok(self)
+    }
+
+    /// The syntaxes of the enabled features
+    ///
+    /// - `datetime"2023-01-01T00:00:00Z"` for RFC 3339 date times, with or
+    ///   without offset, as written by chrono and by time's `rfc3339`
+    ///   serde format
+    /// - `uuid"67e55044-10b1-426f-9247-bb680e5fe0c8"` for hyphenated UUIDs
+    ///
+    /// Both parse into a newtype struct around the string, `DateTime` and
+    /// `Uuid`, which deserializes like the string as well. Only such
+    /// structs are printed as literals, plain strings stay strings.
+    ///
+    /// These are the literals of `ParseOptions::default()` and of printers
+    /// not given any others.
+    pub fn common() -> Self {
+        #[allow(unused_mut)]
+        let mut literals = Literals::new();
+        #[cfg(feature = "datetime")]
+        {
+            let syntax = NewtypeLiteral::new(DATETIME).with_check(datetime);
+            literals = literals.with("datetime", syntax).unwrap();
+        }
+        #[cfg(feature = "uuid")]
+        {
+            let syntax = NewtypeLiteral::new(UUID).with_check(uuid);
+            literals = literals.with("uuid", syntax).unwrap();
+        }
+        literals
+    }
+
+    /// `Literals::common`, built once
+    pub(crate) fn common_ref() -> &'static Literals {
+        static COMMON: OnceLock<Literals> = OnceLock::new();
+        COMMON.get_or_init(Literals::common)
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<&dyn LiteralSyntax> {
+        self.syntaxes.get(prefix).map(|syntax| syntax.as_ref())
+    }
+
+    /// Parses the contents of `prefix"contents"`
+    pub(crate) fn parse(
+        &self,
+        prefix: &str,
+        contents: &str,
+    ) -> anyhow::Result<Value> {
+        match self.get(prefix) {
+            Some(syntax) => syntax.parse(contents).map_err(|error| {
+                anyhow!("Invalid {}\"...\" literal: {}", prefix, error)
+            }),
+            None => Err(anyhow!("Unknown literal prefix {:?}", prefix)),
+        }
+    }
+
+    /// The prefix and string to print `value` with, if any syntax claims it
+    pub(crate) fn print(&self, value: &Value) -> Option<(&str, String)> {
+        self.syntaxes.iter().find_map(|(prefix, syntax)| {
+            syntax
+                .print(value)
+                .map(|contents| (prefix.as_str(), contents))
+        })
+    }
+}
+
+/// Error of `Literals::with` for a prefix that can not be used
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PrefixError {
+    /// The prefix is not an identifier
+    Invalid(String),
+    /// The prefix is taken by a literal of the text format, like `b"..."`
+    Reserved(String),
+}
+
+impl Display for PrefixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::Invalid(prefix) => {
+                write!(f, "Invalid literal prefix {:?}", prefix)
+            },
+            PrefixError::Reserved(prefix) => {
+                write!(f, "The literal prefix {:?} is reserved", prefix)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PrefixError {}
+
+/// Wraps the string in a newtype struct, like `Regex("^[a-z]+$")`
+///
+/// Matches how serde serializes a newtype struct around a `String`,
+/// so the literal deserializes into such a type.
+pub struct NewtypeLiteral {
+    name: TypeIdentifier,
+    check: Option<Box<dyn FormatValidator>>,
+}
+
+impl NewtypeLiteral {
+    pub fn new<T>(name: T) -> Self
+    where
+        T: Into<TypeIdentifier>,
+    {
+        NewtypeLiteral {
+            name: name.into(),
+            check: None,
+        }
+    }
+
+    /// Rejects literals whose string is not valid, see `validate`
+    pub fn with_check<V>(mut self, check: V) -> Self
+    where
+        V: FormatValidator + 'static,
+    {
+        self.check = Some(Box::new(check));
+        self
+    }
+}
+
+impl LiteralSyntax for NewtypeLiteral {
+    fn parse(&self, contents: &str) -> Result<Value, String> {
+        if let Some(check) = &self.check {
+            check.validate(contents)?;
+        }
+
+        Ok(Value::TupleStruct(
+            self.name.clone(),
+            vec![Value::String(contents.into())],
+        ))
+    }
+
+    fn print(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::TupleStruct(name, items) if *name == self.name => {
+                match items.as_slice() {
+                    // A literal that would not parse again is not written
+                    [Value::String(contents)]
+                        if self.check.as_ref().is_none_or(|check| {
+                            check.validate(contents).is_ok()
+                        }) =>
+                    {
+                        Some(contents.to_string())
+                    },
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The string inside a `DateTime` or `Uuid` newtype struct, which
+/// deserializes wherever a string is expected
+pub(crate) fn tagged_string(value: &Value) -> Option<&Value> {
+    match value {
+        Value::TupleStruct(name, items) => {
+            match (name.segments.as_slice(), items.as_slice()) {
+                ([segment], [string @ Value::String(_)])
+                    if segment.generics.is_none()
+                        && matches!(
+                            segment.identifier.0.as_str(),
+                            DATETIME | UUID
+                        ) =>
+                {
+                    Some(string)
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Serializes a field as a `datetime"..."` literal, with
+/// `#[serde(with = "typed_format::literals::datetime")]`
+///
+/// The field has to serialize as a string, as the date times of chrono and
+/// time do. Other formats than this one just see the string.
+#[cfg(feature = "datetime")]
+pub mod datetime {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(super::DATETIME, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serializes a field as a `uuid"..."` literal, with
+/// `#[serde(with = "typed_format::literals::uuid")]`, see `datetime`
+#[cfg(feature = "uuid")]
+pub mod uuid {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(super::UUID, value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Keeps the string as it is, but only accepts strings passing a check
+///
+/// Printing writes every string that passes the check with this syntax.
+pub struct StringLiteral {
+    check: Box<dyn FormatValidator>,
+}
+
+impl StringLiteral {
+    pub fn new<V>(check: V) -> Self
+    where
+        V: FormatValidator + 'static,
+    {
+        StringLiteral {
+            check: Box::new(check),
+        }
+    }
+}
+
+impl LiteralSyntax for StringLiteral {
+    fn parse(&self, contents: &str) -> Result<Value, String> {
+        self.check.validate(contents)?;
+        Ok(Value::String(contents.into()))
+    }
+
+    fn print(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) if self.check.validate(s).is_ok() => {
+                Some(s.to_string())
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Takes `count` ASCII digits from the start of `s`
+#[cfg(feature = "datetime")]
+fn digits(s: &str, count: usize) -> Option<(u32, &str)> {
+    let (number, rest) = (s.get(..count)?, &s[count..]);
+    if !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((number.parse().ok()?, rest))
+}
+
+/// `YYYY-MM-DDTHH:MM:SS`, with optional fraction and offset
+#[cfg(feature = "datetime")]
+fn datetime(value: &str) -> Result<(), String> {
+    fn parse(s: &str) -> Option<()> {
+        let (_, s) = digits(s, 4)?;
+        let (month, s) = digits(s.strip_prefix('-')?, 2)?;
+        let (day, s) = digits(s.strip_prefix('-')?, 2)?;
+        let s = s.strip_prefix(&['T', 't'][..])?;
+        let (hour, s) = digits(s, 2)?;
+        let (minute, s) = digits(s.strip_prefix(':')?, 2)?;
+        let (second, mut s) = digits(s.strip_prefix(':')?, 2)?;
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        if let Some(fraction) = s.strip_prefix('.') {
+            let length = fraction
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(fraction.len());
+            if length == 0 {
+                return None;
+            }
+            s = &fraction[length..];
+        }
+        match s {
+            "" | "Z" | "z" => Some(()),
+            _ => {
+                let s = s.strip_prefix(&['+', '-'][..])?;
+                let (hours, s) = digits(s, 2)?;
+                let (minutes, s) = digits(s.strip_prefix(':')?, 2)?;
+                if s.is_empty() && hours <= 23 && minutes <= 59 {
+                    Some(())
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    parse(value).ok_or_else(|| format!("{:?} is not a date time", value))
+}
+
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` with hexadecimal digits
+#[cfg(feature = "uuid")]
+fn uuid(value: &str) -> Result<(), String> {
+    let groups = value.split('-').map(str::len).collect::<Vec<_>>();
+    if groups == [8, 4, 4, 4, 12]
+        && value.chars().all(|c| c == '-' || c.is_ascii_hexdigit())
+    {
+        Ok(())
+    } else {
+        Err(format!("{:?} is not a UUID", value))
+    }
+}
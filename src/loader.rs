@@ -0,0 +1,486 @@
+//! Loading documents that are split across files
+//!
+//! A `Loader` reads a document through a `Vfs` and replaces every
+//! `include("relative/path.tyf")` in it by the document at that path,
+//! relative to the file containing the include:
+//!
+//! ```text
+//! Config(
+//!     server: include("server.tyf"),
+//!     plugins: [include("plugins/auth.tyf"), include("plugins/cache.tyf")],
+//! )
+//! ```
+//!
+//! Included files may include further files, cycles are reported as
+//! errors.
+//!
+//! Loaders can also expand variables in strings, see
+//! `Loader::expand_env`.
+
+use crate::{
+    value::{
+        deserializer::ValueDeserializerError,
+        diagnostic::ParseError,
+        path::{Path as ValuePath, PathSegment},
+        Value,
+    },
+    vfs::{StdFs, Vfs},
+};
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error,
+    fmt,
+    fmt::{Display, Formatter},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Name of the tuple struct that is replaced by the included document
+pub const INCLUDE: &str = "include";
+
+/// Looks up the value of a variable by name
+type Variables<'lt> = Box<dyn Fn(&str) -> Option<String> + 'lt>;
+
+/// Error of loading a document, each variant names the file it is about
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read
+    Io { file: PathBuf, error: io::Error },
+    /// The file is not a valid document
+    Parse {
+        file: PathBuf,
+        error: Box<ParseError>,
+    },
+    /// The document does not fit the type it is loaded as
+    Deserialize {
+        file: PathBuf,
+        error: ValueDeserializerError,
+    },
+    /// The file is not below the directory of `Loader::sandbox`
+    OutsideSandbox { file: PathBuf, root: PathBuf },
+    /// The files that include each other, the first one is repeated at
+    /// the end
+    Cycle(Vec<PathBuf>),
+    /// A string of the file could not be expanded, see
+    /// `Loader::expand_env`, `path` leads to the string
+    Variable {
+        file: PathBuf,
+        path: ValuePath,
+        message: String,
+    },
+    /// An include has another argument than a single string
+    InvalidInclude,
+    /// Loading a file included by `file` failed
+    Included {
+        file: PathBuf,
+        error: Box<LoadError>,
+    },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { file, error } => {
+                write!(f, "Could not read {}: {}", file.display(), error)
+            },
+            LoadError::Parse { file, error } => {
+                write!(f, "Could not parse {}: {}", file.display(), error)
+            },
+            LoadError::Deserialize { file, error } => {
+                write!(f, "Could not deserialize {}: {}", file.display(), error)
+            },
+            LoadError::OutsideSandbox { file, root } => {
+                write!(f, "{} is outside of {}", file.display(), root.display())
+            },
+            LoadError::Cycle(files) => {
+                f.write_str("Include cycle: ")?;
+                for (index, file) in files.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" -> ")?;
+                    }
+                    write!(f, "{}", file.display())?;
+                }
+                Ok(())
+            },
+            LoadError::Variable {
+                file,
+                path,
+                message,
+            } => write!(
+                f,
+                "Could not expand {}: At {}: {}",
+                file.display(),
+                path,
+                message
+            ),
+            LoadError::InvalidInclude => {
+                f.write_str("Expected include(\"path\")")
+            },
+            LoadError::Included { file, error } => {
+                write!(f, "Included from {}: {}", file.display(), error)
+            },
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            LoadError::Parse { error, .. } => Some(error.as_ref()),
+            LoadError::Deserialize { error, .. } => Some(error),
+            LoadError::Included { error, .. } => Some(error.as_ref()),
+            LoadError::OutsideSandbox { .. }
+            | LoadError::Cycle(_)
+            | LoadError::Variable { .. }
+            | LoadError::InvalidInclude => None,
+        }
+    }
+}
+
+/// Reads documents and resolves their includes
+pub struct Loader<'lt> {
+    vfs: &'lt dyn Vfs,
+    /// Directory no file may be read from outside of
+    sandbox: Option<PathBuf>,
+    /// Present if variables in strings are expanded
+    variables: Option<Variables<'lt>>,
+}
+
+impl Default for Loader<'static> {
+    fn default() -> Self {
+        Loader::new()
+    }
+}
+
+impl fmt::Debug for Loader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Loader")
+            .field("sandbox", &self.sandbox)
+            .field("expands_variables", &self.variables.is_some())
+            .finish()
+    }
+}
+
+impl Loader<'static> {
+    /// Loads from the file system of the operating system
+    pub fn new() -> Self {
+        Loader::with_vfs(&StdFs)
+    }
+}
+
+impl<'lt> Loader<'lt> {
+    /// Loads from `vfs`, which resolves all paths
+    pub fn with_vfs(vfs: &'lt dyn Vfs) -> Self {
+        Loader {
+            vfs,
+            sandbox: None,
+            variables: None,
+        }
+    }
+
+    /// Only allows reading files below `root`
+    ///
+    /// Paths are compared after resolving `.` and `..`, and again after
+    /// resolving symbolic links with `Vfs::canonicalize`, so a link inside
+    /// of `root` can't lead out of it.
+    pub fn sandbox<P>(mut self, root: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.sandbox = Some(normalize(root.as_ref()));
+        self
+    }
+
+    /// Expands `${NAME}` in strings to environment variables
+    ///
+    /// `${NAME:default}` expands to `default` if the variable is not set,
+    /// otherwise a missing variable is an error. `$$` is a single `$`, so
+    /// `$${NAME}` stays `${NAME}`. Strings are expanded before the
+    /// includes of their file are resolved, so include paths can contain
+    /// variables as well.
+    pub fn expand_env(self) -> Self {
+        self.expand_variables(|name| std::env::var(name).ok())
+    }
+
+    /// Like `expand_env`, but looks up variables with `lookup`
+    pub fn expand_variables<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + 'lt,
+    {
+        self.variables = Some(Box::new(lookup));
+        self
+    }
+
+    /// Reads the document at `path` with all includes resolved
+    pub fn load_value<P>(&self, path: P) -> Result<Value, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        self.load_file(&normalize(path.as_ref()), &mut Vec::new())
+    }
+
+    /// Reads the document at `path` and deserializes it as T
+    pub fn load<T, P>(&self, path: P) -> Result<T, LoadError>
+    where
+        T: DeserializeOwned,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        self.load_value(path)?.deserialize().map_err(|error| {
+            LoadError::Deserialize {
+                file: path.to_path_buf(),
+                error,
+            }
+        })
+    }
+
+    /// Resolves the includes of an already parsed document
+    ///
+    /// Includes are relative to `directory`.
+    pub fn resolve<P>(
+        &self,
+        value: &mut Value,
+        directory: P,
+    ) -> Result<(), LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        self.resolve_includes(
+            value,
+            &normalize(directory.as_ref()),
+            &mut Vec::new(),
+        )
+    }
+
+    /// `stack` holds the files currently being loaded, outermost first
+    fn load_file(
+        &self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<Value, LoadError> {
+        let io = |file: &Path| {
+            let file = file.to_path_buf();
+            move |error| LoadError::Io { file, error }
+        };
+
+        if let Some(root) = &self.sandbox {
+            let outside = || LoadError::OutsideSandbox {
+                file: path.to_path_buf(),
+                root: root.clone(),
+            };
+            if !path.starts_with(root) {
+                return Err(outside());
+            }
+
+            let resolved = self.vfs.canonicalize(path).map_err(io(path))?;
+            let canonical_root =
+                self.vfs.canonicalize(root).map_err(io(root))?;
+            if !resolved.starts_with(canonical_root) {
+                return Err(outside());
+            }
+        }
+        if stack.iter().any(|loading| loading == path) {
+            let mut cycle = stack.clone();
+            cycle.push(path.to_path_buf());
+            return Err(LoadError::Cycle(cycle));
+        }
+
+        let content = self.vfs.read_to_string(path).map_err(io(path))?;
+        let mut value =
+            Value::parse(&content).map_err(|error| LoadError::Parse {
+                file: path.to_path_buf(),
+                error: Box::new(error),
+            })?;
+        if let Some(variables) = &self.variables {
+            let mut location = ValuePath::root();
+            expand_strings(&mut value, &mut location, variables).map_err(
+                |message| LoadError::Variable {
+                    file: path.to_path_buf(),
+                    path: location,
+                    message,
+                },
+            )?;
+        }
+
+        stack.push(path.to_path_buf());
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let result = self.resolve_includes(&mut value, directory, stack);
+        stack.pop();
+
+        result.map_err(|error| LoadError::Included {
+            file: path.to_path_buf(),
+            error: Box::new(error),
+        })?;
+        Ok(value)
+    }
+
+    fn resolve_includes(
+        &self,
+        value: &mut Value,
+        directory: &Path,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), LoadError> {
+        if let Some(include) = include_path(value)? {
+            let path = normalize(&directory.join(include));
+            *value = self.load_file(&path, stack)?;
+            return Ok(());
+        }
+
+        match value {
+            Value::List(items)
+            | Value::Tuple(items)
+            | Value::TupleStruct(_, items) => {
+                for item in items {
+                    self.resolve_includes(item, directory, stack)?;
+                }
+            },
+            Value::Map(map) => {
+                for item in map.values_mut() {
+                    self.resolve_includes(item, directory, stack)?;
+                }
+            },
+            Value::Struct(_, fields) => {
+                for item in fields.values_mut() {
+                    self.resolve_includes(item, directory, stack)?;
+                }
+            },
+            Value::Option(Some(inner)) => {
+                self.resolve_includes(inner, directory, stack)?
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+/// Expands the variables in all strings below `value`
+///
+/// On failure `path` is left at the string that could not be expanded.
+fn expand_strings(
+    value: &mut Value,
+    path: &mut ValuePath,
+    variables: &Variables,
+) -> Result<(), String> {
+    let mut child = |segment, value: &mut Value| -> Result<(), String> {
+        path.push(segment);
+        expand_strings(value, path, variables)?;
+        path.pop();
+        Ok(())
+    };
+
+    match value {
+        Value::String(string) => {
+            if let Some(expanded) = expand(string, variables)? {
+                *string = expanded.into();
+            }
+        },
+        Value::List(items)
+        | Value::Tuple(items)
+        | Value::TupleStruct(_, items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                child(PathSegment::Index(index), item)?;
+            }
+        },
+        Value::Map(map) => {
+            for (key, item) in map.iter_mut() {
+                child(PathSegment::Key(key.clone()), item)?;
+            }
+        },
+        Value::Struct(_, fields) => {
+            for (field, item) in fields.iter_mut() {
+                child(PathSegment::Field(field.clone()), item)?;
+            }
+        },
+        Value::Option(Some(inner)) => expand_strings(inner, path, variables)?,
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Expands `${NAME}`, `${NAME:default}` and `$$`, None if `input` has
+/// nothing to expand
+fn expand(
+    input: &str,
+    variables: &Variables,
+) -> Result<Option<String>, String> {
+    if !input.contains('$') {
+        return Ok(None);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| {
+                format!("Unterminated variable in {:?}", input)
+            })?;
+            let (name, default) = match after[..end].split_once(':') {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            if name.is_empty() {
+                return Err(format!("Variable without a name in {:?}", input));
+            }
+
+            match (variables(name), default) {
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => {
+                    return Err(format!("Variable {} is not set", name))
+                },
+            }
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+
+    Ok(Some(output))
+}
+
+/// The path of an `include("path")`, None for all other values
+fn include_path(value: &Value) -> Result<Option<&str>, LoadError> {
+    let (identifier, items) = match value {
+        Value::TupleStruct(identifier, items) => (identifier, items),
+        _ => return Ok(None),
+    };
+    match identifier.segments.as_slice() {
+        [segment]
+            if segment.identifier.0 == INCLUDE
+                && segment.generics.is_none() => {},
+        _ => return Ok(None),
+    }
+
+    match items.as_slice() {
+        [Value::String(path)] => Ok(Some(path)),
+        _ => Err(LoadError::InvalidInclude),
+    }
+}
+
+/// Resolves `.` and `..` without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                },
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {},
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
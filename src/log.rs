@@ -0,0 +1,132 @@
+//! Append only files of records, one compact value per line
+//!
+//! `ValueLogWriter` appends records, `ValueLogReader` reads them back.
+//! Since every record is a line of its own, the files can be followed
+//! while they are written and inspected with line based tools.
+
+use crate::{
+    error::Error,
+    ser::{to_string_into, TextSerializerError},
+    value::Value,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io,
+    io::{BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+};
+
+/// Appends values as lines of the compact text format
+///
+/// Each record is serialized completely before it is written and flushed,
+/// so a failing `Serialize` implementation does not leave half a record
+/// behind and readers see every record as soon as it was written.
+pub struct ValueLogWriter<W> {
+    writer: W,
+    /// Reused for the text of each record
+    buffer: String,
+}
+
+impl<W> ValueLogWriter<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        ValueLogWriter {
+            writer,
+            buffer: String::new(),
+        }
+    }
+
+    /// Writes `value` as a single line and flushes the writer
+    pub fn write<T>(&mut self, value: &T) -> Result<(), TextSerializerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.buffer.clear();
+        to_string_into(&mut self.buffer, value)?;
+        self.buffer.push('\n');
+
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the records of a `ValueLogWriter` as `T`
+///
+/// Every line is read as a record of its own, so a torn or invalid line is
+/// reported as an error with its line number and the records after it are
+/// still read. Empty lines, `---` and lines of `//` comments are skipped.
+pub struct ValueLogReader<R, T> {
+    reader: BufReader<R>,
+    /// Reused for the bytes of each line
+    buffer: Vec<u8>,
+    /// Number of the line in `buffer`
+    line: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<R, T> ValueLogReader<R, T>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        ValueLogReader {
+            reader: BufReader::new(reader),
+            buffer: Vec::new(),
+            line: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R, T> Iterator for ValueLogReader<R, T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(error) => return Some(Err(error.into())),
+            }
+
+            let line = self.line;
+            let record_error = |error: Error| Error::Record {
+                line,
+                error: Box::new(error),
+            };
+            let record = match std::str::from_utf8(&self.buffer) {
+                Ok(record) => record.trim(),
+                Err(error) => {
+                    let error =
+                        io::Error::new(io::ErrorKind::InvalidData, error);
+                    return Some(Err(record_error(error.into())));
+                },
+            };
+            if record.is_empty() || record == "---" || record.starts_with("//")
+            {
+                continue;
+            }
+
+            let value = Value::parse(record)
+                .map_err(Error::from)
+                .and_then(|value| Ok(value.deserialize()?));
+            return Some(value.map_err(record_error));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    error::Error,
+    value::{json, Value},
+    vfs::{StdFs, Vfs},
+};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// File formats that can be converted between
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FileFormat {
+    Json,
+    Tyf,
+}
+
+impl FileFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Json => "json",
+            FileFormat::Tyf => "tyf",
+        }
+    }
+
+    pub fn read(self, input: &str) -> Result<Value, Error> {
+        match self {
+            FileFormat::Json => json::from_json(input),
+            FileFormat::Tyf => Ok(Value::parse(input)?),
+        }
+    }
+
+    pub fn write(self, value: &Value) -> String {
+        match self {
+            FileFormat::Json => json::to_json(value, true),
+            FileFormat::Tyf => value.to_string_pretty(),
+        }
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, UnknownFormat> {
+        match s {
+            "json" => Ok(FileFormat::Json),
+            "tyf" => Ok(FileFormat::Tyf),
+            other => Err(UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+/// Error of parsing a `FileFormat` from a name other than `json` or `tyf`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UnknownFormat(pub String);
+
+impl Display for UnknownFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown format {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownFormat {}
+
+/// Outcome of converting a directory tree
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Pairs of source and written file
+    pub converted: Vec<(PathBuf, PathBuf)>,
+    pub failed: Vec<(PathBuf, Error)>,
+    /// Number of files that did not have the source extension
+    pub skipped: usize,
+}
+
+impl MigrationReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (path, error) in &self.failed {
+            writeln!(f, "failed: {}: {}", path.display(), error)?;
+        }
+
+        write!(
+            f,
+            "{} converted, {} failed, {} skipped",
+            self.converted.len(),
+            self.failed.len(),
+            self.skipped
+        )
+    }
+}
+
+/// Converts every file with the extension of `from` below `input`
+///
+/// The converted files are written below `output` with the same relative
+/// path and the extension of `to`. `output` may be the same as `input`.
+/// Failures of single files are collected in the report,
+/// only errors while walking the directories abort the migration.
+pub fn migrate_tree(
+    input: &Path,
+    output: &Path,
+    from: FileFormat,
+    to: FileFormat,
+) -> Result<MigrationReport, Error> {
+    migrate_tree_in(&StdFs, input, output, from, to)
+}
+
+/// Like `migrate_tree`, reading and writing through `vfs`
+pub fn migrate_tree_in(
+    vfs: &dyn Vfs,
+    input: &Path,
+    output: &Path,
+    from: FileFormat,
+    to: FileFormat,
+) -> Result<MigrationReport, Error> {
+    let mut report = MigrationReport::default();
+
+    migrate_directory(vfs, input, output, from, to, &mut report)?;
+
+    Ok(report)
+}
+
+fn migrate_directory(
+    vfs: &dyn Vfs,
+    input: &Path,
+    output: &Path,
+    from: FileFormat,
+    to: FileFormat,
+    report: &mut MigrationReport,
+) -> Result<(), Error> {
+    let entries = vfs.read_dir(input).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Could not read {}: {}", input.display(), e),
+        )
+    })?;
+
+    for entry in entries {
+        let path = entry.path;
+        let target = output.join(path.file_name().unwrap());
+
+        if entry.is_dir {
+            migrate_directory(vfs, &path, &target, from, to, report)?;
+        } else if path.extension().is_some_and(|e| e == from.extension()) {
+            let target = target.with_extension(to.extension());
+
+            match migrate_file(vfs, &path, &target, from, to) {
+                Ok(()) => report.converted.push((path, target)),
+                Err(error) => report.failed.push((path, error)),
+            }
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn migrate_file(
+    vfs: &dyn Vfs,
+    input: &Path,
+    output: &Path,
+    from: FileFormat,
+    to: FileFormat,
+) -> Result<(), Error> {
+    let value = from.read(&vfs.read_to_string(input)?)?;
+
+    if let Some(parent) = output.parent() {
+        vfs.create_dir_all(parent)?;
+    }
+    vfs.write(output, &to.write(&value))?;
+
+    Ok(())
+}
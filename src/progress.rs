@@ -0,0 +1,134 @@
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// How far loading a document got
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct Progress {
+    /// Bytes of input consumed so far
+    pub bytes: usize,
+    /// Values built so far, counting every nested value
+    pub nodes: usize,
+}
+
+/// Shared flag to stop loading a document from another thread
+///
+/// A token can also be built from a condition, such as a deadline,
+/// which is evaluated whenever the token is checked.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    condition: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// A token that is cancelled once `condition` returns true
+    pub fn from_fn<F>(condition: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        CancellationToken {
+            flag: Arc::default(),
+            condition: Some(Arc::new(condition)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+            || self.condition.as_ref().is_some_and(|condition| condition())
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Error for a load that was stopped through its CancellationToken
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Loading was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Receives progress while a document is loaded
+///
+/// Progress is reported after every element of the outermost collection
+/// and once at the end. Cancellation is checked at the same points.
+#[derive(Default)]
+pub struct ProgressReporter<'lt> {
+    callback: Option<Box<dyn FnMut(Progress) + 'lt>>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl<'lt> ProgressReporter<'lt> {
+    pub fn new() -> Self {
+        ProgressReporter::default()
+    }
+
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(Progress) + 'lt,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Reports progress, then checks for cancellation
+    pub(crate) fn report(
+        &mut self,
+        progress: Progress,
+    ) -> Result<(), Cancelled> {
+        if let Some(callback) = &mut self.callback {
+            callback(progress);
+        }
+
+        match &self.cancellation {
+            Some(token) => token.check(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'lt> fmt::Debug for ProgressReporter<'lt> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("callback", &self.callback.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
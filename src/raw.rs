@@ -0,0 +1,100 @@
+use crate::{
+    error::Error,
+    value::{diagnostic::ParseError, Value},
+};
+use serde::{
+    de::{DeserializeOwned, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+/// Name of the newtype struct the serializers and deserializers of this
+/// crate treat as a raw fragment
+pub(crate) const TOKEN: &str = "$typed_format::RawFragment";
+
+/// Text of a single value whose interpretation is deferred
+///
+/// When deserialized, it captures the text of the value at its position
+/// instead of interpreting it. When serialized, the text is written as is.
+/// This lets a document contain sections owned by someone else,
+/// such as plugin configuration, without encoding them as strings.
+///
+/// Deserializing from a Value or through `from_reader` captures
+/// an equivalent compact text, since comments and layout are not kept.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RawFragment(String);
+
+impl RawFragment {
+    /// Wraps text after checking that it is a single valid value
+    pub fn from_string(text: String) -> Result<Self, ParseError> {
+        Value::parse(&text)?;
+        Ok(RawFragment(text))
+    }
+
+    /// Captures the text of a value
+    pub fn from_value(value: &Value) -> Self {
+        RawFragment(value.to_string_compact())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    pub fn parse(&self) -> Result<Value, ParseError> {
+        Value::parse(&self.0)
+    }
+
+    pub fn deserialize<T>(&self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        crate::from_str(&self.0)
+    }
+}
+
+impl Display for RawFragment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for RawFragment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for RawFragment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RawFragmentVisitor;
+
+        impl<'de> Visitor<'de> for RawFragmentVisitor {
+            type Value = RawFragment;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                write!(f, "the text of a value")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(RawFragment(v.to_string()))
+            }
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+                Ok(RawFragment(v))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TOKEN, RawFragmentVisitor)
+    }
+}
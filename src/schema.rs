@@ -0,0 +1,331 @@
+use crate::value::{
+    map::Map,
+    types::{Identifier, Type, TypeIdentifier},
+    Value,
+};
+use std::ops::Range;
+
+mod describe;
+mod validate;
+
+pub use describe::{Fields, Schema};
+
+/// Source of randomness for [`generate`]
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    /// A number in `range`, or its start if the range is empty
+    fn gen_range(&mut self, range: Range<u64>) -> u64 {
+        if range.start >= range.end {
+            range.start
+        } else {
+            range.start + self.next_u64() % (range.end - range.start)
+        }
+    }
+
+    fn gen_bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
+
+/// Small deterministic generator (xorshift64*), good enough for test data
+#[derive(Clone, Debug)]
+pub struct XorShiftRng(u64);
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> Self {
+        // A state of 0 would only ever produce 0
+        XorShiftRng(seed.max(1))
+    }
+}
+
+impl Rng for XorShiftRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Controls the size of generated documents
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    /// Lengths of lists and maps are picked uniformly from this range
+    pub collection_length: Range<usize>,
+    /// Lengths of strings are picked uniformly from this range
+    pub string_length: Range<usize>,
+    /// Below this depth collections are empty and options are None
+    pub max_depth: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            collection_length: 0..5,
+            string_length: 0..16,
+            max_depth: 4,
+        }
+    }
+}
+
+/// Generates a random value matching the given Rust type
+///
+/// Primitives, strings, `Option`, `Box`, the std collections, arrays and
+/// tuples are understood. Other named types produce a unit struct,
+/// since their fields are not known; use [`Schema::generate`] for them.
+pub fn generate<R>(t: &Type, rng: &mut R, options: &GenerateOptions) -> Value
+where
+    R: Rng,
+{
+    Generator { rng, options }.generate(t, 0)
+}
+
+impl Schema {
+    /// Generates a random value with the described shape
+    ///
+    /// Structs get all their fields and enums one of their variants,
+    /// picked uniformly. `Any` produces `()`.
+    pub fn generate<R>(&self, rng: &mut R, options: &GenerateOptions) -> Value
+    where
+        R: Rng,
+    {
+        Generator { rng, options }.generate_schema(self, 0)
+    }
+}
+
+struct Generator<'lt, R> {
+    rng: &'lt mut R,
+    options: &'lt GenerateOptions,
+}
+
+impl<'lt, R> Generator<'lt, R>
+where
+    R: Rng,
+{
+    fn length(&mut self, range: &Range<usize>) -> usize {
+        self.rng.gen_range(range.start as u64..range.end as u64) as usize
+    }
+
+    fn collection_length(&mut self, depth: usize) -> usize {
+        if depth >= self.options.max_depth {
+            0
+        } else {
+            self.length(&self.options.collection_length)
+        }
+    }
+
+    fn string(&mut self) -> String {
+        const CHARACTERS: &[u8] =
+            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _";
+
+        let length = self.length(&self.options.string_length);
+
+        (0..length)
+            .map(|_| {
+                let index = self.rng.gen_range(0..CHARACTERS.len() as u64);
+                CHARACTERS[index as usize] as char
+            })
+            .collect()
+    }
+
+    fn number(&mut self, name: &str) -> Option<Value> {
+        let bits = match name {
+            "u8" | "i8" => 8,
+            "u16" | "i16" => 16,
+            "u32" | "i32" => 32,
+            "u64" | "i64" | "usize" | "isize" | "u128" | "i128" => 64,
+            "f32" | "f64" => {
+                let value = self.rng.gen_range(0..2_000_000) as f64 / 100.0;
+                return Some(Value::Number((value - 10_000.0).into()));
+            },
+            _ => return None,
+        };
+
+        let unsigned = self.rng.next_u64() >> (64 - bits);
+        Some(Value::Number(if name.starts_with('i') {
+            // Reinterpret as a signed number of the same width
+            let shift = 64 - bits;
+            (((unsigned << shift) as i64) >> shift).into()
+        } else {
+            unsigned.into()
+        }))
+    }
+
+    fn generate(&mut self, t: &Type, depth: usize) -> Value {
+        match t {
+            Type::Tuple(types) if types.is_empty() => Value::Unit,
+            Type::Tuple(types) => Value::Tuple(
+                types.iter().map(|t| self.generate(t, depth + 1)).collect(),
+            ),
+            Type::Array { content, size } => {
+                let size = size.parse().unwrap_or(0);
+                Value::Tuple(
+                    (0..size)
+                        .map(|_| self.generate(content, depth + 1))
+                        .collect(),
+                )
+            },
+            Type::TypeIdentifier(identifier) => {
+                self.generate_named(identifier, depth)
+            },
+        }
+    }
+
+    fn generate_schema(&mut self, schema: &Schema, depth: usize) -> Value {
+        match schema {
+            Schema::Any => Value::Unit,
+            Schema::Primitive("ByteBuf") => {
+                let length = self.collection_length(depth);
+                Value::Bytes(
+                    (0..length).map(|_| self.rng.next_u64() as u8).collect(),
+                )
+            },
+            Schema::Primitive(_) => self.generate(&schema.to_type(), depth),
+
+            Schema::Option(inner) => {
+                if depth >= self.options.max_depth || self.rng.gen_bool() {
+                    Value::Option(None)
+                } else {
+                    let inner = self.generate_schema(inner, depth + 1);
+                    Value::Option(Some(Box::new(inner)))
+                }
+            },
+            Schema::List(item) => {
+                let length = self.collection_length(depth);
+                Value::List(
+                    (0..length)
+                        .map(|_| self.generate_schema(item, depth + 1))
+                        .collect(),
+                )
+            },
+            Schema::Map(key, value) => {
+                let length = self.collection_length(depth);
+                let mut map = Map::new();
+                for _ in 0..length {
+                    map.insert(
+                        self.generate_schema(key, depth + 1),
+                        self.generate_schema(value, depth + 1),
+                    );
+                }
+                Value::Map(map)
+            },
+            Schema::Tuple(items) if items.is_empty() => Value::Unit,
+            Schema::Tuple(items) => Value::Tuple(
+                items
+                    .iter()
+                    .map(|item| self.generate_schema(item, depth + 1))
+                    .collect(),
+            ),
+
+            Schema::Struct { name, fields } => {
+                self.generate_fields(name.as_str().into(), fields, depth)
+            },
+            Schema::Enum { name, variants } => {
+                let index = self.rng.gen_range(0..variants.len() as u64);
+                match variants.iter().nth(index as usize) {
+                    Some((variant, fields)) => self.generate_fields(
+                        (name.as_str(), variant.as_str()).into(),
+                        fields,
+                        depth,
+                    ),
+                    None => Value::UnitStruct(name.as_str().into()),
+                }
+            },
+        }
+    }
+
+    fn generate_fields(
+        &mut self,
+        identifier: TypeIdentifier,
+        fields: &Fields,
+        depth: usize,
+    ) -> Value {
+        match fields {
+            Fields::Unit => Value::UnitStruct(identifier),
+            Fields::Tuple(items) => Value::TupleStruct(
+                identifier,
+                items
+                    .iter()
+                    .map(|item| self.generate_schema(item, depth + 1))
+                    .collect(),
+            ),
+            Fields::Named(fields) => Value::Struct(
+                identifier,
+                fields
+                    .iter()
+                    .map(|(name, schema)| {
+                        let value = self.generate_schema(schema, depth + 1);
+                        (Identifier::from(name.as_str()), value)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn generate_named(
+        &mut self,
+        identifier: &TypeIdentifier,
+        depth: usize,
+    ) -> Value {
+        let last = match identifier.segments.last() {
+            Some(last) => last,
+            None => return Value::Unit,
+        };
+        let generics = last
+            .generics
+            .as_ref()
+            .map(|g| g.types.as_slice())
+            .unwrap_or(&[]);
+        let name = last.identifier.0.as_str();
+
+        if let Some(number) = self.number(name) {
+            return number;
+        }
+
+        match (name, generics) {
+            ("bool", _) => Value::Bool(self.rng.gen_bool()),
+            ("char", _) => {
+                Value::Char(self.string().chars().next().unwrap_or('a'))
+            },
+            ("String", _) | ("str", _) => Value::String(self.string().into()),
+
+            ("Option", [inner]) => {
+                if depth >= self.options.max_depth || self.rng.gen_bool() {
+                    Value::Option(None)
+                } else {
+                    let inner = self.generate(inner, depth + 1);
+                    Value::Option(Some(Box::new(inner)))
+                }
+            },
+            ("Box", [inner]) | ("Rc", [inner]) | ("Arc", [inner]) => {
+                self.generate(inner, depth)
+            },
+
+            ("Vec", [inner])
+            | ("VecDeque", [inner])
+            | ("HashSet", [inner])
+            | ("BTreeSet", [inner]) => {
+                let length = self.collection_length(depth);
+                Value::List(
+                    (0..length)
+                        .map(|_| self.generate(inner, depth + 1))
+                        .collect(),
+                )
+            },
+            ("HashMap", [key, value]) | ("BTreeMap", [key, value]) => {
+                let length = self.collection_length(depth);
+                let mut map = Map::new();
+                for _ in 0..length {
+                    map.insert(
+                        self.generate(key, depth + 1),
+                        self.generate(value, depth + 1),
+                    );
+                }
+                Value::Map(map)
+            },
+
+            _ => Value::UnitStruct(identifier.clone()),
+        }
+    }
+}
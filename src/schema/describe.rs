@@ -0,0 +1,656 @@
+use crate::{
+    raw,
+    value::{
+        embed,
+        serializer::ValueSerializerError,
+        types::{
+            GenericIdentifier, Generics, Identifier, Type, TypeIdentifier,
+        },
+        Value,
+    },
+};
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+        SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+use std::collections::BTreeMap;
+
+/// Shape of a Rust type as serde sees it
+///
+/// Built by tracing the serialization of sample values, so it only
+/// contains what the samples showed: enum variants that never occurred
+/// are missing and the items of lists that were always empty are `Any`.
+/// Fields skipped while serializing are missing as well.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Schema {
+    /// Any value, where the samples gave no information
+    Any,
+    /// A primitive by its Rust name, like `u16`, `String` or `()`
+    Primitive(&'static str),
+    Option(Box<Schema>),
+    List(Box<Schema>),
+    Map(Box<Schema>, Box<Schema>),
+    Tuple(Vec<Schema>),
+    /// A unit, tuple or named struct
+    Struct {
+        name: String,
+        fields: Fields,
+    },
+    /// An enum with the variants seen so far
+    Enum {
+        name: String,
+        variants: BTreeMap<String, Fields>,
+    },
+}
+
+/// Fields of a struct or enum variant
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Fields {
+    Unit,
+    Tuple(Vec<Schema>),
+    Named(BTreeMap<String, Schema>),
+}
+
+impl Schema {
+    /// Describes `T` by serializing `sample`
+    pub fn trace<T>(sample: &T) -> Result<Schema, ValueSerializerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        sample.serialize(Tracer)
+    }
+
+    /// Describes `T` by serializing all samples and merging the results
+    pub fn trace_all<'lt, T, I>(
+        samples: I,
+    ) -> Result<Schema, ValueSerializerError>
+    where
+        T: Serialize + ?Sized + 'lt,
+        I: IntoIterator<Item = &'lt T>,
+    {
+        samples.into_iter().try_fold(Schema::Any, |schema, sample| {
+            Ok(schema.merge(Schema::trace(sample)?))
+        })
+    }
+
+    /// Combines what two descriptions of the same type know
+    ///
+    /// `Any` is replaced by the other side, enum variants and struct
+    /// fields are united. Where the shapes disagree, `self` is kept.
+    pub fn merge(self, other: Schema) -> Schema {
+        match (self, other) {
+            (Schema::Any, other) => other,
+            (schema, Schema::Any) => schema,
+
+            (Schema::Option(a), Schema::Option(b)) => {
+                Schema::Option(Box::new(a.merge(*b)))
+            },
+            (Schema::List(a), Schema::List(b)) => {
+                Schema::List(Box::new(a.merge(*b)))
+            },
+            (Schema::Map(a_key, a_value), Schema::Map(b_key, b_value)) => {
+                Schema::Map(
+                    Box::new(a_key.merge(*b_key)),
+                    Box::new(a_value.merge(*b_value)),
+                )
+            },
+            (Schema::Tuple(a), Schema::Tuple(b)) if a.len() == b.len() => {
+                Schema::Tuple(merge_items(a, b))
+            },
+
+            (
+                Schema::Struct { name, fields: a },
+                Schema::Struct {
+                    name: other_name,
+                    fields: b,
+                },
+            ) if name == other_name => Schema::Struct {
+                name,
+                fields: a.merge(b),
+            },
+            (
+                Schema::Enum { name, mut variants },
+                Schema::Enum {
+                    name: other_name,
+                    variants: other,
+                },
+            ) if name == other_name => {
+                for (variant, fields) in other {
+                    let fields = match variants.remove(&variant) {
+                        Some(existing) => existing.merge(fields),
+                        None => fields,
+                    };
+                    variants.insert(variant, fields);
+                }
+                Schema::Enum { name, variants }
+            },
+
+            (schema, _) => schema,
+        }
+    }
+
+    /// The Rust type described, as it would be written in a type
+    /// annotation
+    ///
+    /// `Any` becomes `Value`, since that is what could hold it.
+    pub fn to_type(&self) -> Type {
+        match self {
+            Schema::Any => "Value".into(),
+            Schema::Primitive("()") => Type::Tuple(Vec::new()),
+            Schema::Primitive(name) => (*name).into(),
+            Schema::Option(inner) => generic("Option", vec![inner.to_type()]),
+            Schema::List(item) => generic("Vec", vec![item.to_type()]),
+            Schema::Map(key, value) => {
+                generic("BTreeMap", vec![key.to_type(), value.to_type()])
+            },
+            Schema::Tuple(items) => {
+                Type::Tuple(items.iter().map(Schema::to_type).collect())
+            },
+            Schema::Struct { name, .. } | Schema::Enum { name, .. } => {
+                name.as_str().into()
+            },
+        }
+    }
+
+    /// The description as a value, which can be printed and parsed
+    ///
+    /// Primitives are their type names, `Option`, `Vec` and `BTreeMap`
+    /// are tuple structs of their contents and structs look like
+    /// instances with the descriptions of their fields. Enums are lists
+    /// of their variants, as in `[Shape::Circle { radius: f64 }, Shape::Empty]`.
+    pub fn to_value(&self) -> Value {
+        match self {
+            // Type names in value position parse as unit structs
+            Schema::Any => Value::UnitStruct("Value".into()),
+            Schema::Primitive("()") => Value::Unit,
+            Schema::Primitive(name) => Value::UnitStruct((*name).into()),
+            Schema::Option(inner) => {
+                Value::TupleStruct("Option".into(), vec![inner.to_value()])
+            },
+            Schema::List(item) => {
+                Value::TupleStruct("Vec".into(), vec![item.to_value()])
+            },
+            Schema::Map(key, value) => Value::TupleStruct(
+                "BTreeMap".into(),
+                vec![key.to_value(), value.to_value()],
+            ),
+            Schema::Tuple(items) => {
+                Value::Tuple(items.iter().map(Schema::to_value).collect())
+            },
+            Schema::Struct { name, fields } => {
+                fields.to_value(name.as_str().into())
+            },
+            Schema::Enum { name, variants } => Value::List(
+                variants
+                    .iter()
+                    .map(|(variant, fields)| {
+                        fields
+                            .to_value((name.as_str(), variant.as_str()).into())
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Fields {
+    /// Combines the fields of two samples, see [`Schema::merge`]
+    pub fn merge(self, other: Fields) -> Fields {
+        match (self, other) {
+            (Fields::Tuple(a), Fields::Tuple(b)) if a.len() == b.len() => {
+                Fields::Tuple(merge_items(a, b))
+            },
+            (Fields::Named(mut fields), Fields::Named(other)) => {
+                for (name, schema) in other {
+                    let schema = match fields.remove(&name) {
+                        Some(existing) => existing.merge(schema),
+                        None => schema,
+                    };
+                    fields.insert(name, schema);
+                }
+                Fields::Named(fields)
+            },
+            (fields, _) => fields,
+        }
+    }
+
+    fn to_value(&self, identifier: TypeIdentifier) -> Value {
+        match self {
+            Fields::Unit => Value::UnitStruct(identifier),
+            Fields::Tuple(items) => Value::TupleStruct(
+                identifier,
+                items.iter().map(Schema::to_value).collect(),
+            ),
+            Fields::Named(fields) => Value::Struct(
+                identifier,
+                fields
+                    .iter()
+                    .map(|(name, schema)| {
+                        (Identifier::from(name.as_str()), schema.to_value())
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn merge_items(a: Vec<Schema>, b: Vec<Schema>) -> Vec<Schema> {
+    a.into_iter().zip(b).map(|(a, b)| a.merge(b)).collect()
+}
+
+fn generic(name: &str, types: Vec<Type>) -> Type {
+    Type::TypeIdentifier(TypeIdentifier {
+        segments: vec![GenericIdentifier {
+            identifier: name.into(),
+            generics: Some(Generics { types }),
+        }],
+    })
+}
+
+/// What a compound being serialized turns into
+#[derive(Copy, Clone)]
+enum Container {
+    Tuple,
+    Struct(&'static str),
+    Variant(&'static str, &'static str),
+}
+
+impl Container {
+    fn finish(self, fields: Fields) -> Schema {
+        match (self, fields) {
+            (Container::Tuple, Fields::Tuple(items)) => Schema::Tuple(items),
+            (Container::Tuple, _) => Schema::Tuple(Vec::new()),
+            (Container::Struct(name), fields) => Schema::Struct {
+                name: name.to_string(),
+                fields,
+            },
+            (Container::Variant(name, variant), fields) => {
+                let mut variants = BTreeMap::new();
+                variants.insert(variant.to_string(), fields);
+                Schema::Enum {
+                    name: name.to_string(),
+                    variants,
+                }
+            },
+        }
+    }
+}
+
+/// Serializer producing the schema of what is serialized instead of data
+struct Tracer;
+
+impl Serializer for Tracer {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+    type SerializeSeq = TraceSeq;
+    type SerializeTuple = TraceTuple;
+    type SerializeTupleStruct = TraceTuple;
+    type SerializeTupleVariant = TraceTuple;
+    type SerializeMap = TraceMap;
+    type SerializeStruct = TraceStruct;
+    type SerializeStructVariant = TraceStruct;
+
+    fn serialize_bool(self, _: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("bool"))
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("i8"))
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("i16"))
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("i32"))
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("i64"))
+    }
+
+    fn serialize_i128(self, _: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("i128"))
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("u8"))
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("u16"))
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("u32"))
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("u64"))
+    }
+
+    fn serialize_u128(self, _: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("u128"))
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("f32"))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("f64"))
+    }
+
+    fn serialize_char(self, _: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("char"))
+    }
+
+    fn serialize_str(self, _: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("String"))
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("ByteBuf"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Option(Box::new(Schema::Any)))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(Schema::Option(Box::new(value.serialize(Tracer)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Primitive("()"))
+    }
+
+    fn serialize_unit_struct(
+        self,
+        name: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Container::Struct(name).finish(Fields::Unit))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Container::Variant(name, variant).finish(Fields::Unit))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        // Embedded values and raw fragments may contain anything
+        if name == embed::TOKEN || name == raw::TOKEN {
+            return Ok(Schema::Any);
+        }
+
+        let inner = value.serialize(Tracer)?;
+        Ok(Container::Struct(name).finish(Fields::Tuple(vec![inner])))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let inner = value.serialize(Tracer)?;
+        Ok(
+            Container::Variant(name, variant)
+                .finish(Fields::Tuple(vec![inner])),
+        )
+    }
+
+    fn serialize_seq(
+        self,
+        _: Option<usize>,
+    ) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(TraceSeq { item: Schema::Any })
+    }
+
+    fn serialize_tuple(
+        self,
+        len: usize,
+    ) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(TraceTuple {
+            container: Container::Tuple,
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(TraceTuple {
+            container: Container::Struct(name),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(TraceTuple {
+            container: Container::Variant(name, variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(
+        self,
+        _: Option<usize>,
+    ) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(TraceMap {
+            key: Schema::Any,
+            value: Schema::Any,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(TraceStruct {
+            container: Container::Struct(name),
+            fields: BTreeMap::new(),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(TraceStruct {
+            container: Container::Variant(name, variant),
+            fields: BTreeMap::new(),
+        })
+    }
+}
+
+/// Merges the schemas of all items
+struct TraceSeq {
+    item: Schema,
+}
+
+impl SerializeSeq for TraceSeq {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let item = std::mem::replace(&mut self.item, Schema::Any);
+        self.item = item.merge(value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::List(Box::new(self.item)))
+    }
+}
+
+struct TraceTuple {
+    container: Container,
+    items: Vec<Schema>,
+}
+
+impl SerializeTuple for TraceTuple {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.items.push(value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.container.finish(Fields::Tuple(self.items)))
+    }
+}
+
+impl SerializeTupleStruct for TraceTuple {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        SerializeTuple::end(self)
+    }
+}
+
+impl SerializeTupleVariant for TraceTuple {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        SerializeTuple::end(self)
+    }
+}
+
+/// Merges the schemas of all keys and of all values
+struct TraceMap {
+    key: Schema,
+    value: Schema,
+}
+
+impl SerializeMap for TraceMap {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let existing = std::mem::replace(&mut self.key, Schema::Any);
+        self.key = existing.merge(key.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let existing = std::mem::replace(&mut self.value, Schema::Any);
+        self.value = existing.merge(value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Map(Box::new(self.key), Box::new(self.value)))
+    }
+}
+
+struct TraceStruct {
+    container: Container,
+    fields: BTreeMap<String, Schema>,
+}
+
+impl SerializeStruct for TraceStruct {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.fields
+            .insert(key.to_string(), value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.container.finish(Fields::Named(self.fields)))
+    }
+}
+
+impl SerializeStructVariant for TraceStruct {
+    type Ok = Schema;
+    type Error = ValueSerializerError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        SerializeStruct::end(self)
+    }
+}
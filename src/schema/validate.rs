@@ -0,0 +1,281 @@
+use crate::{
+    schema::{Fields, Schema},
+    validate::ValidationError,
+    value::{
+        path::{Path, PathSegment},
+        types::{Identifier, Type},
+        Value,
+    },
+};
+use std::collections::BTreeMap;
+
+impl Schema {
+    /// Checks that `value` has the described shape
+    ///
+    /// All mismatches are reported, each at the path of the offending
+    /// value with the expected type as its format. Missing `Option`
+    /// fields are fine, as serde treats them as `None`. Unknown fields
+    /// are not reported, since serde skips them by default and the
+    /// fields of structs written as tuples are not checked, since a
+    /// traced schema does not know their order.
+    pub fn validate(&self, value: &Value) -> Vec<ValidationError> {
+        let mut checker = Checker {
+            path: Path::root(),
+            errors: Vec::new(),
+        };
+        checker.check(self, value);
+        checker.errors
+    }
+}
+
+struct Checker {
+    path: Path,
+    errors: Vec<ValidationError>,
+}
+
+impl Checker {
+    fn error(&mut self, schema: &Schema, message: String) {
+        self.errors.push(ValidationError {
+            path: self.path.clone(),
+            format: schema.to_type().to_string(),
+            message,
+            span: None,
+        });
+    }
+
+    fn mismatch(&mut self, schema: &Schema, value: &Value) {
+        let message = format!(
+            "Expected {}, found {}",
+            schema.to_type(),
+            value.variant_name()
+        );
+        self.error(schema, message);
+    }
+
+    fn within<F>(&mut self, segment: PathSegment, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.path.push(segment);
+        f(self);
+        self.path.pop();
+    }
+
+    fn check(&mut self, schema: &Schema, value: &Value) {
+        match (schema, value) {
+            (Schema::Any, _) => {},
+            (Schema::Primitive(name), value) => {
+                if let Some(message) = check_primitive(name, value) {
+                    self.error(schema, message);
+                }
+            },
+
+            (Schema::Option(_), Value::Option(None)) => {},
+            (Schema::Option(inner), Value::Option(Some(value))) => {
+                self.check(inner, value)
+            },
+            (Schema::List(item), Value::List(items)) => {
+                for (index, value) in items.iter().enumerate() {
+                    self.within(PathSegment::Index(index), |checker| {
+                        checker.check(item, value)
+                    });
+                }
+            },
+            (Schema::Map(key_schema, value_schema), Value::Map(map)) => {
+                for (key, value) in map {
+                    self.within(PathSegment::Key(key.clone()), |checker| {
+                        checker.check(key_schema, key);
+                        checker.check(value_schema, value);
+                    });
+                }
+            },
+            (Schema::Tuple(schemas), Value::Tuple(items)) => {
+                self.check_items(schema, schemas, items)
+            },
+
+            (Schema::Struct { fields, .. }, value) => {
+                self.check_fields(schema, fields, value)
+            },
+            (Schema::Enum { name, variants }, value) => {
+                let variant = match value {
+                    Value::UnitStruct(identifier)
+                    | Value::Type(Type::TypeIdentifier(identifier))
+                    | Value::TupleStruct(identifier, _)
+                    | Value::Struct(identifier, _) => identifier
+                        .segments
+                        .last()
+                        .map(|last| last.identifier.0.as_str()),
+                    // Unit variants written as the tags of internally
+                    // tagged enums
+                    Value::String(variant) => Some(&**variant),
+                    _ => None,
+                };
+
+                match variant.map(|variant| (variant, variants.get(variant))) {
+                    Some((_, Some(fields))) => {
+                        self.check_variant(schema, fields, value)
+                    },
+                    Some((variant, None)) => {
+                        let message =
+                            format!("Unknown variant {}::{}", name, variant);
+                        self.error(schema, message);
+                    },
+                    None => self.mismatch(schema, value),
+                }
+            },
+
+            (_, value) => self.mismatch(schema, value),
+        }
+    }
+
+    fn check_items(
+        &mut self,
+        schema: &Schema,
+        schemas: &[Schema],
+        items: &[Value],
+    ) {
+        if schemas.len() != items.len() {
+            let message = format!(
+                "Expected {} items, found {}",
+                schemas.len(),
+                items.len()
+            );
+            self.error(schema, message);
+            return;
+        }
+
+        for (index, (item, value)) in schemas.iter().zip(items).enumerate() {
+            self.within(PathSegment::Index(index), |checker| {
+                checker.check(item, value)
+            });
+        }
+    }
+
+    /// Checks a struct in any of the forms the deserializer accepts
+    fn check_fields(
+        &mut self,
+        schema: &Schema,
+        fields: &Fields,
+        value: &Value,
+    ) {
+        match (fields, value) {
+            (
+                Fields::Unit,
+                Value::Unit
+                | Value::UnitStruct(_)
+                | Value::Type(Type::TypeIdentifier(_)),
+            ) => {},
+            (Fields::Unit, Value::TupleStruct(_, items))
+                if items.is_empty() => {},
+
+            (Fields::Tuple(schemas), Value::TupleStruct(_, items))
+            | (Fields::Tuple(schemas), Value::Tuple(items)) => {
+                self.check_items(schema, schemas, items)
+            },
+
+            (Fields::Named(fields), Value::Struct(_, values)) => self
+                .check_named(fields, |name| {
+                    values.get(&Identifier::from(name))
+                }),
+            (Fields::Named(fields), Value::Unit) => {
+                self.check_named(fields, |_| None)
+            },
+            (Fields::Named(fields), Value::Map(map)) => self
+                .check_named(fields, |name| {
+                    map.get(&Value::String(name.into()))
+                }),
+            (Fields::Named(_), Value::Tuple(_))
+            | (Fields::Named(_), Value::TupleStruct(_, _)) => {},
+
+            (_, value) => self.mismatch(schema, value),
+        }
+    }
+
+    fn check_named<'lt, F>(&mut self, fields: &BTreeMap<String, Schema>, get: F)
+    where
+        F: Fn(&str) -> Option<&'lt Value>,
+    {
+        for (name, field) in fields {
+            self.within(PathSegment::Field(name.as_str().into()), |checker| {
+                match (get(name), field) {
+                    (Some(value), field) => checker.check(field, value),
+                    (None, Schema::Option(_)) => {},
+                    (None, field) => {
+                        let message = format!("Missing field {}", name);
+                        checker.error(field, message);
+                    },
+                }
+            });
+        }
+    }
+
+    /// Checks the contents of an enum variant like the variant access of
+    /// the deserializer
+    fn check_variant(
+        &mut self,
+        schema: &Schema,
+        fields: &Fields,
+        value: &Value,
+    ) {
+        match (fields, value) {
+            (Fields::Unit, Value::String(_)) => {},
+            // Newtype variants need their tuple struct, unlike structs
+            (Fields::Tuple(schemas), Value::TupleStruct(_, items))
+                if schemas.len() == 1 =>
+            {
+                self.check_items(schema, schemas, items)
+            },
+            (Fields::Tuple(schemas), _) if schemas.len() == 1 => {
+                self.mismatch(schema, value)
+            },
+            _ => self.check_fields(schema, fields, value),
+        }
+    }
+}
+
+/// Describes why `value` can not be deserialized as the primitive `name`
+fn check_primitive(name: &str, value: &Value) -> Option<String> {
+    let fits = match (name, value) {
+        ("()", Value::Unit)
+        | ("bool", Value::Bool(_))
+        | ("char", Value::Char(_))
+        | ("String", Value::String(_))
+        | ("ByteBuf", Value::Bytes(_)) => return None,
+        ("ByteBuf", Value::List(items)) => {
+            return items
+                .iter()
+                .any(|item| check_primitive("u8", item).is_some())
+                .then(|| "Expected bytes or a list of u8".to_string())
+        },
+
+        ("i8", Value::Number(v)) => v.to_primitive::<i8>().is_some(),
+        ("i16", Value::Number(v)) => v.to_primitive::<i16>().is_some(),
+        ("i32", Value::Number(v)) => v.to_primitive::<i32>().is_some(),
+        ("i64", Value::Number(v)) => v.to_primitive::<i64>().is_some(),
+        ("i128", Value::Number(v)) => v.to_primitive::<i128>().is_some(),
+        ("u8", Value::Number(v)) => v.to_primitive::<u8>().is_some(),
+        ("u16", Value::Number(v)) => v.to_primitive::<u16>().is_some(),
+        ("u32", Value::Number(v)) => v.to_primitive::<u32>().is_some(),
+        ("u64", Value::Number(v)) => v.to_primitive::<u64>().is_some(),
+        ("u128", Value::Number(v)) => v.to_primitive::<u128>().is_some(),
+        ("f32", Value::Number(_)) | ("f64", Value::Number(_)) => true,
+
+        (_, value) => {
+            return Some(format!(
+                "Expected {}, found {}",
+                name,
+                value.variant_name()
+            ))
+        },
+    };
+
+    if fits {
+        None
+    } else {
+        Some(format!(
+            "{} does not fit {}",
+            value.to_string_compact(),
+            name
+        ))
+    }
+}
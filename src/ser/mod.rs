@@ -0,0 +1,747 @@
+//! Serializer writing the text format directly, without building a Value
+
+use crate::{
+    literals,
+    literals::Literals,
+    progress::{CancellationToken, Cancelled},
+    raw,
+    value::{
+        embed,
+        number::Number,
+        options::UnitVariantStyle,
+        printer::{
+            escape_bytes, escape_char, escape_string, quote_string,
+            ValuePrinter,
+        },
+        types::Identifier,
+        Value,
+    },
+};
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+        SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter},
+    io,
+    io::Write,
+    mem,
+};
+
+/// Writes `value` in the compact text format
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), TextSerializerError>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut TextSerializer::compact(writer))
+}
+
+/// Writes `value` in the pretty text format
+pub fn to_writer_pretty<W, T>(
+    writer: W,
+    value: &T,
+) -> Result<(), TextSerializerError>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut TextSerializer::pretty(writer))
+}
+
+/// Writes `value` in the compact text format until `token` is cancelled
+///
+/// The token is checked before every item, a cancelled write fails with
+/// `TextSerializerError::Cancelled` and leaves partial output behind.
+pub fn to_writer_with_cancellation<W, T>(
+    writer: W,
+    value: &T,
+    token: CancellationToken,
+) -> Result<(), TextSerializerError>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut TextSerializer::compact(writer).cancellation(token))
+}
+
+/// Appends `value` in the compact text format to `buffer`
+///
+/// Serializing many small values into the same buffer avoids
+/// allocating a new String for each of them.
+pub fn to_string_into<T>(
+    buffer: &mut String,
+    value: &T,
+) -> Result<(), TextSerializerError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = mem::take(buffer).into_bytes();
+    let result = to_writer(&mut bytes, value);
+    // Only whole strings are written, so this cannot fail
+    *buffer = String::from_utf8(bytes).expect("Serializer wrote invalid UTF-8");
+    result
+}
+
+/// Buffers larger than this are not kept for the next call
+const MAX_POOLED_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Serializes `value` into a buffer owned by the current thread
+/// and passes the compact text to `f`
+///
+/// The buffer is reused by the next call on the same thread. Calls nested
+/// inside of `f` use a buffer of their own.
+pub fn with_buffer<T, F, R>(value: &T, f: F) -> Result<R, TextSerializerError>
+where
+    T: Serialize + ?Sized,
+    F: FnOnce(&str) -> R,
+{
+    BUFFER.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut buffer) => {
+            buffer.clear();
+            let result =
+                to_string_into(&mut buffer, value).map(|()| f(&buffer));
+            if buffer.capacity() > MAX_POOLED_CAPACITY {
+                *buffer = String::new();
+            }
+            result
+        },
+        Err(_) => {
+            let mut buffer = String::new();
+            to_string_into(&mut buffer, value)?;
+            Ok(f(&buffer))
+        },
+    })
+}
+
+#[derive(Debug)]
+pub enum TextSerializerError {
+    Io(io::Error),
+    Custom(String),
+    Cancelled,
+}
+
+impl Display for TextSerializerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextSerializerError::Io(io) => Display::fmt(io, f),
+            TextSerializerError::Custom(custom) => Display::fmt(custom, f),
+            TextSerializerError::Cancelled => Display::fmt(&Cancelled, f),
+        }
+    }
+}
+
+impl std::error::Error for TextSerializerError {}
+
+impl serde::ser::Error for TextSerializerError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        TextSerializerError::Custom(msg.to_string())
+    }
+}
+
+impl From<io::Error> for TextSerializerError {
+    fn from(e: io::Error) -> Self {
+        TextSerializerError::Io(e)
+    }
+}
+
+impl From<Cancelled> for TextSerializerError {
+    fn from(_: Cancelled) -> Self {
+        TextSerializerError::Cancelled
+    }
+}
+
+/// Streaming serializer producing the same text as `ValuePrinter`
+///
+/// Map entries and struct fields are written in the order they are
+/// serialized. The printer keeps struct fields in that order as well, but
+/// sorts map entries unless the `preserve_order` feature is enabled. Both
+/// parse to the same value.
+pub struct TextSerializer<'indent, W> {
+    writer: W,
+    indentation: &'indent str,
+    indentation_level: usize,
+    pretty: bool,
+    cancellation: Option<CancellationToken>,
+    human_readable: bool,
+    unit_variants: UnitVariantStyle,
+}
+
+impl<W> TextSerializer<'static, W>
+where
+    W: Write,
+{
+    pub fn pretty(writer: W) -> Self {
+        TextSerializer::new(writer, "    ", true)
+    }
+    pub fn compact(writer: W) -> Self {
+        TextSerializer::new(writer, "", false)
+    }
+}
+
+impl<'indent, W> TextSerializer<'indent, W>
+where
+    W: Write,
+{
+    pub fn new(writer: W, indentation: &'indent str, pretty: bool) -> Self {
+        TextSerializer {
+            writer,
+            indentation,
+            indentation_level: 0,
+            pretty,
+            cancellation: None,
+            human_readable: true,
+            unit_variants: UnitVariantStyle::Path,
+        }
+    }
+
+    /// Stops serializing before the next item once `token` is cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Whether the serialized types are told to write human readable
+    /// representations, true by default
+    ///
+    /// Types like `IpAddr` write bytes or numbers instead of strings if this
+    /// is false. Such documents have to be read with the same setting.
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+
+    /// How unit variants like `Test::Baz` are written
+    pub fn unit_variants(mut self, style: UnitVariantStyle) -> Self {
+        self.unit_variants = style;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_indent(&mut self) -> io::Result<()> {
+        if self.pretty {
+            for _ in 0..self.indentation_level {
+                self.writer.write_all(self.indentation.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_newline(&mut self) -> io::Result<()> {
+        if self.pretty {
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    /// Writes an opening delimiter, items follow with [`Compound`]
+    fn open(
+        &mut self,
+        prefix: &str,
+        close: &'static str,
+    ) -> Result<Compound<'_, 'indent, W>, TextSerializerError> {
+        write!(self.writer, "{}", prefix)?;
+        self.indentation_level += 1;
+
+        Ok(Compound {
+            serializer: self,
+            close,
+            empty: true,
+        })
+    }
+}
+
+/// Writes the items of a collection
+pub struct Compound<'lt, 'indent, W> {
+    serializer: &'lt mut TextSerializer<'indent, W>,
+    close: &'static str,
+    empty: bool,
+}
+
+impl<'lt, 'indent, W> Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    fn begin_item(&mut self) -> Result<(), TextSerializerError> {
+        if let Some(token) = &self.serializer.cancellation {
+            token.check()?;
+        }
+        if self.empty {
+            self.serializer.write_newline()?;
+            self.empty = false;
+        }
+        self.serializer.write_indent()?;
+        Ok(())
+    }
+
+    fn end_item(&mut self) -> Result<(), TextSerializerError> {
+        write!(self.serializer.writer, ",")?;
+        self.serializer.write_newline()?;
+        Ok(())
+    }
+
+    fn item<T>(&mut self, value: &T) -> Result<(), TextSerializerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_item()?;
+        value.serialize(&mut *self.serializer)?;
+        self.end_item()
+    }
+
+    fn write_separator(&mut self) -> Result<(), TextSerializerError> {
+        write!(self.serializer.writer, ":")?;
+        if self.serializer.pretty {
+            write!(self.serializer.writer, " ")?;
+        }
+        Ok(())
+    }
+
+    fn field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), TextSerializerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_item()?;
+
+        let identifier = Identifier::from(key);
+        if identifier.is_valid() {
+            write!(self.serializer.writer, "{}", key)?;
+        } else {
+            write!(self.serializer.writer, "\"{}\"", escape_string(key))?;
+        }
+        self.write_separator()?;
+
+        value.serialize(&mut *self.serializer)?;
+        self.end_item()
+    }
+
+    fn close(self) -> Result<(), TextSerializerError> {
+        self.serializer.indentation_level -= 1;
+        if !self.empty {
+            self.serializer.write_indent()?;
+        }
+        write!(self.serializer.writer, "{}", self.close)?;
+        Ok(())
+    }
+}
+
+impl<'lt, 'indent, W> serde::Serializer for &'lt mut TextSerializer<'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+    type SerializeSeq = Compound<'lt, 'indent, W>;
+    type SerializeTuple = Compound<'lt, 'indent, W>;
+    type SerializeTupleStruct = Compound<'lt, 'indent, W>;
+    type SerializeTupleVariant = Compound<'lt, 'indent, W>;
+    type SerializeMap = Compound<'lt, 'indent, W>;
+    type SerializeStruct = Compound<'lt, 'indent, W>;
+    type SerializeStructVariant = Compound<'lt, 'indent, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", v)?)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", Number::from(v))?)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", Number::from(v))?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "'{}'", escape_char(v))?)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(self.writer.write_all(quote_string(v).as_bytes())?)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "b\"{}\"", escape_bytes(v))?)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "None")?)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        write!(self.writer, "Some(")?;
+        self.write_newline()?;
+
+        self.indentation_level += 1;
+        self.write_indent()?;
+        value.serialize(&mut *self)?;
+        self.indentation_level -= 1;
+
+        self.write_newline()?;
+        self.write_indent()?;
+        Ok(write!(self.writer, ")")?)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "()")?)
+    }
+
+    fn serialize_unit_struct(
+        self,
+        name: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(write!(self.writer, "{}", name)?)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(match self.unit_variants {
+            UnitVariantStyle::Path => {
+                write!(self.writer, "{}::{}", name, variant)
+            },
+            UnitVariantStyle::Name => write!(self.writer, "{}", variant),
+            UnitVariantStyle::Call => write!(self.writer, "{}()", variant),
+        }?)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        if name == embed::TOKEN {
+            if let Some(value) = embed::capture(value) {
+                let mut text = String::new();
+                ValuePrinter::new(self.indentation, self.pretty)
+                    .at_level(self.indentation_level)
+                    .write(&value, &mut text)
+                    .expect("Writing to a String cannot fail");
+                return Ok(self.writer.write_all(text.as_bytes())?);
+            }
+        }
+        if name == literals::DATETIME || name == literals::UUID {
+            // `datetime"..."` and `uuid"..."`, see `literals::datetime`
+            if let Ok(inner) = Value::try_new(value) {
+                let tagged = Value::TupleStruct(name.into(), vec![inner]);
+                if let Some((prefix, contents)) =
+                    Literals::common_ref().print(&tagged)
+                {
+                    let contents = escape_string(&contents);
+                    return Ok(write!(
+                        self.writer,
+                        "{}\"{}\"",
+                        prefix, contents
+                    )?);
+                }
+            }
+        }
+        if name == raw::TOKEN {
+            // Written verbatim, without checking the text
+            return match Value::try_new(value) {
+                Ok(Value::String(text)) => Ok(write!(self.writer, "{}", text)?),
+                _ => Err(TextSerializerError::Custom(
+                    "Raw fragment is not a string".to_string(),
+                )),
+            };
+        }
+
+        let mut compound = self.open(&format!("{}(", name), ")")?;
+        compound.item(value)?;
+        compound.close()
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut compound =
+            self.open(&format!("{}::{}(", name, variant), ")")?;
+        compound.item(value)?;
+        compound.close()
+    }
+
+    fn serialize_seq(
+        self,
+        _: Option<usize>,
+    ) -> Result<Self::SerializeSeq, Self::Error> {
+        self.open("[", "]")
+    }
+
+    fn serialize_tuple(
+        self,
+        _: usize,
+    ) -> Result<Self::SerializeTuple, Self::Error> {
+        self.open("(", ")")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.open(&format!("{}(", name), ")")
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.open(&format!("{}::{}(", name, variant), ")")
+    }
+
+    fn serialize_map(
+        self,
+        _: Option<usize>,
+    ) -> Result<Self::SerializeMap, Self::Error> {
+        self.open("{", "}")
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.open(&format!("{}(", name), ")")
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.open(&format!("{}::{}(", name, variant), ")")
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+}
+
+impl<'lt, 'indent, W> SerializeSeq for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeTuple for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeTupleStruct for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeTupleVariant for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeMap for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_item()?;
+        key.serialize(&mut *self.serializer)?;
+        self.write_separator()
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(&mut *self.serializer)?;
+        self.end_item()
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeStruct for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'lt, 'indent, W> SerializeStructVariant for Compound<'lt, 'indent, W>
+where
+    W: Write,
+{
+    type Ok = ();
+    type Error = TextSerializerError;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{
+    error::Error,
+    value::{
+        path::{Path, PathSegment},
+        serializer::ValueSerializerError,
+        Value,
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+};
+
+/// A deserialized T together with the Value it was read from
+///
+/// Struct fields T does not model are kept in a side map when it is
+/// created and written back when it is saved, so settings written by a
+/// newer version of an application survive being edited by an older one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Typed<T> {
+    value: Value,
+    parsed: T,
+    /// Fields of the original tree T did not pick up, by their path
+    unknown: BTreeMap<Path, Value>,
+    /// Lengths of the lists containing unknown fields, when read
+    ///
+    /// Items are found by their index, which another item takes once one
+    /// is added or removed. Their unknown fields are only written back
+    /// while the list keeps its length.
+    lists: BTreeMap<Path, usize>,
+}
+
+impl<T> Typed<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        let parsed = value.deserialize()?;
+
+        let mut unknown = BTreeMap::new();
+        let known = Value::try_new(&parsed)?;
+        collect_unknown(&value, &known, &mut Path::root(), &mut unknown);
+        let lists = list_lengths(&known, unknown.keys());
+
+        Ok(Typed {
+            value,
+            parsed,
+            unknown,
+            lists,
+        })
+    }
+}
+
+impl<T> Typed<T> {
+    /// The tree as it was read, without any changes made to T
+    pub fn original(&self) -> &Value {
+        &self.value
+    }
+
+    /// Fields T does not model, written back on save
+    pub fn unknown_fields(&self) -> &BTreeMap<Path, Value> {
+        &self.unknown
+    }
+    /// Drops the unknown fields, they are not written back anymore
+    pub fn clear_unknown_fields(&mut self) {
+        self.unknown.clear();
+        self.lists.clear();
+    }
+
+    pub fn into_inner(self) -> T {
+        self.parsed
+    }
+    pub fn into_parts(self) -> (Value, T) {
+        (self.value, self.parsed)
+    }
+}
+
+impl<T> Typed<T>
+where
+    T: Serialize,
+{
+    /// The current state of T with the unknown fields added back
+    ///
+    /// Unknown fields whose struct no longer exists are dropped, as are
+    /// those inside lists that grew or shrank.
+    pub fn to_value(&self) -> Result<Value, ValueSerializerError> {
+        let mut value = Value::try_new(&self.parsed)?;
+        let kept = self
+            .unknown
+            .iter()
+            .filter(|(path, _)| {
+                self.lists.iter().all(|(list, len)| {
+                    !path.starts_with(list)
+                        || list_length(&value, list) == Some(*len)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (path, field) in kept {
+            insert(&mut value, &path.segments, field);
+        }
+        Ok(value)
+    }
+
+    pub fn to_string_pretty(&self) -> Result<String, ValueSerializerError> {
+        Ok(self.to_value()?.to_string_pretty())
+    }
+    pub fn to_string_compact(&self) -> Result<String, ValueSerializerError> {
+        Ok(self.to_value()?.to_string_compact())
+    }
+}
+
+impl<T> Deref for Typed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.parsed
+    }
+}
+
+impl<T> DerefMut for Typed<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.parsed
+    }
+}
+
+/// Finds the struct fields of `original` missing from `known`
+///
+/// `known` is T serialized right after it was deserialized from `original`.
+fn collect_unknown(
+    original: &Value,
+    known: &Value,
+    path: &mut Path,
+    unknown: &mut BTreeMap<Path, Value>,
+) {
+    fn child(
+        segment: PathSegment,
+        original: &Value,
+        known: &Value,
+        path: &mut Path,
+        unknown: &mut BTreeMap<Path, Value>,
+    ) {
+        path.push(segment);
+        collect_unknown(original, known, path, unknown);
+        path.pop();
+    }
+
+    match (original, known) {
+        (Value::Struct(_, original), Value::Struct(_, known)) => {
+            for (field, value) in original {
+                match known.get(field) {
+                    Some(known) => child(
+                        PathSegment::Field(field.clone()),
+                        value,
+                        known,
+                        path,
+                        unknown,
+                    ),
+                    None => {
+                        let path = path.clone().field(field.clone());
+                        unknown.insert(path, value.clone());
+                    },
+                }
+            }
+        },
+        (Value::Map(original), Value::Map(known)) => {
+            for (key, value) in original {
+                if let Some(known) = known.get(key) {
+                    let key = PathSegment::Key(key.clone());
+                    child(key, value, known, path, unknown);
+                }
+            }
+        },
+        (Value::Option(Some(original)), Value::Option(Some(known))) => {
+            collect_unknown(original, known, path, unknown)
+        },
+        (Value::Tuple(original), Value::Tuple(known))
+        | (Value::List(original), Value::List(known))
+        | (Value::TupleStruct(_, original), Value::TupleStruct(_, known)) => {
+            for (index, (original, known)) in
+                original.iter().zip(known).enumerate()
+            {
+                let index = PathSegment::Index(index);
+                child(index, original, known, path, unknown);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// The lengths of the lists in `known` that `paths` lead through
+fn list_lengths<'lt, I>(known: &Value, paths: I) -> BTreeMap<Path, usize>
+where
+    I: IntoIterator<Item = &'lt Path>,
+{
+    let mut lists = BTreeMap::new();
+    for path in paths {
+        let mut prefix = Path::root();
+        for segment in &path.segments {
+            if let Some(len) = list_length(known, &prefix) {
+                lists.insert(prefix.clone(), len);
+            }
+            prefix.push(segment.clone());
+        }
+    }
+    lists
+}
+
+/// The length of the list at `path`, options are looked through
+fn list_length(value: &Value, path: &Path) -> Option<usize> {
+    match value.get_path(path)? {
+        Value::List(items) => Some(items.len()),
+        Value::Option(Some(inner)) => match inner.as_ref() {
+            Value::List(items) => Some(items.len()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Adds a field at `segments` unless it exists, options are looked through
+fn insert(value: &mut Value, segments: &[PathSegment], field: &Value) {
+    let value = match value {
+        Value::Option(Some(inner)) => inner.as_mut(),
+        value => value,
+    };
+
+    match (value, segments) {
+        (Value::Struct(_, fields), [PathSegment::Field(name)]) => {
+            fields.entry(name.clone()).or_insert_with(|| field.clone());
+        },
+        (Value::Struct(_, fields), [PathSegment::Field(name), rest @ ..]) => {
+            if let Some(child) = fields.get_mut(name) {
+                insert(child, rest, field);
+            }
+        },
+        (Value::Map(entries), [PathSegment::Key(key), rest @ ..]) => {
+            if let Some(child) = entries.get_mut(key) {
+                insert(child, rest, field);
+            }
+        },
+        (Value::Tuple(items), [PathSegment::Index(index), rest @ ..])
+        | (Value::List(items), [PathSegment::Index(index), rest @ ..])
+        | (
+            Value::TupleStruct(_, items),
+            [PathSegment::Index(index), rest @ ..],
+        ) => {
+            if let Some(child) = items.get_mut(*index) {
+                insert(child, rest, field);
+            }
+        },
+        _ => {},
+    }
+}
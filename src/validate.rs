@@ -0,0 +1,239 @@
+//! Checks of string values against formats like `email` or `url`
+//!
+//! Rules name the format expected at a path, a `Validators` registry maps
+//! format names to checks. All rules are checked and all failures
+//! reported at once, with the span of the offending value when the
+//! document was parsed with `validate_str`.
+//!
+//! With the `regex` feature, a `regex::Regex` validates the strings it
+//! matches, as in `.with("slug", Regex::new("^[a-z-]+$")?)`.
+
+use crate::{
+    value::{diagnostic::ParseError, path::Path, spans::Spans, Value},
+    vfs::{StdFs, Vfs},
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fmt::{Display, Formatter},
+    net::IpAddr,
+    ops::Range,
+};
+
+/// A check of a string value
+pub trait FormatValidator: Send + Sync {
+    /// Describes the problem if `value` is not valid
+    fn validate(&self, value: &str) -> Result<(), String>;
+}
+
+impl<F> FormatValidator for F
+where
+    F: Fn(&str) -> Result<(), String> + Send + Sync,
+{
+    fn validate(&self, value: &str) -> Result<(), String> {
+        self(value)
+    }
+}
+
+/// A value that failed its rule
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ValidationError {
+    pub path: Path,
+    pub format: String,
+    pub message: String,
+    /// Byte range of the value in the document, if known
+    pub span: Option<Range<usize>>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)?;
+        if let Some(span) = &self.span {
+            write!(f, " at {}..{}", span.start, span.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Formats expected at paths of a document
+pub type Rules = BTreeMap<Path, String>;
+
+/// Format validators by name
+pub struct Validators {
+    formats: BTreeMap<String, Box<dyn FormatValidator>>,
+}
+
+impl Default for Validators {
+    fn default() -> Self {
+        Validators::new()
+    }
+}
+
+impl fmt::Debug for Validators {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.formats.keys()).finish()
+    }
+}
+
+impl Validators {
+    /// The built in formats `email`, `url`, `ip` and `path-exists`
+    pub fn new() -> Self {
+        Validators::empty()
+            .with("email", email)
+            .with("url", url)
+            .with("ip", ip)
+            .with("path-exists", PathExists(&StdFs))
+    }
+
+    pub fn empty() -> Self {
+        Validators {
+            formats: BTreeMap::new(),
+        }
+    }
+
+    /// Adds or replaces the validator of a format
+    pub fn with<N, V>(mut self, name: N, validator: V) -> Self
+    where
+        N: Into<String>,
+        V: FormatValidator + 'static,
+    {
+        self.formats.insert(name.into(), Box::new(validator));
+        self
+    }
+
+    /// Checks every rule
+    ///
+    /// Paths missing from `value` or holding `None` are skipped,
+    /// so optional settings only have to be valid when present.
+    pub fn validate(
+        &self,
+        value: &Value,
+        rules: &Rules,
+    ) -> Vec<ValidationError> {
+        self.validate_with_spans(value, rules, None)
+    }
+
+    /// Parses `input` and checks every rule, errors point into `input`
+    pub fn validate_str(
+        &self,
+        input: &str,
+        rules: &Rules,
+    ) -> Result<Vec<ValidationError>, ParseError> {
+        let (value, spans) = Value::parse_with_spans(input)?;
+        Ok(self.validate_with_spans(&value, rules, Some(&spans)))
+    }
+
+    fn validate_with_spans(
+        &self,
+        value: &Value,
+        rules: &Rules,
+        spans: Option<&Spans>,
+    ) -> Vec<ValidationError> {
+        rules
+            .iter()
+            .filter_map(|(path, format)| {
+                let mut target = value.get_path(path)?;
+                while let Value::Option(option) = target {
+                    target = option.as_deref()?;
+                }
+
+                let result = match (self.formats.get(format), target) {
+                    (None, _) => Err(format!("Unknown format {:?}", format)),
+                    (Some(validator), Value::String(s)) => {
+                        validator.validate(s)
+                    },
+                    (Some(_), _) => Err("Expected a string".to_string()),
+                };
+
+                result.err().map(|message| ValidationError {
+                    path: path.clone(),
+                    format: format.clone(),
+                    message,
+                    span: spans.and_then(|spans| spans.get(path)),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Checks that a file or directory exists in a `Vfs`
+pub struct PathExists(pub &'static dyn Vfs);
+
+impl FormatValidator for PathExists {
+    fn validate(&self, value: &str) -> Result<(), String> {
+        if self.0.exists(std::path::Path::new(value)) {
+            Ok(())
+        } else {
+            Err(format!("{} does not exist", value))
+        }
+    }
+}
+
+/// Accepts strings the pattern matches, anchor it to match them whole
+#[cfg(feature = "regex")]
+impl FormatValidator for regex::Regex {
+    fn validate(&self, value: &str) -> Result<(), String> {
+        if self.is_match(value) {
+            Ok(())
+        } else {
+            Err(format!("{:?} does not match {}", value, self))
+        }
+    }
+}
+
+/// `local@domain.tld`, without whitespace
+fn email(value: &str) -> Result<(), String> {
+    let invalid = || Err(format!("{:?} is not an email address", value));
+
+    if value.contains(char::is_whitespace) {
+        return invalid();
+    }
+    match value.split_once('@') {
+        Some((local, domain))
+            if !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.') =>
+        {
+            Ok(())
+        },
+        _ => invalid(),
+    }
+}
+
+/// `scheme://host...`, without whitespace
+fn url(value: &str) -> Result<(), String> {
+    let invalid = || Err(format!("{:?} is not a URL", value));
+
+    if value.contains(char::is_whitespace) {
+        return invalid();
+    }
+    let (scheme, rest) = match value.split_once("://") {
+        Some(parts) => parts,
+        None => return invalid(),
+    };
+
+    let mut scheme_chars = scheme.chars();
+    let valid_scheme = scheme_chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    let host = rest.split(&['/', '?', '#'][..]).next().unwrap_or("");
+
+    if valid_scheme && !host.is_empty() {
+        Ok(())
+    } else {
+        invalid()
+    }
+}
+
+/// An IPv4 or IPv6 address
+fn ip(value: &str) -> Result<(), String> {
+    value
+        .parse::<IpAddr>()
+        .map(|_| ())
+        .map_err(|_| format!("{:?} is not an IP address", value))
+}
@@ -0,0 +1,175 @@
+//! Repeated subtrees written once, as `&name value` and `*name`
+
+use crate::value::{types::Identifier, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    marker::PhantomData,
+};
+
+/// How a value is written by a printer with anchors
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Mark {
+    /// The first occurrence, written as `&name value`
+    Anchor(String),
+    /// A later occurrence, written as `*name`
+    Alias(String),
+}
+
+/// Collections that occur more than once in a value, see
+/// `ValuePrinter::with_anchors`
+///
+/// The first occurrence of such a collection gets an anchor, the others
+/// are replaced by aliases of it. Anchors are named after the field or
+/// map key holding the collection where possible. Collections are only
+/// found in the exact value they were detected in, which stays borrowed.
+#[derive(Clone, Default, Debug)]
+pub struct Anchors<'value> {
+    /// Marks by the address of the values they apply to
+    marks: HashMap<usize, Mark>,
+    marker: PhantomData<&'value Value>,
+}
+
+impl<'value> Anchors<'value> {
+    pub fn detect(value: &'value Value) -> Self {
+        let mut counts = BTreeMap::new();
+        count(value, &mut counts);
+
+        let mut detector = Detector {
+            counts,
+            names: BTreeMap::new(),
+            taken: HashSet::new(),
+            aliased: HashSet::new(),
+            marks: HashMap::new(),
+        };
+        detector.visit(value, None);
+
+        // Anchors inside of an anchored value repeat with it, but only
+        // the copy behind the anchor is written
+        let Detector {
+            mut marks, aliased, ..
+        } = detector;
+        marks.retain(|_, mark| match mark {
+            Mark::Anchor(name) => aliased.contains(name),
+            Mark::Alias(_) => true,
+        });
+
+        Anchors {
+            marks,
+            marker: PhantomData,
+        }
+    }
+
+    /// Number of anchors
+    pub fn len(&self) -> usize {
+        self.marks
+            .values()
+            .filter(|mark| matches!(mark, Mark::Anchor(_)))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    pub(crate) fn get(&self, value: &Value) -> Option<&Mark> {
+        self.marks.get(&address(value))
+    }
+}
+
+fn address(value: &Value) -> usize {
+    value as *const Value as usize
+}
+
+/// Whether a value is worth an anchor
+fn is_collection(value: &Value) -> bool {
+    match value {
+        Value::List(items)
+        | Value::Tuple(items)
+        | Value::TupleStruct(_, items) => !items.is_empty(),
+        Value::Map(map) => !map.is_empty(),
+        Value::Struct(_, fields) => !fields.is_empty(),
+        _ => false,
+    }
+}
+
+/// Calls `function` for the children of `value` that get written, along
+/// with the field name or map key they are written after
+fn for_each_child<'value, F>(value: &'value Value, mut function: F)
+where
+    F: FnMut(&'value Value, Option<&'value str>),
+{
+    match value {
+        Value::List(items)
+        | Value::Tuple(items)
+        | Value::TupleStruct(_, items) => {
+            items.iter().for_each(|item| function(item, None))
+        },
+        Value::Map(map) => map.iter().for_each(|(key, value)| {
+            let key = match key {
+                Value::String(key) => Some(key.as_ref()),
+                _ => None,
+            };
+            function(value, key)
+        }),
+        Value::Struct(_, fields) => fields
+            .iter()
+            .for_each(|(field, value)| function(value, Some(&field.0))),
+        Value::Option(Some(inner)) => function(inner, None),
+        _ => {},
+    }
+}
+
+fn count<'value>(
+    value: &'value Value,
+    counts: &mut BTreeMap<&'value Value, usize>,
+) {
+    if is_collection(value) {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    for_each_child(value, |child, _| count(child, counts));
+}
+
+struct Detector<'value> {
+    counts: BTreeMap<&'value Value, usize>,
+    /// Anchor names of the collections seen so far
+    names: BTreeMap<&'value Value, String>,
+    taken: HashSet<String>,
+    /// Anchors with at least one alias
+    aliased: HashSet<String>,
+    marks: HashMap<usize, Mark>,
+}
+
+impl<'value> Detector<'value> {
+    /// Visits the values in the order they are written
+    fn visit(&mut self, value: &'value Value, hint: Option<&str>) {
+        let repeated = self.counts.get(value).is_some_and(|count| *count > 1);
+        if repeated {
+            if let Some(name) = self.names.get(value) {
+                self.aliased.insert(name.clone());
+                self.marks.insert(address(value), Mark::Alias(name.clone()));
+                return;
+            }
+
+            let name = self.unique_name(hint);
+            self.names.insert(value, name.clone());
+            self.marks.insert(address(value), Mark::Anchor(name));
+        }
+
+        for_each_child(value, |child, hint| self.visit(child, hint));
+    }
+
+    fn unique_name(&mut self, hint: Option<&str>) -> String {
+        let base = hint
+            .filter(|hint| Identifier::from(*hint).is_valid())
+            .unwrap_or("anchor");
+
+        let mut name = base.to_string();
+        let mut suffix = 1;
+        while self.taken.contains(&name) {
+            suffix += 1;
+            name = format!("{}_{}", base, suffix);
+        }
+        self.taken.insert(name.clone());
+        name
+    }
+}
@@ -0,0 +1,186 @@
+//! Random values and types for fuzzing and property tests
+//!
+//! The generated trees are at most a few levels deep and print to text that
+//! reads back as the same value. Some values can not be told apart in the
+//! text format, so these are never generated: keywords like `true` or `None`
+//! as type names and empty tuples and structs, which read as `()` and tuple
+//! structs.
+//!
+//! The fuzz targets in `fuzz/` are built on these implementations.
+
+use crate::value::{
+    map::{Map, OrderedMap},
+    number::Number,
+    types::{GenericIdentifier, Generics, Identifier, Type, TypeIdentifier},
+    Value,
+};
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use std::sync::Arc;
+
+/// Nesting of the generated values and types
+const MAX_DEPTH: usize = 4;
+
+/// Names that are read as something else than a type
+const KEYWORDS: &[&str] = &["true", "false", "None", "Some", "inf", "NaN"];
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        value(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Number {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.choose_index(5)? {
+            0 => Number::I64(u.arbitrary()?),
+            1 => Number::U64(u.arbitrary()?),
+            2 => Number::I128(u.arbitrary()?),
+            3 => Number::U128(u.arbitrary()?),
+            _ => Number::F64(u.arbitrary()?),
+        })
+    }
+}
+
+/// Any string, as struct fields with other names are quoted
+impl<'a> Arbitrary<'a> for Identifier {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Identifier(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for GenericIdentifier {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        generic_identifier(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TypeIdentifier {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        type_identifier(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Generics {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        generics(u, MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Type {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        generic_type(u, MAX_DEPTH)
+    }
+}
+
+fn value(u: &mut Unstructured, depth: usize) -> Result<Value> {
+    let scalars = 9;
+    let kinds = if depth == 0 { scalars } else { scalars + 7 };
+
+    Ok(match u.choose_index(kinds)? {
+        0 => Value::Unit,
+        1 => Value::Bool(u.arbitrary()?),
+        2 => Value::Char(u.arbitrary()?),
+        3 => Value::String(Arc::from(String::arbitrary(u)?)),
+        4 => Value::Bytes(u.arbitrary()?),
+        5 => Value::Number(u.arbitrary()?),
+        6 => Value::Option(None),
+        7 => Value::UnitStruct(type_identifier(u, depth)?),
+        8 => Value::Type(generic_type(u, depth)?),
+
+        9 => Value::List(values(u, depth, 0)?),
+        10 => Value::Tuple(values(u, depth, 1)?),
+        11 => Value::TupleStruct(
+            type_identifier(u, depth - 1)?,
+            values(u, depth, 0)?,
+        ),
+        12 => Value::Option(Some(Box::new(value(u, depth - 1)?))),
+        13 => {
+            let mut fields = OrderedMap::new();
+            for _ in 0..u.int_in_range(1..=4)? {
+                fields.insert(u.arbitrary()?, value(u, depth - 1)?);
+            }
+            Value::Struct(type_identifier(u, depth - 1)?, fields)
+        },
+        _ => {
+            let mut map = Map::new();
+            for _ in 0..u.int_in_range(0..=4)? {
+                map.insert(value(u, depth - 1)?, value(u, depth - 1)?);
+            }
+            Value::Map(map)
+        },
+    })
+}
+
+fn values(
+    u: &mut Unstructured,
+    depth: usize,
+    min: usize,
+) -> Result<Vec<Value>> {
+    (0..u.int_in_range(min..=4)?)
+        .map(|_| value(u, depth - 1))
+        .collect()
+}
+
+/// A name that is read as a type, like `Foo_1`
+fn name(u: &mut Unstructured) -> Result<Identifier> {
+    const START: &[u8] = b"_abcrxyzABCXYZ";
+    const CONTINUE: &[u8] = b"_abcrxyzABCXYZ0189";
+
+    let mut name = String::new();
+    name.push(*u.choose(START)? as char);
+    for _ in 0..u.int_in_range(0..=6)? {
+        name.push(*u.choose(CONTINUE)? as char);
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    Ok(Identifier(name))
+}
+
+fn generic_identifier(
+    u: &mut Unstructured,
+    depth: usize,
+) -> Result<GenericIdentifier> {
+    let generics = if depth > 0 && u.ratio(1, 4)? {
+        Some(generics(u, depth - 1)?)
+    } else {
+        None
+    };
+    Ok(GenericIdentifier {
+        identifier: name(u)?,
+        generics,
+    })
+}
+
+fn type_identifier(
+    u: &mut Unstructured,
+    depth: usize,
+) -> Result<TypeIdentifier> {
+    let segments = (0..u.int_in_range(1..=3)?)
+        .map(|_| generic_identifier(u, depth))
+        .collect::<Result<_>>()?;
+    Ok(TypeIdentifier { segments })
+}
+
+fn generics(u: &mut Unstructured, depth: usize) -> Result<Generics> {
+    let types = (0..u.int_in_range(1..=3)?)
+        .map(|_| generic_type(u, depth))
+        .collect::<Result<_>>()?;
+    Ok(Generics { types })
+}
+
+fn generic_type(u: &mut Unstructured, depth: usize) -> Result<Type> {
+    let kinds = if depth == 0 { 1 } else { 3 };
+    Ok(match u.choose_index(kinds)? {
+        0 => Type::TypeIdentifier(type_identifier(u, depth)?),
+        1 => Type::Array {
+            content: Box::new(generic_type(u, depth - 1)?),
+            size: u32::arbitrary(u)?.to_string(),
+        },
+        _ => Type::Tuple(
+            (0..u.int_in_range(0..=3)?)
+                .map(|_| generic_type(u, depth - 1))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
@@ -0,0 +1,474 @@
+//! Compact binary encoding of values
+//!
+//! The encoding keeps the full data model, including struct names and
+//! type values, but needs no text parsing to read. It starts with the
+//! magic bytes `TYFB` and a version byte, followed by a single value.
+//!
+//! Every value starts with a tag byte. Lengths, counts and integers up
+//! to 64 bits are LEB128 varints, signed integers zigzag encoded first.
+//! 128 bit integers and floats are stored as little endian bytes.
+//! Strings, byte strings and names are a length followed by their bytes.
+
+use crate::value::{
+    map::{Map, OrderedMap},
+    number::Number,
+    types::{GenericIdentifier, Generics, Identifier, Type, TypeIdentifier},
+    Value,
+};
+use std::{
+    convert::TryInto,
+    fmt::{Display, Formatter},
+    io,
+    io::Write,
+};
+
+/// Start of every encoded value, the last byte is the version
+pub const MAGIC: &[u8] = b"TYFB\x01";
+
+const UNIT: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const CHAR: u8 = 3;
+const STRING: u8 = 4;
+const BYTES: u8 = 5;
+const I64: u8 = 6;
+const U64: u8 = 7;
+const I128: u8 = 8;
+const U128: u8 = 9;
+const F64: u8 = 10;
+const TYPE: u8 = 11;
+const UNIT_STRUCT: u8 = 12;
+const LIST: u8 = 13;
+const TUPLE: u8 = 14;
+const MAP: u8 = 15;
+const NONE: u8 = 16;
+const SOME: u8 = 17;
+const STRUCT: u8 = 18;
+const TUPLE_STRUCT: u8 = 19;
+
+const TYPE_IDENTIFIER: u8 = 0;
+const TYPE_ARRAY: u8 = 1;
+const TYPE_TUPLE: u8 = 2;
+
+/// Input that is not a valid encoding, the message names the offset
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DecodeError {
+    pub message: String,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+macro_rules! decode_error {
+    ($($arg:tt)*) => {
+        DecodeError {
+            message: format!($($arg)*),
+        }
+    };
+}
+
+/// Encodes a value into a new buffer
+pub fn to_bytes(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value).unwrap();
+    bytes
+}
+
+/// Writes the encoding of a value
+pub fn to_writer<W>(writer: W, value: &Value) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut encoder = Encoder { writer };
+    encoder.writer.write_all(MAGIC)?;
+    encoder.value(value)
+}
+
+/// Decodes a value, the input has to end after it
+pub fn from_bytes(bytes: &[u8]) -> Result<Value> {
+    let mut decoder = Decoder { bytes, position: 0 };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(decode_error!("Not a binary encoded value"));
+    }
+
+    let value = decoder.value()?;
+    if decoder.position != bytes.len() {
+        return Err(decode_error!(
+            "Unexpected bytes after the value at offset {}",
+            decoder.position
+        ));
+    }
+    Ok(value)
+}
+
+struct Encoder<W> {
+    writer: W,
+}
+
+impl<W> Encoder<W>
+where
+    W: Write,
+{
+    fn byte(&mut self, byte: u8) -> io::Result<()> {
+        self.writer.write_all(&[byte])
+    }
+
+    fn varint(&mut self, mut v: u64) -> io::Result<()> {
+        loop {
+            let byte = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                return self.byte(byte);
+            }
+            self.byte(byte | 0x80)?;
+        }
+    }
+
+    fn length(&mut self, length: usize) -> io::Result<()> {
+        self.varint(length as u64)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.length(bytes.len())?;
+        self.writer.write_all(bytes)
+    }
+
+    fn value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Unit => self.byte(UNIT),
+            Value::Bool(false) => self.byte(FALSE),
+            Value::Bool(true) => self.byte(TRUE),
+            Value::Char(c) => {
+                self.byte(CHAR)?;
+                self.varint(*c as u64)
+            },
+            Value::String(s) => {
+                self.byte(STRING)?;
+                self.bytes(s.as_bytes())
+            },
+            Value::Bytes(bytes) => {
+                self.byte(BYTES)?;
+                self.bytes(bytes)
+            },
+            Value::Number(number) => self.number(*number),
+            Value::Type(t) => {
+                self.byte(TYPE)?;
+                self.type_(t)
+            },
+            Value::UnitStruct(identifier) => {
+                self.byte(UNIT_STRUCT)?;
+                self.type_identifier(identifier)
+            },
+            Value::List(items) => {
+                self.byte(LIST)?;
+                self.items(items)
+            },
+            Value::Tuple(items) => {
+                self.byte(TUPLE)?;
+                self.items(items)
+            },
+            Value::Map(map) => {
+                self.byte(MAP)?;
+                self.length(map.len())?;
+                for (key, value) in map {
+                    self.value(key)?;
+                    self.value(value)?;
+                }
+                Ok(())
+            },
+            Value::Option(None) => self.byte(NONE),
+            Value::Option(Some(value)) => {
+                self.byte(SOME)?;
+                self.value(value)
+            },
+            Value::Struct(identifier, fields) => {
+                self.byte(STRUCT)?;
+                self.type_identifier(identifier)?;
+                self.length(fields.len())?;
+                for (name, value) in fields {
+                    self.bytes(name.0.as_bytes())?;
+                    self.value(value)?;
+                }
+                Ok(())
+            },
+            Value::TupleStruct(identifier, items) => {
+                self.byte(TUPLE_STRUCT)?;
+                self.type_identifier(identifier)?;
+                self.items(items)
+            },
+        }
+    }
+
+    fn number(&mut self, number: Number) -> io::Result<()> {
+        match number {
+            Number::I64(v) => {
+                self.byte(I64)?;
+                self.varint(((v << 1) ^ (v >> 63)) as u64)
+            },
+            Number::U64(v) => {
+                self.byte(U64)?;
+                self.varint(v)
+            },
+            Number::I128(v) => {
+                self.byte(I128)?;
+                self.writer.write_all(&v.to_le_bytes())
+            },
+            Number::U128(v) => {
+                self.byte(U128)?;
+                self.writer.write_all(&v.to_le_bytes())
+            },
+            Number::F64(v) => {
+                self.byte(F64)?;
+                self.writer.write_all(&v.to_le_bytes())
+            },
+        }
+    }
+
+    fn items(&mut self, items: &[Value]) -> io::Result<()> {
+        self.length(items.len())?;
+        items.iter().try_for_each(|item| self.value(item))
+    }
+
+    fn type_(&mut self, t: &Type) -> io::Result<()> {
+        match t {
+            Type::TypeIdentifier(identifier) => {
+                self.byte(TYPE_IDENTIFIER)?;
+                self.type_identifier(identifier)
+            },
+            Type::Array { content, size } => {
+                self.byte(TYPE_ARRAY)?;
+                self.type_(content)?;
+                self.bytes(size.as_bytes())
+            },
+            Type::Tuple(types) => {
+                self.byte(TYPE_TUPLE)?;
+                self.types(types)
+            },
+        }
+    }
+
+    fn types(&mut self, types: &[Type]) -> io::Result<()> {
+        self.length(types.len())?;
+        types.iter().try_for_each(|t| self.type_(t))
+    }
+
+    fn type_identifier(
+        &mut self,
+        identifier: &TypeIdentifier,
+    ) -> io::Result<()> {
+        self.length(identifier.segments.len())?;
+        for segment in &identifier.segments {
+            self.bytes(segment.identifier.0.as_bytes())?;
+            match &segment.generics {
+                None => self.byte(0)?,
+                Some(generics) => {
+                    self.byte(1)?;
+                    self.types(&generics.types)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Decoder<'lt> {
+    bytes: &'lt [u8],
+    position: usize,
+}
+
+impl<'lt> Decoder<'lt> {
+    fn take(&mut self, length: usize) -> Result<&'lt [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                decode_error!(
+                    "Unexpected end of input at offset {}",
+                    self.position
+                )
+            })?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let start = self.position;
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            v |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(decode_error!("Invalid varint at offset {}", start))
+    }
+
+    /// A length or count, which can not exceed the remaining input
+    /// since every element takes at least one byte
+    fn length(&mut self) -> Result<usize> {
+        let start = self.position;
+        let length = self.varint()?;
+        if length > (self.bytes.len() - self.position) as u64 {
+            return Err(decode_error!("Invalid length at offset {}", start));
+        }
+        Ok(length as usize)
+    }
+
+    fn bytes(&mut self) -> Result<&'lt [u8]> {
+        let length = self.length()?;
+        self.take(length)
+    }
+
+    fn string(&mut self) -> Result<&'lt str> {
+        let start = self.position;
+        std::str::from_utf8(self.bytes()?).map_err(|_| {
+            decode_error!("Invalid UTF-8 in string at offset {}", start)
+        })
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let start = self.position;
+        Ok(match self.byte()? {
+            UNIT => Value::Unit,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            CHAR => {
+                let c = self.varint()?;
+                Value::Char(
+                    c.try_into()
+                        .ok()
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| {
+                            decode_error!("Invalid char at offset {}", start)
+                        })?,
+                )
+            },
+            STRING => Value::String(self.string()?.into()),
+            BYTES => Value::Bytes(self.bytes()?.to_vec()),
+            I64 => {
+                let v = self.varint()?;
+                Value::Number(Number::I64((v >> 1) as i64 ^ -((v & 1) as i64)))
+            },
+            U64 => Value::Number(Number::U64(self.varint()?)),
+            I128 => {
+                Value::Number(Number::I128(i128::from_le_bytes(self.array()?)))
+            },
+            U128 => {
+                Value::Number(Number::U128(u128::from_le_bytes(self.array()?)))
+            },
+            F64 => {
+                Value::Number(Number::F64(f64::from_le_bytes(self.array()?)))
+            },
+            TYPE => Value::Type(self.type_()?),
+            UNIT_STRUCT => Value::UnitStruct(self.type_identifier()?),
+            LIST => Value::List(self.items()?),
+            TUPLE => Value::Tuple(self.items()?),
+            MAP => {
+                let length = self.length()?;
+                let mut map = Map::new();
+                for _ in 0..length {
+                    let key = self.value()?;
+                    map.insert(key, self.value()?);
+                }
+                Value::Map(map)
+            },
+            NONE => Value::Option(None),
+            SOME => Value::Option(Some(Box::new(self.value()?))),
+            STRUCT => {
+                let identifier = self.type_identifier()?;
+                let length = self.length()?;
+                let mut fields = OrderedMap::new();
+                for _ in 0..length {
+                    let name = Identifier(self.string()?.to_string());
+                    fields.insert(name, self.value()?);
+                }
+                Value::Struct(identifier, fields)
+            },
+            TUPLE_STRUCT => {
+                let identifier = self.type_identifier()?;
+                Value::TupleStruct(identifier, self.items()?)
+            },
+            tag => {
+                return Err(decode_error!(
+                    "Unknown value tag {} at offset {}",
+                    tag,
+                    start
+                ))
+            },
+        })
+    }
+
+    fn items(&mut self) -> Result<Vec<Value>> {
+        let length = self.length()?;
+        (0..length).map(|_| self.value()).collect()
+    }
+
+    fn type_(&mut self) -> Result<Type> {
+        let start = self.position;
+        Ok(match self.byte()? {
+            TYPE_IDENTIFIER => Type::TypeIdentifier(self.type_identifier()?),
+            TYPE_ARRAY => Type::Array {
+                content: Box::new(self.type_()?),
+                size: self.string()?.to_string(),
+            },
+            TYPE_TUPLE => Type::Tuple(self.types()?),
+            tag => {
+                return Err(decode_error!(
+                    "Unknown type tag {} at offset {}",
+                    tag,
+                    start
+                ))
+            },
+        })
+    }
+
+    fn types(&mut self) -> Result<Vec<Type>> {
+        let length = self.length()?;
+        (0..length).map(|_| self.type_()).collect()
+    }
+
+    fn type_identifier(&mut self) -> Result<TypeIdentifier> {
+        let length = self.length()?;
+        let segments = (0..length)
+            .map(|_| {
+                let identifier = Identifier(self.string()?.to_string());
+                let start = self.position;
+                let generics = match self.byte()? {
+                    0 => None,
+                    1 => Some(Generics {
+                        types: self.types()?,
+                    }),
+                    _ => {
+                        return Err(decode_error!(
+                            "Invalid generics at offset {}",
+                            start
+                        ))
+                    },
+                };
+                Ok(GenericIdentifier {
+                    identifier,
+                    generics,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(TypeIdentifier { segments })
+    }
+}
@@ -0,0 +1,155 @@
+use crate::value::path::{Path, PathSegment};
+use std::collections::{btree_map, BTreeMap, VecDeque};
+
+/// Comments of a document, attached to the value following them
+///
+/// Filled by `Value::parse_with_comments` and written back by
+/// `ValuePrinter::with_comments`. A comment belongs to the struct field,
+/// map entry or item it precedes. Comments after the last value of the
+/// document are attached to the document itself.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct Comments {
+    entries: BTreeMap<Path, Vec<String>>,
+}
+
+impl Comments {
+    pub fn new() -> Self {
+        Comments::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Comments in front of the value at `path`, in document order
+    pub fn get(&self, path: &Path) -> &[String] {
+        self.entries.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add<S>(&mut self, path: Path, comment: S)
+    where
+        S: Into<String>,
+    {
+        self.entries.entry(path).or_default().push(comment.into());
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Vec<String> {
+        self.entries.remove(path).unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, Path, Vec<String>> {
+        self.entries.iter()
+    }
+
+    /// Whether a comment is attached to any value inside of `path`
+    pub(crate) fn any_inside(&self, path: &Path) -> bool {
+        // Paths below `path` directly follow it in the ordering
+        self.entries
+            .range(path.clone()..)
+            .find(|(p, _)| *p != path)
+            .is_some_and(|(p, _)| p.starts_with(path))
+    }
+}
+
+/// Attaches comments to paths while a document is parsed
+pub(crate) struct CommentCollector {
+    /// Comments not attached yet, with the offset they end at
+    pending: VecDeque<(usize, String)>,
+    path: Path,
+    comments: Comments,
+}
+
+impl CommentCollector {
+    pub(crate) fn new(input: &str) -> Self {
+        CommentCollector {
+            pending: scan(input).into(),
+            path: Path::root(),
+            comments: Comments::new(),
+        }
+    }
+
+    /// Attaches the comments before `start` to the current path
+    pub(crate) fn attach(&mut self, start: usize) {
+        while let Some((end, _)) = self.pending.front() {
+            if *end > start {
+                break;
+            }
+            let (_, comment) = self.pending.pop_front().unwrap();
+            self.comments.add(self.path.clone(), comment);
+        }
+    }
+
+    /// Descends into the child starting at offset `start`
+    pub(crate) fn enter(&mut self, segment: PathSegment, start: usize) {
+        self.path.push(segment);
+        self.attach(start);
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.path.pop();
+    }
+
+    pub(crate) fn finish(mut self) -> Comments {
+        self.path = Path::root();
+        self.attach(usize::MAX);
+        self.comments
+    }
+}
+
+/// Finds all comments with the offset they end at, skipping literals
+fn scan(input: &str) -> Vec<(usize, String)> {
+    let mut comments = Vec::new();
+    let mut rest = input;
+
+    let offset = |rest: &str| input.len() - rest.len();
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let text = rest[2..end].strip_prefix(' ').unwrap_or(&rest[2..end]);
+            comments.push((offset(&rest[end..]), text.trim_end().to_string()));
+            rest = &rest[end..];
+        } else if rest.starts_with("/*") {
+            let end = rest.find("*/").map_or(rest.len(), |end| end + 2);
+            let text = rest[2..end].trim_end_matches("*/").trim();
+            comments.push((offset(&rest[end..]), text.to_string()));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            rest = skip_quoted(rest, c);
+        } else if c == 'r' && rest[1..].trim_start_matches('#').starts_with('"')
+        {
+            let hashes =
+                rest[1..].len() - rest[1..].trim_start_matches('#').len();
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            let content = &rest[hashes + 2..];
+            rest = match content.find(&terminator) {
+                Some(end) => &content[end + terminator.len()..],
+                None => "",
+            };
+        } else if c.is_alphanumeric() || c == '_' {
+            // Identifiers may contain an `r`, which does not start a raw string
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            rest = &rest[end..];
+        } else {
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    comments
+}
+
+/// Skips a string or char literal
+fn skip_quoted(input: &str, quote: char) -> &str {
+    let mut escaped = false;
+    for (index, c) in input.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return &input[index + 1..],
+            _ => {},
+        }
+    }
+    ""
+}
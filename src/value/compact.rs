@@ -0,0 +1,682 @@
+//! Values of large documents in a few allocations
+//!
+//! A `CompactValue` keeps the nodes of a whole document in one arena, with
+//! the items of every collection next to each other. Strings, field names
+//! and type names are interned, each distinct one is stored once. Parsing
+//! into it skips the allocations `Value` needs for every collection,
+//! string and name, which makes up most of the time and memory spent on
+//! large documents.
+//! `cargo bench -- compact_value` compares parsing into it with
+//! `Value::parse`.
+//!
+//! Maps keep their entries in the order they were written, like struct
+//! fields. `to_value` sorts them the way `Value::Map` does.
+
+use crate::value::{
+    diagnostic::ParseError,
+    number::Number,
+    options::ParseOptions,
+    parser,
+    types::{Identifier, Type, TypeIdentifier},
+    Value,
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    fmt::{Debug, Formatter},
+    hash::Hash,
+    slice,
+    sync::Arc,
+};
+
+/// A value stored in an arena, see the module documentation
+///
+/// It is read through `root`, or converted with `to_value`:
+///
+/// ```
+/// # use typed_format::value::compact::{CompactValue, ValueRef};
+/// let value = CompactValue::parse(r#"Config(name: "web", ports: [80, 443])"#)
+///     .unwrap();
+///
+/// if let ValueRef::Struct(_, mut fields) = value.root().get() {
+///     let (name, ports) = fields.nth(1).unwrap();
+///     assert_eq!(name, "ports");
+///     assert_eq!(ports.to_value().to_string_compact(), "[80,443,]");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CompactValue {
+    root: Node,
+    arena: Arena,
+}
+
+impl CompactValue {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        CompactValue::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parses like `Value::parse_with`
+    ///
+    /// Strings are always shared, as if `dedup_strings` was set.
+    pub fn parse_with(
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        parser::parse_main_compact(input, options)
+    }
+
+    pub fn from_value(value: &Value) -> Self {
+        let mut builder = Builder::new();
+        let root = builder.value(value);
+        builder.finish(root)
+    }
+
+    pub fn root(&self) -> CompactRef<'_> {
+        CompactRef {
+            arena: &self.arena,
+            node: self.root,
+        }
+    }
+
+    /// Builds the equivalent `Value`, sharing its strings
+    pub fn to_value(&self) -> Value {
+        self.root().to_value()
+    }
+}
+
+impl From<&Value> for CompactValue {
+    fn from(value: &Value) -> Self {
+        CompactValue::from_value(value)
+    }
+}
+
+impl From<&CompactValue> for Value {
+    fn from(value: &CompactValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl Debug for CompactValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.root(), f)
+    }
+}
+
+/// A value inside of a `CompactValue`
+#[derive(Copy, Clone)]
+pub struct CompactRef<'lt> {
+    arena: &'lt Arena,
+    node: Node,
+}
+
+impl<'lt> CompactRef<'lt> {
+    pub fn get(self) -> ValueRef<'lt> {
+        let arena = self.arena;
+        match self.node {
+            Node::Unit => ValueRef::Unit,
+            Node::Bool(b) => ValueRef::Bool(b),
+            Node::Char(c) => ValueRef::Char(c),
+            Node::String(symbol) => ValueRef::String(arena.strings.get(symbol)),
+            Node::I64(v) => ValueRef::Number(Number::I64(v)),
+            Node::U64(v) => ValueRef::Number(Number::U64(v)),
+            Node::F64(v) => ValueRef::Number(Number::F64(v)),
+            Node::UnitStruct(name) => {
+                ValueRef::UnitStruct(arena.names.get(name))
+            },
+            Node::None => ValueRef::Option(None),
+            Node::Some(index) => ValueRef::Option(Some(CompactRef {
+                arena,
+                node: arena.nodes[index as usize],
+            })),
+            Node::List(slice) => ValueRef::List(arena.items(slice)),
+            Node::Tuple(slice) => ValueRef::Tuple(arena.items(slice)),
+            Node::Map(slice) => ValueRef::Map(Entries(arena.items(slice))),
+            Node::Struct(name, slice) => ValueRef::Struct(
+                arena.names.get(name),
+                Fields(arena.items(slice)),
+            ),
+            Node::TupleStruct(name, slice) => {
+                ValueRef::TupleStruct(arena.names.get(name), arena.items(slice))
+            },
+            Node::Other(index) => match &arena.others[index as usize] {
+                Value::Bytes(bytes) => ValueRef::Bytes(bytes),
+                Value::Number(number) => ValueRef::Number(*number),
+                Value::Type(v) => ValueRef::Type(v),
+                other => unreachable!("{:?} has a node of its own", other),
+            },
+        }
+    }
+
+    /// Builds the equivalent `Value`, sharing its strings
+    pub fn to_value(self) -> Value {
+        let arena = self.arena;
+        match self.node {
+            Node::String(symbol) => {
+                Value::String(arena.strings.get(symbol).clone())
+            },
+            Node::Other(index) => arena.others[index as usize].clone(),
+            _ => match self.get() {
+                ValueRef::Unit => Value::Unit,
+                ValueRef::Bool(b) => Value::Bool(b),
+                ValueRef::Char(c) => Value::Char(c),
+                ValueRef::Number(number) => Value::Number(number),
+                ValueRef::UnitStruct(name) => Value::UnitStruct(name.clone()),
+                ValueRef::Option(option) => Value::Option(
+                    option.map(|inner| Box::new(inner.to_value())),
+                ),
+                ValueRef::List(items) => {
+                    Value::List(items.map(CompactRef::to_value).collect())
+                },
+                ValueRef::Tuple(items) => {
+                    Value::Tuple(items.map(CompactRef::to_value).collect())
+                },
+                ValueRef::Map(entries) => Value::Map(
+                    entries
+                        .map(|(key, value)| (key.to_value(), value.to_value()))
+                        .collect(),
+                ),
+                ValueRef::Struct(name, fields) => Value::Struct(
+                    name.clone(),
+                    fields
+                        .map(|(field, value)| {
+                            (Identifier::from(field), value.to_value())
+                        })
+                        .collect(),
+                ),
+                ValueRef::TupleStruct(name, items) => Value::TupleStruct(
+                    name.clone(),
+                    items.map(CompactRef::to_value).collect(),
+                ),
+                ValueRef::String(_)
+                | ValueRef::Bytes(_)
+                | ValueRef::Type(_) => {
+                    unreachable!("Stored as a Value")
+                },
+            },
+        }
+    }
+}
+
+impl Debug for CompactRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.to_value(), f)
+    }
+}
+
+/// The contents of a `CompactRef`, with the variants of `Value`
+#[derive(Clone, Debug)]
+pub enum ValueRef<'lt> {
+    Unit,
+    Bool(bool),
+    Char(char),
+    String(&'lt str),
+    Bytes(&'lt [u8]),
+    Number(Number),
+
+    Type(&'lt Type),
+    UnitStruct(&'lt TypeIdentifier),
+
+    List(Items<'lt>),
+    Tuple(Items<'lt>),
+    Map(Entries<'lt>),
+    Option(Option<CompactRef<'lt>>),
+
+    Struct(&'lt TypeIdentifier, Fields<'lt>),
+    TupleStruct(&'lt TypeIdentifier, Items<'lt>),
+}
+
+/// The items of a list, tuple or tuple struct
+#[derive(Clone)]
+pub struct Items<'lt> {
+    arena: &'lt Arena,
+    nodes: slice::Iter<'lt, Node>,
+}
+
+impl<'lt> Iterator for Items<'lt> {
+    type Item = CompactRef<'lt>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = *self.nodes.next()?;
+        Some(CompactRef {
+            arena: self.arena,
+            node,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Items<'_> {}
+
+impl Debug for Items<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// The entries of a map, in the order they were written
+#[derive(Clone)]
+pub struct Entries<'lt>(Items<'lt>);
+
+impl<'lt> Iterator for Entries<'lt> {
+    type Item = (CompactRef<'lt>, CompactRef<'lt>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((self.0.next()?, self.0.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len() / 2;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Entries<'_> {}
+
+impl Debug for Entries<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.clone()).finish()
+    }
+}
+
+/// The fields of a struct, in the order they were written
+#[derive(Clone)]
+pub struct Fields<'lt>(Items<'lt>);
+
+impl<'lt> Iterator for Fields<'lt> {
+    type Item = (&'lt str, CompactRef<'lt>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let field = match self.0.next()?.node {
+            Node::String(symbol) => self.0.arena.strings.get(symbol),
+            node => unreachable!("Field name {:?}", node),
+        };
+        Some((field, self.0.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len() / 2;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Fields<'_> {}
+
+impl Debug for Fields<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.clone()).finish()
+    }
+}
+
+/// Everything of a `CompactValue` but its root node
+#[derive(Clone, Default)]
+struct Arena {
+    /// Items of all collections, those of each collection next to each
+    /// other. Entries of maps and structs take two nodes, the key first.
+    nodes: Vec<Node>,
+    /// Strings and field names
+    strings: Interner<str>,
+    /// Names of structs
+    names: Interner<TypeIdentifier>,
+    /// Rare values without a node of their own: bytes, types and
+    /// numbers of 128 bits
+    others: Vec<Value>,
+}
+
+impl Arena {
+    fn items(&self, slice: Slice) -> Items<'_> {
+        let start = slice.start as usize;
+        Items {
+            arena: self,
+            nodes: self.nodes[start..start + slice.len as usize].iter(),
+        }
+    }
+}
+
+/// A value in an `Arena`, small enough to be copied around
+///
+/// Indices are 32 bits, which limits documents to 4 billion nodes.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Node {
+    Unit,
+    Bool(bool),
+    Char(char),
+    /// Index into `Arena::strings`
+    String(u32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    /// Index into `Arena::names`
+    UnitStruct(u32),
+    None,
+    /// Index of the inner node in `Arena::nodes`
+    Some(u32),
+    List(Slice),
+    Tuple(Slice),
+    Map(Slice),
+    Struct(u32, Slice),
+    TupleStruct(u32, Slice),
+    /// Index into `Arena::others`
+    Other(u32),
+}
+
+/// Consecutive nodes in `Arena::nodes`
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Slice {
+    start: u32,
+    len: u32,
+}
+
+/// Stores every distinct item once, behind a number
+struct Interner<T: ?Sized> {
+    items: Vec<Arc<T>>,
+    indices: HashMap<Arc<T>, u32>,
+}
+
+impl<T: ?Sized> Clone for Interner<T> {
+    fn clone(&self) -> Self {
+        Interner {
+            items: self.items.clone(),
+            indices: self.indices.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized> Default for Interner<T> {
+    fn default() -> Self {
+        Interner {
+            items: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Interner<T>
+where
+    T: Hash + Eq + ?Sized,
+{
+    /// The number of `item`, which `share` only has to store if it is new
+    fn intern<F>(&mut self, item: &T, share: F) -> u32
+    where
+        F: FnOnce() -> Arc<T>,
+    {
+        if let Some(index) = self.indices.get(item) {
+            return *index;
+        }
+
+        let shared = share();
+        let index = to_index(self.items.len());
+        self.items.push(shared.clone());
+        self.indices.insert(shared, index);
+        index
+    }
+
+    fn get(&self, index: u32) -> &Arc<T> {
+        &self.items[index as usize]
+    }
+}
+
+fn to_index(index: usize) -> u32 {
+    u32::try_from(index).expect("Compact values hold at most 2^32 nodes")
+}
+
+/// A map key or struct field that can be looked up without comparing
+/// whole values
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum Key {
+    Unit,
+    Bool(bool),
+    Char(char),
+    String(u32),
+    Number(Number),
+    UnitStruct(u32),
+    None,
+}
+
+impl Key {
+    fn of(node: Node) -> Option<Key> {
+        Some(match node {
+            Node::Unit => Key::Unit,
+            Node::Bool(b) => Key::Bool(b),
+            Node::Char(c) => Key::Char(c),
+            Node::String(symbol) => Key::String(symbol),
+            Node::I64(v) => Key::Number(Number::I64(v)),
+            Node::U64(v) => Key::Number(Number::U64(v)),
+            Node::F64(v) => Key::Number(Number::F64(v)),
+            Node::UnitStruct(name) => Key::UnitStruct(name),
+            Node::None => Key::None,
+            _ => return None,
+        })
+    }
+}
+
+/// Maps and structs with up to this many entries are searched entry by
+/// entry while they are built, which is faster than hashing for the few
+/// fields of most structs
+const SCANNED_ENTRIES: usize = 8;
+
+/// A collection in a `Builder` that is not complete yet
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Open {
+    id: u32,
+    /// Position of its first item in `Builder::pending`
+    start: usize,
+}
+
+/// Builds a `CompactValue` from the leaves up
+pub(crate) struct Builder {
+    arena: Arena,
+    /// Items of the collections being built, moved to the arena once
+    /// their collection is complete
+    pending: Vec<Node>,
+    /// Positions in `pending` of the keys of the open maps and structs
+    /// with more than `SCANNED_ENTRIES` entries
+    keys: HashMap<(u32, Key), usize>,
+    /// Number of collections opened so far
+    opened: u32,
+    /// The names of `plain_name`, found without making a `TypeIdentifier`
+    plain_names: HashMap<Box<str>, u32>,
+}
+
+impl Builder {
+    pub(crate) fn new() -> Self {
+        Builder {
+            arena: Arena::default(),
+            pending: Vec::new(),
+            keys: HashMap::new(),
+            opened: 0,
+            plain_names: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn finish(self, root: Node) -> CompactValue {
+        CompactValue {
+            root,
+            arena: self.arena,
+        }
+    }
+
+    pub(crate) fn string(&mut self, string: &str) -> Node {
+        Node::String(self.arena.strings.intern(string, || string.into()))
+    }
+
+    pub(crate) fn name(&mut self, name: &TypeIdentifier) -> u32 {
+        self.arena.names.intern(name, || Arc::new(name.clone()))
+    }
+
+    /// Like `name`, for a name of a single segment without generics
+    pub(crate) fn plain_name(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.plain_names.get(name) {
+            return *index;
+        }
+
+        let index = self.name(&TypeIdentifier::from(name));
+        self.plain_names.insert(name.into(), index);
+        index
+    }
+
+    pub(crate) fn some(&mut self, inner: Node) -> Node {
+        self.arena.nodes.push(inner);
+        Node::Some(to_index(self.arena.nodes.len() - 1))
+    }
+
+    pub(crate) fn open(&mut self) -> Open {
+        self.opened += 1;
+        Open {
+            id: self.opened,
+            start: self.pending.len(),
+        }
+    }
+
+    /// Adds an item to the innermost open collection
+    pub(crate) fn push(&mut self, node: Node) {
+        self.pending.push(node);
+    }
+
+    /// Moves the items of the innermost open collection to the arena
+    pub(crate) fn close(&mut self, open: Open) -> Slice {
+        let slice = Slice {
+            start: to_index(self.arena.nodes.len()),
+            len: to_index(self.pending.len() - open.start),
+        };
+        self.arena.nodes.extend(self.pending.drain(open.start..));
+        slice
+    }
+
+    /// Whether the innermost open map or struct has an entry with `key`
+    pub(crate) fn contains_key(&self, open: Open, key: Node) -> bool {
+        self.position(open, key).is_some()
+    }
+
+    /// Adds an entry to the innermost open map or struct, replacing the
+    /// value of an existing one with the same key in place
+    pub(crate) fn insert(&mut self, open: Open, key: Node, value: Node) {
+        if let Some(position) = self.position(open, key) {
+            self.pending[position + 1] = value;
+            return;
+        }
+
+        // Once it grows past the scanned entries, all keys are indexed
+        let entries = (self.pending.len() - open.start) / 2;
+        if entries == SCANNED_ENTRIES {
+            for position in (open.start..self.pending.len()).step_by(2) {
+                if let Some(hashed) = Key::of(self.pending[position]) {
+                    self.keys.insert((open.id, hashed), position);
+                }
+            }
+        }
+        if entries >= SCANNED_ENTRIES {
+            if let Some(hashed) = Key::of(key) {
+                self.keys.insert((open.id, hashed), self.pending.len());
+            }
+        }
+        self.pending.push(key);
+        self.pending.push(value);
+    }
+
+    /// Like `close`, for maps and structs built with `insert`
+    pub(crate) fn close_entries(&mut self, open: Open) -> Slice {
+        if self.pending.len() - open.start > 2 * SCANNED_ENTRIES {
+            for key in self.pending[open.start..].iter().step_by(2) {
+                if let Some(hashed) = Key::of(*key) {
+                    self.keys.remove(&(open.id, hashed));
+                }
+            }
+        }
+        self.close(open)
+    }
+
+    fn position(&self, open: Open, key: Node) -> Option<usize> {
+        let entries = open.start..self.pending.len();
+        match Key::of(key) {
+            Some(hashed) if entries.len() > 2 * SCANNED_ENTRIES => {
+                self.keys.get(&(open.id, hashed)).copied()
+            },
+            Some(hashed) => entries.step_by(2).find(|position| {
+                Key::of(self.pending[*position]) == Some(hashed)
+            }),
+            // Collections and rare values are compared as values, which
+            // is slow but hardly ever needed
+            None => {
+                let key = self.to_value(key);
+                entries.step_by(2).find(|position| {
+                    Key::of(self.pending[*position]).is_none()
+                        && self.to_value(self.pending[*position]) == key
+                })
+            },
+        }
+    }
+
+    /// The value of a node whose collections are complete
+    pub(crate) fn to_value(&self, node: Node) -> Value {
+        CompactRef {
+            arena: &self.arena,
+            node,
+        }
+        .to_value()
+    }
+
+    /// Stores a whole value
+    pub(crate) fn value(&mut self, value: &Value) -> Node {
+        match value {
+            Value::Unit => Node::Unit,
+            Value::Bool(b) => Node::Bool(*b),
+            Value::Char(c) => Node::Char(*c),
+            Value::String(s) => {
+                Node::String(self.arena.strings.intern(s, || s.clone()))
+            },
+            Value::Number(Number::I64(v)) => Node::I64(*v),
+            Value::Number(Number::U64(v)) => Node::U64(*v),
+            Value::Number(Number::F64(v)) => Node::F64(*v),
+            Value::Number(_) | Value::Bytes(_) | Value::Type(_) => {
+                self.arena.others.push(value.clone());
+                Node::Other(to_index(self.arena.others.len() - 1))
+            },
+            Value::UnitStruct(name) => Node::UnitStruct(self.name(name)),
+            Value::Option(None) => Node::None,
+            Value::Option(Some(inner)) => {
+                let inner = self.value(inner);
+                self.some(inner)
+            },
+            Value::List(items) => Node::List(self.values(items)),
+            Value::Tuple(items) => Node::Tuple(self.values(items)),
+            Value::Map(map) => {
+                let open = self.open();
+                for (key, value) in map {
+                    let key = self.value(key);
+                    let value = self.value(value);
+                    self.push(key);
+                    self.push(value);
+                }
+                Node::Map(self.close(open))
+            },
+            Value::Struct(name, fields) => {
+                let name = self.name(name);
+                let open = self.open();
+                for (field, value) in fields {
+                    let field = self.string(&field.0);
+                    let value = self.value(value);
+                    self.push(field);
+                    self.push(value);
+                }
+                Node::Struct(name, self.close(open))
+            },
+            Value::TupleStruct(name, items) => {
+                let name = self.name(name);
+                Node::TupleStruct(name, self.values(items))
+            },
+        }
+    }
+
+    fn values(&mut self, values: &[Value]) -> Slice {
+        let open = self.open();
+        for value in values {
+            let node = self.value(value);
+            self.push(node);
+        }
+        self.close(open)
+    }
+}
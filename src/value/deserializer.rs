@@ -1,36 +1,307 @@
-use crate::value::{
-    types::{GenericIdentifier, Identifier, Type, TypeIdentifier},
-    ParsedNumber, Value,
+use crate::{
+    literals, raw,
+    value::{
+        embed,
+        map::{self, OrderedIter, OrderedMap},
+        number::Number,
+        options::{DeserializeOptions, TypeNameCheck},
+        path::{Path, PathSegment},
+        types::{Identifier, Type, TypeIdentifier},
+        Value,
+    },
 };
-use anyhow::{anyhow, Context, Error};
 use serde::{
     de::{
-        DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess,
-        Visitor,
+        value::BorrowedStrDeserializer, DeserializeSeed, EnumAccess,
+        IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
     },
     Deserializer,
 };
 use std::{
-    collections::btree_map,
+    collections::HashMap,
     fmt::{Display, Formatter},
+    ops::Range,
 };
 
-pub struct ValueDeserializer<'value> {
+/// Deserializes Rust types from a Value
+///
+/// Strings and bytes that `S` can lend out for `'de` are visited as
+/// borrowed, so types like `&str` can point into them.
+pub struct ValueDeserializer<'value, S = FromValue> {
     pub value: &'value Value,
+    shared: Shared<S>,
+    /// Set for map keys, which read a name like `host` as a string
+    key: bool,
+}
+
+impl<'value> ValueDeserializer<'value> {
+    /// A deserializer lending out the strings of `value` itself
+    pub fn new(value: &'value Value) -> Self {
+        ValueDeserializer::with_source(value, FromValue)
+    }
 }
 
+impl<'value, S> ValueDeserializer<'value, S> {
+    /// A deserializer lending out strings from `source`
+    pub(crate) fn with_source(value: &'value Value, source: S) -> Self {
+        ValueDeserializer {
+            value,
+            shared: Shared {
+                source,
+                options: DeserializeOptions::default(),
+            },
+            key: false,
+        }
+    }
+
+    /// A deserializer for a map key, which reads a name like `host` as a
+    /// string
+    pub(crate) fn map_key(value: &'value Value, source: S) -> Self {
+        ValueDeserializer {
+            key: true,
+            ..Self::with_source(value, source)
+        }
+    }
+
+    /// Applies settings like skipping unknown fields
+    pub fn with_options(mut self, options: DeserializeOptions) -> Self {
+        self.shared.options = options;
+        self
+    }
+
+    /// A deserializer for a part of the value
+    fn child(value: &'value Value, shared: Shared<S>) -> Self {
+        ValueDeserializer {
+            value,
+            shared,
+            key: false,
+        }
+    }
+
+    /// A deserializer for a map key, see `bare_key`
+    fn key(value: &'value Value, shared: Shared<S>) -> Self {
+        ValueDeserializer {
+            value,
+            shared,
+            key: true,
+        }
+    }
+
+    /// Fails if the document names a type other than `name`
+    ///
+    /// Structs written without a name pass, as do variants and
+    /// `deserialize_any`, which have no name to compare to.
+    pub(crate) fn check_type_name(
+        &self,
+        name: &str,
+        identifier: &TypeIdentifier,
+    ) -> Result<(), ValueDeserializerError> {
+        let segments = identifier.segments.as_slice();
+        let expected = name.split("::").collect::<Vec<_>>();
+        let ends_with_name = || {
+            segments.len() >= expected.len()
+                && segments[segments.len() - expected.len()..]
+                    .iter()
+                    .zip(&expected)
+                    .all(|(segment, name)| segment.identifier.0 == *name)
+        };
+        let matches = match (self.shared.options.type_names, segments) {
+            (TypeNameCheck::Ignore, _) | (_, []) => true,
+            _ if name.is_empty() => true,
+            (TypeNameCheck::LastSegment, [.., last]) => {
+                name.rsplit("::").next() == Some(last.identifier.0.as_str())
+            },
+            (TypeNameCheck::Suffix, _) => ends_with_name(),
+            (TypeNameCheck::Exact, _) => {
+                segments.len() == expected.len() && ends_with_name()
+            },
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(ValueDeserializerError::TypeMismatch {
+                expected: name.to_string(),
+                found: identifier.to_string(),
+                path: Path::root(),
+            })
+        }
+    }
+}
+
+/// State a deserializer passes on to the parts of its value
+#[derive(Copy, Clone)]
+struct Shared<S> {
+    source: S,
+    options: DeserializeOptions,
+}
+
+/// Lends out strings of a deserialized Value for the lifetime `'de`
+pub trait StringSource<'value, 'de>: Copy {
+    fn borrow_str(self, string: &'value str) -> Option<&'de str>;
+    fn borrow_bytes(self, bytes: &'value [u8]) -> Option<&'de [u8]>;
+}
+
+/// Lends out the strings of the Value being deserialized
+#[derive(Copy, Clone, Debug)]
+pub struct FromValue;
+
+impl<'de> StringSource<'de, 'de> for FromValue {
+    fn borrow_str(self, string: &'de str) -> Option<&'de str> {
+        Some(string)
+    }
+
+    fn borrow_bytes(self, bytes: &'de [u8]) -> Option<&'de [u8]> {
+        Some(bytes)
+    }
+}
+
+/// Lends out nothing, for Values that don't live as long as `'de`
+#[derive(Copy, Clone, Debug)]
+pub struct Transient;
+
+impl<'value, 'de> StringSource<'value, 'de> for Transient {
+    fn borrow_str(self, _: &'value str) -> Option<&'de str> {
+        None
+    }
+
+    fn borrow_bytes(self, _: &'value [u8]) -> Option<&'de [u8]> {
+        None
+    }
+}
+
+/// Lends out slices of the text a Value was parsed from
+///
+/// Only strings written without escape sequences have such a slice.
+pub(crate) struct InputStrings<'de> {
+    input: &'de str,
+    /// Ranges of string contents, by the address of the parsed string
+    ranges: HashMap<usize, Range<usize>>,
+}
+
+impl<'de> InputStrings<'de> {
+    pub(crate) fn new(
+        input: &'de str,
+        ranges: HashMap<usize, Range<usize>>,
+    ) -> Self {
+        InputStrings { input, ranges }
+    }
+
+    /// The key of a parsed string in `ranges`
+    pub(crate) fn address(string: &str) -> usize {
+        string.as_ptr() as usize
+    }
+}
+
+impl<'value, 'de> StringSource<'value, 'de> for &InputStrings<'de> {
+    fn borrow_str(self, string: &'value str) -> Option<&'de str> {
+        let range = self.ranges.get(&InputStrings::address(string))?;
+        // The address may belong to a string that was dropped while parsing
+        Some(&self.input[range.clone()]).filter(|slice| *slice == string)
+    }
+
+    fn borrow_bytes(self, _: &'value [u8]) -> Option<&'de [u8]> {
+        None
+    }
+}
+
+/// Error of deserializing a value, with the location of the failing part
+///
+/// Displays as `servers[2].port: Expected Number, found String`.
 #[derive(Debug)]
-pub struct ValueDeserializerError(pub anyhow::Error);
+pub enum ValueDeserializerError {
+    /// A value of another kind or with another type name than expected
+    TypeMismatch {
+        expected: String,
+        found: String,
+        path: Path,
+    },
+    /// A struct field the Rust type does not have
+    UnknownField {
+        field: String,
+        expected: &'static [&'static str],
+        path: Path,
+    },
+    /// A struct field the Rust type requires, but the value does not have
+    MissingField { field: &'static str, path: Path },
+    /// A number that does not fit the Rust number type
+    InvalidNumber { number: Number, path: Path },
+    /// Any other error, like one raised by a Deserialize implementation
+    Custom { message: String, path: Path },
+}
 
-impl From<anyhow::Error> for ValueDeserializerError {
-    fn from(e: Error) -> Self {
-        ValueDeserializerError(e)
+impl ValueDeserializerError {
+    /// Path from the deserialized value to the part that failed
+    pub fn path(&self) -> &Path {
+        match self {
+            ValueDeserializerError::TypeMismatch { path, .. }
+            | ValueDeserializerError::UnknownField { path, .. }
+            | ValueDeserializerError::MissingField { path, .. }
+            | ValueDeserializerError::InvalidNumber { path, .. }
+            | ValueDeserializerError::Custom { path, .. } => path,
+        }
+    }
+
+    fn path_mut(&mut self) -> &mut Path {
+        match self {
+            ValueDeserializerError::TypeMismatch { path, .. }
+            | ValueDeserializerError::UnknownField { path, .. }
+            | ValueDeserializerError::MissingField { path, .. }
+            | ValueDeserializerError::InvalidNumber { path, .. }
+            | ValueDeserializerError::Custom { path, .. } => path,
+        }
+    }
+
+    /// Moves the error into a child of the value at `segment`
+    pub(crate) fn within(mut self, segment: PathSegment) -> Self {
+        self.path_mut().segments.insert(0, segment);
+        self
+    }
+
+    pub(crate) fn custom_message<M>(message: M) -> Self
+    where
+        M: Display,
+    {
+        ValueDeserializerError::Custom {
+            message: message.to_string(),
+            path: Path::root(),
+        }
     }
 }
 
 impl Display for ValueDeserializerError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self.0, f)
+        if !self.path().is_root() {
+            write!(f, "{}: ", self.path())?;
+        }
+        match self {
+            ValueDeserializerError::TypeMismatch {
+                expected, found, ..
+            } => write!(f, "Expected {}, found {}", expected, found),
+            ValueDeserializerError::UnknownField {
+                field, expected, ..
+            } => {
+                write!(f, "unknown field `{}`, ", field)?;
+                if expected.is_empty() {
+                    write!(f, "there are no fields")
+                } else {
+                    let expected = expected
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect::<Vec<_>>();
+                    write!(f, "expected one of {}", expected.join(", "))
+                }
+            },
+            ValueDeserializerError::MissingField { field, .. } => {
+                write!(f, "missing field `{}`", field)
+            },
+            ValueDeserializerError::InvalidNumber { number, .. } => {
+                write!(f, "{} does not fit the number type", number)
+            },
+            ValueDeserializerError::Custom { message, .. } => {
+                Display::fmt(message, f)
+            },
+        }
     }
 }
 
@@ -41,15 +312,54 @@ impl serde::de::Error for ValueDeserializerError {
     where
         T: Display,
     {
-        ValueDeserializerError(anyhow!("{}", msg))
+        ValueDeserializerError::custom_message(msg)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        ValueDeserializerError::UnknownField {
+            field: field.to_string(),
+            expected,
+            path: Path::root(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ValueDeserializerError::MissingField {
+            field,
+            path: Path::root(),
+        }
+    }
+}
+
+/// The name of a map key written as an identifier, as in
+/// `{ host: "localhost" }`, which reads as a string
+fn bare_key(key: &Value) -> Option<&str> {
+    match key {
+        Value::UnitStruct(name) => name.bare_name().map(|name| name.0.as_str()),
+        _ => None,
+    }
+}
+
+/// Error for a value of the wrong kind
+pub(crate) fn unexpected(
+    expected: &str,
+    found: &Value,
+) -> ValueDeserializerError {
+    ValueDeserializerError::TypeMismatch {
+        expected: expected.to_string(),
+        found: found.variant_name().to_string(),
+        path: Path::root(),
     }
 }
 
 macro_rules! number_body {
     ($this:expr, $visitor:expr, $visit_function:ident) => {{
-        expect_deserialize!($this, Value::Number(__v), {
-            let __v = __v.parse().with_context(|| {
-                format!("Failed to parse {:?} as number", ($this).value)
+        expect_deserialize!($this, "Number", Value::Number(__v), {
+            let __v = __v.to_primitive().ok_or_else(|| {
+                ValueDeserializerError::InvalidNumber {
+                    number: *__v,
+                    path: Path::root(),
+                }
             })?;
             Ok(($visitor).$visit_function::<ValueDeserializerError>(__v)?)
         })
@@ -57,19 +367,18 @@ macro_rules! number_body {
 }
 
 macro_rules! expect_deserialize {
-    ($this:expr, $pattern:pat, $match_arm:expr) => {{
+    ($this:expr, $expected:expr, $pattern:pat, $match_arm:expr) => {{
         match ($this).value {
             $pattern => $match_arm,
-            _ => Err(ValueDeserializerError(anyhow!(
-                "Expected {}, found {:?}",
-                stringify!($pattern),
-                ($this).value,
-            ))),
+            _ => Err(unexpected($expected, ($this).value)),
         }
     }};
 }
 
-impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
+impl<'value, 'de, S> Deserializer<'de> for ValueDeserializer<'value, S>
+where
+    S: StringSource<'value, 'de>,
+{
     type Error = ValueDeserializerError;
 
     fn deserialize_any<V>(
@@ -84,20 +393,36 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
             Value::Bool(_) => self.deserialize_bool(visitor),
             Value::Char(_) => self.deserialize_char(visitor),
             Value::String(_) => self.deserialize_str(visitor),
-            Value::Number(v) => match ParsedNumber::parse(&v) {
-                Some(ParsedNumber::U64(v)) => visitor.visit_u64(v),
-                Some(ParsedNumber::I64(v)) => visitor.visit_i64(v),
-                Some(ParsedNumber::F64(v)) => visitor.visit_f64(v),
-                None => Err(ValueDeserializerError(anyhow!(
-                    "Could not parse '{}' as a number",
-                    v
-                ))),
+            Value::Bytes(_) => self.deserialize_bytes(visitor),
+            Value::Number(v) => match *v {
+                Number::I64(v) => visitor.visit_i64(v),
+                Number::U64(v) => visitor.visit_u64(v),
+                Number::I128(v) => visitor.visit_i128(v),
+                Number::U128(v) => visitor.visit_u128(v),
+                Number::F64(v) => visitor.visit_f64(v),
             },
-            Value::Type(_) => self.deserialize_identifier(visitor),
+            Value::Type(Type::TypeIdentifier(_)) | Value::UnitStruct(_) => {
+                self.deserialize_identifier(visitor)
+            },
+            // Array and tuple types have no name to visit
+            Value::Type(t) => visitor.visit_string(t.to_string()),
             Value::List(_) => self.deserialize_seq(visitor),
             Value::Tuple(_) => self.deserialize_tuple(0, visitor),
             Value::Map(_) => self.deserialize_map(visitor),
             Value::Option(_) => self.deserialize_option(visitor),
+            // Enum variants are visited as a map from their name to their
+            // contents, which untagged and internally tagged enums can
+            // buffer and dispatch on
+            Value::Struct(identifier, _)
+            | Value::TupleStruct(identifier, _)
+                if identifier.variant().is_some() =>
+            {
+                visitor.visit_map(ValueDeserializerVariant {
+                    name: identifier.variant().map(|name| name.0.as_str()),
+                    value: self.value,
+                    shared: self.shared,
+                })
+            },
             Value::Struct(_, _) => self.deserialize_struct("", &[], visitor),
             Value::TupleStruct(_, _) => {
                 self.deserialize_tuple_struct("", 0, visitor)
@@ -116,10 +441,7 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
             Value::Bool(v) => {
                 Ok(visitor.visit_bool::<ValueDeserializerError>(*v)?)
             },
-            _ => Err(ValueDeserializerError(anyhow!(
-                "Expected Bool, found {:?}",
-                self.value
-            ))),
+            _ => Err(unexpected("Bool", self.value)),
         }
     }
 
@@ -163,6 +485,16 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
         number_body!(self, visitor, visit_i64)
     }
 
+    fn deserialize_i128<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        number_body!(self, visitor, visit_i128)
+    }
+
     fn deserialize_u8<V>(
         self,
         visitor: V,
@@ -203,6 +535,16 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
         number_body!(self, visitor, visit_u64)
     }
 
+    fn deserialize_u128<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        number_body!(self, visitor, visit_u128)
+    }
+
     fn deserialize_f32<V>(
         self,
         visitor: V,
@@ -232,6 +574,7 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     {
         expect_deserialize!(
             self,
+            "Char",
             Value::Char(c),
             visitor.visit_char::<ValueDeserializerError>(*c)
         )
@@ -244,11 +587,22 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(
-            self,
-            Value::String(s),
-            visitor.visit_str::<ValueDeserializerError>(&s)
-        )
+        // `datetime"..."` and `uuid"..."` literals hold their string
+        let string = match literals::tagged_string(self.value) {
+            Some(Value::String(s)) => s,
+            _ => match self.value {
+                Value::String(s) => s,
+                key if self.key => match bare_key(key) {
+                    Some(name) => name,
+                    None => return Err(unexpected("String", self.value)),
+                },
+                _ => return Err(unexpected("String", self.value)),
+            },
+        };
+        match self.shared.source.borrow_str(string) {
+            Some(s) => visitor.visit_borrowed_str(s),
+            None => visitor.visit_str(string),
+        }
     }
 
     fn deserialize_string<V>(
@@ -268,13 +622,26 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::String(s), {
-            let b = base64::decode(&s).with_context(|| {
-                format!("Could not decode as base 64: {:?}", self.value)
-            })?;
-
-            visitor.visit_bytes::<ValueDeserializerError>(&b)
-        })
+        match self.value {
+            Value::Bytes(b) => match self.shared.source.borrow_bytes(b) {
+                Some(b) => visitor.visit_borrowed_bytes(b),
+                None => visitor.visit_bytes(b),
+            },
+            // Sequences of u8, as serde writes them by default
+            Value::List(items) => {
+                let b = items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        item.deserialize::<u8>().map_err(|error| {
+                            error.within(PathSegment::Index(index))
+                        })
+                    })
+                    .collect::<Result<Vec<u8>, _>>()?;
+                visitor.visit_byte_buf(b)
+            },
+            _ => Err(unexpected("Bytes", self.value)),
+        }
     }
 
     fn deserialize_byte_buf<V>(
@@ -294,12 +661,14 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::Option(v), {
-            match v {
-                Some(value) => visitor.visit_some(ValueDeserializer { value }),
-                None => visitor.visit_none::<ValueDeserializerError>(),
-            }
-        })
+        match self.value {
+            Value::Option(Some(value)) => {
+                visitor.visit_some(ValueDeserializer::child(value, self.shared))
+            },
+            Value::Option(None) => visitor.visit_none(),
+            _ if self.shared.options.implicit_some => visitor.visit_some(self),
+            _ => Err(unexpected("Option", self.value)),
+        }
     }
 
     fn deserialize_unit<V>(
@@ -309,7 +678,7 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::Unit, {
+        expect_deserialize!(self, "Unit", Value::Unit, {
             visitor.visit_unit::<ValueDeserializerError>()
         })
     }
@@ -322,7 +691,20 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple_struct(name, 0, visitor)
+        match self.value {
+            // Written as `()` when names are omitted
+            Value::Unit => visitor.visit_unit::<ValueDeserializerError>(),
+            Value::UnitStruct(identifier)
+            | Value::Type(Type::TypeIdentifier(identifier)) => {
+                self.check_type_name(name, identifier)?;
+                visitor.visit_unit::<ValueDeserializerError>()
+            },
+            Value::TupleStruct(identifier, seq) if seq.is_empty() => {
+                self.check_type_name(name, identifier)?;
+                visitor.visit_unit::<ValueDeserializerError>()
+            },
+            _ => Err(unexpected("UnitStruct", self.value)),
+        }
     }
 
     fn deserialize_newtype_struct<V>(
@@ -333,7 +715,23 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple_struct(name, 1, visitor)
+        if name == raw::TOKEN {
+            return visitor.visit_string(self.value.to_string_compact());
+        }
+        if name == embed::TOKEN {
+            embed::hand_over(self.value.clone());
+            let result = visitor.visit_unit();
+            embed::discard();
+            return result;
+        }
+
+        match self.value {
+            Value::TupleStruct(..) | Value::Tuple(_) => {
+                self.deserialize_tuple_struct(name, 1, visitor)
+            },
+            // Written without the name and parentheses, as in `length: 5`
+            _ => visitor.visit_newtype_struct(self),
+        }
     }
 
     fn deserialize_seq<V>(
@@ -343,8 +741,8 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::List(seq), {
-            visitor.visit_seq(ValueDeserializerSequence(&seq))
+        expect_deserialize!(self, "List", Value::List(seq), {
+            visitor.visit_seq(ValueDeserializerSequence::new(seq, self.shared))
         })
     }
 
@@ -356,23 +754,31 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::Tuple(seq), {
-            visitor.visit_seq(ValueDeserializerSequence(&seq))
+        expect_deserialize!(self, "Tuple", Value::Tuple(seq), {
+            visitor.visit_seq(ValueDeserializerSequence::new(seq, self.shared))
         })
     }
 
     fn deserialize_tuple_struct<V>(
         self,
-        _: &'static str,
+        name: &'static str,
         _: usize,
         visitor: V,
     ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::TupleStruct(_, seq), {
-            visitor.visit_seq(ValueDeserializerSequence(&seq))
-        })
+        match self.value {
+            Value::TupleStruct(identifier, seq) => {
+                self.check_type_name(name, identifier)?;
+                visitor
+                    .visit_seq(ValueDeserializerSequence::new(seq, self.shared))
+            },
+            // Tuple structs without their name are plain tuples
+            Value::Tuple(seq) => visitor
+                .visit_seq(ValueDeserializerSequence::new(seq, self.shared)),
+            _ => Err(unexpected("TupleStruct", self.value)),
+        }
     }
 
     fn deserialize_map<V>(
@@ -382,30 +788,76 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::Map(map), {
+        expect_deserialize!(self, "Map", Value::Map(map), {
             visitor.visit_map(ValueDeserializerMap {
                 iter: map.iter(),
                 current_value: None,
                 current_key: None,
+                last_key: None,
+                known: None,
+                shared: self.shared,
             })
         })
     }
 
     fn deserialize_struct<V>(
         self,
-        _: &'static str,
-        _: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        expect_deserialize!(self, Value::Struct(_, map), {
-            visitor.visit_map(ValueDeserializerStruct {
-                iter: map.iter(),
-                current_value: None,
-                current_key: None,
-            })
+        static EMPTY: OrderedMap<Identifier, Value> = OrderedMap::new();
+
+        // Without fields, there is nothing to tell the unknown ones apart
+        let known = Some(fields).filter(|fields| {
+            self.shared.options.ignore_unknown_fields && !fields.is_empty()
+        });
+
+        let map = match self.value {
+            Value::Struct(identifier, map) => {
+                self.check_type_name(name, identifier)?;
+                map
+            },
+            // Empty structs without their name are written as `()`
+            Value::Unit => &EMPTY,
+            // Keyed by field names or indices, like other formats write them
+            Value::Map(map) => {
+                return visitor.visit_map(ValueDeserializerMap {
+                    iter: map.iter(),
+                    current_value: None,
+                    current_key: None,
+                    last_key: None,
+                    known,
+                    shared: self.shared,
+                })
+            },
+            // Fields in the order of their declaration
+            Value::TupleStruct(identifier, items) => {
+                self.check_type_name(name, identifier)?;
+                return visitor.visit_seq(ValueDeserializerSequence::new(
+                    items,
+                    self.shared,
+                ));
+            },
+            Value::Tuple(items) => {
+                return visitor.visit_seq(ValueDeserializerSequence::new(
+                    items,
+                    self.shared,
+                ))
+            },
+            _ => return Err(unexpected("Struct", self.value)),
+        };
+
+        visitor.visit_map(ValueDeserializerStruct {
+            iter: map.iter(),
+            current_value: None,
+            current_key: None,
+            last_key: None,
+            known,
+            shared: self.shared,
         })
     }
 
@@ -418,7 +870,10 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(ValueDeserializerEnum { value: self.value })
+        visitor.visit_enum(ValueDeserializerEnum {
+            value: self.value,
+            shared: self.shared,
+        })
     }
 
     fn deserialize_identifier<V>(
@@ -430,31 +885,29 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     {
         match self.value {
             Value::TupleStruct(identifier, _)
-            | Value::Struct(identifier, _) => {
-                match identifier.segments.last() {
-                    Some(identifier) => {
-                        return visitor
-                            .visit_str(identifier.identifier.0.as_str())
-                    },
-                    _ => {},
+            | Value::Struct(identifier, _)
+            | Value::UnitStruct(identifier)
+            | Value::Type(Type::TypeIdentifier(identifier)) => {
+                if let Some(identifier) = identifier.segments.last() {
+                    let name = identifier.identifier.0.as_str();
+                    return match self.shared.source.borrow_str(name) {
+                        Some(name) => visitor.visit_borrowed_str(name),
+                        None => visitor.visit_str(name),
+                    };
                 }
             },
-            Value::Type(t) => match t {
-                Type::TypeIdentifier(identifier) => {
-                    match identifier.segments.last() {
-                        Some(identifier) => {
-                            return visitor
-                                .visit_str(identifier.identifier.0.as_str())
-                        },
-                        _ => {},
-                    }
-                },
-                _ => {},
+            // Tags of internally tagged enums
+            Value::String(_) => return self.deserialize_str(visitor),
+            // Field indices, which serde accepts instead of names
+            Value::Number(number) => {
+                if let Some(index) = number.to_primitive() {
+                    return visitor.visit_u64(index);
+                }
             },
             _ => {},
         }
 
-        Err(anyhow!("{:?} is not Identifier", self.value).into())
+        Err(unexpected("Identifier", self.value))
     }
 
     fn deserialize_ignored_any<V>(
@@ -464,13 +917,36 @@ impl<'value, 'de> Deserializer<'de> for ValueDeserializer<'value> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        // The value is already parsed, there is nothing left to consume
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        !self.shared.options.compact_representations
     }
 }
 
-struct ValueDeserializerSequence<'lt>(&'lt [Value]);
+struct ValueDeserializerSequence<'lt, S> {
+    items: &'lt [Value],
+    /// Index of the next item, for error paths
+    index: usize,
+    shared: Shared<S>,
+}
 
-impl<'lt, 'de> SeqAccess<'de> for ValueDeserializerSequence<'lt> {
+impl<'lt, S> ValueDeserializerSequence<'lt, S> {
+    fn new(items: &'lt [Value], shared: Shared<S>) -> Self {
+        ValueDeserializerSequence {
+            items,
+            index: 0,
+            shared,
+        }
+    }
+}
+
+impl<'lt, 'de, S> SeqAccess<'de> for ValueDeserializerSequence<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
     type Error = ValueDeserializerError;
 
     fn next_element_seed<T>(
@@ -480,38 +956,67 @@ impl<'lt, 'de> SeqAccess<'de> for ValueDeserializerSequence<'lt> {
     where
         T: DeserializeSeed<'de>,
     {
-        match self.0.first() {
+        match self.items.get(self.index) {
             None => Ok(None),
             Some(value) => {
-                let result =
-                    Ok(Some(seed.deserialize(ValueDeserializer { value })?));
-                self.0 = &(self.0)[1..];
-                result
+                let result = seed
+                    .deserialize(ValueDeserializer::child(value, self.shared))
+                    .map_err(|error| {
+                        error.within(PathSegment::Index(self.index))
+                    })?;
+                self.index += 1;
+                Ok(Some(result))
             },
         }
     }
 
     fn size_hint(&self) -> Option<usize> {
-        Some(self.0.len())
+        Some(self.items.len() - self.index)
     }
 }
 
-struct ValueDeserializerMap<'lt> {
-    iter: btree_map::Iter<'lt, Value, Value>,
+struct ValueDeserializerMap<'lt, S> {
+    iter: map::Iter<'lt, Value, Value>,
     current_key: Option<&'lt Value>,
     current_value: Option<&'lt Value>,
+    /// Key of the entry last visited, for error paths
+    last_key: Option<&'lt Value>,
+    /// Fields of the struct read from the map, if unknown ones are skipped
+    known: Option<&'static [&'static str]>,
+    shared: Shared<S>,
 }
 
-impl<'lt> ValueDeserializerMap<'lt> {
+impl<'lt, S> ValueDeserializerMap<'lt, S> {
     fn next(&mut self) {
-        if let Some((key, value)) = self.iter.next() {
+        let known = self.known;
+        let is_unknown = |key: &Value| match (known, key) {
+            (Some(known), Value::String(name)) => !known.contains(&&**name),
+            (Some(known), Value::Number(index)) => index
+                .to_primitive::<u64>()
+                .is_none_or(|index| index >= known.len() as u64),
+            (Some(known), key) => {
+                bare_key(key).is_some_and(|name| !known.contains(&name))
+            },
+            _ => false,
+        };
+
+        if let Some((key, value)) = self.iter.find(|(key, _)| !is_unknown(key))
+        {
             self.current_key = Some(key);
             self.current_value = Some(value);
+            self.last_key = Some(key);
         }
     }
+
+    fn segment(&self) -> PathSegment {
+        PathSegment::Key(self.last_key.cloned().unwrap_or(Value::Unit))
+    }
 }
 
-impl<'lt, 'de> MapAccess<'de> for ValueDeserializerMap<'lt> {
+impl<'lt, 'de, S> MapAccess<'de> for ValueDeserializerMap<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
     type Error = ValueDeserializerError;
 
     fn next_key_seed<K>(
@@ -527,9 +1032,10 @@ impl<'lt, 'de> MapAccess<'de> for ValueDeserializerMap<'lt> {
 
         match self.current_key.take() {
             None => Ok(None),
-            Some(value) => {
-                Ok(Some(seed.deserialize(ValueDeserializer { value })?))
-            },
+            Some(value) => Ok(Some(
+                seed.deserialize(ValueDeserializer::key(value, self.shared))
+                    .map_err(|error| error.within(self.segment()))?,
+            )),
         }
     }
 
@@ -545,30 +1051,51 @@ impl<'lt, 'de> MapAccess<'de> for ValueDeserializerMap<'lt> {
         }
 
         match self.current_value.take() {
-            None => {
-                Err(anyhow!("Called next_value on empty map iterator").into())
-            },
-            Some(value) => seed.deserialize(ValueDeserializer { value }),
+            None => Err(ValueDeserializerError::custom_message(
+                "Called next_value on empty map iterator",
+            )),
+            Some(value) => seed
+                .deserialize(ValueDeserializer::child(value, self.shared))
+                .map_err(|error| error.within(self.segment())),
         }
     }
 }
 
-struct ValueDeserializerStruct<'lt> {
-    iter: btree_map::Iter<'lt, Identifier, Value>,
+struct ValueDeserializerStruct<'lt, S> {
+    iter: OrderedIter<'lt, Identifier, Value>,
     current_key: Option<&'lt Identifier>,
     current_value: Option<&'lt Value>,
+    /// Field last visited, for error paths
+    last_key: Option<&'lt Identifier>,
+    /// Fields of the target struct, if unknown ones are skipped
+    known: Option<&'static [&'static str]>,
+    shared: Shared<S>,
 }
 
-impl<'lt> ValueDeserializerStruct<'lt> {
+impl<'lt, S> ValueDeserializerStruct<'lt, S> {
     fn next(&mut self) {
-        if let Some((key, value)) = self.iter.next() {
+        let known = self.known;
+        if let Some((key, value)) = self.iter.find(|(key, _)| {
+            known.is_none_or(|known| known.contains(&key.0.as_str()))
+        }) {
             self.current_key = Some(key);
             self.current_value = Some(value);
+            self.last_key = Some(key);
+        }
+    }
+
+    fn segment(&self) -> PathSegment {
+        match self.last_key {
+            Some(key) => PathSegment::Field(key.clone()),
+            None => PathSegment::Index(0),
         }
     }
 }
 
-impl<'lt, 'de> MapAccess<'de> for ValueDeserializerStruct<'lt> {
+impl<'lt, 'de, S> MapAccess<'de> for ValueDeserializerStruct<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
     type Error = ValueDeserializerError;
 
     fn next_key_seed<K>(
@@ -584,16 +1111,7 @@ impl<'lt, 'de> MapAccess<'de> for ValueDeserializerStruct<'lt> {
 
         match self.current_key.take() {
             None => Ok(None),
-            Some(value) => {
-                let value = Value::Type(Type::TypeIdentifier(TypeIdentifier {
-                    segments: vec![GenericIdentifier {
-                        identifier: value.clone(),
-                        generics: None,
-                    }],
-                }));
-                let value = &value;
-                Ok(Some(seed.deserialize(ValueDeserializer { value })?))
-            },
+            Some(key) => Ok(Some(name_seed(seed, &key.0, self.shared.source)?)),
         }
     }
 
@@ -609,19 +1127,135 @@ impl<'lt, 'de> MapAccess<'de> for ValueDeserializerStruct<'lt> {
         }
 
         match self.current_value.take() {
-            None => {
-                Err(anyhow!("Called next_value on empty map iterator").into())
+            None => Err(ValueDeserializerError::custom_message(
+                "Called next_value on empty map iterator",
+            )),
+            Some(value) => seed
+                .deserialize(ValueDeserializer::child(value, self.shared))
+                .map_err(|error| error.within(self.segment())),
+        }
+    }
+}
+
+/// Deserializes a name, borrowing it from `source` if possible
+fn name_seed<'lt, 'de, T, S>(
+    seed: T,
+    name: &'lt str,
+    source: S,
+) -> Result<T::Value, ValueDeserializerError>
+where
+    T: DeserializeSeed<'de>,
+    S: StringSource<'lt, 'de>,
+{
+    match source.borrow_str(name) {
+        Some(name) => seed.deserialize(BorrowedStrDeserializer::new(name)),
+        None => seed.deserialize(name.into_deserializer()),
+    }
+}
+
+/// An enum variant as a map with a single entry, see `deserialize_any`
+struct ValueDeserializerVariant<'lt, S> {
+    /// Taken once the key was visited
+    name: Option<&'lt str>,
+    value: &'lt Value,
+    shared: Shared<S>,
+}
+
+impl<'lt, 'de, S> MapAccess<'de> for ValueDeserializerVariant<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
+    type Error = ValueDeserializerError;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<<K as DeserializeSeed<'de>>::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.name.take() {
+            None => Ok(None),
+            Some(name) => Ok(Some(name_seed(seed, name, self.shared.source)?)),
+        }
+    }
+
+    fn next_value_seed<V>(
+        &mut self,
+        seed: V,
+    ) -> Result<<V as DeserializeSeed<'de>>::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value {
+            // Newtype variants contain their only item, not a tuple of it
+            Value::TupleStruct(_, items) if items.len() == 1 => seed
+                .deserialize(ValueDeserializer::child(&items[0], self.shared))
+                .map_err(|error| error.within(PathSegment::Index(0))),
+            value => seed.deserialize(VariantContents {
+                value,
+                shared: self.shared,
+            }),
+        }
+    }
+}
+
+/// The items or fields of an enum variant, without its name
+struct VariantContents<'lt, S> {
+    value: &'lt Value,
+    shared: Shared<S>,
+}
+
+impl<'lt, 'de, S> Deserializer<'de> for VariantContents<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
+    type Error = ValueDeserializerError;
+
+    fn deserialize_any<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::TupleStruct(_, items) => visitor
+                .visit_seq(ValueDeserializerSequence::new(items, self.shared)),
+            Value::Struct(_, fields) => {
+                visitor.visit_map(ValueDeserializerStruct {
+                    iter: fields.iter(),
+                    current_value: None,
+                    current_key: None,
+                    last_key: None,
+                    known: None,
+                    shared: self.shared,
+                })
             },
-            Some(value) => seed.deserialize(ValueDeserializer { value }),
+            other => Err(unexpected("enum variant", other)),
         }
     }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        !self.shared.options.compact_representations
+    }
 }
 
-struct ValueDeserializerEnum<'lt> {
+struct ValueDeserializerEnum<'lt, S> {
     value: &'lt Value,
+    shared: Shared<S>,
 }
 
-impl<'lt, 'de> EnumAccess<'de> for ValueDeserializerEnum<'lt> {
+impl<'lt, 'de, S> EnumAccess<'de> for ValueDeserializerEnum<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
     type Error = ValueDeserializerError;
     type Variant = Self;
 
@@ -632,17 +1266,29 @@ impl<'lt, 'de> EnumAccess<'de> for ValueDeserializerEnum<'lt> {
     where
         V: DeserializeSeed<'de>,
     {
-        let value =
-            seed.deserialize(ValueDeserializer { value: self.value })?;
+        let value = seed
+            .deserialize(ValueDeserializer::child(self.value, self.shared))?;
         Ok((value, self))
     }
 }
 
-impl<'lt, 'de> VariantAccess<'de> for ValueDeserializerEnum<'lt> {
+impl<'lt, 'de, S> VariantAccess<'de> for ValueDeserializerEnum<'lt, S>
+where
+    S: StringSource<'lt, 'de>,
+{
     type Error = ValueDeserializerError;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
+        // Written as `Baz`, `Test::Baz` or `Baz()`, but without contents
+        match self.value {
+            Value::TupleStruct(_, items) if !items.is_empty() => {
+                Err(unexpected("UnitStruct", self.value))
+            },
+            Value::Struct(_, fields) if !fields.is_empty() => {
+                Err(unexpected("UnitStruct", self.value))
+            },
+            _ => Ok(()),
+        }
     }
 
     fn newtype_variant_seed<T>(
@@ -652,19 +1298,27 @@ impl<'lt, 'de> VariantAccess<'de> for ValueDeserializerEnum<'lt> {
     where
         T: DeserializeSeed<'de>,
     {
-        expect_deserialize!(self, Value::TupleStruct(_, fields), {
-            if fields.len() != 1 {
-                return Err(anyhow!(
-                    "Expected newtype variant {:?}",
-                    self.value
-                )
-                .into());
-            }
+        expect_deserialize!(
+            self,
+            "TupleStruct",
+            Value::TupleStruct(_, fields),
+            {
+                let field = match fields.as_slice() {
+                    [field] => field,
+                    _ => {
+                        return Err(ValueDeserializerError::custom_message(
+                            format_args!(
+                                "Expected newtype variant {:?}",
+                                self.value
+                            ),
+                        ))
+                    },
+                };
 
-            seed.deserialize(ValueDeserializer {
-                value: fields.get(0).unwrap(),
-            })
-        })
+                seed.deserialize(ValueDeserializer::child(field, self.shared))
+                    .map_err(|error| error.within(PathSegment::Index(0)))
+            }
+        )
     }
 
     fn tuple_variant<V>(
@@ -675,7 +1329,7 @@ impl<'lt, 'de> VariantAccess<'de> for ValueDeserializerEnum<'lt> {
     where
         V: Visitor<'de>,
     {
-        ValueDeserializer { value: self.value }
+        ValueDeserializer::child(self.value, self.shared)
             .deserialize_tuple_struct("", len, visitor)
     }
 
@@ -687,7 +1341,7 @@ impl<'lt, 'de> VariantAccess<'de> for ValueDeserializerEnum<'lt> {
     where
         V: Visitor<'de>,
     {
-        ValueDeserializer { value: self.value }
+        ValueDeserializer::child(self.value, self.shared)
             .deserialize_struct("", fields, visitor)
     }
 }
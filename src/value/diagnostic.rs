@@ -0,0 +1,656 @@
+use crate::value::types::Identifier;
+use std::{
+    error::Error,
+    fmt,
+    fmt::{Display, Formatter},
+    ops::Range,
+};
+
+/// Replaces a byte range of the input
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Edit {
+    pub span: Range<usize>,
+    pub replacement: String,
+}
+
+/// A likely fix for a syntax error, made of one or more edits
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Suggestion {
+    pub message: String,
+    pub edits: Vec<Edit>,
+}
+
+/// Error for input that could not be parsed
+///
+/// It points at the offending position of the input, lists the grammar
+/// rules that would have been accepted there and carries suggestions for
+/// common mistakes, such as `=` instead of `:` or missing commas.
+/// Displaying it shows the line of the input with a caret at the position.
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    pub message: String,
+    /// Byte offset of the error in the input
+    pub offset: usize,
+    /// Line of the error, starting at 1
+    pub line: usize,
+    /// Column of the error in characters, starting at 1
+    pub column: usize,
+    /// Names of the grammar rules expected at the offset, see `grammar`
+    pub expected: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+    input: String,
+}
+
+impl ParseError {
+    /// An error for input that does not match the grammar
+    pub(crate) fn syntax(
+        input: &str,
+        offset: usize,
+        expected: Vec<String>,
+        unexpected: Vec<String>,
+    ) -> Self {
+        let message = if !expected.is_empty() {
+            format!("expected {}", one_of(&expected))
+        } else if !unexpected.is_empty() {
+            format!("unexpected {}", one_of(&unexpected))
+        } else {
+            "unexpected input".to_string()
+        };
+
+        ParseError {
+            expected,
+            suggestions: suggest(input),
+            ..ParseError::new(input, offset, message)
+        }
+    }
+
+    /// An error for input that matches the grammar, but is not a valid
+    /// value, like a number out of range
+    pub(crate) fn new<M>(input: &str, offset: usize, message: M) -> Self
+    where
+        M: Display,
+    {
+        let (line, column) = line_column(input, offset);
+
+        ParseError {
+            message: message.to_string(),
+            offset,
+            line,
+            column,
+            expected: Vec::new(),
+            suggestions: Vec::new(),
+            input: input.to_string(),
+        }
+    }
+
+    /// The line of the input containing the error
+    pub fn snippet(&self) -> &str {
+        let before = &self.input[..self.offset];
+        let after = &self.input[self.offset..];
+        let start = before.rfind('\n').map_or(0, |i| i + 1);
+        let end = after
+            .find('\n')
+            .map_or(self.input.len(), |i| self.offset + i);
+
+        self.input[start..end].trim_end_matches('\r')
+    }
+
+    /// The input with the edits of all suggestions applied
+    pub fn fixed(&self) -> String {
+        let mut edits = self
+            .suggestions
+            .iter()
+            .flat_map(|s| s.edits.iter())
+            .collect::<Vec<_>>();
+        edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+
+        let mut fixed = String::new();
+        let mut position = 0;
+        for edit in edits {
+            // Overlapping edits can not both be applied
+            if edit.span.start < position {
+                continue;
+            }
+            fixed.push_str(&self.input[position..edit.span.start]);
+            fixed.push_str(&edit.replacement);
+            position = edit.span.end;
+        }
+        fixed.push_str(&self.input[position..]);
+
+        fixed
+    }
+}
+
+/// Line and column of a byte offset, both starting at 1
+fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+    (line, column)
+}
+
+/// `a`, `a or b` and `a, b or c`
+fn one_of(names: &[String]) -> String {
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )?;
+
+        // Tabs are kept, so the caret lines up with the snippet
+        let gutter = " ".repeat(self.line.to_string().len());
+        let snippet = self.snippet();
+        let indent = snippet
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, snippet)?;
+        write!(f, "{} | {}^", gutter, indent)?;
+
+        for suggestion in &self.suggestions {
+            let (line, column) =
+                line_column(&self.input, suggestion.edits[0].span.start);
+            write!(f, "\nhelp: {} ({}:{})", suggestion.message, line, column)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
+
+/// Error for a string that is not a type name, like `Vec<u8`
+///
+/// Type names are a single line, so it displays as a single line as well:
+/// ``Invalid type `Vec<u8`: expected generics at column 7``.
+#[derive(Clone, Debug)]
+pub struct TypeParseError {
+    /// Where and why the string stops being a type
+    pub error: ParseError,
+}
+
+impl TypeParseError {
+    /// The string that was parsed
+    pub fn name(&self) -> &str {
+        &self.error.input
+    }
+}
+
+impl Display for TypeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid type `{}`: {} at column {}",
+            self.name(),
+            self.error.message,
+            self.error.column
+        )
+    }
+}
+
+impl Error for TypeParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum TokenKind {
+    Identifier,
+    /// Strings, chars and numbers
+    Literal,
+    Open(char),
+    Close(char),
+    Punctuation(char),
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+impl Token {
+    fn ends_value(&self) -> bool {
+        match self.kind {
+            TokenKind::Identifier
+            | TokenKind::Literal
+            | TokenKind::Close(_) => true,
+            TokenKind::Punctuation(c) => c == '>',
+            TokenKind::Open(_) => false,
+        }
+    }
+
+    fn starts_value(&self) -> bool {
+        match self.kind {
+            TokenKind::Identifier | TokenKind::Literal | TokenKind::Open(_) => {
+                true
+            },
+            TokenKind::Close(_) | TokenKind::Punctuation(_) => false,
+        }
+    }
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+fn edit<S>(span: Range<usize>, replacement: S) -> Edit
+where
+    S: Into<String>,
+{
+    Edit {
+        span,
+        replacement: replacement.into(),
+    }
+}
+
+fn suggestion<S>(message: S, edits: Vec<Edit>) -> Suggestion
+where
+    S: Into<String>,
+{
+    Suggestion {
+        message: message.into(),
+        edits,
+    }
+}
+
+/// Splits the input into tokens, loosely enough to survive broken input
+struct Lexer<'lt> {
+    input: &'lt str,
+    position: usize,
+    suggestions: Vec<Suggestion>,
+}
+
+impl<'lt> Lexer<'lt> {
+    fn rest(&self) -> &'lt str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat_while<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(char) -> bool,
+    {
+        while self.peek().is_some_and(&mut predicate) {
+            self.bump();
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("//") {
+                self.eat_while(|c| c != '\n');
+            } else if rest.starts_with("/*") {
+                self.position += rest.find("*/").map_or(rest.len(), |e| e + 2);
+            } else if self.peek().is_some_and(char::is_whitespace) {
+                self.eat_while(char::is_whitespace);
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Consumes a quoted literal, returning whether it was terminated
+    fn quoted(&mut self, quote: char) -> bool {
+        let mut escaped = false;
+        while let Some(c) = self.bump() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                c if c == quote => return true,
+                '\n' if quote == '\'' => return false,
+                _ => {},
+            }
+        }
+        false
+    }
+
+    fn string(&mut self) {
+        self.bump();
+        if !self.quoted('"') {
+            self.suggestions.push(suggestion(
+                "close the string",
+                vec![edit(self.position..self.position, "\"")],
+            ));
+        }
+    }
+
+    fn raw_string(&mut self) {
+        self.bump();
+        let hashes = self.rest().chars().take_while(|c| *c == '#').count();
+        self.position += hashes + 1;
+
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        match self.rest().find(&terminator) {
+            Some(end) => self.position += end + terminator.len(),
+            None => {
+                self.position = self.input.len();
+                self.suggestions.push(suggestion(
+                    "close the raw string",
+                    vec![edit(self.position..self.position, terminator)],
+                ));
+            },
+        }
+    }
+
+    /// Whether a lifetime like `'a` of generic arguments starts here,
+    /// rather than a char
+    fn at_lifetime(&self) -> bool {
+        let name = &self.rest()[1..];
+        let length = name
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(name.len());
+        length > 0 && !name[length..].starts_with('\'')
+    }
+
+    fn char(&mut self, start: usize) {
+        self.bump();
+        let terminated = self.quoted('\'');
+        let content =
+            &self.input[start + 1..self.position - terminated as usize];
+
+        // 'text' is a string in many other languages
+        if terminated
+            && content.chars().count() > 1
+            && !content.starts_with('\\')
+        {
+            self.suggestions.push(suggestion(
+                "strings are written in double quotes",
+                vec![
+                    edit(start..start + 1, "\""),
+                    edit(self.position - 1..self.position, "\""),
+                ],
+            ));
+        }
+    }
+
+    fn number(&mut self) {
+        self.bump();
+        self.eat_while(|c| c.is_ascii_digit() || c == '.');
+        if let Some('e') | Some('E') = self.peek() {
+            self.bump();
+            if let Some('+') | Some('-') = self.peek() {
+                self.bump();
+            }
+            self.eat_while(|c| c.is_ascii_digit());
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.skip_trivia();
+
+        let start = self.position;
+        let rest = self.rest();
+        let c = self.peek()?;
+
+        let kind = match c {
+            '"' => {
+                self.string();
+                TokenKind::Literal
+            },
+            '\'' if self.at_lifetime() => {
+                self.bump();
+                self.eat_while(|c| c.is_alphanumeric() || c == '_');
+                TokenKind::Identifier
+            },
+            '\'' => {
+                self.char(start);
+                TokenKind::Literal
+            },
+            'r' if rest[1..].trim_start_matches('#').starts_with('"') => {
+                self.raw_string();
+                TokenKind::Literal
+            },
+            '-' | '0'..='9' => {
+                self.number();
+                TokenKind::Literal
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                self.eat_while(|c| c.is_alphanumeric() || c == '_');
+                TokenKind::Identifier
+            },
+            '(' | '[' | '{' => {
+                self.bump();
+                TokenKind::Open(c)
+            },
+            ')' | ']' | '}' => {
+                self.bump();
+                TokenKind::Close(c)
+            },
+            _ if rest.starts_with("::") => {
+                self.position += 2;
+                TokenKind::Punctuation(c)
+            },
+            _ if rest.starts_with("=>") => {
+                self.position += 2;
+                TokenKind::Punctuation('=')
+            },
+            _ => {
+                self.bump();
+                TokenKind::Punctuation(c)
+            },
+        };
+
+        Some(Token {
+            kind,
+            span: start..self.position,
+        })
+    }
+}
+
+/// Offset of the first delimiter nested deeper than `max`
+///
+/// The angle brackets of types and generics count as delimiters, since
+/// they nest just as deep.
+pub(crate) fn nesting_over(input: &str, max: usize) -> Option<usize> {
+    let mut lexer = Lexer {
+        input,
+        position: 0,
+        suggestions: Vec::new(),
+    };
+
+    let mut depth = 0usize;
+    while let Some(token) = lexer.next_token() {
+        match token.kind {
+            TokenKind::Open(_) | TokenKind::Punctuation('<') => {
+                depth += 1;
+                if depth > max {
+                    return Some(token.span.start);
+                }
+            },
+            TokenKind::Close(_) | TokenKind::Punctuation('>') => {
+                depth = depth.saturating_sub(1)
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// An opening delimiter that has not been closed yet
+struct OpenDelimiter {
+    delimiter: char,
+    span: Range<usize>,
+    /// A brace directly following a type name, as in `Name { field: 0 }`
+    struct_brace: bool,
+}
+
+/// Looks for common mistakes in input that failed to parse
+pub(crate) fn suggest(input: &str) -> Vec<Suggestion> {
+    let mut lexer = Lexer {
+        input,
+        position: 0,
+        suggestions: Vec::new(),
+    };
+
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token() {
+        tokens.push(token);
+    }
+
+    let mut suggestions = lexer.suggestions;
+    let mut stack: Vec<OpenDelimiter> = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|i| &tokens[i]);
+        let next = tokens.get(index + 1);
+        let text = &input[token.span.clone()];
+
+        match token.kind {
+            TokenKind::Open(delimiter) => {
+                let struct_brace = delimiter == '{'
+                    && previous.is_some_and(|p| {
+                        p.kind == TokenKind::Identifier
+                            || p.kind == TokenKind::Punctuation('>')
+                    });
+                stack.push(OpenDelimiter {
+                    delimiter,
+                    span: token.span.clone(),
+                    struct_brace,
+                });
+            },
+            TokenKind::Close(delimiter) => close_delimiter(
+                input,
+                &mut stack,
+                &mut suggestions,
+                token,
+                delimiter,
+            ),
+
+            TokenKind::Punctuation('=') => suggestions.push(suggestion(
+                format!("use ':' instead of '{}'", text),
+                vec![edit(token.span.clone(), ":")],
+            )),
+
+            TokenKind::Identifier if text == "null" => {
+                suggestions.push(suggestion(
+                    "use 'None' for a missing value",
+                    vec![edit(token.span.clone(), "None")],
+                ))
+            },
+
+            // "name": value inside of struct braces
+            TokenKind::Literal
+                if stack.last().is_some_and(|open| open.struct_brace)
+                    && text.len() >= 2
+                    && text.starts_with('"')
+                    && text.ends_with('"')
+                    && Identifier::from(&text[1..text.len() - 1])
+                        .is_valid()
+                    && next.is_some_and(|n| {
+                        n.kind == TokenKind::Punctuation(':')
+                            || n.kind == TokenKind::Punctuation('=')
+                    }) =>
+            {
+                suggestions.push(suggestion(
+                    "field names do not need quotes",
+                    vec![edit(token.span.clone(), &text[1..text.len() - 1])],
+                ))
+            },
+
+            _ => {},
+        }
+
+        if let Some(next) = next {
+            let call = (token.kind == TokenKind::Identifier
+                || token.kind == TokenKind::Punctuation('>'))
+                && (next.kind == TokenKind::Open('(')
+                    || next.kind == TokenKind::Open('{'));
+
+            if token.ends_value() && next.starts_value() && !call {
+                suggestions.push(suggestion(
+                    "add a ',' between the items",
+                    vec![edit(token.span.end..token.span.end, ",")],
+                ));
+            }
+        }
+    }
+
+    // Everything still open is closed at the end, innermost first
+    while let Some(open) = stack.pop() {
+        suggestions.push(unclosed(input, &open, input.len()));
+    }
+
+    suggestions
+}
+
+/// Closes `open` at `position`, which is where the help points
+fn unclosed(input: &str, open: &OpenDelimiter, position: usize) -> Suggestion {
+    let mut edits = Vec::new();
+    let close = if open.struct_brace {
+        edits.push(edit(open.span.clone(), "("));
+        ')'
+    } else {
+        closing(open.delimiter)
+    };
+    edits.push(edit(position..position, close.to_string()));
+
+    let (line, column) = line_column(input, open.span.start);
+    suggestion(
+        format!(
+            "close the '{}' opened at {}:{}",
+            open.delimiter, line, column
+        ),
+        edits,
+    )
+}
+
+fn close_delimiter(
+    input: &str,
+    stack: &mut Vec<OpenDelimiter>,
+    suggestions: &mut Vec<Suggestion>,
+    token: &Token,
+    delimiter: char,
+) {
+    let matches = |open: &OpenDelimiter| closing(open.delimiter) == delimiter;
+
+    if !stack.iter().any(matches) {
+        suggestions.push(suggestion(
+            format!("remove the unmatched '{}'", delimiter),
+            vec![edit(token.span.clone(), "")],
+        ));
+        return;
+    }
+
+    // Delimiters opened after the matching one were never closed
+    while !matches(stack.last().unwrap()) {
+        let open = stack.pop().unwrap();
+        suggestions.push(unclosed(input, &open, token.span.start));
+    }
+
+    let open = stack.pop().unwrap();
+    if open.struct_brace {
+        suggestions.push(suggestion(
+            "struct fields are written in parentheses",
+            vec![edit(open.span, "("), edit(token.span.clone(), ")")],
+        ));
+    }
+}
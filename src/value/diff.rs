@@ -0,0 +1,219 @@
+//! Structural differences between values, see `Value::diff`
+
+use crate::value::{
+    path::{Path, PathSegment},
+    printer::ValuePrinter,
+    Value,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+/// A single difference, at a path of the old value
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Change {
+    Added { path: Path, value: Value },
+    Removed { path: Path, value: Value },
+    Changed { path: Path, old: Value, new: Value },
+}
+
+impl Change {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Differences between two values, see `Value::diff`
+///
+/// Displaying it shows a line per removed and added value, a change is
+/// shown as the removal of the old and the addition of the new value:
+///
+/// ```text
+/// - server.port: 80
+/// + server.port: 8080
+/// + server.host: "localhost"
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct ValueDiff {
+    /// Changes in the order of their paths, except that the entries added
+    /// to a struct, map or list follow its other changes
+    pub changes: Vec<Change>,
+}
+
+impl ValueDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Change> {
+        self.changes.iter()
+    }
+}
+
+impl<'lt> IntoIterator for &'lt ValueDiff {
+    type Item = &'lt Change;
+    type IntoIter = std::slice::Iter<'lt, Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Display for ValueDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let line = |f: &mut Formatter<'_>, sign, path: &Path, value| {
+            let path = if path.is_root() {
+                "(root)".to_string()
+            } else {
+                path.to_string()
+            };
+            write!(f, "{} {}: ", sign, path)?;
+            ValuePrinter::inline().write(value, f)?;
+            writeln!(f)
+        };
+
+        for change in &self.changes {
+            match change {
+                Change::Added { path, value } => line(f, '+', path, value)?,
+                Change::Removed { path, value } => line(f, '-', path, value)?,
+                Change::Changed { path, old, new } => {
+                    line(f, '-', path, old)?;
+                    line(f, '+', path, new)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects the changes from `old` to `new` below `path`
+///
+/// Structs of the same type and maps are compared by field and key,
+/// lists by position, so an item inserted at the front shows up as a
+/// change of every following item.
+pub(crate) fn diff(
+    old: &Value,
+    new: &Value,
+    path: &mut Path,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (Value::Option(Some(old)), Value::Option(Some(new))) => {
+            diff(old, new, path, changes)
+        },
+
+        (Value::Struct(old_name, old), Value::Struct(new_name, new))
+            if old_name == new_name =>
+        {
+            let old = old
+                .iter()
+                .map(|(field, value)| {
+                    (PathSegment::Field(field.clone()), value)
+                })
+                .collect();
+            let new = new
+                .iter()
+                .map(|(field, value)| {
+                    (PathSegment::Field(field.clone()), value)
+                })
+                .collect();
+            diff_entries(old, new, path, changes)
+        },
+        (Value::Map(old), Value::Map(new)) => {
+            let old = old
+                .iter()
+                .map(|(key, value)| (PathSegment::Key(key.clone()), value))
+                .collect();
+            let new = new
+                .iter()
+                .map(|(key, value)| (PathSegment::Key(key.clone()), value))
+                .collect();
+            diff_entries(old, new, path, changes)
+        },
+
+        (Value::List(old), Value::List(new)) => {
+            diff_items(old, new, path, changes)
+        },
+        (Value::Tuple(old), Value::Tuple(new)) if old.len() == new.len() => {
+            diff_items(old, new, path, changes)
+        },
+        (
+            Value::TupleStruct(old_name, old),
+            Value::TupleStruct(new_name, new),
+        ) if old_name == new_name && old.len() == new.len() => {
+            diff_items(old, new, path, changes)
+        },
+
+        (old, new) if old != new => changes.push(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {},
+    }
+}
+
+fn diff_entries(
+    old: BTreeMap<PathSegment, &Value>,
+    mut new: BTreeMap<PathSegment, &Value>,
+    path: &mut Path,
+    changes: &mut Vec<Change>,
+) {
+    for (segment, old) in old {
+        let new = new.remove(&segment);
+        path.push(segment);
+        match new {
+            Some(new) => diff(old, new, path, changes),
+            None => changes.push(Change::Removed {
+                path: path.clone(),
+                value: old.clone(),
+            }),
+        }
+        path.pop();
+    }
+
+    for (segment, new) in new {
+        path.push(segment);
+        changes.push(Change::Added {
+            path: path.clone(),
+            value: new.clone(),
+        });
+        path.pop();
+    }
+}
+
+fn diff_items(
+    old: &[Value],
+    new: &[Value],
+    path: &mut Path,
+    changes: &mut Vec<Change>,
+) {
+    for (index, (old, new)) in old.iter().zip(new).enumerate() {
+        path.push(PathSegment::Index(index));
+        diff(old, new, path, changes);
+        path.pop();
+    }
+
+    for (index, old) in old.iter().enumerate().skip(new.len()) {
+        changes.push(Change::Removed {
+            path: path.clone().index(index),
+            value: old.clone(),
+        });
+    }
+    for (index, new) in new.iter().enumerate().skip(old.len()) {
+        changes.push(Change::Added {
+            path: path.clone().index(index),
+            value: new.clone(),
+        });
+    }
+}
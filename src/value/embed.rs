@@ -0,0 +1,317 @@
+//! `Serialize` and `Deserialize` for `Value`
+//!
+//! Other formats see a value like serde_json represents Rust data:
+//! structs become maps, newtypes their content and enum variants
+//! a map from the variant name to its content.
+//! This makes transcoding to formats like JSON or MessagePack a matter of
+//! serializing the parsed `Value`:
+//!
+//! | Value                  | Data model                        |
+//! |------------------------|-----------------------------------|
+//! | `Name(a: 1)`           | map `{"a": 1}`                    |
+//! | `app::Name(a: 1)`      | map `{"a": 1}`                    |
+//! | `Name(1, 2)`           | tuple `[1, 2]`                    |
+//! | `Name(1)`              | its content, `1`                  |
+//! | `Name`                 | unit                              |
+//! | `Enum::Variant`        | str `"Variant"`                   |
+//! | `Enum::Variant(a: 1)`  | map `{"Variant": {"a": 1}}`       |
+//! | `Enum::Variant(1, 2)`  | map `{"Variant": [1, 2]}`         |
+//! | `Enum::Variant(1)`     | map `{"Variant": 1}`              |
+//! | `<Vec<u8>>`            | str `"Vec<u8>"`                   |
+//! | `b"ab"`                | bytes                             |
+//! | `Some(x)` and `None`   | some and none                     |
+//!
+//! A path names an enum variant when the segment before the variant is a
+//! type, see `TypeIdentifier::variant`. The module paths written by
+//! `SerializeOptions::type_paths` keep naming structs.
+//!
+//! Formats that print newtype structs, like RON, show one extra newtype
+//! around the outermost value.
+//!
+//! The serializers and deserializers of this crate keep the value as is,
+//! including struct and enum names. A value asks for this by wrapping
+//! itself in a newtype struct named `TOKEN`, and the value itself
+//! is handed over through a thread local slot instead of the data model.
+
+use crate::value::{
+    map::{Map, OrderedMap},
+    number::Number,
+    serializer::ValueSerializer,
+    types::{Identifier, TypeIdentifier},
+    Value,
+};
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq, SerializeTuple},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    fmt::Formatter,
+};
+
+/// Name of the newtype struct a `Value` serializes itself as
+pub(crate) const TOKEN: &str = "$typed_format::Value";
+
+thread_local! {
+    /// Set while a serializer of this crate waits for a value
+    static WANTED: Cell<bool> = const { Cell::new(false) };
+    /// The value passed between a `Value` and a serializer or deserializer
+    static HANDED_OVER: RefCell<Option<Value>> = const { RefCell::new(None) };
+}
+
+/// Takes the value out of the content of a `TOKEN` newtype struct
+///
+/// Returns None if the content is not a `Value`.
+pub(crate) fn capture<T>(content: &T) -> Option<Value>
+where
+    T: Serialize + ?Sized,
+{
+    WANTED.with(|wanted| wanted.set(true));
+    let _ = content.serialize(ValueSerializer);
+    WANTED.with(|wanted| wanted.set(false));
+
+    HANDED_OVER.with(|slot| slot.borrow_mut().take())
+}
+
+/// Makes `value` the result of the `Value` being deserialized
+///
+/// The deserializer calls this right before `visit_unit`.
+pub(crate) fn hand_over(value: Value) {
+    HANDED_OVER.with(|slot| *slot.borrow_mut() = Some(value));
+}
+
+/// Drops a value that no `Value` picked up
+pub(crate) fn discard() {
+    HANDED_OVER.with(|slot| slot.borrow_mut().take());
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, &Content(self))
+    }
+}
+
+/// The data model representation of a value
+struct Content<'lt>(&'lt Value);
+
+impl<'lt> Serialize for Content<'lt> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if WANTED.with(|wanted| wanted.replace(false)) {
+            HANDED_OVER.with(|slot| *slot.borrow_mut() = Some(self.0.clone()));
+            return serializer.serialize_unit();
+        }
+
+        match self.0 {
+            Value::Unit => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Char(v) => serializer.serialize_char(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::Number(v) => match *v {
+                Number::I64(v) => serializer.serialize_i64(v),
+                Number::U64(v) => serializer.serialize_u64(v),
+                Number::I128(v) => serializer.serialize_i128(v),
+                Number::U128(v) => serializer.serialize_u128(v),
+                Number::F64(v) => serializer.serialize_f64(v),
+            },
+            Value::Type(v) => serializer.collect_str(v),
+            Value::UnitStruct(identifier) => match variant(identifier) {
+                Some(variant) => serializer.serialize_str(variant),
+                None => serializer.serialize_unit(),
+            },
+            Value::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Content(item))?;
+                }
+                seq.end()
+            },
+            Value::Tuple(items) => Items(items).serialize(serializer),
+            Value::Map(map) => {
+                let mut entries = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    entries.serialize_entry(&Content(key), &Content(value))?;
+                }
+                entries.end()
+            },
+            Value::Option(None) => serializer.serialize_none(),
+            Value::Option(Some(v)) => serializer.serialize_some(&Content(v)),
+            Value::Struct(identifier, fields) => {
+                let fields = Fields(fields);
+                match variant(identifier) {
+                    Some(variant) => single_entry(serializer, variant, &fields),
+                    None => fields.serialize(serializer),
+                }
+            },
+            Value::TupleStruct(identifier, items) => {
+                match (variant(identifier), items.as_slice()) {
+                    (Some(variant), [newtype]) => {
+                        single_entry(serializer, variant, &Content(newtype))
+                    },
+                    (Some(variant), items) => {
+                        single_entry(serializer, variant, &Items(items))
+                    },
+                    (None, [newtype]) => Content(newtype).serialize(serializer),
+                    (None, items) => Items(items).serialize(serializer),
+                }
+            },
+        }
+    }
+}
+
+/// The items of a tuple or tuple struct
+struct Items<'lt>(&'lt [Value]);
+
+impl<'lt> Serialize for Items<'lt> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for item in self.0 {
+            tuple.serialize_element(&Content(item))?;
+        }
+        tuple.end()
+    }
+}
+
+/// The fields of a struct, as a map with string keys
+struct Fields<'lt>(&'lt OrderedMap<Identifier, Value>);
+
+impl<'lt> Serialize for Fields<'lt> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key.0.as_str(), &Content(value))?;
+        }
+        map.end()
+    }
+}
+
+fn single_entry<S, T>(
+    serializer: S,
+    key: &str,
+    value: &T,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(key, value)?;
+    map.end()
+}
+
+/// The name of an enum variant, see `TypeIdentifier::variant`
+fn variant(identifier: &TypeIdentifier) -> Option<&str> {
+    identifier.variant().map(|variant| variant.0.as_str())
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<Value, E> {
+        Ok(Value::Char(v))
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.into()))
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Option(None))
+    }
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = <Value as Deserialize>::deserialize(deserializer)?;
+        Ok(Value::Option(Some(Box::new(value))))
+    }
+
+    /// A value handed over by a deserializer of this crate, or `()`
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        let value = HANDED_OVER.with(|slot| slot.borrow_mut().take());
+        Ok(value.unwrap_or(Value::Unit))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::List(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Map::new();
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+        Ok(Value::Map(entries))
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    error::Error,
+    value::{
+        diagnostic::ParseError,
+        map::Map,
+        number::Number,
+        path::Path,
+        types::{Identifier, TypeIdentifier},
+        Value,
+    },
+};
+use serde_json::Value as JsonValue;
+use std::iter::once;
+
+/// How structs are represented in JSON
+#[derive(Clone, Default, Debug)]
+pub struct JsonOptions {
+    /// Key of an object entry holding the struct name, like `"$type"`
+    ///
+    /// Structs become objects with their name under this key, tuple
+    /// structs use the keys `"0"`, `"1"`, ... for their items and unit
+    /// structs are objects with only the name. Reading turns such objects
+    /// back into structs. Without a key, structs and enums follow the
+    /// representation serde_json uses and objects are read as maps.
+    pub type_tag: Option<String>,
+    /// Writes one entry per line
+    pub pretty: bool,
+}
+
+impl JsonOptions {
+    pub fn type_tag<S>(mut self, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.type_tag = Some(key.into());
+        self
+    }
+
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+}
+
+/// Parses JSON text into a value
+///
+/// Objects become maps with string keys, `null` becomes `None`
+pub fn from_json(input: &str) -> Result<Value, Error> {
+    from_json_with_options(input, &JsonOptions::default())
+}
+
+/// Parses JSON text into a value, see `JsonOptions`
+pub fn from_json_with_options(
+    input: &str,
+    options: &JsonOptions,
+) -> Result<Value, Error> {
+    let json = serde_json::from_str(input)?;
+    match &options.type_tag {
+        Some(tag) => read_tagged(json, tag),
+        None => Ok(Value::from(json)),
+    }
+}
+
+/// Writes a value as JSON text
+///
+/// Structs and enums follow the representation serde_json uses
+pub fn to_json(value: &Value, pretty: bool) -> String {
+    to_json_with_options(value, &JsonOptions::default().pretty(pretty))
+}
+
+/// Writes a value as JSON text, see `JsonOptions`
+pub fn to_json_with_options(value: &Value, options: &JsonOptions) -> String {
+    let json = write_value(value, options.type_tag.as_deref());
+
+    if options.pretty {
+        serde_json::to_string_pretty(&json)
+    } else {
+        serde_json::to_string(&json)
+    }
+    .expect("JSON values can always be written")
+}
+
+/// Converts JSON text to the pretty text format
+pub fn json_to_tyf(
+    input: &str,
+    options: &JsonOptions,
+) -> Result<String, Error> {
+    Ok(from_json_with_options(input, options)?.to_string_pretty())
+}
+
+/// Converts the text format to JSON text
+pub fn tyf_to_json(
+    input: &str,
+    options: &JsonOptions,
+) -> Result<String, ParseError> {
+    Ok(to_json_with_options(&Value::parse(input)?, options))
+}
+
+impl From<&Value> for JsonValue {
+    /// Structs and enums follow the representation serde_json uses,
+    /// infinities and NaN become `null`
+    fn from(value: &Value) -> Self {
+        write_value(value, None)
+    }
+}
+
+impl From<Value> for JsonValue {
+    fn from(value: Value) -> Self {
+        JsonValue::from(&value)
+    }
+}
+
+impl From<JsonValue> for Value {
+    /// Objects become maps with string keys, `null` becomes `None`
+    fn from(json: JsonValue) -> Self {
+        match json {
+            JsonValue::Null => Value::Option(None),
+            JsonValue::Bool(b) => Value::Bool(b),
+            JsonValue::Number(n) => Value::Number(read_number(&n)),
+            JsonValue::String(s) => Value::String(s.into()),
+            JsonValue::Array(items) => {
+                Value::List(items.into_iter().map(Value::from).collect())
+            },
+            JsonValue::Object(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (Value::String(key.into()), Value::from(value))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Reads objects with the type tag as structs, see `untag`
+fn read_tagged(json: JsonValue, tag: &str) -> Result<Value, Error> {
+    Ok(match json {
+        JsonValue::Array(items) => Value::List(
+            items
+                .into_iter()
+                .map(|item| read_tagged(item, tag))
+                .collect::<Result<_, _>>()?,
+        ),
+        JsonValue::Object(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                map.insert(Value::String(key.into()), read_tagged(value, tag)?);
+            }
+            untag(map, tag)?
+        },
+        scalar => scalar.into(),
+    })
+}
+
+/// Integers are kept exact as far as JSON numbers can hold them
+fn read_number(number: &serde_json::Number) -> Number {
+    if let Some(v) = number.as_u64() {
+        Number::U64(v)
+    } else if let Some(v) = number.as_i64() {
+        Number::I64(v)
+    } else {
+        Number::F64(number.as_f64().unwrap_or(f64::NAN))
+    }
+}
+
+/// 128 bit integers outside of the 64 bit range are written as floats
+fn write_number(number: Number) -> JsonValue {
+    if let Some(v) = number.as_u64() {
+        v.into()
+    } else if let Some(v) = number.as_i64() {
+        v.into()
+    } else {
+        // JSON has no infinities or NaN
+        serde_json::Number::from_f64(number.as_f64())
+            .map_or(JsonValue::Null, JsonValue::Number)
+    }
+}
+
+fn write_value(value: &Value, tag: Option<&str>) -> JsonValue {
+    match value {
+        Value::Unit | Value::Option(None) => JsonValue::Null,
+        Value::Bool(b) => JsonValue::Bool(*b),
+        Value::Char(c) => JsonValue::String(c.to_string()),
+        Value::String(s) => JsonValue::String(s.to_string()),
+        Value::Number(n) => write_number(*n),
+        // Like serde_json, as an array of numbers
+        Value::Bytes(b) => b.iter().map(|&b| JsonValue::from(b)).collect(),
+        Value::Type(t) => JsonValue::String(t.to_string()),
+        Value::Option(Some(value)) => write_value(value, tag),
+        Value::List(items) | Value::Tuple(items) => {
+            items.iter().map(|item| write_value(item, tag)).collect()
+        },
+        Value::Map(map) => JsonValue::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Value::String(s) => s.to_string(),
+                        other => other.to_string_compact(),
+                    };
+                    (key, write_value(value, tag))
+                })
+                .collect(),
+        ),
+
+        Value::UnitStruct(identifier)
+        | Value::Struct(identifier, _)
+        | Value::TupleStruct(identifier, _)
+            if tag.is_some() =>
+        {
+            write_tagged(identifier, value, tag.unwrap_or_default())
+        },
+
+        Value::UnitStruct(identifier) => match variant(identifier) {
+            Some(variant) => JsonValue::String(variant.to_string()),
+            None => JsonValue::Null,
+        },
+        Value::TupleStruct(identifier, items) => write_variant(
+            identifier,
+            match items.as_slice() {
+                [newtype] => write_value(newtype, tag),
+                items => {
+                    items.iter().map(|item| write_value(item, tag)).collect()
+                },
+            },
+        ),
+        Value::Struct(identifier, fields) => write_variant(
+            identifier,
+            JsonValue::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| {
+                        (key.0.clone(), write_value(value, tag))
+                    })
+                    .collect(),
+            ),
+        ),
+    }
+}
+
+/// Writes a struct as an object with its name under the type tag
+fn write_tagged(
+    identifier: &TypeIdentifier,
+    value: &Value,
+    tag: &str,
+) -> JsonValue {
+    let name = JsonValue::String(identifier.to_string());
+    let entries = once((tag.to_string(), name));
+
+    JsonValue::Object(match value {
+        Value::Struct(_, fields) => entries
+            .chain(fields.iter().map(|(key, value)| {
+                (key.0.clone(), write_value(value, Some(tag)))
+            }))
+            .collect(),
+        Value::TupleStruct(_, items) => entries
+            .chain(items.iter().enumerate().map(|(index, item)| {
+                (index.to_string(), write_value(item, Some(tag)))
+            }))
+            .collect(),
+        _ => entries.collect(),
+    })
+}
+
+/// Wraps enum variants in an object with the variant name as key
+fn write_variant(identifier: &TypeIdentifier, content: JsonValue) -> JsonValue {
+    match variant(identifier) {
+        Some(variant) => {
+            JsonValue::Object(once((variant.to_string(), content)).collect())
+        },
+        None => content,
+    }
+}
+
+/// Turns an object with a type tag back into a struct
+///
+/// Objects without the tag stay maps
+fn untag(mut map: Map<Value, Value>, tag: &str) -> Result<Value, Error> {
+    let name = match map.remove(&Value::String(tag.into())) {
+        Some(Value::String(name)) => {
+            TypeIdentifier::parse(&name).map_err(|e| invalid(e.to_string()))?
+        },
+        Some(other) => {
+            return Err(invalid(format!(
+                "Expected a type name, found {:?}",
+                other
+            )))
+        },
+        None => return Ok(Value::Map(map)),
+    };
+
+    if map.is_empty() {
+        return Ok(Value::UnitStruct(name));
+    }
+
+    // Keys "0", "1", ... are the items of a tuple struct,
+    // they can not be confused with fields since they are no identifiers
+    let indices = map
+        .keys()
+        .map(|key| match key {
+            Value::String(key) => key
+                .parse::<usize>()
+                .ok()
+                .filter(|index| index.to_string() == key.as_ref()),
+            _ => None,
+        })
+        .collect::<Option<Vec<usize>>>();
+    if let Some(indices) = indices {
+        let mut items = indices
+            .into_iter()
+            .zip(map.into_values())
+            .collect::<Vec<_>>();
+        items.sort_by_key(|(index, _)| *index);
+
+        if items.iter().enumerate().any(|(i, (index, _))| i != *index) {
+            return Err(invalid(format!(
+                "Missing item of tuple struct {}",
+                name
+            )));
+        }
+        return Ok(Value::TupleStruct(
+            name,
+            items.into_iter().map(|(_, value)| value).collect(),
+        ));
+    }
+
+    let fields = map
+        .into_iter()
+        .map(|(key, value)| match key {
+            Value::String(key) => (Identifier::from(key.as_ref()), value),
+            _ => unreachable!("JSON keys are strings"),
+        })
+        .collect();
+    Ok(Value::Struct(name, fields))
+}
+
+fn invalid(message: String) -> Error {
+    Error::Invalid {
+        path: Path::root(),
+        message,
+    }
+}
+
+/// Enum variants are serialized with their enum name as the first segment
+fn variant(identifier: &TypeIdentifier) -> Option<&str> {
+    match identifier.segments.as_slice() {
+        [.., _, variant] => Some(variant.identifier.0.as_str()),
+        _ => None,
+    }
+}
@@ -0,0 +1,401 @@
+//! The maps of `Value::Map` and of struct fields
+//!
+//! Struct fields are kept in an `OrderedMap`, in the order they were
+//! written or serialized in, which is the declaration order of Rust
+//! structs. Maps are `BTreeMap`s, which sort their entries, unless the
+//! `preserve_order` feature makes them `OrderedMap`s as well.
+
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap},
+    fmt,
+    fmt::{Debug, Formatter},
+    iter::FromIterator,
+    ops::Index,
+    slice, vec,
+};
+
+#[cfg(not(feature = "preserve_order"))]
+pub type Map<K, V> = BTreeMap<K, V>;
+#[cfg(not(feature = "preserve_order"))]
+pub type Iter<'lt, K, V> = btree_map::Iter<'lt, K, V>;
+
+#[cfg(feature = "preserve_order")]
+pub type Map<K, V> = OrderedMap<K, V>;
+#[cfg(feature = "preserve_order")]
+pub type Iter<'lt, K, V> = OrderedIter<'lt, K, V>;
+
+/// A map that iterates in insertion order
+///
+/// Replacing the value of a key keeps its position, removing an entry
+/// moves the later ones forward. Maps are compared like `BTreeMap`s, by
+/// their entries in key order, so the order does not affect equality.
+#[derive(Clone)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    /// Position of every key in `entries`
+    positions: BTreeMap<K, usize>,
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        OrderedMap::new()
+    }
+}
+
+impl<K, V> OrderedMap<K, V> {
+    pub const fn new() -> Self {
+        OrderedMap {
+            entries: Vec::new(),
+            positions: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+
+    pub fn iter(&self) -> OrderedIter<'_, K, V> {
+        OrderedIter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> OrderedIterMut<'_, K, V> {
+        OrderedIterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + '_ {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + '_ {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut V> + '_ {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+
+    pub fn into_keys(self) -> impl DoubleEndedIterator<Item = K> {
+        self.entries.into_iter().map(|(key, _)| key)
+    }
+
+    pub fn into_values(self) -> impl DoubleEndedIterator<Item = V> {
+        self.entries.into_iter().map(|(_, value)| value)
+    }
+
+    /// The entries in key order
+    fn sorted(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.positions.values().map(move |position| {
+            let (key, value) = &self.entries[*position];
+            (key, value)
+        })
+    }
+}
+
+impl<K, V> OrderedMap<K, V>
+where
+    K: Ord + Clone,
+{
+    /// Inserts at the end, or replaces the value of an existing key in place
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.positions.entry(key) {
+            btree_map::Entry::Occupied(entry) => Some(std::mem::replace(
+                &mut self.entries[*entry.get()].1,
+                value,
+            )),
+            btree_map::Entry::Vacant(entry) => {
+                self.entries.push((entry.key().clone(), value));
+                entry.insert(self.entries.len() - 1);
+                None
+            },
+        }
+    }
+
+    /// The entry of `key`, for inserting it only if it is missing
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        Entry { map: self, key }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let position = *self.positions.get(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let position = *self.positions.get(key)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.positions.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let removed = self.positions.remove(key)?;
+        for position in self.positions.values_mut() {
+            if *position > removed {
+                *position -= 1;
+            }
+        }
+        Some(self.entries.remove(removed))
+    }
+
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let entries = std::mem::take(&mut self.entries);
+        self.positions.clear();
+        for (key, mut value) in entries {
+            if keep(&key, &mut value) {
+                self.insert(key, value);
+            }
+        }
+    }
+
+    /// Moves all entries of `other` into this map
+    pub fn append(&mut self, other: &mut Self) {
+        self.extend(std::mem::take(other));
+    }
+}
+
+/// An entry of an `OrderedMap` that may be missing
+pub struct Entry<'lt, K, V> {
+    map: &'lt mut OrderedMap<K, V>,
+    key: K,
+}
+
+impl<'lt, K, V> Entry<'lt, K, V>
+where
+    K: Ord + Clone,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn or_insert(self, default: V) -> &'lt mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Appends the entry if it is missing
+    pub fn or_insert_with<F>(self, default: F) -> &'lt mut V
+    where
+        F: FnOnce() -> V,
+    {
+        let position = match self.map.positions.get(&self.key) {
+            Some(position) => *position,
+            None => {
+                self.map.insert(self.key, default());
+                self.map.entries.len() - 1
+            },
+        };
+        &mut self.map.entries[position].1
+    }
+}
+
+impl<K, V> PartialEq for OrderedMap<K, V>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.sorted().eq(other.sorted())
+    }
+}
+
+impl<K, V> Eq for OrderedMap<K, V>
+where
+    K: Eq,
+    V: Eq,
+{
+}
+
+impl<K, V> PartialOrd for OrderedMap<K, V>
+where
+    K: PartialOrd,
+    V: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.sorted().partial_cmp(other.sorted())
+    }
+}
+
+impl<K, V> Ord for OrderedMap<K, V>
+where
+    K: Ord,
+    V: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sorted().cmp(other.sorted())
+    }
+}
+
+impl<K, V> Debug for OrderedMap<K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, Q, V> Index<&Q> for OrderedMap<K, V>
+where
+    K: Ord + Clone + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for OrderedMap<K, V>
+where
+    K: Ord + Clone,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = OrderedMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> Extend<(K, V)> for OrderedMap<K, V>
+where
+    K: Ord + Clone,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for OrderedMap<K, V>
+where
+    K: Ord + Clone,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'lt, K, V> IntoIterator for &'lt OrderedMap<K, V> {
+    type Item = (&'lt K, &'lt V);
+    type IntoIter = OrderedIter<'lt, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'lt, K, V> IntoIterator for &'lt mut OrderedMap<K, V> {
+    type Item = (&'lt K, &'lt mut V);
+    type IntoIter = OrderedIterMut<'lt, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The entries of an `OrderedMap` in insertion order
+#[derive(Clone, Debug)]
+pub struct OrderedIter<'lt, K, V>(slice::Iter<'lt, (K, V)>);
+
+impl<'lt, K, V> Iterator for OrderedIter<'lt, K, V> {
+    type Item = (&'lt K, &'lt V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for OrderedIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for OrderedIter<'_, K, V> {}
+
+/// The entries of an `OrderedMap` in insertion order, with mutable values
+#[derive(Debug)]
+pub struct OrderedIterMut<'lt, K, V>(slice::IterMut<'lt, (K, V)>);
+
+impl<'lt, K, V> Iterator for OrderedIterMut<'lt, K, V> {
+    type Item = (&'lt K, &'lt mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for OrderedIterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for OrderedIterMut<'_, K, V> {}
@@ -0,0 +1,61 @@
+//! Layering of values, see `Value::merge`
+
+use crate::value::Value;
+
+/// How `Value::merge` combines values
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct MergeStrategy {
+    pub lists: ListMerge,
+}
+
+/// How lists are combined, see `MergeStrategy`
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum ListMerge {
+    /// The list of the overlay replaces the other one
+    #[default]
+    Replace,
+    /// The items of the overlay are appended
+    Append,
+}
+
+/// Merges `overlay` into `base`, see `Value::merge`
+pub(crate) fn merge(
+    base: &mut Value,
+    overlay: &Value,
+    strategy: MergeStrategy,
+) {
+    match (base, overlay) {
+        // Overlays may leave out the name of a struct
+        (Value::Struct(name, fields), Value::Struct(overlay_name, overlay))
+            if overlay_name == name || overlay_name.segments.is_empty() =>
+        {
+            for (field, value) in overlay {
+                match fields.get_mut(field) {
+                    Some(existing) => merge(existing, value, strategy),
+                    None => {
+                        fields.insert(field.clone(), value.clone());
+                    },
+                }
+            }
+        },
+        (Value::Map(map), Value::Map(overlay)) => {
+            for (key, value) in overlay {
+                match map.get_mut(key) {
+                    Some(existing) => merge(existing, value, strategy),
+                    None => {
+                        map.insert(key.clone(), value.clone());
+                    },
+                }
+            }
+        },
+        (Value::Option(Some(inner)), Value::Option(Some(overlay))) => {
+            merge(inner, overlay, strategy)
+        },
+        (Value::List(items), Value::List(overlay))
+            if strategy.lists == ListMerge::Append =>
+        {
+            items.extend(overlay.iter().cloned())
+        },
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
@@ -1,34 +1,91 @@
+pub mod anchors;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod binary;
+pub mod comments;
+pub mod compact;
 pub mod deserializer;
+pub mod diagnostic;
+pub mod diff;
+pub(crate) mod embed;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod map;
+pub mod merge;
+pub mod number;
+pub mod options;
+pub(crate) mod owned;
 pub(crate) mod parser;
+pub mod patch;
+pub mod path;
 pub mod printer;
+#[cfg(feature = "ron")]
+pub mod ron;
 pub mod serializer;
+pub mod spans;
+pub mod stream;
+#[cfg(feature = "toml")]
+pub mod toml;
 pub mod types;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
-use crate::value::{
-    deserializer::{ValueDeserializer, ValueDeserializerError},
-    printer::ValuePrinter,
-    serializer::{ValueSerializer, ValueSerializerError},
-    types::{Identifier, Type, TypeIdentifier},
+use crate::{
+    error::Error,
+    progress::ProgressReporter,
+    value::{
+        binary::DecodeError,
+        comments::Comments,
+        deserializer::{ValueDeserializer, ValueDeserializerError},
+        diagnostic::ParseError,
+        diff::ValueDiff,
+        map::{Map, OrderedMap},
+        merge::MergeStrategy,
+        number::Number,
+        options::{DeserializeOptions, ParseOptions, SerializeOptions},
+        patch::{PatchError, ValuePatch},
+        path::{Path, PathSegment, ValueIndex},
+        printer::ValuePrinter,
+        serializer::{ValueSerializer, ValueSerializerError},
+        spans::Spans,
+        types::{Identifier, Type, TypeIdentifier},
+    },
+};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed},
+    Deserialize, Serialize,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fmt::{Display, Formatter},
+    iter::FromIterator,
+    ops::{Index, IndexMut},
+    str::FromStr,
+    sync::Arc,
 };
-use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub enum Value {
     Unit,
     Bool(bool),
     Char(char),
-    String(String),
-    Number(String),
+    String(Arc<str>),
+    /// Binary data, written as a byte string like `b"\x00data"`
+    Bytes(Vec<u8>),
+    Number(Number),
 
     Type(Type),
+    /// A unit struct or unit enum variant, such as `Foo` or `Enum::Variant`
+    UnitStruct(TypeIdentifier),
 
     List(Vec<Value>),
     Tuple(Vec<Value>),
-    Map(BTreeMap<Value, Value>),
+    Map(Map<Value, Value>),
     Option(Option<Box<Value>>),
 
-    Struct(TypeIdentifier, BTreeMap<Identifier, Value>),
+    /// A struct with its fields in the order they were written or serialized
+    Struct(TypeIdentifier, OrderedMap<Identifier, Value>),
     TupleStruct(TypeIdentifier, Vec<Value>),
 }
 
@@ -40,12 +97,13 @@ pub enum ParsedNumber {
 }
 
 impl ParsedNumber {
+    /// Parses a number literal, see `number` for the accepted notations
     pub fn parse(s: &str) -> Option<Self> {
-        Some(if let Ok(v) = s.parse() {
+        Some(if let Some(v) = number::parse_literal(s) {
             ParsedNumber::U64(v)
-        } else if let Ok(v) = s.parse() {
+        } else if let Some(v) = number::parse_literal(s) {
             ParsedNumber::I64(v)
-        } else if let Ok(v) = s.parse() {
+        } else if let Some(v) = number::parse_literal(s) {
             ParsedNumber::F64(v)
         } else {
             return None;
@@ -60,6 +118,24 @@ impl Value {
     {
         s.serialize(ValueSerializer)
     }
+    /// Like `try_new`, with options like writing full type paths
+    ///
+    /// With `root_type_path`, the path of `T` is taken from
+    /// `std::any::type_name`, see `SerializeOptions`.
+    pub fn try_new_with<T>(
+        value: &T,
+        options: &SerializeOptions,
+    ) -> Result<Value, ValueSerializerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        if options.root_type_path {
+            let mut options = options.clone();
+            options.insert_type_path::<T>();
+            return value.serialize(ValueSerializer::with_options(&options));
+        }
+        value.serialize(ValueSerializer::with_options(options))
+    }
     pub fn new<S>(s: S) -> Value
     where
         S: Serialize,
@@ -67,15 +143,157 @@ impl Value {
         Self::try_new(s).unwrap()
     }
 
-    pub fn parse(string: &str) -> anyhow::Result<Self> {
-        parser::parse_main_value(string)
+    /// `Result::Ok(value)`, as serde serializes `Ok` of a `Result`
+    pub fn ok(value: Value) -> Value {
+        Value::TupleStruct(("Result", "Ok").into(), vec![value])
+    }
+    /// `Result::Err(value)`, as serde serializes `Err` of a `Result`
+    pub fn err(value: Value) -> Value {
+        Value::TupleStruct(("Result", "Err").into(), vec![value])
+    }
+
+    /// Views an `Ok(..)` or `Err(..)` value as a Result
+    ///
+    /// Both the serialized form `Result::Ok(..)` and the short form `Ok(..)`
+    /// are recognized.
+    pub fn as_result(&self) -> Option<Result<&Value, &Value>> {
+        let (identifier, items) = match self {
+            Value::TupleStruct(identifier, items) => (identifier, items),
+            _ => return None,
+        };
+        let value = match items.as_slice() {
+            [value] => value,
+            _ => return None,
+        };
+
+        let names = identifier
+            .segments
+            .iter()
+            .map(|segment| segment.identifier.0.as_str())
+            .collect::<Vec<_>>();
+        match names.as_slice() {
+            ["Ok"] | ["Result", "Ok"] => Some(Ok(value)),
+            ["Err"] | ["Result", "Err"] => Some(Err(value)),
+            _ => None,
+        }
+    }
+
+    pub fn parse(string: &str) -> Result<Self, ParseError> {
+        Self::parse_with(string, &ParseOptions::default())
+    }
+    /// Parses a value with limits and leniencies, see `ParseOptions`
+    pub fn parse_with(
+        string: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        parser::parse_main_value(string, options)
+    }
+    /// Parses a sequence of values, as in log files
+    ///
+    /// Each value starts on a new line, or values are separated by lines
+    /// of `---`. See `ValueStream` to read them one at a time.
+    pub fn parse_all(string: &str) -> Result<Vec<Self>, ParseError> {
+        parser::parse_main_documents(string, &ParseOptions::default())
+    }
+    /// Parses a value while reporting progress, see `ProgressReporter`
+    ///
+    /// Fails with `Cancelled` if its token was cancelled during the parse.
+    pub fn parse_with_progress<'lt>(
+        string: &str,
+        options: &'lt ParseOptions,
+        progress: ProgressReporter<'lt>,
+    ) -> Result<Self, Error> {
+        parser::parse_main_value_with_progress(string, options, progress)
+    }
+
+    /// Parses a value and keeps its comments, see `Comments`
+    pub fn parse_with_comments(
+        string: &str,
+    ) -> Result<(Self, Comments), ParseError> {
+        parser::parse_main_value_with_comments(string, &ParseOptions::default())
+    }
+
+    /// Parses a value and the byte range of each of its parts, see `Spans`
+    pub fn parse_with_spans(string: &str) -> Result<(Self, Spans), ParseError> {
+        parser::parse_main_value_with_spans(string, &ParseOptions::default())
+    }
+
+    /// Decodes the binary encoding, see `binary`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        binary::from_bytes(bytes)
+    }
+
+    /// Encodes this value in the binary encoding, see `binary`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::to_bytes(self)
+    }
+
+    /// Parses JSON text, see `json::JsonOptions`
+    #[cfg(feature = "json")]
+    pub fn from_json(
+        input: &str,
+        options: &json::JsonOptions,
+    ) -> Result<Self, Error> {
+        json::from_json_with_options(input, options)
+    }
+
+    /// Writes this value as JSON text, see `json::JsonOptions`
+    #[cfg(feature = "json")]
+    pub fn to_json(&self, options: &json::JsonOptions) -> String {
+        json::to_json_with_options(self, options)
+    }
+
+    /// Reads a TOML document, see `toml`
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(input: &str) -> Result<Self, Error> {
+        toml::from_toml(input)
+    }
+
+    /// Reads a YAML document, see `yaml`
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(input: &str) -> Result<Self, Error> {
+        yaml::from_yaml(input)
     }
 
     pub fn deserialize<'lt, T>(&'lt self) -> Result<T, ValueDeserializerError>
     where
         T: Deserialize<'lt>,
     {
-        T::deserialize(ValueDeserializer { value: self })
+        T::deserialize(ValueDeserializer::new(self))
+    }
+    /// Deserializes with settings like skipping unknown fields,
+    /// see `DeserializeOptions`
+    pub fn deserialize_with<'lt, T>(
+        &'lt self,
+        options: &DeserializeOptions,
+    ) -> Result<T, ValueDeserializerError>
+    where
+        T: Deserialize<'lt>,
+    {
+        T::deserialize(ValueDeserializer::new(self).with_options(*options))
+    }
+
+    /// Deserializes with a seed, for types that need some state to be
+    /// built, like an interner or a registry of types
+    pub fn deserialize_seed<'lt, S>(
+        &'lt self,
+        seed: S,
+    ) -> Result<S::Value, ValueDeserializerError>
+    where
+        S: DeserializeSeed<'lt>,
+    {
+        seed.deserialize(ValueDeserializer::new(self))
+    }
+
+    /// Restores the struct and enum names, using the definition of T
+    ///
+    /// Meant for documents printed with `ValuePrinter::omit_names`.
+    pub fn retag<T>(&self) -> Result<Value, Error>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let typed: T = self.deserialize()?;
+        Ok(Value::try_new(typed)?)
     }
 
     pub fn to_string_pretty(&self) -> String {
@@ -86,6 +304,30 @@ impl Value {
 
         buffer
     }
+    pub fn to_string_auto(&self) -> String {
+        let mut buffer = String::new();
+        let printer = ValuePrinter::auto();
+
+        printer.write(self, &mut buffer).unwrap();
+
+        buffer
+    }
+    pub fn to_string_diff(&self) -> String {
+        let mut buffer = String::new();
+        let printer = ValuePrinter::diff();
+
+        printer.write(self, &mut buffer).unwrap();
+
+        buffer
+    }
+    pub fn to_string_fit(&self) -> String {
+        let mut buffer = String::new();
+        let printer = ValuePrinter::fit();
+
+        printer.write(self, &mut buffer).unwrap();
+
+        buffer
+    }
     pub fn to_string_compact(&self) -> String {
         let mut buffer = String::new();
         let printer = ValuePrinter::compact();
@@ -95,11 +337,416 @@ impl Value {
         buffer
     }
 
+    /// The changes that turn this value into `other`, see `ValueDiff`
+    pub fn diff(&self, other: &Value) -> ValueDiff {
+        let mut changes = Vec::new();
+        diff::diff(self, other, &mut Path::root(), &mut changes);
+        ValueDiff { changes }
+    }
+
+    /// Deep merges `overlay` into this value, as in layered configuration
+    ///
+    /// Fields of structs and entries of maps are merged one by one, so
+    /// the overlay only needs to contain what it changes. Structs without
+    /// a name merge into structs of any name. Everything else, including
+    /// `None`, is replaced by the overlay, lists are replaced or appended
+    /// to as the strategy says.
+    pub fn merge(&mut self, overlay: &Value, strategy: MergeStrategy) {
+        merge::merge(self, overlay, strategy)
+    }
+
+    /// Applies the operations of `patch` in order
+    ///
+    /// Either all operations succeed or this value is left unchanged.
+    pub fn apply_patch(
+        &mut self,
+        patch: &ValuePatch,
+    ) -> Result<(), PatchError> {
+        let mut patched = self.clone();
+        patch.apply_to(&mut patched)?;
+        *self = patched;
+        Ok(())
+    }
+
+    /// A patch that turns this value into `other`, see `diff`
+    pub fn diff_as_patch(&self, other: &Value) -> ValuePatch {
+        ValuePatch::from_changes(&self.diff(other).changes)
+    }
+
+    /// Creates a copy that only contains the given paths and their ancestors
+    ///
+    /// Items of lists and tuples that are not selected are removed,
+    /// so the indices of the remaining items may change.
+    /// Paths that do not exist in this value are ignored.
+    pub fn project(&self, paths: &[Path]) -> Value {
+        let paths = paths
+            .iter()
+            .map(|path| path.segments.as_slice())
+            .collect::<Vec<_>>();
+
+        path::project(self, &paths).unwrap_or(Value::Unit)
+    }
+
+    /// The struct field, list item or map entry selected by `index`
+    ///
+    /// ```ignore
+    /// value.get("server")?.get("ports")?.get(0)
+    /// ```
+    ///
+    /// Options are looked through, so fields of an optional struct
+    /// can be accessed directly.
+    pub fn get<I>(&self, index: I) -> Option<&Value>
+    where
+        I: ValueIndex,
+    {
+        path::child(self, &index.segment())
+    }
+
+    /// The value at `path`, or None if any step of it does not exist
+    pub fn get_path(&self, path: &Path) -> Option<&Value> {
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| path::child(value, segment))
+    }
+
+    /// The value at a path like `server.ports[0]`, see `Path`
+    ///
+    /// Returns None if the path is invalid or does not exist
+    pub fn pointer(&self, path: &str) -> Option<&Value> {
+        self.get_path(&Path::parse(path).ok()?)
+    }
+
+    /// Like `get`, but returns a mutable reference to the child
+    pub fn get_mut<I>(&mut self, index: I) -> Option<&mut Value>
+    where
+        I: ValueIndex,
+    {
+        path::child_mut(self, &index.segment())
+    }
+
+    /// Like `get_path`, but returns a mutable reference to the value
+    pub fn get_path_mut(&mut self, path: &Path) -> Option<&mut Value> {
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| path::child_mut(value, segment))
+    }
+
+    /// Sets a struct field or map entry, returning the value it replaced
+    ///
+    /// Options are looked through like in `get`.
+    pub fn insert<I>(
+        &mut self,
+        index: I,
+        value: Value,
+    ) -> Result<Option<Value>, Error>
+    where
+        I: ValueIndex,
+    {
+        let segment = index.segment();
+        match self {
+            Value::Option(Some(inner)) => inner.insert(segment, value),
+            Value::Map(map) => {
+                let key = path::map_key(map, &segment);
+                Ok(map.insert(key, value))
+            },
+            Value::Struct(_, fields) => match path::field_name(&segment) {
+                Some(field) => Ok(fields.insert(field, value)),
+                None => Err(Error::Invalid {
+                    path: Path::root(),
+                    message: format!("Can not use {} as a field", segment),
+                }),
+            },
+            other => Err(other.mismatch("struct or map")),
+        }
+    }
+
+    /// Removes a struct field, map entry or list item and returns it
+    ///
+    /// Later items of a list or tuple move up by one.
+    pub fn remove<I>(&mut self, index: I) -> Option<Value>
+    where
+        I: ValueIndex,
+    {
+        let segment = index.segment();
+        match (self, &segment) {
+            (Value::Option(Some(inner)), _) => inner.remove(segment),
+            (Value::List(items), PathSegment::Index(index))
+            | (Value::Tuple(items), PathSegment::Index(index))
+            | (Value::TupleStruct(_, items), PathSegment::Index(index))
+                if *index < items.len() =>
+            {
+                Some(items.remove(*index))
+            },
+            (Value::Map(map), segment) => {
+                let key = path::map_key(map, segment);
+                map.remove(&key)
+            },
+            (Value::Struct(_, fields), segment) => {
+                fields.remove(&path::field_name(segment)?)
+            },
+            _ => None,
+        }
+    }
+
+    /// Appends an item to a list or tuple
+    pub fn push(&mut self, value: Value) -> Result<(), Error> {
+        match self {
+            Value::Option(Some(inner)) => inner.push(value),
+            Value::List(items)
+            | Value::Tuple(items)
+            | Value::TupleStruct(_, items) => {
+                items.push(value);
+                Ok(())
+            },
+            other => Err(other.mismatch("list")),
+        }
+    }
+
+    /// Takes the value out of an option, leaving `None` in its place
+    ///
+    /// Returns None for `None` and for values that are not options.
+    pub fn take(&mut self) -> Option<Value> {
+        match self {
+            Value::Option(inner) => inner.take().map(|inner| *inner),
+            _ => None,
+        }
+    }
+
+    /// Paths of all values in this tree for which `predicate` returns true
+    ///
+    /// The value inside of `Some` has the same path as the option itself.
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<Path>
+    where
+        F: FnMut(&Value) -> bool,
+    {
+        let mut found = Vec::new();
+        path::find_all(self, &mut Path::root(), &mut predicate, &mut found);
+        found
+    }
+
+    /// Paths of all structs, tuple structs and unit structs of a type
+    ///
+    /// `name` may be qualified like `auth::Credentials` and is compared
+    /// with the end of the type path, generic arguments are ignored.
+    pub fn find_by_type(&self, name: &str) -> Vec<Path> {
+        let name = name.split("::").map(str::trim).collect::<Vec<_>>();
+
+        self.find_all(|value| match value {
+            Value::Struct(identifier, _)
+            | Value::TupleStruct(identifier, _)
+            | Value::UnitStruct(identifier) => {
+                identifier.segments.len() >= name.len()
+                    && identifier
+                        .segments
+                        .iter()
+                        .rev()
+                        .zip(name.iter().rev())
+                        .all(|(segment, name)| segment.identifier == *name)
+            },
+            _ => false,
+        })
+    }
+
     pub fn parse_number(&self) -> Option<ParsedNumber> {
-        if let Value::Number(s) = self {
-            ParsedNumber::parse(&s)
-        } else {
-            None
+        match self {
+            Value::Number(Number::F64(v)) => Some(ParsedNumber::F64(*v)),
+            Value::Number(number) => Some(if let Some(v) = number.as_u64() {
+                ParsedNumber::U64(v)
+            } else if let Some(v) = number.as_i64() {
+                ParsedNumber::I64(v)
+            } else {
+                ParsedNumber::F64(number.as_f64())
+            }),
+            _ => None,
+        }
+    }
+
+    /// The entries of a map
+    pub fn into_map(self) -> Result<Map<Value, Value>, Error> {
+        match self {
+            Value::Map(map) => Ok(map),
+            other => Err(other.mismatch("map")),
+        }
+    }
+
+    /// The entries of a map with string keys
+    pub fn into_string_map(self) -> Result<HashMap<String, Value>, Error> {
+        self.into_map()?
+            .into_iter()
+            .map(|(key, value)| match key {
+                Value::String(key) => Ok((key.to_string(), value)),
+                other => Err(other.mismatch("string key")),
+            })
+            .collect()
+    }
+
+    /// The items of a list or tuple
+    pub fn into_list(self) -> Result<Vec<Value>, Error> {
+        match self {
+            Value::List(items) | Value::Tuple(items) => Ok(items),
+            other => Err(other.mismatch("list")),
+        }
+    }
+
+    /// The fields of a struct, without its name
+    pub fn into_struct_fields(
+        self,
+    ) -> Result<OrderedMap<Identifier, Value>, Error> {
+        match self {
+            Value::Struct(_, fields) => Ok(fields),
+            other => Err(other.mismatch("struct")),
+        }
+    }
+
+    /// Creates a struct from its name and fields
+    pub fn from_struct_fields<T, I, F>(name: T, fields: I) -> Value
+    where
+        T: Into<TypeIdentifier>,
+        I: IntoIterator<Item = (F, Value)>,
+        F: Into<Identifier>,
+    {
+        Value::Struct(
+            name.into(),
+            fields
+                .into_iter()
+                .map(|(field, value)| (field.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Name of the variant, for error messages
+    pub(crate) fn variant_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "Unit",
+            Value::Bool(_) => "Bool",
+            Value::Char(_) => "Char",
+            Value::String(_) => "String",
+            Value::Bytes(_) => "Bytes",
+            Value::Number(_) => "Number",
+            Value::Type(_) => "Type",
+            Value::UnitStruct(_) => "UnitStruct",
+            Value::List(_) => "List",
+            Value::Tuple(_) => "Tuple",
+            Value::Map(_) => "Map",
+            Value::Option(_) => "Option",
+            Value::Struct(_, _) => "Struct",
+            Value::TupleStruct(_, _) => "TupleStruct",
+        }
+    }
+
+    fn mismatch(&self, expected: &'static str) -> Error {
+        let found = match self {
+            Value::Unit => "unit",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::String(_) => "string",
+            Value::Bytes(_) => "bytes",
+            Value::Number(_) => "number",
+            Value::Type(_) => "type",
+            Value::UnitStruct(_) => "unit struct",
+            Value::List(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::Map(_) => "map",
+            Value::Option(_) => "option",
+            Value::Struct(_, _) => "struct",
+            Value::TupleStruct(_, _) => "tuple struct",
+        };
+        Error::KindMismatch { expected, found }
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Value::parse(s)
+    }
+}
+
+/// Writes the value with `ValuePrinter::auto`
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        ValuePrinter::auto().write(self, f)
+    }
+}
+
+impl From<Type> for Value {
+    fn from(t: Type) -> Self {
+        Value::Type(t)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Value::List(items)
+    }
+}
+
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(items: I) -> Self {
+        Value::List(items.into_iter().collect())
+    }
+}
+
+impl From<BTreeMap<Value, Value>> for Value {
+    fn from(map: BTreeMap<Value, Value>) -> Self {
+        Value::Map(map.into_iter().collect())
+    }
+}
+
+impl From<OrderedMap<Value, Value>> for Value {
+    fn from(map: OrderedMap<Value, Value>) -> Self {
+        Value::Map(map.into_iter().collect())
+    }
+}
+
+impl FromIterator<(Value, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (Value, Value)>>(entries: I) -> Self {
+        Value::Map(entries.into_iter().collect())
+    }
+}
+
+impl From<HashMap<String, Value>> for Value {
+    fn from(map: HashMap<String, Value>) -> Self {
+        map.into_iter()
+            .map(|(key, value)| (Value::String(key.into()), value))
+            .collect()
+    }
+}
+
+impl<I> Index<I> for Value
+where
+    I: ValueIndex,
+{
+    type Output = Value;
+
+    /// Like `get`, but panics if there is no such child
+    fn index(&self, index: I) -> &Value {
+        let segment = index.segment();
+        path::child(self, &segment).unwrap_or_else(|| {
+            panic!(
+                "No value at {} in {}",
+                Path::from(vec![segment]),
+                self.to_string_compact()
+            )
+        })
+    }
+}
+
+impl<I> IndexMut<I> for Value
+where
+    I: ValueIndex,
+{
+    /// Like `get_mut`, but panics if there is no such child
+    fn index_mut(&mut self, index: I) -> &mut Value {
+        let segment = index.segment();
+        if path::child(self, &segment).is_none() {
+            panic!(
+                "No value at {} in {}",
+                Path::from(vec![segment]),
+                self.to_string_compact()
+            )
         }
+        path::child_mut(self, &segment).unwrap()
     }
 }
@@ -0,0 +1,302 @@
+//! Numbers of `Value` and the literals they are parsed from
+//!
+//! Literals may use radix prefixes and digit separators, as in `0xFF`,
+//! `0o77`, `0b1010` and `1_000_000`. Floats may also be `inf`, `-inf`
+//! and `NaN`, the way Rust prints them. Parsed numbers are printed in
+//! plain decimal notation.
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    fmt::{Display, Formatter},
+    hash::{Hash, Hasher},
+};
+
+/// A type a number literal can be parsed into
+pub(crate) trait NumberLiteral: Sized {
+    fn parse_literal(literal: &str) -> Option<Self>;
+}
+
+/// Splits off the sign and radix prefix, removing digit separators
+///
+/// Returns the radix and the digits including the sign. Literals
+/// without a prefix or separators, by far the most common, are borrowed.
+fn split(literal: &str) -> (u32, Cow<'_, str>) {
+    let (sign, unsigned) = match literal.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", literal),
+    };
+
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x") => (16, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0b") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+
+    let digits = if digits.contains('_') {
+        Cow::Owned(format!("{}{}", sign, digits.replace('_', "")))
+    } else if radix == 10 {
+        Cow::Borrowed(literal)
+    } else if sign.is_empty() {
+        Cow::Borrowed(digits)
+    } else {
+        Cow::Owned(format!("{}{}", sign, digits))
+    };
+    (radix, digits)
+}
+
+macro_rules! integer_literal {
+    ($($t:ty),*) => {$(
+        impl NumberLiteral for $t {
+            fn parse_literal(literal: &str) -> Option<Self> {
+                let (radix, digits) = split(literal);
+                <$t>::from_str_radix(&digits, radix).ok()
+            }
+        }
+    )*};
+}
+
+integer_literal!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+macro_rules! float_literal {
+    ($($t:ty),*) => {$(
+        impl NumberLiteral for $t {
+            fn parse_literal(literal: &str) -> Option<Self> {
+                match split(literal) {
+                    (10, digits) => digits.parse().ok(),
+                    (radix, digits) => u128::from_str_radix(&digits, radix)
+                        .map(|v| v as $t)
+                        .or_else(|_| {
+                            i128::from_str_radix(&digits, radix).map(|v| v as $t)
+                        })
+                        .ok(),
+                }
+            }
+        }
+    )*};
+}
+
+float_literal!(f32, f64);
+
+pub(crate) fn parse_literal<T>(literal: &str) -> Option<T>
+where
+    T: NumberLiteral,
+{
+    T::parse_literal(literal)
+}
+
+/// A number of any primitive type, without loss of precision
+///
+/// Numbers are equal and ordered by their numeric value, so `I64(5)`
+/// and `U64(5)` are the same number. Floats are never equal to integers
+/// and are ordered after an integer of the same value.
+#[derive(Copy, Clone, Debug)]
+pub enum Number {
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+}
+
+/// An integer of any width, for comparisons
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+enum Integer {
+    Negative(i128),
+    Positive(u128),
+}
+
+impl Number {
+    /// Parses a literal into the smallest fitting variant
+    ///
+    /// Integers too large for 128 bits are parsed as floats,
+    /// unless they have a radix prefix.
+    pub fn parse(literal: &str) -> Option<Self> {
+        Some(if let Some(v) = parse_literal(literal) {
+            Number::U64(v)
+        } else if let Some(v) = parse_literal(literal) {
+            Number::I64(v)
+        } else if let Some(v) = parse_literal(literal) {
+            Number::U128(v)
+        } else if let Some(v) = parse_literal(literal) {
+            Number::I128(v)
+        } else {
+            Number::F64(match split(literal) {
+                (10, _) => parse_literal(literal)?,
+                _ => return None,
+            })
+        })
+    }
+
+    fn integer(self) -> Option<Integer> {
+        Some(match self {
+            Number::I64(v) if v < 0 => Integer::Negative(v as i128),
+            Number::I128(v) if v < 0 => Integer::Negative(v),
+            Number::I64(v) => Integer::Positive(v as u128),
+            Number::I128(v) => Integer::Positive(v as u128),
+            Number::U64(v) => Integer::Positive(v as u128),
+            Number::U128(v) => Integer::Positive(v),
+            Number::F64(_) => return None,
+        })
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !self.is_f64()
+    }
+    pub fn is_f64(&self) -> bool {
+        matches!(self, Number::F64(_))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.to_primitive()
+    }
+    pub fn as_u64(&self) -> Option<u64> {
+        self.to_primitive()
+    }
+    pub fn as_i128(&self) -> Option<i128> {
+        self.to_primitive()
+    }
+    pub fn as_u128(&self) -> Option<u128> {
+        self.to_primitive()
+    }
+    /// The number as a float, integers may be rounded
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Number::I64(v) => v as f64,
+            Number::U64(v) => v as f64,
+            Number::I128(v) => v as f64,
+            Number::U128(v) => v as f64,
+            Number::F64(v) => v,
+        }
+    }
+
+    /// Converts to a primitive, None if it does not fit
+    ///
+    /// Floats are only converted to floats.
+    pub(crate) fn to_primitive<T>(self) -> Option<T>
+    where
+        T: FromNumber,
+    {
+        T::from_number(self)
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::I64(v) => Display::fmt(v, f),
+            Number::U64(v) => Display::fmt(v, f),
+            Number::I128(v) => Display::fmt(v, f),
+            Number::U128(v) => Display::fmt(v, f),
+            // Debug keeps the `.0` of whole numbers, so they stay floats
+            Number::F64(v) => fmt::Debug::fmt(v, f),
+        }
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.integer(), other.integer()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (None, None) => self.as_f64().total_cmp(&other.as_f64()),
+            (Some(_), None) => compare_float(self.as_f64(), other.as_f64())
+                .then(Ordering::Less),
+            (None, Some(_)) => compare_float(self.as_f64(), other.as_f64())
+                .then(Ordering::Greater),
+        }
+    }
+}
+
+/// Orders NaN by its sign, outside of all other numbers
+fn compare_float(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or_else(|| {
+        if a.is_nan() {
+            a.total_cmp(&0.0)
+        } else {
+            0.0_f64.total_cmp(&b)
+        }
+    })
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.integer() {
+            Some(integer) => integer.hash(state),
+            None => self.as_f64().to_bits().hash(state),
+        }
+    }
+}
+
+macro_rules! number_from {
+    ($($t:ty => $variant:ident),*) => {$(
+        impl From<$t> for Number {
+            fn from(v: $t) -> Self {
+                Number::$variant(v.into())
+            }
+        }
+    )*};
+}
+
+number_from!(
+    i8 => I64, i16 => I64, i32 => I64, i64 => I64, i128 => I128,
+    u8 => U64, u16 => U64, u32 => U64, u64 => U64, u128 => U128,
+    f64 => F64
+);
+
+impl From<f32> for Number {
+    /// Goes through the shortest decimal form, so `0.1f32` becomes `0.1`
+    fn from(v: f32) -> Self {
+        Number::F64(v.to_string().parse().unwrap_or_else(|_| v.into()))
+    }
+}
+
+/// A primitive a `Number` can be converted to
+pub(crate) trait FromNumber: Sized {
+    fn from_number(number: Number) -> Option<Self>;
+}
+
+macro_rules! integer_from_number {
+    ($($t:ty),*) => {$(
+        impl FromNumber for $t {
+            fn from_number(number: Number) -> Option<Self> {
+                match number {
+                    Number::I64(v) => <$t>::try_from(v).ok(),
+                    Number::U64(v) => <$t>::try_from(v).ok(),
+                    Number::I128(v) => <$t>::try_from(v).ok(),
+                    Number::U128(v) => <$t>::try_from(v).ok(),
+                    Number::F64(_) => None,
+                }
+            }
+        }
+    )*};
+}
+
+integer_from_number!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+impl FromNumber for f32 {
+    fn from_number(number: Number) -> Option<Self> {
+        Some(number.as_f64() as f32)
+    }
+}
+impl FromNumber for f64 {
+    fn from_number(number: Number) -> Option<Self> {
+        Some(number.as_f64())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::literals::Literals;
+#[cfg(feature = "exprs")]
+use crate::value::Value;
+#[cfg(feature = "exprs")]
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// Nesting the parser accepts unless `ParseOptions::max_depth` says
+/// otherwise, well within the stack of a thread in a debug build
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Configuration for parsing the text format
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Maximum length in bytes of a single string literal, after unescaping
+    ///
+    /// Parsing fails as soon as a string grows past this length,
+    /// which protects against huge allocations from untrusted inputs.
+    /// `TextDeserializer::max_string_length` delivers longer strings in
+    /// chunks instead.
+    pub max_string_length: Option<usize>,
+    /// Maximum length in bytes of identifiers, including field names
+    pub max_identifier_length: Option<usize>,
+    /// Maximum size in bytes of the whole input
+    pub max_input_size: Option<usize>,
+    /// Maximum number of nested parentheses, brackets and braces
+    ///
+    /// Checked before parsing, since deeply nested input can overflow
+    /// the stack of the parser. Defaults to `DEFAULT_MAX_DEPTH`, None
+    /// removes the limit for trusted input on a large enough stack.
+    pub max_depth: Option<usize>,
+    /// Share the allocation of identical strings in the parsed value
+    ///
+    /// Saves memory for documents that repeat the same strings many times,
+    /// at the cost of a lookup for every string.
+    pub dedup_strings: bool,
+    /// Fail on a map key or struct field that appears twice
+    ///
+    /// Otherwise the last occurrence wins and earlier ones are dropped.
+    pub reject_duplicate_keys: bool,
+    /// Keep unknown escape sequences like `\q` as they are written,
+    /// instead of failing
+    pub allow_unknown_escapes: bool,
+    /// Ignore anything after the first value, instead of failing
+    pub allow_trailing_garbage: bool,
+    /// Custom literal syntaxes like `ip"10.0.0.1"`, see `Literals`
+    pub literals: Literals,
+    /// Values that expressions can refer to by name, like `base + "/api"`
+    #[cfg(feature = "exprs")]
+    pub constants: BTreeMap<String, Value>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_string_length: None,
+            max_identifier_length: None,
+            max_input_size: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            dedup_strings: false,
+            reject_duplicate_keys: false,
+            allow_unknown_escapes: false,
+            allow_trailing_garbage: false,
+            literals: Literals::common_ref().clone(),
+            #[cfg(feature = "exprs")]
+            constants: BTreeMap::new(),
+        }
+    }
+}
+
+/// Configuration for serializing Rust types into a Value
+#[derive(Clone, Default, Debug)]
+pub struct SerializeOptions {
+    /// Full paths like `app::config::Config`, written instead of the short
+    /// type names serde reports
+    ///
+    /// Filled by `with_type_path`. Enum variants are written after the
+    /// path of their enum. A name that ends several of the paths, like
+    /// `Config` for `client::Config` and `server::Config`, is ambiguous and
+    /// stays short.
+    pub type_paths: BTreeSet<&'static str>,
+    /// Write the full path of the type passed to `Value::try_new_with`,
+    /// without listing it in `type_paths`
+    pub root_type_path: bool,
+    /// Report `is_human_readable() == false` to the serialized types
+    ///
+    /// Types like `IpAddr` or `chrono::DateTime` then choose their compact
+    /// representations, bytes or numbers instead of strings. Such values
+    /// have to be deserialized with the same setting.
+    pub compact_representations: bool,
+    /// How unit variants like `Mode::Fast` are written
+    pub unit_variants: UnitVariantStyle,
+}
+
+/// How unit enum variants are written, see `SerializeOptions::unit_variants`
+///
+/// Deserializing accepts all of them, whichever was chosen.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum UnitVariantStyle {
+    /// With the path of their enum, `Test::Baz`
+    #[default]
+    Path,
+    /// Just the name of the variant, `Baz`
+    Name,
+    /// Like a tuple variant without items, `Baz()`
+    Call,
+}
+
+impl SerializeOptions {
+    /// Writes `T` with its full path, as given by `std::any::type_name`
+    ///
+    /// Generic arguments are left out, so `Wrapper<u8>` and `Wrapper<u16>`
+    /// share a path.
+    pub fn with_type_path<T>(mut self) -> Self
+    where
+        T: ?Sized,
+    {
+        self.insert_type_path::<T>();
+        self
+    }
+
+    pub(crate) fn insert_type_path<T>(&mut self)
+    where
+        T: ?Sized,
+    {
+        let path = std::any::type_name::<T>().trim_start_matches('&');
+        let path = path.split('<').next().unwrap_or(path);
+        self.type_paths.insert(path);
+    }
+}
+
+/// Configuration for deserializing Rust types from a Value
+#[derive(Copy, Clone, Default, Debug)]
+pub struct DeserializeOptions {
+    /// Skip struct fields the type doesn't declare, even for types with
+    /// `#[serde(deny_unknown_fields)]`
+    ///
+    /// Lets older versions of a type read documents of newer ones.
+    pub ignore_unknown_fields: bool,
+    /// Whether the type names in the document have to match the names of
+    /// the structs they are read into
+    ///
+    /// Catches documents for the wrong type, like a `ServerConfig` file
+    /// read as a `ClientConfig`.
+    pub type_names: TypeNameCheck,
+    /// Report `is_human_readable() == false` to the deserialized types, to
+    /// read values serialized with
+    /// `SerializeOptions::compact_representations`
+    pub compact_representations: bool,
+    /// Read a value where an option is expected as `Some(value)`, so both
+    /// `port: 80` and `port: Some(80)` fill a `port: Option<u16>`
+    ///
+    /// Fields left out of a document need no such setting: serde reads
+    /// missing options as `None` and fills fields marked with
+    /// `#[serde(default)]`.
+    pub implicit_some: bool,
+}
+
+/// How the names of structs in a document are compared to the names of
+/// the Rust types, see `DeserializeOptions::type_names`
+///
+/// Structs written without a name, as tuples, maps or `()`, always pass.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum TypeNameCheck {
+    /// Any name is accepted
+    #[default]
+    Ignore,
+    /// The last segment has to match, so `config::ServerConfig` is read as
+    /// a `ServerConfig`
+    LastSegment,
+    /// The path in the document has to end with the name, so
+    /// `app::config::Config` is read as a `config::Config`
+    ///
+    /// Serde names containing `::`, as set by `#[serde(rename)]`, are
+    /// compared segment by segment, for other names this is the same as
+    /// `LastSegment`.
+    Suffix,
+    /// The name has to match, including the path if it has one
+    Exact,
+}
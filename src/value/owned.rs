@@ -0,0 +1,536 @@
+//! Deserializing a `Value` by moving its parts out
+//!
+//! `Value` is itself a deserializer, which hands its lists, maps, fields,
+//! byte strings and nested `Value`s over to the visitor instead of copying
+//! them, so reading a large document into owned types does not pay for a
+//! second copy of it. Strings are shared `Arc<str>`s and are copied either
+//! way.
+//!
+//! Values are read like `ValueDeserializer` reads them, which handles the
+//! parts that have nothing to move, like numbers and names, and errors
+//! carry the same paths.
+
+use crate::{
+    raw,
+    value::{
+        deserializer::{
+            unexpected, Transient, ValueDeserializer, ValueDeserializerError,
+        },
+        embed,
+        map::Map,
+        path::PathSegment,
+        types::Identifier,
+        Value,
+    },
+};
+use serde::{
+    de::{
+        DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+        VariantAccess, Visitor,
+    },
+    Deserializer,
+};
+use std::vec;
+
+impl<'de> IntoDeserializer<'de, ValueDeserializerError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The deserializer for the parts of `value` that have nothing to move
+fn borrowed(value: &Value) -> ValueDeserializer<'_, Transient> {
+    ValueDeserializer::with_source(value, Transient)
+}
+
+macro_rules! forward_to_borrowed {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(
+                self,
+                visitor: V,
+            ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                borrowed(&self).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ValueDeserializerError;
+
+    fn deserialize_any<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(_) => self.deserialize_byte_buf(visitor),
+            Value::List(_) => self.deserialize_seq(visitor),
+            Value::Tuple(_) => self.deserialize_tuple(0, visitor),
+            Value::Map(_) => self.deserialize_map(visitor),
+            Value::Option(_) => self.deserialize_option(visitor),
+            // Enum variants are visited as a map from their name to their
+            // contents, see `ValueDeserializer::deserialize_any`
+            Value::Struct(ref identifier, _)
+            | Value::TupleStruct(ref identifier, _)
+                if identifier.variant().is_some() =>
+            {
+                borrowed(&self).deserialize_any(visitor)
+            },
+            Value::Struct(_, _) => self.deserialize_struct("", &[], visitor),
+            Value::TupleStruct(_, _) => {
+                self.deserialize_tuple_struct("", 0, visitor)
+            },
+            _ => borrowed(&self).deserialize_any(visitor),
+        }
+    }
+
+    forward_to_borrowed! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16
+        deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_bytes<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            _ => borrowed(&self).deserialize_byte_buf(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Option(Some(value)) => visitor.visit_some(*value),
+            Value::Option(None) => visitor.visit_none(),
+            _ => Err(unexpected("Option", &self)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        borrowed(&self).deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == raw::TOKEN {
+            return borrowed(&self).deserialize_newtype_struct(name, visitor);
+        }
+        if name == embed::TOKEN {
+            embed::hand_over(self);
+            let result = visitor.visit_unit();
+            embed::discard();
+            return result;
+        }
+
+        match self {
+            Value::TupleStruct(..) | Value::Tuple(_) => {
+                self.deserialize_tuple_struct(name, 1, visitor)
+            },
+            // Written without the name and parentheses, as in `length: 5`
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_seq<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::List(items) => visitor.visit_seq(Items::new(items)),
+            _ => Err(unexpected("List", &self)),
+        }
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Tuple(items) => visitor.visit_seq(Items::new(items)),
+            _ => Err(unexpected("Tuple", &self)),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let Value::TupleStruct(identifier, _) = &self {
+            borrowed(&self).check_type_name(name, identifier)?;
+        }
+
+        match self {
+            Value::TupleStruct(_, items) | Value::Tuple(items) => {
+                visitor.visit_seq(Items::new(items))
+            },
+            _ => Err(unexpected("TupleStruct", &self)),
+        }
+    }
+
+    fn deserialize_map<V>(
+        self,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Map(map) => visitor.visit_map(Entries::new(map)),
+            _ => Err(unexpected("Map", &self)),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let Value::Struct(identifier, _)
+        | Value::TupleStruct(identifier, _) = &self
+        {
+            borrowed(&self).check_type_name(name, identifier)?;
+        }
+
+        match self {
+            Value::Struct(_, fields) => visitor.visit_map(Fields {
+                fields: fields.into_iter(),
+                value: None,
+                field: None,
+            }),
+            // Keyed by field names or indices, like other formats write them
+            Value::Map(map) => visitor.visit_map(Entries::new(map)),
+            // Fields in the order of their declaration
+            Value::TupleStruct(_, items) | Value::Tuple(items) => {
+                visitor.visit_seq(Items::new(items))
+            },
+            // Empty structs without their name are written as `()`
+            _ => borrowed(&self).deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(Variant(self))
+    }
+}
+
+/// The items of a list, tuple or tuple struct
+struct Items {
+    items: vec::IntoIter<Value>,
+    /// Index of the next item, for error paths
+    index: usize,
+}
+
+impl Items {
+    fn new(items: Vec<Value>) -> Self {
+        Items {
+            items: items.into_iter(),
+            index: 0,
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Items {
+    type Error = ValueDeserializerError;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<<T as DeserializeSeed<'de>>::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.items.next() {
+            None => Ok(None),
+            Some(value) => {
+                let result = seed.deserialize(value).map_err(|error| {
+                    error.within(PathSegment::Index(self.index))
+                })?;
+                self.index += 1;
+                Ok(Some(result))
+            },
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// The entries of a map
+struct Entries {
+    entries: <Map<Value, Value> as IntoIterator>::IntoIter,
+    value: Option<Value>,
+    /// Key of the entry last visited, for error paths
+    key: Option<Value>,
+}
+
+impl Entries {
+    fn new(map: Map<Value, Value>) -> Self {
+        Entries {
+            entries: map.into_iter(),
+            value: None,
+            key: None,
+        }
+    }
+
+    fn segment(&self) -> PathSegment {
+        PathSegment::Key(self.key.clone().unwrap_or(Value::Unit))
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = ValueDeserializerError;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<<K as DeserializeSeed<'de>>::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            None => Ok(None),
+            Some((key, value)) => {
+                self.key = Some(key.clone());
+                self.value = Some(value);
+                let key = match key {
+                    // Names have nothing to move and read as strings here
+                    Value::UnitStruct(_) => seed.deserialize(
+                        ValueDeserializer::map_key(&key, Transient),
+                    ),
+                    key => seed.deserialize(key),
+                };
+                key.map(Some).map_err(|error| error.within(self.segment()))
+            },
+        }
+    }
+
+    fn next_value_seed<V>(
+        &mut self,
+        seed: V,
+    ) -> Result<<V as DeserializeSeed<'de>>::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            None => Err(ValueDeserializerError::custom_message(
+                "Called next_value before next_key",
+            )),
+            Some(value) => seed
+                .deserialize(value)
+                .map_err(|error| error.within(self.segment())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// The fields of a struct
+struct Fields {
+    fields: vec::IntoIter<(Identifier, Value)>,
+    value: Option<Value>,
+    /// Field last visited, for error paths
+    field: Option<Identifier>,
+}
+
+impl Fields {
+    fn segment(&self) -> PathSegment {
+        match &self.field {
+            Some(field) => PathSegment::Field(field.clone()),
+            None => PathSegment::Index(0),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Fields {
+    type Error = ValueDeserializerError;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<<K as DeserializeSeed<'de>>::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.next() {
+            None => Ok(None),
+            Some((field, value)) => {
+                self.value = Some(value);
+                let key: Result<_, ValueDeserializerError> =
+                    seed.deserialize(field.0.as_str().into_deserializer());
+                self.field = Some(field);
+                key.map(Some)
+            },
+        }
+    }
+
+    fn next_value_seed<V>(
+        &mut self,
+        seed: V,
+    ) -> Result<<V as DeserializeSeed<'de>>::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            None => Err(ValueDeserializerError::custom_message(
+                "Called next_value before next_key",
+            )),
+            Some(value) => seed
+                .deserialize(value)
+                .map_err(|error| error.within(self.segment())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// An enum variant, named by the last segment of its type
+struct Variant(Value);
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = ValueDeserializerError;
+    type Variant = Self;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> Result<(<V as DeserializeSeed<'de>>::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(borrowed(&self.0))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant {
+    type Error = ValueDeserializerError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match &self.0 {
+            Value::TupleStruct(_, items) if !items.is_empty() => {
+                Err(unexpected("UnitStruct", &self.0))
+            },
+            Value::Struct(_, fields) if !fields.is_empty() => {
+                Err(unexpected("UnitStruct", &self.0))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(
+        self,
+        seed: T,
+    ) -> Result<<T as DeserializeSeed<'de>>::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0 {
+            Value::TupleStruct(_, mut items) if items.len() == 1 => seed
+                .deserialize(items.pop().unwrap())
+                .map_err(|error| error.within(PathSegment::Index(0))),
+            Value::TupleStruct(..) => {
+                Err(ValueDeserializerError::custom_message(format_args!(
+                    "Expected newtype variant {:?}",
+                    self.0
+                )))
+            },
+            other => Err(unexpected("TupleStruct", &other)),
+        }
+    }
+
+    fn tuple_variant<V>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_tuple_struct("", len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<<V as Visitor<'de>>::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_struct("", fields, visitor)
+    }
+}
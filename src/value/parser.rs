@@ -1,47 +1,255 @@
-use crate::value::{
-    types::{GenericIdentifier, Generics, Identifier, Type, TypeIdentifier},
-    Value,
+use crate::{
+    error::Error,
+    progress::{Cancelled, Progress, ProgressReporter},
+    value::{
+        comments::{CommentCollector, Comments},
+        compact::{Builder, CompactValue, Node, Slice},
+        deserializer::InputStrings,
+        diagnostic::{nesting_over, ParseError, TypeParseError},
+        map::{Map, OrderedMap},
+        number::Number,
+        options::ParseOptions,
+        path::PathSegment,
+        spans::{SpanCollector, Spans},
+        types::{
+            GenericIdentifier, Generics, Identifier, Type, TypeIdentifier,
+        },
+        Value,
+    },
 };
 use anyhow::anyhow;
-use pest::{iterators::Pair, Parser};
+use pest::{
+    error::{ErrorVariant, InputLocation},
+    iterators::Pair,
+    Parser,
+};
 use pest_derive::*;
-use std::{collections::BTreeMap, str::Chars};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    str::Chars,
+    sync::Arc,
+};
+
+mod descent;
+#[cfg(feature = "exprs")]
+mod expr;
+#[cfg(feature = "rayon")]
+mod parallel;
 
 #[derive(Parser)]
 #[grammar = "value/value.pest"]
 struct ValueParser;
 
+/// State shared by all parse functions during a single parse
+pub(crate) struct ParseContext<'options> {
+    options: &'options ParseOptions,
+    /// Previously parsed strings, if deduplication is enabled
+    strings: HashSet<Arc<str>>,
+    /// Present if comments are kept
+    comments: Option<CommentCollector>,
+    /// Present if the spans of values are kept
+    spans: Option<SpanCollector>,
+    /// Present if strings are borrowed from the input, the ranges of those
+    /// without escape sequences by their address, see `InputStrings`
+    borrowable: Option<HashMap<usize, Range<usize>>>,
+    /// Present if progress is reported
+    progress: Option<ProgressReporter<'options>>,
+    /// Number of collections the current value is nested in
+    depth: usize,
+    /// Number of values parsed so far
+    nodes: usize,
+    /// Start of the innermost value that failed to parse
+    error_offset: Option<usize>,
+    /// Values named with `&name` so far, repeated by `*name`
+    anchors: HashMap<String, Value>,
+}
+
+/// The optional collectors of a `ParseContext`
+#[derive(Copy, Clone)]
+struct Collectors {
+    comments: bool,
+    spans: bool,
+    borrowable: bool,
+}
+
+impl<'options> ParseContext<'options> {
+    fn new(options: &'options ParseOptions) -> Self {
+        ParseContext {
+            options,
+            strings: HashSet::new(),
+            comments: None,
+            spans: None,
+            borrowable: None,
+            progress: None,
+            depth: 0,
+            nodes: 0,
+            error_offset: None,
+            anchors: HashMap::new(),
+        }
+    }
+
+    /// Descends into the child value starting at offset `start`
+    ///
+    /// The segment is only built if comments are collected
+    fn enter<F>(&mut self, segment: F, start: usize)
+    where
+        F: FnOnce() -> PathSegment,
+    {
+        self.depth += 1;
+        if self.comments.is_none() && self.spans.is_none() {
+            return;
+        }
+
+        let segment = segment();
+        if let Some(spans) = &mut self.spans {
+            spans.enter(segment.clone());
+        }
+        if let Some(comments) = &mut self.comments {
+            comments.enter(segment, start);
+        }
+    }
+    /// Ascends from the child value ending at offset `end`
+    ///
+    /// Progress is reported after every element of the outermost collection
+    fn leave(&mut self, end: usize) -> anyhow::Result<()> {
+        self.depth -= 1;
+        if let Some(comments) = &mut self.comments {
+            comments.leave();
+        }
+        if let Some(spans) = &mut self.spans {
+            spans.leave();
+        }
+
+        if self.depth == 0 {
+            self.report(end)?;
+        }
+        Ok(())
+    }
+
+    /// Which collectors are present, to rebuild them in `restart`
+    fn collectors(&self) -> Collectors {
+        Collectors {
+            comments: self.comments.is_some(),
+            spans: self.spans.is_some(),
+            borrowable: self.borrowable.is_some(),
+        }
+    }
+
+    /// Forgets everything parsed so far, to parse the input once more
+    ///
+    /// The given collectors are rebuilt, since finishing a parse may have
+    /// taken them, and progress is still reported.
+    fn restart(&mut self, input: &str, collectors: Collectors) {
+        *self = ParseContext {
+            comments: collectors.comments.then(|| CommentCollector::new(input)),
+            spans: collectors.spans.then(SpanCollector::default),
+            borrowable: collectors.borrowable.then(HashMap::new),
+            progress: self.progress.take(),
+            ..ParseContext::new(self.options)
+        };
+    }
+
+    fn report(&mut self, bytes: usize) -> anyhow::Result<()> {
+        let nodes = self.nodes;
+        if let Some(progress) = &mut self.progress {
+            progress.report(Progress { bytes, nodes })?;
+        }
+        Ok(())
+    }
+
+    fn share_string(&mut self, string: String) -> Arc<str> {
+        if !self.options.dedup_strings {
+            return string.into();
+        }
+
+        match self.strings.get(string.as_str()) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared: Arc<str> = string.into();
+                self.strings.insert(shared.clone());
+                shared
+            },
+        }
+    }
+
+    /// Fails on a duplicate map key or struct field if they are rejected
+    fn check_duplicate<D>(
+        &mut self,
+        duplicate: bool,
+        start: usize,
+        describe: D,
+    ) -> anyhow::Result<()>
+    where
+        D: FnOnce() -> String,
+    {
+        if self.options.reject_duplicate_keys && duplicate {
+            // Located at the second occurrence
+            self.error_offset.get_or_insert(start);
+            return Err(anyhow!("Duplicate {}", describe()));
+        }
+        Ok(())
+    }
+
+    fn check_identifier_length(&self, identifier: &str) -> anyhow::Result<()> {
+        match self.options.max_identifier_length {
+            Some(max) if identifier.len() > max => Err(anyhow!(
+                "Identifier of length {} exceeds the maximum of {}",
+                identifier.len(),
+                max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
 fn extract_result<T, E>(x: Option<Result<T, E>>) -> Result<Option<T>, E> {
     x.map_or(Ok(None), |v| v.map(Some))
 }
 
-fn parse_identifier(pair: Pair<Rule>) -> anyhow::Result<Identifier> {
+fn parse_identifier(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Identifier> {
     assert_eq!(pair.as_rule(), Rule::identifier);
+    context.check_identifier_length(pair.as_str())?;
     Ok(Identifier(pair.as_str().to_string()))
 }
 
-fn parse_type_identifier(pair: Pair<Rule>) -> anyhow::Result<TypeIdentifier> {
-    fn parse_generics(pair: Pair<Rule>) -> anyhow::Result<Generics> {
+fn parse_type_identifier(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<TypeIdentifier> {
+    fn parse_generics(
+        context: &mut ParseContext,
+        pair: Pair<Rule>,
+    ) -> anyhow::Result<Generics> {
         assert_eq!(pair.as_rule(), Rule::generics);
 
         Ok(Generics {
             types: pair
                 .into_inner()
-                .map(parse_generic_type)
+                .filter(|pair| pair.as_rule() != Rule::lifetime)
+                .map(|pair| parse_generic_type(context, pair))
                 .collect::<anyhow::Result<Vec<Type>>>()?,
         })
     }
 
     fn parse_generic_identifier(
+        context: &mut ParseContext,
         pair: Pair<Rule>,
     ) -> anyhow::Result<GenericIdentifier> {
         assert_eq!(pair.as_rule(), Rule::generic_identifier);
 
         let mut pairs = pair.into_inner();
 
-        let identifier = parse_identifier(pairs.next().unwrap())?;
+        let identifier = parse_identifier(context, pairs.next().unwrap())?;
 
-        let generics = extract_result(pairs.next().map(parse_generics))?;
+        // Generics consisting only of lifetimes are dropped entirely
+        let generics = extract_result(
+            pairs.next().map(|pair| parse_generics(context, pair)),
+        )?
+        .filter(|generics| !generics.types.is_empty());
 
         Ok(GenericIdentifier {
             identifier,
@@ -51,31 +259,35 @@ fn parse_type_identifier(pair: Pair<Rule>) -> anyhow::Result<TypeIdentifier> {
 
     let segments = pair
         .into_inner()
-        .map(parse_generic_identifier)
+        .map(|pair| parse_generic_identifier(context, pair))
         .collect::<anyhow::Result<Vec<GenericIdentifier>>>()?;
 
     Ok(TypeIdentifier { segments })
 }
 
-fn parse_generic_type(pair: Pair<Rule>) -> anyhow::Result<Type> {
+fn parse_generic_type(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Type> {
     assert_eq!(pair.as_rule(), Rule::generic_type);
 
     let inner = pair.into_inner().next().unwrap();
 
     Ok(match inner.as_rule() {
         Rule::type_identifier => {
-            Type::TypeIdentifier(parse_type_identifier(inner)?)
+            Type::TypeIdentifier(parse_type_identifier(context, inner)?)
         },
         Rule::tuple_type => Type::Tuple(
             inner
                 .into_inner()
-                .map(parse_generic_type)
+                .map(|pair| parse_generic_type(context, pair))
                 .collect::<anyhow::Result<Vec<Type>>>()?,
         ),
         Rule::array_type => {
             let mut inner = inner.into_inner();
 
-            let content = Box::new(parse_generic_type(inner.next().unwrap())?);
+            let content =
+                Box::new(parse_generic_type(context, inner.next().unwrap())?);
             let size = inner.next().unwrap().as_str().to_string();
 
             Type::Array { content, size }
@@ -84,101 +296,188 @@ fn parse_generic_type(pair: Pair<Rule>) -> anyhow::Result<Type> {
     })
 }
 
-fn parse_tuple_inner(pair: Pair<Rule>) -> anyhow::Result<Vec<Value>> {
+fn parse_tuple_inner(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Vec<Value>> {
     assert!(pair.as_rule() == Rule::tuple || pair.as_rule() == Rule::list);
 
-    pair.into_inner().map(parse_value).collect()
+    pair.into_inner()
+        .enumerate()
+        .map(|(index, pair)| {
+            let span = pair.as_span();
+            context.enter(|| PathSegment::Index(index), span.start());
+            let value = parse_value(context, pair)?;
+            context.leave(span.end())?;
+            Ok(value)
+        })
+        .collect()
 }
 
-fn parse_tuple(pair: Pair<Rule>) -> anyhow::Result<Value> {
+fn parse_tuple(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
     assert_eq!(pair.as_rule(), Rule::tuple);
 
-    Ok(Value::Tuple(parse_tuple_inner(pair)?))
+    Ok(Value::Tuple(parse_tuple_inner(context, pair)?))
 }
 
-fn parse_list(pair: Pair<Rule>) -> anyhow::Result<Value> {
+fn parse_list(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
     assert_eq!(pair.as_rule(), Rule::list);
 
-    Ok(Value::List(parse_tuple_inner(pair)?))
+    Ok(Value::List(parse_tuple_inner(context, pair)?))
 }
 
-fn parse_tuple_struct(pair: Pair<Rule>) -> anyhow::Result<Value> {
+fn parse_tuple_struct(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
     assert_eq!(pair.as_rule(), Rule::tuple_struct);
 
     let mut pairs = pair.into_inner();
 
-    let identifier = parse_type_identifier(pairs.next().unwrap())?;
-    let tuple = parse_tuple_inner(pairs.next().unwrap())?;
+    let identifier = parse_type_identifier(context, pairs.next().unwrap())?;
+    let tuple = parse_tuple_inner(context, pairs.next().unwrap())?;
 
     Ok(Value::TupleStruct(identifier, tuple))
 }
 
-fn parse_named_struct(pair: Pair<Rule>) -> anyhow::Result<Value> {
-    fn parse_named_tuple_entry(
-        pair: Pair<Rule>,
-    ) -> anyhow::Result<(Identifier, Value)> {
-        assert_eq!(pair.as_rule(), Rule::named_tuple_entry);
+fn parse_named_tuple_entry(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<(Identifier, Value)> {
+    assert_eq!(pair.as_rule(), Rule::named_tuple_entry);
 
-        let mut pairs = pair.into_inner();
+    let span = pair.as_span();
+    let mut pairs = pair.into_inner();
 
-        let identifier = parse_identifier(pairs.next().unwrap())?;
-        let value = parse_value(pairs.next().unwrap())?;
+    let key = pairs.next().unwrap();
+    let identifier = match key.as_rule() {
+        Rule::string => {
+            let identifier = parse_string_literal(context, key)?;
+            context.check_identifier_length(&identifier)?;
+            Identifier(identifier)
+        },
+        _ => parse_identifier(context, key)?,
+    };
 
-        Ok((identifier, value))
-    }
+    context.enter(|| PathSegment::Field(identifier.clone()), span.start());
+    let value = parse_value(context, pairs.next().unwrap())?;
+    context.leave(span.end())?;
 
-    fn parse_named_tuple(
-        pair: Pair<Rule>,
-    ) -> anyhow::Result<BTreeMap<Identifier, Value>> {
-        assert_eq!(pair.as_rule(), Rule::named_tuple);
+    Ok((identifier, value))
+}
 
-        pair.into_inner().map(parse_named_tuple_entry).collect()
+fn parse_named_tuple(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<OrderedMap<Identifier, Value>> {
+    assert_eq!(pair.as_rule(), Rule::named_tuple);
+
+    let mut fields = OrderedMap::new();
+    for pair in pair.into_inner() {
+        let start = pair.as_span().start();
+        let (identifier, value) = parse_named_tuple_entry(context, pair)?;
+        context.check_duplicate(
+            fields.contains_key(&identifier),
+            start,
+            || format!("field {}", identifier),
+        )?;
+        fields.insert(identifier, value);
     }
+    Ok(fields)
+}
 
+fn parse_named_struct(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
     assert_eq!(pair.as_rule(), Rule::named_struct);
 
     let mut pairs = pair.into_inner();
 
-    let identifier = parse_type_identifier(pairs.next().unwrap())?;
-    let fields = parse_named_tuple(pairs.next().unwrap())?;
+    let identifier = parse_type_identifier(context, pairs.next().unwrap())?;
+    let fields = parse_named_tuple(context, pairs.next().unwrap())?;
 
     Ok(Value::Struct(identifier, fields))
 }
 
-fn parse_map(pair: Pair<Rule>) -> anyhow::Result<Value> {
-    fn parse_map_entry(pair: Pair<Rule>) -> anyhow::Result<(Value, Value)> {
+/// A struct written without its name, as in `(field: 0)`
+fn parse_anonymous_struct(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    let fields = parse_named_tuple(context, pair)?;
+    Ok(Value::Struct(TypeIdentifier { segments: vec![] }, fields))
+}
+
+fn parse_map(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    fn parse_map_entry(
+        context: &mut ParseContext,
+        pair: Pair<Rule>,
+    ) -> anyhow::Result<(Value, Value)> {
         assert_eq!(pair.as_rule(), Rule::map_entry);
 
+        let span = pair.as_span();
         let mut pairs = pair.into_inner();
 
-        let key = parse_value(pairs.next().unwrap())?;
-        let value = parse_value(pairs.next().unwrap())?;
+        let key = pairs.next().unwrap();
+        let key = match key.as_rule() {
+            Rule::bare_key => parse_bare_key(context, key)?,
+            _ => parse_value(context, key)?,
+        };
+
+        context.enter(|| PathSegment::Key(key.clone()), span.start());
+        let value = parse_value(context, pairs.next().unwrap())?;
+        context.leave(span.end())?;
 
         Ok((key, value))
     }
 
     assert_eq!(pair.as_rule(), Rule::map);
 
-    let map = pair
-        .into_inner()
-        .map(parse_map_entry)
-        .collect::<anyhow::Result<BTreeMap<Value, Value>>>()?;
+    let mut map = Map::new();
+    for pair in pair.into_inner() {
+        let start = pair.as_span().start();
+        let (key, value) = parse_map_entry(context, pair)?;
+        context.check_duplicate(map.contains_key(&key), start, || {
+            format!("key {}", key.to_string_compact())
+        })?;
+        map.insert(key, value);
+    }
     Ok(Value::Map(map))
 }
 
 fn parse_number(pair: Pair<Rule>) -> anyhow::Result<Value> {
     assert_eq!(pair.as_rule(), Rule::number);
 
-    Ok(Value::Number(pair.as_str().to_string()))
+    number_value(pair.as_str())
+}
+
+fn number_value(literal: &str) -> anyhow::Result<Value> {
+    Number::parse(literal)
+        .map(Value::Number)
+        .ok_or_else(|| anyhow!("Number {} is out of range", literal))
 }
 
 /// Consumes input until a single char can be unescaped, if necessary
-fn unescape_single(chars: &mut Chars) -> anyhow::Result<char> {
+///
+/// Unknown escapes are kept as they are if `lenient`, by only consuming
+/// the backslash.
+fn unescape_single(chars: &mut Chars, lenient: bool) -> anyhow::Result<char> {
     let c = chars.next().unwrap();
 
     if c != '\\' {
         Ok(c)
     } else {
+        let escaped = chars.clone();
         let next = match chars.next() {
             Some(c) => c,
             None => {
@@ -200,6 +499,22 @@ fn unescape_single(chars: &mut Chars) -> anyhow::Result<char> {
             '"' => '"',
             '\'' => '\'',
 
+            'u' => {
+                let digits = chars.as_str().get(..4).unwrap_or_default();
+                let c = u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .ok_or_else(|| {
+                        anyhow!("Invalid unicode escape \\u{}", digits)
+                    })?;
+                *chars = chars.as_str()[4..].chars();
+                c
+            },
+
+            _ if lenient => {
+                *chars = escaped;
+                '\\'
+            },
             other => {
                 return Err(anyhow!("Unknown escape character {:?}", other))
             },
@@ -207,96 +522,808 @@ fn unescape_single(chars: &mut Chars) -> anyhow::Result<char> {
     }
 }
 
-fn parse_string(pair: Pair<Rule>) -> anyhow::Result<Value> {
-    assert_eq!(pair.as_rule(), Rule::string);
+fn parse_string_literal(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<String> {
+    let is_raw = match pair.as_rule() {
+        Rule::string => false,
+        Rule::raw_string => true,
+        rule => unreachable!("Expected a string, found {:?}", rule),
+    };
+
+    let contents = pair.into_inner().next().unwrap().as_str();
+    string_contents(context, contents, is_raw)
+}
 
-    let raw_string = pair.into_inner().next().unwrap().as_str();
+/// Unescapes the contents of a string literal, raw strings are taken as
+/// they are
+fn string_contents(
+    context: &ParseContext,
+    raw_string: &str,
+    is_raw: bool,
+) -> anyhow::Result<String> {
+    let max_length = context.options.max_string_length;
+
+    if is_raw {
+        if let Some(max) = max_length {
+            if raw_string.len() > max {
+                return Err(anyhow!(
+                    "String exceeds the maximum length of {}",
+                    max
+                ));
+            }
+        }
+        return Ok(raw_string.to_string());
+    }
 
-    fn unescape_string(input: &str) -> anyhow::Result<String> {
-        let mut chars = input.chars();
+    let check_length = |string: &String| match max_length {
+        Some(max) if string.len() > max => {
+            Err(anyhow!("String exceeds the maximum length of {}", max))
+        },
+        _ => Ok(()),
+    };
 
-        Ok(std::iter::from_fn(|| {
-            if chars.as_str().is_empty() {
-                None
-            } else {
-                Some(unescape_single(&mut chars))
-            }
-        })
-        .collect::<anyhow::Result<String>>()?)
+    // Runs between escapes are copied at once, unescaping never grows
+    // the string beyond its literal
+    let mut string = String::with_capacity(raw_string.len());
+    let mut rest = raw_string;
+
+    let lenient = context.options.allow_unknown_escapes;
+    while let Some(escape) = rest.find('\\') {
+        string.push_str(&rest[..escape]);
+
+        let mut chars = rest[escape..].chars();
+        string.push(unescape_single(&mut chars, lenient)?);
+        rest = chars.as_str();
+
+        check_length(&string)?;
     }
+    string.push_str(rest);
+    check_length(&string)?;
 
-    Ok(Value::String(unescape_string(raw_string)?))
+    Ok(string)
 }
-fn parse_char(pair: Pair<Rule>) -> anyhow::Result<Value> {
-    assert_eq!(pair.as_rule(), Rule::value_char);
 
-    let raw_string = pair.into_inner().next().unwrap().as_str();
+fn parse_string(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    let contents = pair.clone().into_inner().next().unwrap().as_span();
+    let string = parse_string_literal(context, pair)?;
+
+    Ok(string_value(
+        context,
+        string,
+        contents.as_str(),
+        contents.start()..contents.end(),
+    ))
+}
 
-    fn unescape_char(input: &str) -> anyhow::Result<char> {
-        let mut chars = input.chars();
+/// Shares a parsed string, which can be borrowed from the input if it is
+/// the same as its `contents` at `range`
+fn string_value(
+    context: &mut ParseContext,
+    string: String,
+    contents: &str,
+    range: Range<usize>,
+) -> Value {
+    let string = context.share_string(string);
+
+    if let Some(borrowable) = &mut context.borrowable {
+        if *string == *contents {
+            borrowable.insert(InputStrings::address(&string), range);
+        }
+    }
+    Value::String(string)
+}
 
-        let c = unescape_single(&mut chars)?;
+/// A map key written as an identifier, read as a unit struct
+fn parse_bare_key(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::bare_key);
 
-        if !chars.as_str().is_empty() {
-            return Err(anyhow!("Garbage at the end of char"));
+    let span = pair.as_span();
+    bare_key_value(context, pair.as_str(), span.start()..span.end())
+}
+
+fn bare_key_value(
+    context: &mut ParseContext,
+    key: &str,
+    range: Range<usize>,
+) -> anyhow::Result<Value> {
+    context.nodes += 1;
+    context.check_identifier_length(key)?;
+
+    // Map keys of strings borrow the name, see `ValueDeserializer`
+    let key = TypeIdentifier::from(key);
+    if let Some(borrowable) = &mut context.borrowable {
+        let name = &key.segments[0].identifier.0;
+        borrowable.insert(InputStrings::address(name), range);
+    }
+    Ok(Value::UnitStruct(key))
+}
+
+/// Decodes the inside of a byte string literal
+pub(crate) fn unescape_bytes(
+    input: &str,
+    lenient: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.clone().next() {
+        let run = chars.as_str();
+        let run = &run[..run.find('\\').unwrap_or(run.len())];
+        if !run.is_empty() {
+            if let Some(c) = run.chars().find(|c| !c.is_ascii()) {
+                return Err(anyhow!(
+                    "Non ASCII character {:?} in byte string",
+                    c
+                ));
+            }
+            bytes.extend_from_slice(run.as_bytes());
+            chars = chars.as_str()[run.len()..].chars();
+        } else if c == '\\' && chars.as_str()[1..].starts_with('x') {
+            let digits = chars.as_str().get(2..4).unwrap_or_default();
+            let byte = u8::from_str_radix(digits, 16).map_err(|_| {
+                anyhow!("Invalid escape \\x{} in byte string", digits)
+            })?;
+            bytes.push(byte);
+            chars = chars.as_str()[4..].chars();
+        } else {
+            let c = unescape_single(&mut chars, lenient)?;
+            if !c.is_ascii() {
+                return Err(anyhow!(
+                    "Non ASCII character {:?} in byte string",
+                    c
+                ));
+            }
+            bytes.push(c as u8);
         }
+    }
 
-        Ok(c)
+    Ok(bytes)
+}
+
+fn parse_bytes(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::byte_string);
+
+    bytes_value(context, pair.into_inner().next().unwrap().as_str())
+}
+
+fn bytes_value(
+    context: &ParseContext,
+    contents: &str,
+) -> anyhow::Result<Value> {
+    let bytes =
+        unescape_bytes(contents, context.options.allow_unknown_escapes)?;
+    if let Some(max) = context.options.max_string_length {
+        if bytes.len() > max {
+            return Err(anyhow!(
+                "String exceeds the maximum length of {}",
+                max
+            ));
+        }
     }
 
-    Ok(Value::Char(unescape_char(raw_string)?))
+    Ok(Value::Bytes(bytes))
+}
+
+fn parse_prefixed_string(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::prefixed_string);
+
+    let mut pairs = pair.into_inner();
+
+    let prefix = pairs.next().unwrap().as_str();
+    let contents = parse_string_literal(context, pairs.next().unwrap())?;
+
+    context.options.literals.parse(prefix, &contents)
 }
 
-fn parse_value(pair: Pair<Rule>) -> anyhow::Result<Value> {
-    match pair.as_rule() {
+fn parse_char(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::value_char);
+
+    char_value(context, pair.into_inner().next().unwrap().as_str())
+}
+
+fn char_value(context: &ParseContext, contents: &str) -> anyhow::Result<Value> {
+    let mut chars = contents.chars();
+
+    let c = unescape_single(&mut chars, context.options.allow_unknown_escapes)?;
+
+    if !chars.as_str().is_empty() {
+        return Err(anyhow!("Garbage at the end of char"));
+    }
+
+    Ok(Value::Char(c))
+}
+
+/// Parses `&name value`, remembering the value for later aliases
+///
+/// An anchor is only defined once its value is complete, so a value can
+/// not contain an alias of itself. Defining a name again replaces it for
+/// the rest of the document.
+fn parse_anchored(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::anchored);
+
+    let mut pairs = pair.into_inner();
+
+    let name = pairs.next().unwrap().as_str().to_string();
+    let value = parse_value(context, pairs.next().unwrap())?;
+
+    context.anchors.insert(name, value.clone());
+    Ok(value)
+}
+
+/// Parses `*name` into a copy of the anchored value
+fn parse_alias(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    assert_eq!(pair.as_rule(), Rule::alias);
+
+    let name = pair.into_inner().next().unwrap().as_str();
+    context
+        .anchors
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("Unknown anchor {}", name))
+}
+
+fn parse_value(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    context.nodes += 1;
+    let span = pair.as_span();
+    if let Some(spans) = &mut context.spans {
+        spans.record(span.start()..span.end());
+    }
+
+    let value = match pair.as_rule() {
         Rule::unit => Ok(Value::Unit),
+        Rule::bool_true => Ok(Value::Bool(true)),
+        Rule::bool_false => Ok(Value::Bool(false)),
 
         Rule::number => parse_number(pair),
-        Rule::string => parse_string(pair),
-        Rule::value_char => parse_char(pair),
+        Rule::string | Rule::raw_string => parse_string(context, pair),
+        Rule::byte_string => parse_bytes(context, pair),
+        Rule::prefixed_string => parse_prefixed_string(context, pair),
+        Rule::value_char => parse_char(context, pair),
 
         Rule::none => Ok(Value::Option(None)),
         Rule::some => Ok(Value::Option(Some(Box::new(parse_value(
+            context,
             pair.into_inner().next().unwrap(),
         )?)))),
 
-        Rule::tuple => parse_tuple(pair),
-        Rule::list => parse_list(pair),
+        Rule::tuple => parse_tuple(context, pair),
+        Rule::list => parse_list(context, pair),
+
+        Rule::tuple_struct => parse_tuple_struct(context, pair),
+        Rule::named_struct => parse_named_struct(context, pair),
+        Rule::named_tuple => parse_anonymous_struct(context, pair),
+        Rule::map => parse_map(context, pair),
+
+        Rule::anchored => parse_anchored(context, pair),
+        Rule::alias => parse_alias(context, pair),
+
+        #[cfg(feature = "exprs")]
+        Rule::expression => expr::parse_expression(context, pair),
+        #[cfg(not(feature = "exprs"))]
+        Rule::expression => Err(anyhow!(
+            "Expressions like `{}` need the exprs feature",
+            pair.as_str().trim()
+        )),
+
+        Rule::generic_type => Ok(match parse_generic_type(context, pair)? {
+            Type::TypeIdentifier(identifier) => Value::UnitStruct(identifier),
+            other => Value::Type(other),
+        }),
+        Rule::type_value => {
+            parse_generic_type(context, pair.into_inner().next().unwrap())
+                .map(Value::Type)
+        },
 
-        Rule::tuple_struct => parse_tuple_struct(pair),
-        Rule::named_struct => parse_named_struct(pair),
-        Rule::map => parse_map(pair),
+        _ => panic!("Unknown value {:#?}", pair),
+    };
 
-        Rule::generic_type => Ok(Value::Type(parse_generic_type(pair)?)),
+    if value.is_err() {
+        context.error_offset.get_or_insert(span.start());
+    }
+    value
+}
 
-        _ => panic!("Unknown value {:#?}", pair),
+/// Like `parse_value`, but builds collections, strings and field names
+/// right in the arena of `builder`, without a `Value` for each of them
+///
+/// Everything else is parsed by `parse_value` and converted.
+fn parse_compact(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+    builder: &mut Builder,
+) -> anyhow::Result<Node> {
+    let start = pair.as_span().start();
+    let node = match pair.as_rule() {
+        Rule::string => {
+            context.nodes += 1;
+            parse_string_literal(context, pair)
+                .map(|string| builder.string(&string))
+        },
+        Rule::some => {
+            context.nodes += 1;
+            parse_compact(context, pair.into_inner().next().unwrap(), builder)
+                .map(|inner| builder.some(inner))
+        },
+
+        Rule::tuple => {
+            context.nodes += 1;
+            parse_compact_items(context, pair, builder).map(Node::Tuple)
+        },
+        Rule::list => {
+            context.nodes += 1;
+            parse_compact_items(context, pair, builder).map(Node::List)
+        },
+        Rule::tuple_struct => {
+            context.nodes += 1;
+            let mut pairs = pair.into_inner();
+            let identifier =
+                parse_type_identifier(context, pairs.next().unwrap())?;
+            let name = builder.name(&identifier);
+            parse_compact_items(context, pairs.next().unwrap(), builder)
+                .map(|items| Node::TupleStruct(name, items))
+        },
+        Rule::named_struct => {
+            context.nodes += 1;
+            let mut pairs = pair.into_inner();
+            let identifier =
+                parse_type_identifier(context, pairs.next().unwrap())?;
+            let name = builder.name(&identifier);
+            parse_compact_fields(context, pairs.next().unwrap(), builder)
+                .map(|fields| Node::Struct(name, fields))
+        },
+        Rule::named_tuple => {
+            context.nodes += 1;
+            let name = builder.name(&TypeIdentifier { segments: vec![] });
+            parse_compact_fields(context, pair, builder)
+                .map(|fields| Node::Struct(name, fields))
+        },
+        Rule::map => {
+            context.nodes += 1;
+            parse_compact_map(context, pair, builder).map(Node::Map)
+        },
+
+        _ => {
+            return parse_value(context, pair)
+                .map(|value| builder.value(&value))
+        },
+    };
+
+    if node.is_err() {
+        context.error_offset.get_or_insert(start);
+    }
+    node
+}
+
+fn parse_compact_items(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+    builder: &mut Builder,
+) -> anyhow::Result<Slice> {
+    let open = builder.open();
+    for (index, pair) in pair.into_inner().enumerate() {
+        let span = pair.as_span();
+        context.enter(|| PathSegment::Index(index), span.start());
+        let node = parse_compact(context, pair, builder)?;
+        context.leave(span.end())?;
+        builder.push(node);
+    }
+    Ok(builder.close(open))
+}
+
+fn parse_compact_fields(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+    builder: &mut Builder,
+) -> anyhow::Result<Slice> {
+    assert_eq!(pair.as_rule(), Rule::named_tuple);
+
+    let open = builder.open();
+    for pair in pair.into_inner() {
+        let span = pair.as_span();
+        let mut pairs = pair.into_inner();
+
+        let key = pairs.next().unwrap();
+        let field = match key.as_rule() {
+            Rule::string => parse_string_literal(context, key)?,
+            _ => key.as_str().to_string(),
+        };
+        context.check_identifier_length(&field)?;
+        let key = builder.string(&field);
+
+        context.enter(
+            || PathSegment::Field(Identifier(field.clone())),
+            span.start(),
+        );
+        let value = parse_compact(context, pairs.next().unwrap(), builder)?;
+        context.leave(span.end())?;
+
+        context.check_duplicate(
+            builder.contains_key(open, key),
+            span.start(),
+            || format!("field {}", field),
+        )?;
+        builder.insert(open, key, value);
+    }
+    Ok(builder.close_entries(open))
+}
+
+fn parse_compact_map(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+    builder: &mut Builder,
+) -> anyhow::Result<Slice> {
+    assert_eq!(pair.as_rule(), Rule::map);
+
+    let open = builder.open();
+    for pair in pair.into_inner() {
+        let span = pair.as_span();
+        let mut pairs = pair.into_inner();
+
+        let key = pairs.next().unwrap();
+        let key = match key.as_rule() {
+            Rule::bare_key => {
+                context.nodes += 1;
+                context.check_identifier_length(key.as_str())?;
+                Node::UnitStruct(builder.plain_name(key.as_str()))
+            },
+            _ => parse_compact(context, key, builder)?,
+        };
+
+        context.enter(|| PathSegment::Key(builder.to_value(key)), span.start());
+        let value = parse_compact(context, pairs.next().unwrap(), builder)?;
+        context.leave(span.end())?;
+
+        context.check_duplicate(
+            builder.contains_key(open, key),
+            span.start(),
+            || format!("key {}", builder.to_value(key).to_string_compact()),
+        )?;
+        builder.insert(open, key, value);
     }
+    Ok(builder.close_entries(open))
 }
 
 /// Utility function to parse a string into a value
-fn parse_starter<F, T>(
+///
+/// Errors are located at the innermost value that failed to parse.
+fn parse_starter<'options, F, T>(
     input: &str,
+    options: &'options ParseOptions,
     rule: Rule,
     function: F,
-) -> anyhow::Result<T>
+) -> Result<T, ParseError>
+where
+    F: FnOnce(&mut ParseContext<'options>, Pair<Rule>) -> anyhow::Result<T>,
+{
+    check_limits(input, options)?;
+    let pair = parse_pair(input, options, rule)?;
+    let start = pair.as_span().start();
+
+    let mut context = ParseContext::new(options);
+    function(&mut context, pair).map_err(|error| {
+        let offset = context.error_offset.unwrap_or(start);
+        ParseError::new(input, offset, format!("{:#}", error))
+    })
+}
+
+/// Parses a single value, with the hand-written parser of `descent` or
+/// with pest if it does not accept the input
+///
+/// `finish` takes the result out of `context` once the value is parsed.
+/// Since errors are always reported by pest, it is called a second time if
+/// the hand-written parser fails, with a fresh context. A cancelled parse
+/// is not repeated.
+fn parse_main<'options, F, T>(
+    input: &str,
+    mut context: ParseContext<'options>,
+    mut finish: F,
+) -> Result<T, ParseError>
 where
-    F: FnOnce(Pair<Rule>) -> anyhow::Result<T>,
+    F: FnMut(
+        &mut ParseContext<'options>,
+        anyhow::Result<Value>,
+    ) -> anyhow::Result<T>,
 {
-    let mut raw = ValueParser::parse(rule, input)?;
-    let pair = raw.next().unwrap();
-    function(pair)
+    let options = context.options;
+    check_input_size(input, options)?;
+    let collectors = context.collectors();
+
+    #[cfg(feature = "rayon")]
+    let value = match parallel::parse(&context, input) {
+        Some(value) => Ok(value),
+        None => descent::parse(&mut context, input),
+    };
+    #[cfg(not(feature = "rayon"))]
+    let value = descent::parse(&mut context, input);
+    match finish(&mut context, value) {
+        Ok(result) => return Ok(result),
+        Err(error) if error.is::<Cancelled>() => {
+            let offset = context.error_offset.unwrap_or(0);
+            return Err(ParseError::new(input, offset, error));
+        },
+        Err(_) => context.restart(input, collectors),
+    }
+
+    check_limits(input, options)?;
+    let pair = parse_pair(input, options, Rule::main_value)?;
+    let start = pair.as_span().start();
+    if let Some(comments) = &mut context.comments {
+        comments.attach(start);
+    }
+
+    let value = parse_value(&mut context, pair);
+    finish(&mut context, value).map_err(|error| {
+        let offset = context.error_offset.unwrap_or(start);
+        ParseError::new(input, offset, format!("{:#}", error))
+    })
 }
 
-pub fn parse_main_value(input: &str) -> anyhow::Result<Value> {
-    parse_starter(input, Rule::main_value, parse_value)
+fn check_limits(input: &str, options: &ParseOptions) -> Result<(), ParseError> {
+    check_input_size(input, options)?;
+    if let Some(max) = options.max_depth {
+        if let Some(offset) = nesting_over(input, max) {
+            return Err(ParseError::new(
+                input,
+                offset,
+                format!("Nesting exceeds the maximum depth of {}", max),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks `ParseOptions::max_input_size`
+///
+/// The hand-written parser checks the depth while parsing, so this is all
+/// that has to be checked before it runs.
+fn check_input_size(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
+    if let Some(max) = options.max_input_size {
+        if input.len() > max {
+            // The input is left out of the error, it may be huge
+            return Err(ParseError::new(
+                "",
+                0,
+                format!(
+                    "Input of {} bytes exceeds the maximum of {}",
+                    input.len(),
+                    max
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Matches the input against the grammar, starting at `rule`
+fn parse_pair<'input>(
+    input: &'input str,
+    options: &ParseOptions,
+    rule: Rule,
+) -> Result<Pair<'input, Rule>, ParseError> {
+    let rule = match rule {
+        Rule::main_value if options.allow_trailing_garbage => {
+            Rule::main_value_prefix
+        },
+        rule => rule,
+    };
+
+    let mut raw = ValueParser::parse(rule, input).map_err(|error| {
+        let offset = match error.location {
+            InputLocation::Pos(offset) => offset,
+            InputLocation::Span((start, _)) => start,
+        };
+        let names = |rules: Vec<Rule>| {
+            rules
+                .iter()
+                // Expressions are part of the grammar either way, but only
+                // worth suggesting if they can be evaluated
+                .filter(|rule| {
+                    cfg!(feature = "exprs") || **rule != Rule::operator
+                })
+                .map(|rule| format!("{:?}", rule))
+                .collect()
+        };
+        match error.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => ParseError::syntax(
+                input,
+                offset,
+                names(positives),
+                names(negatives),
+            ),
+            ErrorVariant::CustomError { message } => {
+                ParseError::new(input, offset, message)
+            },
+        }
+    })?;
+    Ok(raw.next().unwrap())
+}
+
+pub fn parse_main_value(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Value, ParseError> {
+    parse_main(input, ParseContext::new(options), |_, value| value)
+}
+
+/// Parses a value into the arena of a `CompactValue`
+///
+/// Like `parse_main`, the hand-written parser is tried first and pest
+/// parses the input again if it fails, to report the error.
+pub(crate) fn parse_main_compact(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<CompactValue, ParseError> {
+    check_input_size(input, options)?;
+    let mut context = ParseContext::new(options);
+    let mut builder = Builder::new();
+    if let Ok(root) = descent::parse_compact(&mut context, input, &mut builder)
+    {
+        return Ok(builder.finish(root));
+    }
+
+    parse_starter(input, options, Rule::main_value, |context, pair| {
+        let mut builder = Builder::new();
+        let root = parse_compact(context, pair, &mut builder)?;
+        Ok(builder.finish(root))
+    })
+}
+
+/// Parses a sequence of values, each on its own lines or separated by
+/// `---`
+pub fn parse_main_documents(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Value>, ParseError> {
+    parse_starter(input, options, Rule::main_documents, |context, pair| {
+        let mut values = Vec::new();
+        // End of the last value, unless a separator followed it
+        let mut last_end = None;
+
+        for pair in pair.into_inner() {
+            let span = pair.as_span();
+            if pair.as_rule() == Rule::document_separator {
+                last_end = None;
+                continue;
+            }
+
+            if let Some(end) = last_end {
+                if !input[end..span.start()].contains('\n') {
+                    context.error_offset = Some(span.start());
+                    return Err(anyhow!(
+                        "Documents need to be separated by a newline or `---`"
+                    ));
+                }
+            }
+            last_end = Some(span.end());
+
+            values.push(parse_value(context, pair)?);
+        }
+
+        Ok(values)
+    })
+}
+
+/// Parses a value and the comments attached to its parts
+pub fn parse_main_value_with_comments(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Value, Comments), ParseError> {
+    let mut context = ParseContext::new(options);
+    context.comments = Some(CommentCollector::new(input));
+
+    parse_main(input, context, |context, value| {
+        let comments = context.comments.take().unwrap().finish();
+        Ok((value?, comments))
+    })
+}
+
+/// Parses a value and the byte ranges of its parts
+pub fn parse_main_value_with_spans(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Value, Spans), ParseError> {
+    let mut context = ParseContext::new(options);
+    context.spans = Some(SpanCollector::default());
+
+    parse_main(input, context, |context, value| {
+        let spans = context.spans.take().unwrap().finish();
+        Ok((value?, spans))
+    })
+}
+
+/// Parses a value and the ranges of the strings that can be borrowed
+/// from the input
+pub(crate) fn parse_main_value_borrowable<'input>(
+    input: &'input str,
+    options: &ParseOptions,
+) -> Result<(Value, InputStrings<'input>), ParseError> {
+    let mut context = ParseContext::new(options);
+    context.borrowable = Some(HashMap::new());
+
+    parse_main(input, context, |context, value| {
+        let ranges = context.borrowable.take().unwrap();
+        Ok((value?, InputStrings::new(input, ranges)))
+    })
+}
+
+/// Parses a value, reporting progress while it is built
+pub fn parse_main_value_with_progress<'options>(
+    input: &str,
+    options: &'options ParseOptions,
+    progress: ProgressReporter<'options>,
+) -> Result<Value, Error> {
+    let mut context = ParseContext::new(options);
+    context.progress = Some(progress);
+
+    let mut cancelled = false;
+    let value = parse_main(input, context, |context, value| {
+        let value =
+            value.and_then(|value| context.report(input.len()).map(|_| value));
+        cancelled = value
+            .as_ref()
+            .err()
+            .is_some_and(|error| error.is::<Cancelled>());
+
+        value
+    });
+
+    // Cancellation is not an error of the input
+    match value {
+        Err(_) if cancelled => Err(Error::Cancelled),
+        value => Ok(value?),
+    }
 }
 
 pub fn parse_main_type_identifier(
     input: &str,
-) -> anyhow::Result<TypeIdentifier> {
-    parse_starter(input, Rule::main_type_identifier, parse_type_identifier)
+) -> Result<TypeIdentifier, TypeParseError> {
+    parse_starter(
+        input,
+        &ParseOptions::default(),
+        Rule::main_type_identifier,
+        parse_type_identifier,
+    )
+    .map_err(|error| TypeParseError { error })
 }
 
-pub fn parse_main_type(input: &str) -> anyhow::Result<Type> {
-    parse_starter(input, Rule::main_type, parse_generic_type)
+pub fn parse_main_type(input: &str) -> Result<Type, TypeParseError> {
+    parse_starter(
+        input,
+        &ParseOptions::default(),
+        Rule::main_type,
+        parse_generic_type,
+    )
+    .map_err(|error| TypeParseError { error })
 }
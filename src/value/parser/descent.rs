@@ -0,0 +1,1269 @@
+//! A hand-written parser for single values
+//!
+//! pest matches the whole input and builds its token tree before the first
+//! value is made from it. This parser reads the input once and builds the
+//! values right away, which is several times faster.
+//!
+//! It only has to agree with the grammar on the input it accepts. Anything
+//! it does not accept, including every invalid input, is parsed again with
+//! pest, which also reports the error.
+
+use super::{
+    bare_key_value, bytes_value, char_value, number_value, string_contents,
+    string_value, ParseContext,
+};
+use crate::value::{
+    compact::{Builder, Node, Slice},
+    map::{Map, OrderedMap},
+    path::PathSegment,
+    types::{GenericIdentifier, Generics, Identifier, Type, TypeIdentifier},
+    Value,
+};
+use anyhow::anyhow;
+use std::{borrow::Cow, ops::Range};
+
+/// Parses the value making up `input`, see `main_value` and
+/// `main_value_prefix` of the grammar
+pub(super) fn parse(
+    context: &mut ParseContext,
+    input: &str,
+) -> anyhow::Result<Value> {
+    let mut parser = Parser {
+        context,
+        input,
+        position: 0,
+        nesting: 0,
+    };
+
+    parser.skip_trivia();
+    let start = parser.position;
+    if let Some(comments) = &mut parser.context.comments {
+        comments.attach(start);
+    }
+
+    let value = parser.value(true)?;
+    parser.end()?;
+    Ok(value)
+}
+
+/// Like `parse`, but builds the value in the arena of `builder`, see
+/// `Parser::compact`
+pub(super) fn parse_compact(
+    context: &mut ParseContext,
+    input: &str,
+    builder: &mut Builder,
+) -> anyhow::Result<Node> {
+    let mut parser = Parser {
+        context,
+        input,
+        position: 0,
+        nesting: 0,
+    };
+
+    parser.skip_trivia();
+    let node = parser.compact(builder)?;
+    parser.end()?;
+    Ok(node)
+}
+
+/// The elements of a part of a list or map
+#[cfg(feature = "rayon")]
+pub(super) enum Elements {
+    Items(Vec<Value>),
+    Entries(Vec<(Value, Value)>),
+}
+
+/// Parses the elements of a list or map within `range` of `input`, a part
+/// of it cut at the commas between its elements, see `parallel`
+///
+/// Only the last part can end with a trailing comma.
+#[cfg(feature = "rayon")]
+pub(super) fn parse_elements(
+    context: &mut ParseContext,
+    input: &str,
+    range: Range<usize>,
+    map: bool,
+    last: bool,
+) -> anyhow::Result<Elements> {
+    let mut parser = Parser {
+        context,
+        input: &input[..range.end],
+        position: range.start,
+        // Inside the list or map
+        nesting: 1,
+    };
+
+    let mut elements = if map {
+        Elements::Entries(Vec::new())
+    } else {
+        Elements::Items(Vec::new())
+    };
+    loop {
+        parser.skip_trivia();
+        match &mut elements {
+            Elements::Items(items) => {
+                let value = parser.item(items.len())?;
+                items.push(value);
+            },
+            Elements::Entries(entries) => entries.push(parser.entry()?),
+        }
+
+        parser.skip_trivia();
+        if parser.peek().is_none() {
+            return Ok(elements);
+        }
+        parser.expect(b',')?;
+        parser.skip_trivia();
+        if last && parser.peek().is_none() {
+            return Ok(elements);
+        }
+    }
+}
+
+/// Offsets of the delimiters around the list or map making up `input` and
+/// of the commas between its elements
+///
+/// Strings, chars and comments are skipped the way the parser reads them,
+/// so on input the parser accepts, the commas are exactly those that
+/// separate elements. None if `input` is anything else.
+#[cfg(feature = "rayon")]
+pub(super) fn element_commas(
+    context: &mut ParseContext,
+    input: &str,
+) -> Option<(Range<usize>, Vec<usize>)> {
+    let mut parser = Parser {
+        context,
+        input,
+        position: 0,
+        nesting: 0,
+    };
+
+    parser.skip_trivia();
+    let open = parser.position;
+    let close = match parser.peek()? {
+        b'[' => b']',
+        b'{' => b'}',
+        _ => return None,
+    };
+    parser.position += 1;
+
+    let mut commas = Vec::new();
+    let mut depth = 1usize;
+    loop {
+        parser.skip_trivia();
+        let start = parser.position;
+        match parser.peek()? {
+            b'(' | b'[' | b'{' | b'<' => {
+                depth += 1;
+                parser.position += 1;
+            },
+            b')' | b']' | b'}' | b'>' => {
+                depth -= 1;
+                parser.position += 1;
+                if depth == 0 {
+                    if parser.input.as_bytes()[start] != close {
+                        return None;
+                    }
+                    parser.skip_trivia();
+                    return match parser.peek() {
+                        None => Some((open..start, commas)),
+                        Some(_) => None,
+                    };
+                }
+            },
+            b',' => {
+                if depth == 1 {
+                    commas.push(start);
+                }
+                parser.position += 1;
+            },
+            b'"' => parser.position = parser.string_end(start)?,
+            b'\'' => {
+                // A char, or a lifetime in generics
+                let mut chars = parser.input[start + 1..].chars();
+                parser.position += 1;
+                match chars.next()? {
+                    '\\' => {
+                        parser.position += 2;
+                        let rest = &parser.input.as_bytes()[parser.position..];
+                        parser.position += memchr::memchr(b'\'', rest)? + 1;
+                    },
+                    c if chars.next() == Some('\'') => {
+                        parser.position += c.len_utf8() + 1;
+                    },
+                    _ => {},
+                }
+            },
+            byte if is_identifier_start(byte) => {
+                let end = parser.identifier_end(start)?;
+                parser.position = end;
+                if &input[start..end] == "r" {
+                    if let Some(b'"') | Some(b'#') = parser.peek() {
+                        parser.raw_string_contents()?;
+                    }
+                }
+            },
+            _ => {
+                let c = parser.input[start..].chars().next()?;
+                parser.position += c.len_utf8();
+            },
+        }
+    }
+}
+
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_'
+}
+
+fn is_identifier_continue(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// The start of a value beginning with an identifier, see
+/// `Parser::word_start`
+enum Word {
+    Value(Value),
+    /// `Some(`, followed by the value inside it
+    Some,
+    /// The name of a struct, followed by `(...)`
+    Struct(TypeIdentifier),
+}
+
+/// What `(...)` holds, see `Parser::parenthesized`
+enum Parenthesized {
+    Unit,
+    Fields,
+    Items,
+}
+
+struct Parser<'input, 'context, 'options> {
+    context: &'context mut ParseContext<'options>,
+    input: &'input str,
+    position: usize,
+    /// Number of delimiters around the current position
+    nesting: usize,
+}
+
+impl<'input, 'context, 'options> Parser<'input, 'context, 'options> {
+    /// Leaves the input to pest
+    fn reject<T>(&self) -> anyhow::Result<T> {
+        Err(anyhow!("Unexpected input at {}", self.position))
+    }
+
+    fn byte_at(&self, position: usize) -> Option<u8> {
+        self.input.as_bytes().get(position).copied()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.byte_at(self.position)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let eaten = self.peek() == Some(byte);
+        self.position += eaten as usize;
+        eaten
+    }
+
+    fn expect(&mut self, byte: u8) -> anyhow::Result<()> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            self.reject()
+        }
+    }
+
+    /// Consumes an opening delimiter
+    ///
+    /// Nesting deeper than `ParseOptions::max_depth` is left to pest, so
+    /// it is reported by `check_limits`, which counts delimiters the same
+    /// way.
+    fn open(&mut self, byte: u8) -> anyhow::Result<()> {
+        self.expect(byte)?;
+        self.nest()
+    }
+
+    /// Counts a delimiter that has been consumed, see `open`
+    fn nest(&mut self) -> anyhow::Result<()> {
+        self.nesting += 1;
+        match self.context.options.max_depth {
+            Some(max) if self.nesting > max => self.reject(),
+            _ => Ok(()),
+        }
+    }
+
+    fn close(&mut self, byte: u8) -> anyhow::Result<()> {
+        self.expect(byte)?;
+        self.nesting -= 1;
+        Ok(())
+    }
+
+    fn eat_close(&mut self, byte: u8) -> bool {
+        let eaten = self.eat(byte);
+        self.nesting -= eaten as usize;
+        eaten
+    }
+
+    /// Checks what follows the value making up the input
+    fn end(&mut self) -> anyhow::Result<()> {
+        self.skip_trivia();
+        match self.peek() {
+            None => Ok(()),
+            // Part of an expression
+            Some(b'+') | Some(b'-') | Some(b'*') | Some(b'/') | Some(b'%') => {
+                self.reject()
+            },
+            Some(_) if self.context.options.allow_trailing_garbage => Ok(()),
+            Some(_) => self.reject(),
+        }
+    }
+
+    /// End of the whitespace and comments at `position`
+    fn trivia_end(&self, mut position: usize) -> usize {
+        loop {
+            let rest = &self.input[position..];
+            match rest.as_bytes().first() {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => {
+                    position += 1
+                },
+                Some(b'/') if rest.starts_with("//") => {
+                    position +=
+                        rest.find(&['\n', '\r'][..]).unwrap_or(rest.len());
+                },
+                Some(b'/') if rest.starts_with("/*") => {
+                    match rest[2..].find("*/") {
+                        Some(end) => position += end + 4,
+                        None => return position,
+                    }
+                },
+                Some(_) => match rest.chars().next() {
+                    Some(c) if c.is_whitespace() => position += c.len_utf8(),
+                    _ => return position,
+                },
+                None => return position,
+            }
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        self.position = self.trivia_end(self.position);
+    }
+
+    /// End of the identifier at `position`, if there is one
+    fn identifier_end(&self, position: usize) -> Option<usize> {
+        let bytes = &self.input.as_bytes()[position..];
+        if !bytes.first().copied().is_some_and(is_identifier_start) {
+            return None;
+        }
+
+        let length = bytes
+            .iter()
+            .position(|byte| !is_identifier_continue(*byte))
+            .unwrap_or(bytes.len());
+        Some(position + length)
+    }
+
+    /// Consumes an identifier that is part of a type or a struct field
+    fn identifier(&mut self) -> anyhow::Result<&'input str> {
+        let start = self.position;
+        let end = match self.identifier_end(start) {
+            Some(end) => end,
+            None => return self.reject(),
+        };
+
+        self.position = end;
+        Ok(&self.input[start..end])
+    }
+
+    /// End of the string literal with its opening quote at `position`
+    fn string_end(&self, position: usize) -> Option<usize> {
+        let bytes = self.input.as_bytes();
+        let mut position = position + 1;
+        loop {
+            position += memchr::memchr2(b'"', b'\\', &bytes[position..])?;
+            if bytes[position] == b'"' {
+                return Some(position + 1);
+            }
+            // Escaped quotes and backslashes are ASCII, a multi byte
+            // char can be skipped by its first byte
+            if position + 1 >= bytes.len() {
+                return None;
+            }
+            position += 2;
+        }
+    }
+
+    /// Consumes a string literal, returning the range of its contents
+    fn string_literal(&mut self) -> anyhow::Result<Range<usize>> {
+        match self.string_end(self.position) {
+            Some(end) => {
+                let contents = self.position + 1..end - 1;
+                self.position = end;
+                Ok(contents)
+            },
+            None => self.reject(),
+        }
+    }
+
+    /// Whether a field `name:` or `"name":` of a struct starts here
+    fn at_field(&self) -> bool {
+        let end = match self.peek() {
+            Some(b'"') => match self.string_end(self.position) {
+                Some(end) => self.trivia_end(end),
+                None => return false,
+            },
+            _ => match self.identifier_end(self.position) {
+                Some(end) => end,
+                None => return false,
+            },
+        };
+        self.byte_at(end) == Some(b':') && self.byte_at(end + 1) != Some(b':')
+    }
+
+    /// Parses the value at the current position
+    ///
+    /// `record` is false for values that share the path of the one they
+    /// are part of, like map keys or the inside of `Some`, whose spans are
+    /// not kept.
+    fn value(&mut self, record: bool) -> anyhow::Result<Value> {
+        let start = self.position;
+        self.context.nodes += 1;
+
+        let value = match self.peek() {
+            Some(b'(') => self.parenthesized(None)?,
+            Some(b'[') => Value::List(self.items(b'[', b']')?),
+            Some(b'{') => self.map()?,
+            Some(b'"') => {
+                let contents = self.string_literal()?;
+                self.string(contents, false)?
+            },
+            Some(b'\'') => self.char()?,
+            Some(b'<') => {
+                self.open(b'<')?;
+                self.skip_trivia();
+                let value = Value::Type(self.generic_type()?);
+                self.skip_trivia();
+                self.close(b'>')?;
+                value
+            },
+            Some(b'-') | Some(b'0'..=b'9') => self.number()?,
+            Some(b'&') => {
+                self.position += 1;
+                self.skip_trivia();
+                let name = match self.identifier_end(self.position) {
+                    Some(end) => &self.input[self.position..end],
+                    None => return self.reject(),
+                };
+                self.position += name.len();
+                self.skip_trivia();
+
+                let value = self.value(false)?;
+                self.context.anchors.insert(name.to_string(), value.clone());
+                value
+            },
+            Some(b'*') => {
+                let name = match self.identifier_end(self.position + 1) {
+                    Some(end) => &self.input[self.position + 1..end],
+                    None => return self.reject(),
+                };
+                self.position += 1 + name.len();
+                match self.context.anchors.get(name) {
+                    Some(value) => value.clone(),
+                    None => return self.reject(),
+                }
+            },
+            Some(byte) if is_identifier_start(byte) => self.word()?,
+            _ => return self.reject(),
+        };
+
+        if record {
+            if let Some(spans) = &mut self.context.spans {
+                spans.record(start..self.position);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Parses a value starting with an identifier, like a keyword, a
+    /// struct or a prefixed string
+    fn word(&mut self) -> anyhow::Result<Value> {
+        match self.word_start()? {
+            Word::Value(value) => Ok(value),
+            Word::Some => {
+                let value = self.value(false)?;
+                self.close_some()?;
+                Ok(Value::Option(Some(Box::new(value))))
+            },
+            Word::Struct(name) => self.parenthesized(Some(name)),
+        }
+    }
+
+    /// Consumes `)` after the value inside `Some(`
+    fn close_some(&mut self) -> anyhow::Result<()> {
+        self.skip_trivia();
+        self.close(b')')
+    }
+
+    /// Parses the start of a value starting with an identifier, up to the
+    /// parts that are values of their own
+    fn word_start(&mut self) -> anyhow::Result<Word> {
+        let start = self.position;
+        let end = self.identifier_end(start).unwrap();
+        let word = &self.input[start..end];
+        let next = self.byte_at(end);
+
+        match word {
+            "true" => {
+                self.position = end;
+                return Ok(Word::Value(Value::Bool(true)));
+            },
+            "false" => {
+                self.position = end;
+                return Ok(Word::Value(Value::Bool(false)));
+            },
+            "None" => {
+                self.position = end;
+                return Ok(Word::Value(Value::Option(None)));
+            },
+            "Some" if self.byte_at(self.trivia_end(end)) == Some(b'(') => {
+                self.position = self.trivia_end(end) + 1;
+                self.nest()?;
+                self.skip_trivia();
+                return Ok(Word::Some);
+            },
+            // Structs are tried before numbers
+            "inf" | "NaN" => {
+                let after = self.trivia_end(end);
+                return match self.byte_at(after) {
+                    Some(b'(') | Some(b'<') | Some(b':') => self.reject(),
+                    _ => {
+                        self.position = end;
+                        number_value(word).map(Word::Value)
+                    },
+                };
+            },
+
+            "r" if next == Some(b'"') || next == Some(b'#') => {
+                self.position = end;
+                return self.raw_string().map(Word::Value);
+            },
+            "b" if next == Some(b'"') => {
+                self.position = end;
+                return self.byte_string().map(Word::Value);
+            },
+            _ if next == Some(b'"') => {
+                self.position = end;
+                let contents = self.string_literal()?;
+                let contents = string_contents(
+                    self.context,
+                    &self.input[contents],
+                    false,
+                )?;
+                let literals = &self.context.options.literals;
+                return literals.parse(word, &contents).map(Word::Value);
+            },
+            _ => {},
+        }
+
+        let name = self.type_identifier()?;
+        let after = self.trivia_end(self.position);
+        if self.byte_at(after) == Some(b'(') {
+            self.position = after;
+            return Ok(Word::Struct(name));
+        }
+
+        // The span of the type includes the trivia after it, unless it
+        // ends in generics after a `::`
+        let generics = self.input[..self.position].ends_with('>');
+        if name.segments.len() == 1 || !generics {
+            self.position = after;
+        }
+        Ok(Word::Value(Value::UnitStruct(name)))
+    }
+
+    /// Turns the contents of a string literal into a value
+    fn string(
+        &mut self,
+        contents: Range<usize>,
+        is_raw: bool,
+    ) -> anyhow::Result<Value> {
+        let text = &self.input[contents.clone()];
+        let string = string_contents(self.context, text, is_raw)?;
+        Ok(string_value(self.context, string, text, contents))
+    }
+
+    /// Parses `r"..."` or `r#"..."#` after the `r`
+    fn raw_string(&mut self) -> anyhow::Result<Value> {
+        match self.raw_string_contents() {
+            Some(contents) => self.string(contents, true),
+            None => self.reject(),
+        }
+    }
+
+    /// Consumes the hashes and quotes of a raw string after the `r`,
+    /// returning the range of its contents
+    fn raw_string_contents(&mut self) -> Option<Range<usize>> {
+        let rest = &self.input[self.position..];
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        self.position += hashes;
+        if !self.eat(b'"') {
+            return None;
+        }
+
+        let terminator = format!("\"{}", &rest[..hashes]);
+        let start = self.position;
+        let end = start + self.input[start..].find(&terminator)?;
+
+        self.position = end + terminator.len();
+        Some(start..end)
+    }
+
+    /// Parses `b"..."` after the `b`
+    fn byte_string(&mut self) -> anyhow::Result<Value> {
+        self.expect(b'"')?;
+        let bytes = self.input.as_bytes();
+        let start = self.position;
+
+        let mut position = start;
+        loop {
+            position += match memchr::memchr2(b'"', b'\\', &bytes[position..]) {
+                Some(offset) => offset,
+                None => return self.reject(),
+            };
+            if bytes[position] == b'"' {
+                break;
+            }
+            // Only ASCII characters can be escaped
+            match bytes.get(position + 1) {
+                Some(byte) if byte.is_ascii() => position += 2,
+                _ => return self.reject(),
+            }
+        }
+
+        self.position = position + 1;
+        bytes_value(self.context, &self.input[start..position])
+    }
+
+    fn char(&mut self) -> anyhow::Result<Value> {
+        self.expect(b'\'')?;
+        let start = self.position;
+
+        let rest = &self.input[start..];
+        let mut chars = rest.chars();
+        let length = match chars.next() {
+            Some('\\') => {
+                let escaped = chars.as_str();
+                let is_unicode = escaped.starts_with('u')
+                    && escaped.len() >= 5
+                    && escaped.as_bytes()[1..5]
+                        .iter()
+                        .all(u8::is_ascii_hexdigit);
+                match chars.next() {
+                    _ if is_unicode => 6,
+                    Some(c) => 1 + c.len_utf8(),
+                    None => return self.reject(),
+                }
+            },
+            Some('\'') | None => return self.reject(),
+            Some(c) => c.len_utf8(),
+        };
+
+        self.position += length;
+        self.expect(b'\'')?;
+        char_value(self.context, &rest[..length])
+    }
+
+    /// Consumes a number literal, following the `number` rule
+    fn number(&mut self) -> anyhow::Result<Value> {
+        let start = self.position;
+        self.eat(b'-');
+
+        let rest = &self.input.as_bytes()[self.position..];
+        let digits = |is_digit: fn(&u8) -> bool, from: usize| {
+            from + rest[from..]
+                .iter()
+                .position(|byte| !is_digit(byte) && *byte != b'_')
+                .unwrap_or(rest.len() - from)
+        };
+        let prefixed = |prefix: &[u8], is_digit: fn(&u8) -> bool| {
+            rest.starts_with(prefix) && rest.get(2).is_some_and(is_digit)
+        };
+
+        let length = if rest.starts_with(b"inf") || rest.starts_with(b"NaN") {
+            if rest.get(3).copied().is_some_and(is_identifier_continue) {
+                return self.reject();
+            }
+            3
+        } else if prefixed(b"0x", u8::is_ascii_hexdigit) {
+            digits(u8::is_ascii_hexdigit, 3)
+        } else if prefixed(b"0o", |byte| (b'0'..=b'7').contains(byte)) {
+            digits(|byte| (b'0'..=b'7').contains(byte), 3)
+        } else if prefixed(b"0b", |byte| *byte == b'0' || *byte == b'1') {
+            digits(|byte| *byte == b'0' || *byte == b'1', 3)
+        } else {
+            let mut length = match rest.first() {
+                Some(b'0') => 1,
+                Some(b'1'..=b'9') => digits(u8::is_ascii_digit, 1),
+                _ => return self.reject(),
+            };
+            if rest.get(length) == Some(&b'.') {
+                length = digits(u8::is_ascii_digit, length + 1);
+            }
+            if let Some(b'e') | Some(b'E') = rest.get(length) {
+                let mut exponent = length + 1;
+                if let Some(b'+') | Some(b'-') = rest.get(exponent) {
+                    exponent += 1;
+                }
+                let end = exponent
+                    + rest[exponent..]
+                        .iter()
+                        .take_while(|byte| byte.is_ascii_digit())
+                        .count();
+                if end > exponent {
+                    length = end;
+                }
+            }
+            length
+        };
+
+        self.position += length;
+        number_value(&self.input[start..self.position])
+    }
+
+    /// Parses `(...)` after an optional struct name, into a unit, a tuple,
+    /// a tuple struct or a struct
+    fn parenthesized(
+        &mut self,
+        name: Option<TypeIdentifier>,
+    ) -> anyhow::Result<Value> {
+        Ok(match (self.parenthesized_kind(name.is_some())?, name) {
+            (Parenthesized::Unit, _) => Value::Unit,
+            (Parenthesized::Fields, name) => Value::Struct(
+                name.unwrap_or(TypeIdentifier { segments: vec![] }),
+                self.fields()?,
+            ),
+            (Parenthesized::Items, Some(name)) => {
+                Value::TupleStruct(name, self.items(b'(', b')')?)
+            },
+            (Parenthesized::Items, None) => {
+                Value::Tuple(self.items(b'(', b')')?)
+            },
+        })
+    }
+
+    /// What the `(...)` at the current position holds, consuming it if it
+    /// is a unit
+    fn parenthesized_kind(
+        &mut self,
+        named: bool,
+    ) -> anyhow::Result<Parenthesized> {
+        let after = self.trivia_end(self.position + 1);
+        if self.byte_at(after) == Some(b')') && !named {
+            self.nest()?;
+            self.nesting -= 1;
+            self.position = after + 1;
+            return Ok(Parenthesized::Unit);
+        }
+
+        let saved = self.position;
+        self.position = after;
+        let is_struct = self.at_field();
+        self.position = saved;
+
+        Ok(if is_struct {
+            Parenthesized::Fields
+        } else {
+            Parenthesized::Items
+        })
+    }
+
+    /// Parses the items of a tuple or list
+    fn items(&mut self, open: u8, close: u8) -> anyhow::Result<Vec<Value>> {
+        self.open(open)?;
+
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.eat_close(close) {
+                return Ok(items);
+            }
+
+            let value = self.item(items.len())?;
+            items.push(value);
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn item(&mut self, index: usize) -> anyhow::Result<Value> {
+        let start = self.position;
+        self.context.enter(|| PathSegment::Index(index), start);
+        let value = self.value(true)?;
+        self.context.leave(self.position)?;
+        Ok(value)
+    }
+
+    fn fields(&mut self) -> anyhow::Result<OrderedMap<Identifier, Value>> {
+        self.open(b'(')?;
+
+        let mut fields = OrderedMap::new();
+        loop {
+            self.skip_trivia();
+            if self.eat_close(b')') {
+                return Ok(fields);
+            }
+
+            let start = self.position;
+            let identifier = Identifier(self.field_name()?.into_owned());
+
+            self.context
+                .enter(|| PathSegment::Field(identifier.clone()), start);
+            let value = self.value(true)?;
+            self.context.leave(self.position)?;
+
+            self.context.check_duplicate(
+                fields.contains_key(&identifier),
+                start,
+                || format!("field {}", identifier),
+            )?;
+            fields.insert(identifier, value);
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(b')')?;
+                return Ok(fields);
+            }
+        }
+    }
+
+    /// Consumes the name of a struct field and the `:` after it
+    fn field_name(&mut self) -> anyhow::Result<Cow<'input, str>> {
+        let field = if self.peek() == Some(b'"') {
+            let contents = self.string_literal()?;
+            let text = &self.input[contents];
+            Cow::Owned(string_contents(self.context, text, false)?)
+        } else {
+            Cow::Borrowed(self.identifier()?)
+        };
+        self.context.check_identifier_length(&field)?;
+
+        self.skip_trivia();
+        self.expect(b':')?;
+        self.skip_trivia();
+        Ok(field)
+    }
+
+    /// End of a map key written as an identifier, see `bare_key`
+    fn bare_key_end(&self) -> Option<usize> {
+        let end = self.identifier_end(self.position)?;
+        let key = &self.input[self.position..end];
+        if ["true", "false", "None", "inf", "NaN"].contains(&key) {
+            return None;
+        }
+
+        let rest = self.input[end..].trim_start_matches(char::is_whitespace);
+        if rest.starts_with(':') && !rest.starts_with("::") {
+            Some(end)
+        } else {
+            None
+        }
+    }
+
+    fn map(&mut self) -> anyhow::Result<Value> {
+        self.open(b'{')?;
+
+        let mut map = Map::new();
+        loop {
+            self.skip_trivia();
+            if self.eat_close(b'}') {
+                return Ok(Value::Map(map));
+            }
+
+            let start = self.position;
+            let (key, value) = self.entry()?;
+            self.context.check_duplicate(
+                map.contains_key(&key),
+                start,
+                || format!("key {}", key.to_string_compact()),
+            )?;
+            map.insert(key, value);
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(b'}')?;
+                return Ok(Value::Map(map));
+            }
+        }
+    }
+
+    fn entry(&mut self) -> anyhow::Result<(Value, Value)> {
+        let start = self.position;
+        let key = match self.bare_key_end() {
+            Some(end) => {
+                self.position = end;
+                bare_key_value(
+                    self.context,
+                    &self.input[start..end],
+                    start..end,
+                )?
+            },
+            None => self.value(false)?,
+        };
+        self.skip_trivia();
+        self.expect(b':')?;
+        self.skip_trivia();
+
+        self.context.enter(|| PathSegment::Key(key.clone()), start);
+        let value = self.value(true)?;
+        self.context.leave(self.position)?;
+        Ok((key, value))
+    }
+
+    /// Like `value`, but builds collections, strings and field names right
+    /// in the arena of `builder`, without a `Value` for each of them
+    ///
+    /// Everything else is parsed by `value` and converted.
+    fn compact(&mut self, builder: &mut Builder) -> anyhow::Result<Node> {
+        let node = match self.peek() {
+            Some(b'(') => {
+                self.context.nodes += 1;
+                self.compact_parenthesized(None, builder)?
+            },
+            Some(b'[') => {
+                self.context.nodes += 1;
+                Node::List(self.compact_items(b'[', b']', builder)?)
+            },
+            Some(b'{') => {
+                self.context.nodes += 1;
+                Node::Map(self.compact_map(builder)?)
+            },
+            Some(b'"') => {
+                self.context.nodes += 1;
+                let contents = self.string_literal()?;
+                let string = string_contents(
+                    self.context,
+                    &self.input[contents],
+                    false,
+                )?;
+                builder.string(&string)
+            },
+            Some(byte) if is_identifier_start(byte) => {
+                self.context.nodes += 1;
+                if let Some(node) = self.compact_plain_struct(builder)? {
+                    return Ok(node);
+                }
+                match self.word_start()? {
+                    Word::Value(value) => builder.value(&value),
+                    Word::Some => {
+                        let inner = self.compact(builder)?;
+                        self.close_some()?;
+                        builder.some(inner)
+                    },
+                    Word::Struct(name) => {
+                        let name = builder.name(&name);
+                        self.compact_parenthesized(Some(name), builder)?
+                    },
+                }
+            },
+            _ => builder.value(&self.value(false)?),
+        };
+        Ok(node)
+    }
+
+    /// Parses a struct whose name is a single identifier without
+    /// generics, the most common kind, without a `TypeIdentifier` for it
+    ///
+    /// None if the value at the current position is anything else.
+    fn compact_plain_struct(
+        &mut self,
+        builder: &mut Builder,
+    ) -> anyhow::Result<Option<Node>> {
+        let start = self.position;
+        let end = self.identifier_end(start).unwrap();
+        let word = &self.input[start..end];
+        // Keywords and prefixed strings are left to `word_start`
+        let is_type = word.starts_with(|c: char| c.is_ascii_uppercase())
+            && !["None", "Some", "NaN"].contains(&word)
+            && self.byte_at(end) != Some(b'"');
+        let after = self.trivia_end(end);
+        let is_plain = match self.byte_at(after) {
+            Some(b'<') => false,
+            Some(b':') => !self.input[after..].starts_with("::"),
+            _ => true,
+        };
+        if !is_type || !is_plain {
+            return Ok(None);
+        }
+
+        self.context.check_identifier_length(word)?;
+        let name = builder.plain_name(word);
+        self.position = after;
+        if self.peek() == Some(b'(') {
+            self.compact_parenthesized(Some(name), builder).map(Some)
+        } else {
+            Ok(Some(Node::UnitStruct(name)))
+        }
+    }
+
+    /// Like `parenthesized`, see `compact`
+    fn compact_parenthesized(
+        &mut self,
+        name: Option<u32>,
+        builder: &mut Builder,
+    ) -> anyhow::Result<Node> {
+        let kind = self.parenthesized_kind(name.is_some())?;
+        Ok(match (kind, name) {
+            (Parenthesized::Unit, _) => Node::Unit,
+            (Parenthesized::Fields, name) => {
+                let name = name.unwrap_or_else(|| {
+                    builder.name(&TypeIdentifier { segments: vec![] })
+                });
+                Node::Struct(name, self.compact_fields(builder)?)
+            },
+            (Parenthesized::Items, Some(name)) => Node::TupleStruct(
+                name,
+                self.compact_items(b'(', b')', builder)?,
+            ),
+            (Parenthesized::Items, None) => {
+                Node::Tuple(self.compact_items(b'(', b')', builder)?)
+            },
+        })
+    }
+
+    /// Like `items`, see `compact`
+    fn compact_items(
+        &mut self,
+        open: u8,
+        close: u8,
+        builder: &mut Builder,
+    ) -> anyhow::Result<Slice> {
+        self.open(open)?;
+
+        let open = builder.open();
+        let mut index = 0;
+        loop {
+            self.skip_trivia();
+            if self.eat_close(close) {
+                return Ok(builder.close(open));
+            }
+
+            let start = self.position;
+            self.context.enter(|| PathSegment::Index(index), start);
+            let node = self.compact(builder)?;
+            self.context.leave(self.position)?;
+            builder.push(node);
+            index += 1;
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(close)?;
+                return Ok(builder.close(open));
+            }
+        }
+    }
+
+    /// Like `fields`, see `compact`
+    fn compact_fields(
+        &mut self,
+        builder: &mut Builder,
+    ) -> anyhow::Result<Slice> {
+        self.open(b'(')?;
+
+        let open = builder.open();
+        loop {
+            self.skip_trivia();
+            if self.eat_close(b')') {
+                return Ok(builder.close_entries(open));
+            }
+
+            let start = self.position;
+            let field = self.field_name()?;
+            let key = builder.string(&field);
+
+            self.context.enter(
+                || PathSegment::Field(Identifier(field.to_string())),
+                start,
+            );
+            let value = self.compact(builder)?;
+            self.context.leave(self.position)?;
+
+            self.context.check_duplicate(
+                builder.contains_key(open, key),
+                start,
+                || format!("field {}", field),
+            )?;
+            builder.insert(open, key, value);
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(b')')?;
+                return Ok(builder.close_entries(open));
+            }
+        }
+    }
+
+    /// Like `map`, see `compact`
+    fn compact_map(&mut self, builder: &mut Builder) -> anyhow::Result<Slice> {
+        self.open(b'{')?;
+
+        let open = builder.open();
+        loop {
+            self.skip_trivia();
+            if self.eat_close(b'}') {
+                return Ok(builder.close_entries(open));
+            }
+
+            let start = self.position;
+            let key = match self.bare_key_end() {
+                Some(end) => {
+                    let key = &self.input[start..end];
+                    self.position = end;
+                    self.context.nodes += 1;
+                    self.context.check_identifier_length(key)?;
+                    Node::UnitStruct(builder.plain_name(key))
+                },
+                None => self.compact(builder)?,
+            };
+            self.skip_trivia();
+            self.expect(b':')?;
+            self.skip_trivia();
+
+            self.context
+                .enter(|| PathSegment::Key(builder.to_value(key)), start);
+            let value = self.compact(builder)?;
+            self.context.leave(self.position)?;
+
+            self.context.check_duplicate(
+                builder.contains_key(open, key),
+                start,
+                || {
+                    let key = builder.to_value(key);
+                    format!("key {}", key.to_string_compact())
+                },
+            )?;
+            builder.insert(open, key, value);
+
+            self.skip_trivia();
+            if !self.eat(b',') {
+                self.close(b'}')?;
+                return Ok(builder.close_entries(open));
+            }
+        }
+    }
+
+    /// Parses a type, following the `generic_type` rule
+    fn generic_type(&mut self) -> anyhow::Result<Type> {
+        match self.peek() {
+            Some(b'(') => {
+                self.open(b'(')?;
+                let mut types = Vec::new();
+                loop {
+                    self.skip_trivia();
+                    if self.eat_close(b')') {
+                        return Ok(Type::Tuple(types));
+                    }
+                    types.push(self.generic_type()?);
+                    self.skip_trivia();
+                    if !self.eat(b',') {
+                        self.close(b')')?;
+                        return Ok(Type::Tuple(types));
+                    }
+                }
+            },
+            Some(b'[') => {
+                self.open(b'[')?;
+                self.skip_trivia();
+                let content = Box::new(self.generic_type()?);
+                self.skip_trivia();
+                self.expect(b';')?;
+                self.skip_trivia();
+
+                let start = self.position;
+                match self.peek() {
+                    Some(b'0') => self.position += 1,
+                    Some(b'1'..=b'9') => {
+                        let rest = &self.input.as_bytes()[start..];
+                        self.position += rest
+                            .iter()
+                            .position(|byte| !byte.is_ascii_digit())
+                            .unwrap_or(rest.len());
+                    },
+                    _ => return self.reject(),
+                }
+                let size = self.input[start..self.position].to_string();
+
+                self.skip_trivia();
+                self.close(b']')?;
+                Ok(Type::Array { content, size })
+            },
+            _ => Ok(Type::TypeIdentifier(self.type_identifier()?)),
+        }
+    }
+
+    /// Parses a path of identifiers with generics, like `a::Foo<u8>`
+    fn type_identifier(&mut self) -> anyhow::Result<TypeIdentifier> {
+        let mut segments = Vec::new();
+        loop {
+            let identifier = self.identifier()?;
+            self.context.check_identifier_length(identifier)?;
+            let identifier = Identifier(identifier.to_string());
+
+            let after = self.trivia_end(self.position);
+            let generics = if self.byte_at(after) == Some(b'<') {
+                self.position = after;
+                self.generics()?
+            } else {
+                None
+            };
+            segments.push(GenericIdentifier {
+                identifier,
+                generics,
+            });
+
+            let after = self.trivia_end(self.position);
+            if !self.input[after..].starts_with("::") {
+                return Ok(TypeIdentifier { segments });
+            }
+            self.position = after + 2;
+            self.skip_trivia();
+        }
+    }
+
+    /// Parses `<...>`, generics made only of lifetimes are dropped
+    fn generics(&mut self) -> anyhow::Result<Option<Generics>> {
+        self.open(b'<')?;
+
+        let mut types = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.eat(b'\'') {
+                match self.identifier_end(self.position) {
+                    Some(end) => self.position = end,
+                    None => return self.reject(),
+                }
+            } else {
+                types.push(self.generic_type()?);
+            }
+
+            self.skip_trivia();
+            if self.eat(b',') {
+                self.skip_trivia();
+                if self.eat_close(b'>') {
+                    break;
+                }
+            } else {
+                self.close(b'>')?;
+                break;
+            }
+        }
+
+        Ok(if types.is_empty() {
+            None
+        } else {
+            Some(Generics { types })
+        })
+    }
+}
@@ -0,0 +1,143 @@
+//! Evaluation of constant expressions like `1024 * 1024`
+//!
+//! Expressions only combine number and string literals and the constants
+//! of `ParseOptions`, so evaluating them cannot touch anything outside of
+//! the document. Integer arithmetic is checked, integers divide like Rust
+//! integers do, and a float on either side makes the operation a float one.
+
+use super::{parse_number, parse_string_literal, ParseContext, Rule};
+use crate::value::{number::Number, Value};
+use anyhow::anyhow;
+use pest::iterators::Pair;
+use std::convert::TryFrom;
+
+pub(super) fn parse_expression(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    let text = pair.as_str();
+    evaluate(context, pair).map_err(|error| {
+        anyhow!("Could not evaluate `{}`: {}", text.trim(), error)
+    })
+}
+
+/// Evaluates a chain of operands and operators, `*`, `/` and `%` first
+fn evaluate(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    let mut pairs = pair.into_inner();
+    let mut product = operand(context, pairs.next().unwrap())?;
+    let mut sum: Option<(Value, char)> = None;
+
+    while let Some(operator) = pairs.next() {
+        let operator = operator.as_str().chars().next().unwrap();
+        let right = operand(context, pairs.next().unwrap())?;
+
+        if let '*' | '/' | '%' = operator {
+            product = apply(context, operator, product, right)?;
+        } else {
+            let left = match sum.take() {
+                Some((left, pending)) => {
+                    apply(context, pending, left, product)?
+                },
+                None => product,
+            };
+            sum = Some((left, operator));
+            product = right;
+        }
+    }
+
+    match sum {
+        Some((left, pending)) => apply(context, pending, left, product),
+        None => Ok(product),
+    }
+}
+
+fn operand(
+    context: &mut ParseContext,
+    pair: Pair<Rule>,
+) -> anyhow::Result<Value> {
+    match pair.as_rule() {
+        Rule::number => parse_number(pair),
+        Rule::string | Rule::raw_string => {
+            Ok(Value::String(parse_string_literal(context, pair)?.into()))
+        },
+        Rule::reference => context
+            .options
+            .constants
+            .get(pair.as_str())
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown constant {}", pair.as_str())),
+        Rule::group => evaluate(context, pair),
+        rule => unreachable!("Expected an operand, found {:?}", rule),
+    }
+}
+
+fn apply(
+    context: &ParseContext,
+    operator: char,
+    left: Value,
+    right: Value,
+) -> anyhow::Result<Value> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => {
+            match (left.as_i128(), right.as_i128()) {
+                (Some(left), Some(right)) => integer(operator, left, right),
+                _ => Ok(float(operator, left.as_f64(), right.as_f64())),
+            }
+            .map(Value::Number)
+        },
+        (Value::String(left), Value::String(right)) if operator == '+' => {
+            let string = format!("{}{}", left, right);
+            if let Some(max) = context.options.max_string_length {
+                if string.len() > max {
+                    return Err(anyhow!(
+                        "String exceeds the maximum length of {}",
+                        max
+                    ));
+                }
+            }
+            Ok(Value::String(string.into()))
+        },
+        (left, right) => Err(anyhow!(
+            "Cannot apply {} to {} and {}",
+            operator,
+            left.to_string_compact(),
+            right.to_string_compact()
+        )),
+    }
+}
+
+fn integer(operator: char, left: i128, right: i128) -> anyhow::Result<Number> {
+    if right == 0 && (operator == '/' || operator == '%') {
+        return Err(anyhow!("Division by zero"));
+    }
+
+    let result = match operator {
+        '+' => left.checked_add(right),
+        '-' => left.checked_sub(right),
+        '*' => left.checked_mul(right),
+        '/' => left.checked_div(right),
+        '%' => left.checked_rem(right),
+        _ => unreachable!("Unknown operator {}", operator),
+    }
+    .ok_or_else(|| anyhow!("Integer overflow"))?;
+
+    // The smallest variant, as if the result had been written out
+    Ok(i64::try_from(result)
+        .map(Number::from)
+        .or_else(|_| u64::try_from(result).map(Number::from))
+        .unwrap_or_else(|_| Number::from(result)))
+}
+
+fn float(operator: char, left: f64, right: f64) -> Number {
+    Number::from(match operator {
+        '+' => left + right,
+        '-' => left - right,
+        '*' => left * right,
+        '/' => left / right,
+        '%' => left % right,
+        _ => unreachable!("Unknown operator {}", operator),
+    })
+}
@@ -0,0 +1,117 @@
+//! Parsing large lists and maps on several threads
+//!
+//! The outermost list or map is cut into parts at the commas between its
+//! elements, and the parts are parsed by `descent` on the thread pool of
+//! rayon, each with a context of its own. Finding the commas takes a single
+//! pass over the input, which skips strings and comments without looking
+//! at them.
+//!
+//! Whatever a part does not accept is parsed again as a whole on a single
+//! thread, which reports errors as usual. The same happens for an alias to
+//! an anchor in another part, and for a duplicate key that has to be
+//! rejected.
+
+use super::{
+    descent::{self, Elements},
+    ParseContext,
+};
+use crate::value::{map::Map, Value};
+use rayon::prelude::*;
+use std::ops::Range;
+
+/// Inputs smaller than this are parsed faster on a single thread
+const MIN_INPUT_SIZE: usize = 1 << 20;
+
+/// Parses `input` on several threads if it is a large list or map
+///
+/// Returns None if the input has to be parsed on a single thread, as it is
+/// too small, something is collected while parsing or strings are shared.
+pub(super) fn parse(context: &ParseContext, input: &str) -> Option<Value> {
+    let options = context.options;
+    if input.len() < MIN_INPUT_SIZE
+        || options.dedup_strings
+        || context.comments.is_some()
+        || context.spans.is_some()
+        || context.borrowable.is_some()
+        || context.progress.is_some()
+    {
+        return None;
+    }
+
+    let threads = rayon::current_num_threads();
+    if threads < 2 {
+        return None;
+    }
+
+    let mut scan = ParseContext::new(options);
+    let (delimiters, commas) = descent::element_commas(&mut scan, input)?;
+    let map = input.as_bytes()[delimiters.start] == b'{';
+    let parts = parts(delimiters, &commas, threads);
+
+    let results = parts
+        .par_iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let last = index + 1 == parts.len();
+            let mut context = ParseContext::new(options);
+            descent::parse_elements(
+                &mut context,
+                input,
+                range.clone(),
+                map,
+                last,
+            )
+            .ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut items = Vec::new();
+    let mut entries = Map::new();
+    for elements in results {
+        match elements {
+            Elements::Items(part) => items.extend(part),
+            Elements::Entries(part) => {
+                for (key, value) in part {
+                    if options.reject_duplicate_keys
+                        && entries.contains_key(&key)
+                    {
+                        return None;
+                    }
+                    entries.insert(key, value);
+                }
+            },
+        }
+    }
+
+    Some(if map {
+        Value::Map(entries)
+    } else {
+        Value::List(items)
+    })
+}
+
+/// Cuts the inside of `delimiters` into about `count` parts of similar
+/// length, at some of the `commas`
+fn parts(
+    delimiters: Range<usize>,
+    commas: &[usize],
+    count: usize,
+) -> Vec<Range<usize>> {
+    let length = delimiters.end - delimiters.start;
+
+    let mut parts = Vec::with_capacity(count);
+    let mut start = delimiters.start + 1;
+    for part in 1..count {
+        let target = delimiters.start + length * part / count;
+        let index = commas.partition_point(|comma| *comma < target);
+        match commas.get(index) {
+            Some(&comma) if comma >= start => {
+                parts.push(start..comma);
+                start = comma + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(start..delimiters.end);
+    parts
+}
@@ -0,0 +1,323 @@
+//! Edits of values written as documents, see `ValuePatch`
+
+use crate::{
+    error::Error,
+    value::{
+        diff::Change,
+        map::OrderedMap,
+        path::{self, Path, PathSegment},
+        types::{Identifier, TypeIdentifier},
+        Value,
+    },
+};
+use anyhow::anyhow;
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+};
+
+/// A single edit of a `ValuePatch`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PatchOperation {
+    /// Sets a struct field or map entry, or inserts a list item before
+    /// the one at the index
+    Add { path: Path, value: Value },
+    /// Removes a struct field, map entry or list item, which has to exist
+    Remove { path: Path },
+    /// Replaces a value, which has to exist
+    Replace { path: Path, value: Value },
+}
+
+/// An operation of a patch that could not be applied
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PatchError {
+    /// Position of the operation in the patch
+    pub operation: usize,
+    pub path: Path,
+    pub message: String,
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Operation {} at {}: {}",
+            self.operation, self.path, self.message
+        )
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// A list of edits, similar to JSON Patch
+///
+/// The text form is a list of operations with paths in the syntax of
+/// `Path`:
+///
+/// ```text
+/// [
+///     Add(path: "server.host", value: "localhost"),
+///     Replace(path: "server.port", value: 8080),
+///     Remove(path: "plugins[0]"),
+/// ]
+/// ```
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct ValuePatch {
+    pub operations: Vec<PatchOperation>,
+}
+
+impl ValuePatch {
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        ValuePatch::from_value(&Value::parse(input)?)
+    }
+
+    /// Reads the operations of a patch document
+    ///
+    /// The path of an `Error::Invalid` leads into the document.
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let items = match value {
+            Value::List(items) => items,
+            other => return Err(other.mismatch("list")),
+        };
+
+        let operations = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                operation_from_value(item).map_err(|(field, message)| {
+                    let path = Path::root().index(index);
+                    Error::Invalid {
+                        path: field.map_or(path.clone(), |f| path.field(f)),
+                        message,
+                    }
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ValuePatch { operations })
+    }
+
+    /// The patch document, as read by `from_value`
+    pub fn to_value(&self) -> Value {
+        Value::List(self.operations.iter().map(operation_to_value).collect())
+    }
+
+    /// The patch turning `old` into `new`, see `Value::diff_as_patch`
+    pub(crate) fn from_changes(changes: &[Change]) -> Self {
+        let mut operations = Vec::with_capacity(changes.len());
+        // Items removed from the end of a list, removed last to first
+        let mut removed_items: Vec<PatchOperation> = Vec::new();
+
+        for change in changes {
+            let operation = match change {
+                Change::Added { path, value } => PatchOperation::Add {
+                    path: path.clone(),
+                    value: value.clone(),
+                },
+                Change::Removed { path, .. } => {
+                    PatchOperation::Remove { path: path.clone() }
+                },
+                Change::Changed { path, new, .. } => PatchOperation::Replace {
+                    path: path.clone(),
+                    value: new.clone(),
+                },
+            };
+
+            match &operation {
+                PatchOperation::Remove { path }
+                    if matches!(
+                        path.segments.last(),
+                        Some(PathSegment::Index(_))
+                    ) =>
+                {
+                    let same_list = removed_items.last().is_none_or(|last| {
+                        parent(operation_path(last)) == parent(path)
+                    });
+                    if !same_list {
+                        operations.extend(removed_items.drain(..).rev());
+                    }
+                    removed_items.push(operation);
+                },
+                _ => {
+                    operations.extend(removed_items.drain(..).rev());
+                    operations.push(operation);
+                },
+            }
+        }
+        operations.extend(removed_items.drain(..).rev());
+
+        ValuePatch { operations }
+    }
+
+    /// Applies the operations in order, see `Value::apply_patch`
+    pub(crate) fn apply_to(&self, value: &mut Value) -> Result<(), PatchError> {
+        for (index, operation) in self.operations.iter().enumerate() {
+            apply(value, operation).map_err(|error| PatchError {
+                operation: index,
+                path: operation_path(operation).clone(),
+                message: format!("{:#}", error),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ValuePatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.to_value(), f)
+    }
+}
+
+fn operation_path(operation: &PatchOperation) -> &Path {
+    match operation {
+        PatchOperation::Add { path, .. }
+        | PatchOperation::Remove { path }
+        | PatchOperation::Replace { path, .. } => path,
+    }
+}
+
+fn parent(path: &Path) -> &[PathSegment] {
+    &path.segments[..path.segments.len().saturating_sub(1)]
+}
+
+/// Reads a single operation, errors name the field that is wrong, if any
+fn operation_from_value(
+    value: &Value,
+) -> Result<PatchOperation, (Option<&'static str>, String)> {
+    static EMPTY: OrderedMap<Identifier, Value> = OrderedMap::new();
+
+    let (identifier, fields) = match value {
+        Value::Struct(identifier, fields) => (identifier, fields),
+        Value::UnitStruct(identifier) => (identifier, &EMPTY),
+        other => {
+            return Err((
+                None,
+                format!(
+                    "Expected an operation, found {}",
+                    other.variant_name()
+                ),
+            ))
+        },
+    };
+
+    let path = match fields.get(&"path".into()) {
+        Some(Value::String(path)) => {
+            Path::parse(path).map_err(|error| (Some("path"), error.message))?
+        },
+        Some(other) => {
+            return Err((
+                Some("path"),
+                format!(
+                    "Expected the path as a string, found {}",
+                    other.variant_name()
+                ),
+            ))
+        },
+        None => return Err((None, "Missing path".to_string())),
+    };
+    let value = || {
+        fields
+            .get(&"value".into())
+            .cloned()
+            .ok_or_else(|| (None, "Missing value".to_string()))
+    };
+
+    let name = identifier
+        .segments
+        .last()
+        .map(|last| last.identifier.0.as_str());
+    Ok(match name {
+        Some("Add") => PatchOperation::Add {
+            path,
+            value: value()?,
+        },
+        Some("Remove") => PatchOperation::Remove { path },
+        Some("Replace") => PatchOperation::Replace {
+            path,
+            value: value()?,
+        },
+        _ => return Err((None, format!("Unknown operation {}", identifier))),
+    })
+}
+
+fn operation_to_value(operation: &PatchOperation) -> Value {
+    let (name, path, value) = match operation {
+        PatchOperation::Add { path, value } => ("Add", path, Some(value)),
+        PatchOperation::Remove { path } => ("Remove", path, None),
+        PatchOperation::Replace { path, value } => {
+            ("Replace", path, Some(value))
+        },
+    };
+
+    let mut fields = OrderedMap::new();
+    fields.insert("path".into(), Value::String(path.to_string().into()));
+    if let Some(value) = value {
+        fields.insert("value".into(), value.clone());
+    }
+    Value::Struct(TypeIdentifier::from(name), fields)
+}
+
+fn apply(root: &mut Value, operation: &PatchOperation) -> anyhow::Result<()> {
+    let path = operation_path(operation);
+    let (last, parent_path) = match path.segments.split_last() {
+        Some((last, parent)) => (last, parent),
+        None => {
+            return match operation {
+                PatchOperation::Add { value, .. }
+                | PatchOperation::Replace { value, .. } => {
+                    *root = value.clone();
+                    Ok(())
+                },
+                PatchOperation::Remove { .. } => {
+                    Err(anyhow!("The document itself can not be removed"))
+                },
+            };
+        },
+    };
+
+    let parent = parent_path
+        .iter()
+        .try_fold(root, |value, segment| path::child_mut(value, segment))
+        .ok_or_else(|| anyhow!("Parent does not exist"))?;
+
+    match operation {
+        PatchOperation::Add { value, .. } => add(parent, last, value.clone()),
+        PatchOperation::Remove { .. } => parent
+            .remove(last)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("Value does not exist")),
+        PatchOperation::Replace { value, .. } => {
+            let target = path::child_mut(parent, last)
+                .ok_or_else(|| anyhow!("Value does not exist"))?;
+            *target = value.clone();
+            Ok(())
+        },
+    }
+}
+
+fn add(
+    parent: &mut Value,
+    segment: &PathSegment,
+    value: Value,
+) -> anyhow::Result<()> {
+    match (parent, segment) {
+        (Value::Option(Some(inner)), segment) => add(inner, segment, value),
+        (Value::List(items), PathSegment::Index(index))
+        | (Value::Tuple(items), PathSegment::Index(index))
+        | (Value::TupleStruct(_, items), PathSegment::Index(index)) => {
+            if *index > items.len() {
+                return Err(anyhow!(
+                    "Index {} is past the end of {} items",
+                    index,
+                    items.len()
+                ));
+            }
+            items.insert(*index, value);
+            Ok(())
+        },
+        (parent, segment) => {
+            parent.insert(segment, value)?;
+            Ok(())
+        },
+    }
+}
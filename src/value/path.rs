@@ -0,0 +1,516 @@
+use crate::value::{
+    diagnostic::ParseError, map::Map, types::Identifier, Value,
+};
+use std::{
+    fmt,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// A single step from a value to one of its children
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum PathSegment {
+    /// A struct field, or a map entry with a string key
+    Field(Identifier),
+    /// An item of a list, tuple or tuple struct
+    Index(usize),
+    /// A map entry
+    Key(Value),
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(field) if field.is_valid() => {
+                write!(f, ".{}", field)
+            },
+            PathSegment::Field(field) => {
+                let quoted = Value::String(field.0.as_str().into());
+                write!(f, ".{}", quoted.to_string_compact())
+            },
+            PathSegment::Index(index) => write!(f, "[{}]", index),
+            PathSegment::Key(key) => {
+                write!(f, "{{{}}}", key.to_string_compact())
+            },
+        }
+    }
+}
+
+/// Location of a value inside of a document
+///
+/// The text form is a dotted path like `server.ports[0]`,
+/// with map keys in braces: `users{"alice"}.name`
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Default, Debug)]
+pub struct Path {
+    pub segments: Vec<PathSegment>,
+}
+
+impl Path {
+    /// The path of the document itself
+    pub fn root() -> Self {
+        Path::default()
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse_path(input)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn field<I>(mut self, field: I) -> Self
+    where
+        I: Into<Identifier>,
+    {
+        self.segments.push(PathSegment::Field(field.into()));
+        self
+    }
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        self
+    }
+    pub fn key(mut self, key: Value) -> Self {
+        self.segments.push(PathSegment::Key(key));
+        self
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                // The leading dot is left out
+                PathSegment::Field(field) if index == 0 && field.is_valid() => {
+                    write!(f, "{}", field)?
+                },
+                segment => write!(f, "{}", segment)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Path::parse(s)
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Path { segments }
+    }
+}
+
+/// Anything that selects a child of a value, see `Value::get`
+///
+/// Numbers select items of lists and tuples, strings select struct
+/// fields or entries with string keys, a `Value` selects a map entry.
+pub trait ValueIndex {
+    fn segment(&self) -> PathSegment;
+}
+
+impl ValueIndex for usize {
+    fn segment(&self) -> PathSegment {
+        PathSegment::Index(*self)
+    }
+}
+
+impl ValueIndex for str {
+    fn segment(&self) -> PathSegment {
+        PathSegment::Field(self.into())
+    }
+}
+
+impl ValueIndex for String {
+    fn segment(&self) -> PathSegment {
+        PathSegment::Field(self.as_str().into())
+    }
+}
+
+impl ValueIndex for Identifier {
+    fn segment(&self) -> PathSegment {
+        PathSegment::Field(self.clone())
+    }
+}
+
+impl ValueIndex for Value {
+    fn segment(&self) -> PathSegment {
+        PathSegment::Key(self.clone())
+    }
+}
+
+impl ValueIndex for PathSegment {
+    fn segment(&self) -> PathSegment {
+        self.clone()
+    }
+}
+
+impl<T> ValueIndex for &T
+where
+    T: ValueIndex + ?Sized,
+{
+    fn segment(&self) -> PathSegment {
+        (**self).segment()
+    }
+}
+
+/// The child of `value` selected by `segment`, options are looked through
+pub(crate) fn child<'lt>(
+    value: &'lt Value,
+    segment: &PathSegment,
+) -> Option<&'lt Value> {
+    match (value, segment) {
+        (Value::Option(Some(inner)), segment) => child(inner, segment),
+        (Value::List(items), PathSegment::Index(index))
+        | (Value::Tuple(items), PathSegment::Index(index))
+        | (Value::TupleStruct(_, items), PathSegment::Index(index)) => {
+            items.get(*index)
+        },
+        (Value::Struct(_, fields), PathSegment::Field(field)) => {
+            fields.get(field)
+        },
+        (Value::Struct(_, fields), PathSegment::Key(Value::String(key))) => {
+            fields.get(&Identifier::from(key.as_ref()))
+        },
+        (Value::Map(map), PathSegment::Key(key)) => map.get(key),
+        (Value::Map(map), segment) => map.get(&map_key(map, segment)),
+        _ => None,
+    }
+}
+
+/// Like `child`, but mutable
+pub(crate) fn child_mut<'lt>(
+    value: &'lt mut Value,
+    segment: &PathSegment,
+) -> Option<&'lt mut Value> {
+    match (value, segment) {
+        (Value::Option(Some(inner)), segment) => child_mut(inner, segment),
+        (Value::List(items), PathSegment::Index(index))
+        | (Value::Tuple(items), PathSegment::Index(index))
+        | (Value::TupleStruct(_, items), PathSegment::Index(index)) => {
+            items.get_mut(*index)
+        },
+        (Value::Struct(_, fields), PathSegment::Field(field)) => {
+            fields.get_mut(field)
+        },
+        (Value::Struct(_, fields), PathSegment::Key(Value::String(key))) => {
+            fields.get_mut(&Identifier::from(key.as_ref()))
+        },
+        (Value::Map(map), segment) => {
+            let key = map_key(map, segment);
+            map.get_mut(&key)
+        },
+        _ => None,
+    }
+}
+
+/// The key of the map entry selected by `segment`
+///
+/// Field names select string keys, or keys written as identifiers like
+/// `host` in `{ host: "localhost" }` if `map` has no such string.
+pub(crate) fn map_key(map: &Map<Value, Value>, segment: &PathSegment) -> Value {
+    match segment {
+        PathSegment::Field(field) => {
+            let key = Value::String(field.0.as_str().into());
+            let bare = Value::UnitStruct(field.0.as_str().into());
+            if !map.contains_key(&key) && map.contains_key(&bare) {
+                bare
+            } else {
+                key
+            }
+        },
+        PathSegment::Index(index) => Value::Number((*index as u64).into()),
+        PathSegment::Key(key) => key.clone(),
+    }
+}
+
+/// The struct field selected by `segment`, if it can name one
+pub(crate) fn field_name(segment: &PathSegment) -> Option<Identifier> {
+    match segment {
+        PathSegment::Field(field) => Some(field.clone()),
+        PathSegment::Key(Value::String(key)) => Some(key.as_ref().into()),
+        _ => None,
+    }
+}
+
+/// Escapes and the end of a string literal, fed one character at a time
+#[derive(Default)]
+struct StringState {
+    in_string: bool,
+    escaped: bool,
+}
+
+impl StringState {
+    /// Whether `c` is part of a string literal, including its quotes
+    fn step(&mut self, c: char) -> bool {
+        if self.in_string {
+            match c {
+                _ if self.escaped => self.escaped = false,
+                '\\' => self.escaped = true,
+                '"' => self.in_string = false,
+                _ => {},
+            }
+            true
+        } else {
+            self.in_string = c == '"';
+            self.in_string
+        }
+    }
+}
+
+/// Splits off a bracketed part, respecting nesting and string literals
+///
+/// None if it is not closed.
+fn split_delimited(
+    input: &str,
+    open: char,
+    close: char,
+) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut string = StringState::default();
+
+    for (index, c) in input.char_indices() {
+        if string.step(c) {
+            continue;
+        }
+
+        match c {
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&input[1..index], &input[index + 1..]));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    None
+}
+
+fn parse_path(input: &str) -> Result<Path, ParseError> {
+    // Errors are located at the start of `rest`, or within a part of it
+    let error = |rest: &str, message: String| {
+        ParseError::new(input, input.len() - rest.len(), message)
+    };
+    let inner = |rest: &str, start: usize, inner: ParseError| {
+        let offset = input.len() - rest.len() + start + inner.offset;
+        ParseError::new(input, offset, inner.message)
+    };
+    let delimited = |rest, open, close| {
+        split_delimited(rest, open, close)
+            .ok_or_else(|| error(rest, format!("Missing {:?} in path", close)))
+    };
+
+    let mut path = Path::root();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if rest.starts_with('[') {
+            let (index, tail) = delimited(rest, '[', ']')?;
+            let index = index
+                .trim()
+                .parse()
+                .map_err(|e| error(rest, format!("Invalid index: {}", e)))?;
+            path.push(PathSegment::Index(index));
+            rest = tail;
+        } else if rest.starts_with('{') {
+            let (key, tail) = delimited(rest, '{', '}')?;
+            let key = Value::parse(key).map_err(|e| inner(rest, 1, e))?;
+            path.push(PathSegment::Key(key));
+            rest = tail;
+        } else {
+            if rest.starts_with('.') {
+                rest = &rest[1..];
+            } else if !path.is_root() {
+                let message = format!("Expected '.' in path at {:?}", rest);
+                return Err(error(rest, message));
+            }
+
+            if rest.starts_with('"') {
+                let mut string = StringState::default();
+                string.step('"');
+                let end = rest[1..]
+                    .char_indices()
+                    .find(|&(_, c)| string.step(c) && !string.in_string)
+                    .map(|(end, _)| end + 2)
+                    .ok_or_else(|| {
+                        error(rest, "Unterminated field in path".to_string())
+                    })?;
+                match Value::parse(&rest[..end])
+                    .map_err(|e| inner(rest, 0, e))?
+                {
+                    Value::String(field) => {
+                        path.push(PathSegment::Field(field.as_ref().into()))
+                    },
+                    other => {
+                        let message = format!("Invalid field {:?}", other);
+                        return Err(error(rest, message));
+                    },
+                }
+                rest = &rest[end..];
+            } else {
+                let end = rest.find(&['.', '[', '{'][..]).unwrap_or(rest.len());
+                if end == 0 {
+                    let message = format!("Empty field in path at {:?}", rest);
+                    return Err(error(rest, message));
+                }
+                path.push(PathSegment::Field(rest[..end].into()));
+                rest = &rest[end..];
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+/// Keeps only the children of `value` that lie on one of the paths
+///
+/// Returns None if a path leads into a value that has no children
+pub(crate) fn project(
+    value: &Value,
+    paths: &[&[PathSegment]],
+) -> Option<Value> {
+    if paths.iter().any(|path| path.is_empty()) {
+        return Some(value.clone());
+    }
+
+    /// Tails of all paths whose first segment matches
+    fn tails<'lt, F>(
+        paths: &[&'lt [PathSegment]],
+        mut matches: F,
+    ) -> Vec<&'lt [PathSegment]>
+    where
+        F: FnMut(&PathSegment) -> bool,
+    {
+        paths
+            .iter()
+            .filter(|path| matches(&path[0]))
+            .map(|path| &path[1..])
+            .collect()
+    }
+
+    fn project_items(items: &[Value], paths: &[&[PathSegment]]) -> Vec<Value> {
+        items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let tails = tails(paths, |s| *s == PathSegment::Index(index));
+                if tails.is_empty() {
+                    None
+                } else {
+                    project(item, &tails)
+                }
+            })
+            .collect()
+    }
+
+    Some(match value {
+        Value::Option(Some(inner)) => {
+            Value::Option(Some(Box::new(project(inner, paths)?)))
+        },
+        Value::List(items) => Value::List(project_items(items, paths)),
+        Value::Tuple(items) => Value::Tuple(project_items(items, paths)),
+        Value::TupleStruct(identifier, items) => {
+            Value::TupleStruct(identifier.clone(), project_items(items, paths))
+        },
+        Value::Struct(identifier, fields) => Value::Struct(
+            identifier.clone(),
+            fields
+                .iter()
+                .filter_map(|(name, field)| {
+                    let tails = tails(paths, |s| match s {
+                        PathSegment::Field(f) => f == name,
+                        _ => false,
+                    });
+                    if tails.is_empty() {
+                        None
+                    } else {
+                        Some((name.clone(), project(field, &tails)?))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Map(map) => Value::Map(
+            map.iter()
+                .filter_map(|(key, entry)| {
+                    let tails = tails(paths, |s| match (s, key) {
+                        (PathSegment::Key(k), key) => k == key,
+                        (PathSegment::Field(f), Value::String(key)) => {
+                            f.0 == key.as_ref()
+                        },
+                        _ => false,
+                    });
+                    if tails.is_empty() {
+                        None
+                    } else {
+                        Some((key.clone(), project(entry, &tails)?))
+                    }
+                })
+                .collect(),
+        ),
+        _ => return None,
+    })
+}
+
+/// Collects the paths of all values below `value` matching `predicate`
+///
+/// Values are visited parent first, options are looked through
+pub(crate) fn find_all<F>(
+    value: &Value,
+    path: &mut Path,
+    predicate: &mut F,
+    found: &mut Vec<Path>,
+) where
+    F: FnMut(&Value) -> bool,
+{
+    if predicate(value) {
+        found.push(path.clone());
+    }
+    if let Value::Option(Some(inner)) = value {
+        return find_all(inner, path, predicate, found);
+    }
+
+    let mut child = |segment, value| {
+        path.push(segment);
+        find_all(value, path, predicate, found);
+        path.pop();
+    };
+
+    match value {
+        Value::List(items)
+        | Value::Tuple(items)
+        | Value::TupleStruct(_, items) => {
+            for (index, item) in items.iter().enumerate() {
+                child(PathSegment::Index(index), item);
+            }
+        },
+        Value::Struct(_, fields) => {
+            for (name, field) in fields {
+                child(PathSegment::Field(name.clone()), field);
+            }
+        },
+        Value::Map(map) => {
+            for (key, entry) in map {
+                child(PathSegment::Key(key.clone()), entry);
+            }
+        },
+        _ => {},
+    }
+}
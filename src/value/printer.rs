@@ -1,11 +1,88 @@
-use crate::value::Value;
-use std::{fmt, fmt::Write, str::Chars};
+use crate::{
+    literals::Literals,
+    value::{
+        anchors::{Anchors, Mark},
+        comments::Comments,
+        map::{self, OrderedIter},
+        options::UnitVariantStyle,
+        path::{Path, PathSegment},
+        types::{Type, TypeIdentifier},
+        Identifier, Value,
+    },
+};
+use std::{fmt, fmt::Write, io, iter::once, slice, str::Chars};
 
+/// Line width the auto and fit modes try to stay within
+const DEFAULT_LINE_WIDTH: usize = 80;
+/// Deepest nesting of collections the auto mode still puts on a single line
+const AUTO_MAX_INLINE_DEPTH: usize = 2;
+/// Strings longer than this force the auto mode to break a collection up
+const AUTO_MAX_INLINE_STRING: usize = 32;
+
+/// How a printer lays out collections
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PrintMode {
+    /// One item per line, with indentation
+    Pretty,
+    /// Everything on a single line, without any whitespace
+    Compact,
+    /// Everything on a single line, with spaces after separators
+    Inline,
+    /// Chooses between pretty and inline for every subtree,
+    /// based on its size, depth and string lengths
+    Auto,
+    /// One leaf per line, for documents kept under version control
+    ///
+    /// Like pretty, but map keys and options or newtypes around a single
+    /// scalar stay on one line. The layout of an item never depends on
+    /// its siblings, so changing a leaf changes exactly one line.
+    Diff,
+    /// Writes a collection on a single line if it fits into the rest of
+    /// the line, and one item per line otherwise, similar to rustfmt
+    ///
+    /// Unlike auto, only the width counts: it includes the indentation,
+    /// keys and field names before a value and the comma after it.
+    Fit,
+}
+
+/// Writes values as text
+///
+/// The constructors select a layout, which the builder methods refine:
+///
+/// ```
+/// # use typed_format::value::{printer::ValuePrinter, Value};
+/// let value = Value::parse("Config(name: \"web\", ports: [80, 443])").unwrap();
+///
+/// let mut text = String::new();
+/// ValuePrinter::pretty()
+///     .indentation("  ")
+///     .max_inline_width(Some(20))
+///     .trailing_commas(false)
+///     .write(&value, &mut text)
+///     .unwrap();
+/// assert_eq!(text, "Config(\n  name: \"web\",\n  ports: [80, 443]\n)");
+/// ```
 #[derive(Copy, Clone)]
 pub struct ValuePrinter<'indent> {
     indentation_level: usize,
     indentation: &'indent str,
-    pretty: bool,
+    newline: &'indent str,
+    mode: PrintMode,
+    omit_names: bool,
+    unit_variants: UnitVariantStyle,
+    implicit_some: bool,
+    trailing_commas: bool,
+    max_inline_width: Option<usize>,
+    inline_depth: Option<usize>,
+    inline_wrapped_scalars: bool,
+    field_colon: Option<&'indent str>,
+    map_colon: Option<&'indent str>,
+    comments: Option<&'indent Comments>,
+    literals: Option<&'indent Literals>,
+    anchors: Option<&'indent Anchors<'indent>>,
+    /// Width taken on the current line by text around the next value,
+    /// besides the indentation
+    reserved: usize,
 }
 
 impl Default for ValuePrinter<'static> {
@@ -16,10 +93,34 @@ impl Default for ValuePrinter<'static> {
 
 impl<'indent> ValuePrinter<'indent> {
     pub fn new(indentation: &'indent str, pretty: bool) -> Self {
+        ValuePrinter::with_mode(
+            indentation,
+            if pretty {
+                PrintMode::Pretty
+            } else {
+                PrintMode::Compact
+            },
+        )
+    }
+    pub fn with_mode(indentation: &'indent str, mode: PrintMode) -> Self {
         ValuePrinter {
             indentation_level: 0,
             indentation,
-            pretty,
+            newline: "\n",
+            mode,
+            omit_names: false,
+            unit_variants: UnitVariantStyle::Path,
+            implicit_some: false,
+            trailing_commas: true,
+            max_inline_width: None,
+            inline_depth: None,
+            inline_wrapped_scalars: false,
+            field_colon: None,
+            map_colon: None,
+            comments: None,
+            literals: Some(Literals::common_ref()),
+            anchors: None,
+            reserved: 0,
         }
     }
 
@@ -29,6 +130,187 @@ impl<'indent> ValuePrinter<'indent> {
     pub fn compact() -> Self {
         ValuePrinter::new("", false)
     }
+    pub fn inline() -> Self {
+        ValuePrinter::with_mode("", PrintMode::Inline)
+    }
+    pub fn auto() -> Self {
+        ValuePrinter::with_mode("    ", PrintMode::Auto)
+    }
+    pub fn diff() -> Self {
+        ValuePrinter::with_mode("    ", PrintMode::Diff)
+    }
+    pub fn fit() -> Self {
+        ValuePrinter::with_mode("    ", PrintMode::Fit)
+    }
+
+    /// Leaves out the names of structs and enums
+    ///
+    /// Enum variants keep their own name, since it selects the variant.
+    /// Such documents can still be deserialized into the original types,
+    /// which provide the missing names. `Value::retag` restores them.
+    /// Newtype structs may even leave out their parentheses when written
+    /// by hand, `(5)` and `5` both deserialize into a `Meters(u32)`.
+    pub fn omit_names(mut self, omit_names: bool) -> Self {
+        self.omit_names = omit_names;
+        self
+    }
+
+    /// Writes unit variants like `Test::Baz` in another style
+    ///
+    /// The path of the enum can only be left out, the printer doesn't know
+    /// it for variants written without one.
+    pub fn unit_variants(mut self, style: UnitVariantStyle) -> Self {
+        self.unit_variants = style;
+        self
+    }
+
+    /// Writes `Some(value)` as just `value`, for documents read with
+    /// `DeserializeOptions::implicit_some`
+    ///
+    /// Options directly inside options keep their `Some`, since
+    /// `Some(None)` would read back as `None` otherwise.
+    pub fn implicit_some(mut self, implicit_some: bool) -> Self {
+        self.implicit_some = implicit_some;
+        self
+    }
+
+    /// Indents each level of multi line layouts with `indentation`
+    pub fn indentation(mut self, indentation: &'indent str) -> Self {
+        self.indentation = indentation;
+        self
+    }
+
+    /// Ends lines of multi line layouts with `newline`, like `"\r\n"`
+    pub fn newline(mut self, newline: &'indent str) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    /// Writes a comma after the last item of a collection,
+    /// which the inline mode never does
+    pub fn trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    /// Writes collections of the pretty mode on a single line if that
+    /// line, without the indentation, is at most `width` long
+    ///
+    /// The auto and fit modes use it as the line width they try to stay
+    /// within.
+    pub fn max_inline_width(mut self, width: Option<usize>) -> Self {
+        self.max_inline_width = width;
+        self
+    }
+
+    /// Writes everything nested at least `depth` levels deep on a single
+    /// line, in multi line layouts
+    ///
+    /// Depth 1 keeps the items of the outermost collection on their own
+    /// lines, but writes each of them on a single line.
+    pub fn inline_depth(mut self, depth: Option<usize>) -> Self {
+        self.inline_depth = depth;
+        self
+    }
+
+    /// Writes options, newtypes and one element tuples around a single
+    /// scalar on one line in the pretty mode, `Some(5)` instead of breaking
+    /// it over three lines
+    ///
+    /// The diff mode always does, except for tuples. `TextSerializer` can't
+    /// tell a scalar before writing it and keeps breaking them.
+    pub fn inline_wrapped_scalars(mut self, inline: bool) -> Self {
+        self.inline_wrapped_scalars = inline;
+        self
+    }
+
+    /// Writes `colon` between struct fields and their values,
+    /// instead of `:` followed by a space if the layout has spaces
+    pub fn field_colon(mut self, colon: &'indent str) -> Self {
+        self.field_colon = Some(colon);
+        self
+    }
+
+    /// Writes `colon` between map keys and their values,
+    /// instead of `:` followed by a space if the layout has spaces
+    pub fn map_colon(mut self, colon: &'indent str) -> Self {
+        self.map_colon = Some(colon);
+        self
+    }
+
+    /// Writes comments in front of the values they are attached to
+    ///
+    /// Multi line layouts use line comments, the others block comments
+    pub fn with_comments(mut self, comments: &'indent Comments) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Writes values claimed by a custom literal syntax as `prefix"..."`
+    pub fn with_literals(mut self, literals: &'indent Literals) -> Self {
+        self.literals = Some(literals);
+        self
+    }
+
+    /// Writes repeated collections once, with an anchor, and the other
+    /// occurrences as aliases of it
+    ///
+    /// The anchors have to be detected in the value that is written:
+    ///
+    /// ```
+    /// # use typed_format::value::{anchors::Anchors, printer::ValuePrinter, Value};
+    /// let value = Value::parse("(a: [1, 2], b: [1, 2])").unwrap();
+    /// let anchors = Anchors::detect(&value);
+    ///
+    /// let mut text = String::new();
+    /// ValuePrinter::inline()
+    ///     .with_anchors(&anchors)
+    ///     .write(&value, &mut text)
+    ///     .unwrap();
+    /// assert_eq!(text, "(a: &a [1, 2], b: *a)");
+    /// ```
+    pub fn with_anchors(mut self, anchors: &'indent Anchors<'indent>) -> Self {
+        self.anchors = Some(anchors);
+        self
+    }
+
+    /// Starts at an indentation level, for values nested in other output
+    pub(crate) fn at_level(mut self, indentation_level: usize) -> Self {
+        self.indentation_level = indentation_level;
+        self
+    }
+
+    /// Whether collections are broken up into one item per line
+    fn multiline(self) -> bool {
+        matches!(
+            self.mode,
+            PrintMode::Pretty
+                | PrintMode::Auto
+                | PrintMode::Diff
+                | PrintMode::Fit
+        )
+    }
+
+    fn as_inline(self) -> Self {
+        ValuePrinter {
+            mode: PrintMode::Inline,
+            ..self
+        }
+    }
+
+    /// Whether separators are followed by a space
+    fn spaced(self) -> bool {
+        self.mode != PrintMode::Compact
+    }
+
+    /// The separator between keys and values, unless overridden
+    fn colon(self, custom: Option<&'indent str>) -> &'indent str {
+        match custom {
+            Some(colon) => colon,
+            None if self.spaced() => ": ",
+            None => ":",
+        }
+    }
 
     fn indent(self) -> Self {
         let mut new = self;
@@ -36,99 +318,250 @@ impl<'indent> ValuePrinter<'indent> {
         new
     }
 
+    /// Reserves `width` more on the current line for the next value
+    fn reserve(mut self, width: usize) -> Self {
+        self.reserved += width;
+        self
+    }
+
+    /// Width left on the current line for the next value
+    fn remaining_width(self) -> usize {
+        self.max_inline_width
+            .unwrap_or(DEFAULT_LINE_WIDTH)
+            .saturating_sub(self.indentation_level * self.indentation.len())
+            .saturating_sub(self.reserved)
+    }
+
     pub fn write<W>(self, value: &Value, w: &mut W) -> fmt::Result
     where
         W: Write,
     {
+        let mut path = Path::root();
+
+        self.write_comments(&path, w)?;
+        self.write_value(value, &mut path, w)
+    }
+
+    /// Like `write`, but to a byte stream such as a file or socket
+    ///
+    /// The text is passed on as it is written, without buffering all of it.
+    pub fn write_io<W>(self, value: &Value, w: &mut W) -> io::Result<()>
+    where
+        W: io::Write + ?Sized,
+    {
+        let mut adapter = IoAdapter {
+            inner: w,
+            error: None,
+        };
+        self.write(value, &mut adapter).map_err(|fmt::Error| {
+            adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("Formatting failed"))
+        })
+    }
+
+    /// Writes a value with an explicit stack of steps instead of
+    /// recursion, so deeply nested values can't overflow the call stack
+    fn write_value<W>(
+        self,
+        value: &Value,
+        path: &mut Path,
+        w: &mut W,
+    ) -> fmt::Result
+    where
+        W: Write,
+    {
+        let mut steps = vec![Step::Value(self, value)];
+        while let Some(step) = steps.pop() {
+            let result = match step {
+                Step::Value(printer, value) => {
+                    printer.write_start(value, path, &mut steps, w)
+                },
+                Step::Str(s) => w.write_str(s),
+                Step::Newline(printer) => printer.write_newline(w),
+                Step::Indent(printer) => printer.write_indent(w),
+                Step::Items(items) => items.write_next(path, &mut steps, w),
+                Step::Leave => {
+                    path.pop();
+                    Ok(())
+                },
+            };
+
+            if result.is_err() {
+                // Failing to fit a width is expected, and the path is
+                // used afterwards
+                for step in steps {
+                    if let Step::Leave = step {
+                        path.pop();
+                    }
+                }
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the start of a value and pushes the steps writing the rest
+    fn write_start<'value, W>(
+        self,
+        value: &'value Value,
+        path: &mut Path,
+        steps: &mut Vec<Step<'indent, 'value>>,
+        w: &mut W,
+    ) -> fmt::Result
+    where
+        W: Write,
+    {
+        let mark = self.anchors.and_then(|anchors| anchors.get(value));
+        if let Some(Mark::Alias(name)) = mark {
+            return write!(w, "*{}", name);
+        }
+
+        if let Some((prefix, contents)) =
+            self.literals.and_then(|literals| literals.print(value))
+        {
+            return write!(w, "{}\"{}\"", prefix, escape_string(&contents));
+        }
+
+        let nested_deep = self
+            .inline_depth
+            .is_some_and(|depth| self.indentation_level >= depth);
+        let inline = match self.mode {
+            _ if self.multiline() && nested_deep => true,
+            PrintMode::Auto => self.fits_inline(value, path),
+            PrintMode::Diff => is_wrapped_scalar(value, false),
+            PrintMode::Fit => {
+                self.fits_width(value, path, self.remaining_width())
+            },
+            PrintMode::Pretty => {
+                (self.inline_wrapped_scalars && is_wrapped_scalar(value, true))
+                    || self.max_inline_width.is_some_and(|width| {
+                        self.fits_width(value, path, width)
+                    })
+            },
+            _ => false,
+        };
+        if inline {
+            steps.push(Step::Value(self.as_inline(), value));
+            return Ok(());
+        }
+
+        if let Some(Mark::Anchor(name)) = mark {
+            write!(w, "&{} ", name)?;
+        }
+
         match value {
             Value::Unit => write!(w, "()"),
             Value::Bool(b) => write!(w, "{}", b),
             Value::Char(c) => write!(w, "'{}'", escape_char(*c)),
-            Value::String(s) => write!(w, "\"{}\"", escape_string(&s)),
+            Value::String(s) => w.write_str(&quote_string(s)),
+            Value::Bytes(b) => write!(w, "b\"{}\"", escape_bytes(b)),
             Value::Number(v) => write!(w, "{}", v),
-            Value::Type(v) => write!(w, "{}", v),
+            // Other types would read back as tuples or unit structs
+            Value::Type(v @ Type::Array { .. }) => write!(w, "{}", v),
+            Value::Type(v) => write!(w, "<{}>", v),
+            Value::UnitStruct(v) => match self.unit_name(v) {
+                name if name.is_empty() => write!(w, "()"),
+                name => write!(w, "{}", name),
+            },
             Value::List(list) => {
                 write!(w, "[")?;
-                self.write_items_list(w, &list)?;
-                write!(w, "]")
+                self.push_items(Entries::Values(list.iter()), "]", steps, w)
             },
             Value::Tuple(tuple) => {
                 write!(w, "(")?;
-                self.write_items_list(w, &tuple)?;
-                write!(w, ")")
+                self.push_items(Entries::Values(tuple.iter()), ")", steps, w)
             },
             Value::Map(map) => {
                 write!(w, "{{")?;
-
-                if !map.is_empty() {
-                    self.write_newline(w)?;
-
-                    self.indent().write_items(
-                        w,
-                        map,
-                        |inner, (key, value), w| {
-                            inner.write(key, w)?;
-
-                            write!(w, ":")?;
-                            if inner.pretty {
-                                write!(w, " ")?;
-                            }
-
-                            inner.write(value, w)
-                        },
-                    )?;
-
-                    self.write_indent(w)?;
-                }
-
-                write!(w, "}}")
+                self.push_items(Entries::Map(map.iter()), "}", steps, w)
             },
             Value::Option(option) => match option.as_ref() {
                 None => write!(w, "None"),
+                Some(value)
+                    if self.implicit_some
+                        && !matches!(**value, Value::Option(_)) =>
+                {
+                    steps.push(Step::Value(self, value));
+                    Ok(())
+                },
                 Some(value) => {
                     write!(w, "Some(")?;
                     self.write_newline(w)?;
-                    {
-                        let inner = self.indent();
-                        inner.write_indent(w)?;
-                        inner.write(value, w)?;
-                    }
-                    self.write_newline(w)?;
-                    self.write_indent(w)?;
-                    write!(w, ")")
+
+                    let inner = ValuePrinter {
+                        reserved: 0,
+                        ..self.indent()
+                    };
+                    inner.write_indent(w)?;
+
+                    steps.push(Step::Str(")"));
+                    steps.push(Step::Indent(self));
+                    steps.push(Step::Newline(self));
+                    steps.push(Step::Value(inner, value));
+                    Ok(())
                 },
             },
-            Value::Struct(identifier, items) => {
-                write!(w, "{}(", identifier)?;
-
-                if !items.is_empty() {
-                    self.write_newline(w)?;
+            Value::Struct(identifier, fields) => {
+                write!(w, "{}(", self.type_name(identifier))?;
+                self.push_items(Entries::Fields(fields.iter()), ")", steps, w)
+            },
+            Value::TupleStruct(identifier, tuple) => {
+                write!(w, "{}(", self.type_name(identifier))?;
+                self.push_items(Entries::Values(tuple.iter()), ")", steps, w)
+            },
+        }
+    }
 
-                    self.indent().write_items(
-                        w,
-                        items,
-                        |inner, (key, value), w| {
-                            write!(w, "{}", key)?;
+    /// Pushes the steps writing the items of a collection after its
+    /// opening bracket, up to the closing one
+    fn push_items<'value, W>(
+        self,
+        entries: Entries<'value>,
+        close: &'static str,
+        steps: &mut Vec<Step<'indent, 'value>>,
+        w: &mut W,
+    ) -> fmt::Result
+    where
+        W: Write,
+    {
+        steps.push(Step::Str(close));
+        if entries.len() > 0 {
+            self.write_newline(w)?;
 
-                            write!(w, ":")?;
-                            if inner.pretty {
-                                write!(w, " ")?;
-                            }
+            steps.push(Step::Indent(self));
+            steps.push(Step::Items(Items {
+                printer: self.indent(),
+                entries,
+                index: 0,
+            }));
+        }
+        Ok(())
+    }
 
-                            inner.write(value, w)
-                        },
-                    )?;
+    /// The identifier as written in front of a struct
+    fn type_name(self, identifier: &TypeIdentifier) -> String {
+        if !self.omit_names {
+            return identifier.to_string();
+        }
 
-                    self.write_indent(w)?;
-                }
+        // Only enum variants have more than one segment
+        match identifier.segments.as_slice() {
+            [.., _, variant] => variant.identifier.to_string(),
+            _ => String::new(),
+        }
+    }
 
-                write!(w, ")")
+    /// The identifier as written for a unit struct or unit variant
+    fn unit_name(self, identifier: &TypeIdentifier) -> String {
+        match (self.unit_variants, identifier.segments.as_slice()) {
+            (UnitVariantStyle::Name, [.., _, variant]) => {
+                variant.identifier.to_string()
             },
-            Value::TupleStruct(identifier, tuple) => {
-                write!(w, "{}(", identifier)?;
-                self.write_items_list(w, &tuple)?;
-                write!(w, ")")
+            (UnitVariantStyle::Call, [.., _, variant]) => {
+                format!("{}()", variant.identifier)
             },
+            _ => self.type_name(identifier),
         }
     }
 
@@ -136,7 +569,7 @@ impl<'indent> ValuePrinter<'indent> {
     where
         W: Write,
     {
-        if self.pretty {
+        if self.multiline() {
             for _ in 0..self.indentation_level {
                 write!(w, "{}", self.indentation)?;
             }
@@ -148,51 +581,378 @@ impl<'indent> ValuePrinter<'indent> {
     where
         W: Write,
     {
-        if self.pretty {
-            write!(w, "\n")?;
+        if self.multiline() {
+            w.write_str(self.newline)?;
         }
         Ok(())
     }
 
-    fn write_items_list<W>(self, w: &mut W, items: &[Value]) -> fmt::Result
+    /// Writes the comments attached to `path`
+    fn write_comments<W>(self, path: &Path, w: &mut W) -> fmt::Result
     where
         W: Write,
     {
-        if !items.is_empty() {
-            self.write_newline(w)?;
+        let comments = match self.comments {
+            Some(comments) => comments.get(path),
+            None => return Ok(()),
+        };
 
-            self.indent()
-                .write_items(w, items, |inner, it, w| inner.write(it, w))?;
+        for comment in comments {
+            if self.multiline() {
+                for line in comment.lines() {
+                    self.write_indent(w)?;
+                    write!(w, "// {}", line)?;
+                    self.write_newline(w)?;
+                }
+            } else {
+                write!(w, "/* {} */", comment)?;
+                if self.spaced() {
+                    write!(w, " ")?;
+                }
+            }
+        }
+        Ok(())
+    }
 
-            self.write_indent(w)?;
+    /// Decides if the auto mode should put a value on a single line
+    fn fits_inline(self, value: &Value, path: &mut Path) -> bool {
+        let width = self
+            .max_inline_width
+            .unwrap_or(DEFAULT_LINE_WIDTH)
+            .saturating_sub(self.indentation_level * self.indentation.len());
+        if !self.fits_width(value, path, width) {
+            return false;
         }
 
-        Ok(())
+        let statistics = Statistics::of(value);
+        statistics.depth <= AUTO_MAX_INLINE_DEPTH
+            && statistics.longest_string <= AUTO_MAX_INLINE_STRING
+    }
+
+    /// Whether a value written on a single line is at most `width` long
+    fn fits_width(self, value: &Value, path: &mut Path, width: usize) -> bool {
+        self.inline_width(value, path, width).is_some()
     }
 
-    fn write_items<W, It, T, F>(
+    /// Length of a value written on a single line, if at most `limit`
+    fn inline_width(
         self,
+        value: &Value,
+        path: &mut Path,
+        limit: usize,
+    ) -> Option<usize> {
+        // Line comments need a layout with one item per line
+        if self.comments.is_some_and(|c| c.any_inside(path)) {
+            return None;
+        }
+
+        let mut writer = LimitedWriter(limit);
+        self.as_inline()
+            .write_value(value, path, &mut writer)
+            .ok()?;
+        Some(limit - writer.0)
+    }
+
+    /// Width of the last line of a value this printer wrote
+    ///
+    /// Only the fit mode needs it, the others don't reserve any width.
+    fn last_line_width(self, value: &Value, path: &mut Path) -> usize {
+        if self.mode != PrintMode::Fit {
+            return 0;
+        }
+
+        // Otherwise a closing bracket on its own line
+        self.inline_width(value, path, self.remaining_width())
+            .unwrap_or(1)
+    }
+}
+
+/// What is left to write of a value, see `ValuePrinter::write_value`
+enum Step<'indent, 'value> {
+    Value(ValuePrinter<'indent>, &'value Value),
+    Str(&'indent str),
+    Newline(ValuePrinter<'indent>),
+    Indent(ValuePrinter<'indent>),
+    /// The items of a collection that are not written yet
+    Items(Items<'indent, 'value>),
+    /// Leaves the path segment of an item
+    Leave,
+}
+
+/// Items of a collection, written one at a time with their separators
+struct Items<'indent, 'value> {
+    /// The printer of the collection, indented
+    printer: ValuePrinter<'indent>,
+    entries: Entries<'value>,
+    /// Index of the next item
+    index: usize,
+}
+
+impl<'indent, 'value> Items<'indent, 'value> {
+    /// Writes the start of the next item and pushes the steps writing the
+    /// rest of it, followed by the remaining items
+    fn write_next<W>(
+        mut self,
+        path: &mut Path,
+        steps: &mut Vec<Step<'indent, 'value>>,
         w: &mut W,
-        items: It,
-        mut function: F,
     ) -> fmt::Result
     where
         W: Write,
-        It: IntoIterator<Item = T>,
-        F: FnMut(Self, T, &mut W) -> fmt::Result,
     {
-        for it in items {
-            self.write_indent(w)?;
+        let printer = self.printer;
+        let index = self.index;
+        let item = match self.entries.next() {
+            Some(item) => item,
+            None => return Ok(()),
+        };
+        let last = self.entries.len() == 0;
+        let comma = !last || printer.trailing_commas;
+        self.index += 1;
+        steps.push(Step::Items(self));
 
-            function(self, it, w)?;
-            write!(w, ",")?;
-            self.write_newline(w)?;
+        // Segments are only needed to find comments of an item
+        if printer.comments.is_some() {
+            path.push(item.segment(index));
+            steps.push(Step::Leave);
         }
 
+        let inner = if printer.mode == PrintMode::Inline {
+            if index > 0 {
+                write!(w, ", ")?;
+            }
+
+            printer.write_comments(path, w)?;
+            printer
+        } else {
+            printer.write_comments(path, w)?;
+            printer.write_indent(w)?;
+
+            steps.push(Step::Newline(printer));
+            if comma {
+                steps.push(Step::Str(","));
+            }
+            ValuePrinter {
+                reserved: comma as usize,
+                ..printer
+            }
+        };
+
+        match item {
+            Item::Value(value) => steps.push(Step::Value(inner, value)),
+            Item::Entry(key, value) => {
+                let colon = inner.colon(inner.map_colon);
+                let key_width = inner.last_line_width(key, path);
+                steps.push(Step::Value(
+                    inner.reserve(key_width + colon.len()),
+                    value,
+                ));
+                steps.push(Step::Str(colon));
+                if inner.mode == PrintMode::Diff {
+                    steps.push(Step::Value(inner.as_inline(), key));
+                } else {
+                    steps.push(Step::Value(inner, key));
+                }
+            },
+            Item::Field(key, value) => {
+                let mut name = String::new();
+                write_identifier(key, &mut name)?;
+                let colon = inner.colon(inner.field_colon);
+
+                w.write_str(&name)?;
+                w.write_str(colon)?;
+                steps.push(Step::Value(
+                    inner.reserve(name.len() + colon.len()),
+                    value,
+                ));
+            },
+        }
         Ok(())
     }
 }
 
+/// The items of the different kinds of collections
+enum Entries<'value> {
+    Values(slice::Iter<'value, Value>),
+    Map(map::Iter<'value, Value, Value>),
+    Fields(OrderedIter<'value, Identifier, Value>),
+}
+
+enum Item<'value> {
+    Value(&'value Value),
+    Entry(&'value Value, &'value Value),
+    Field(&'value Identifier, &'value Value),
+}
+
+impl Item<'_> {
+    fn segment(&self, index: usize) -> PathSegment {
+        match self {
+            Item::Value(_) => PathSegment::Index(index),
+            Item::Entry(key, _) => PathSegment::Key((*key).clone()),
+            Item::Field(key, _) => PathSegment::Field((*key).clone()),
+        }
+    }
+}
+
+impl<'value> Iterator for Entries<'value> {
+    type Item = Item<'value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Entries::Values(items) => items.next().map(Item::Value),
+            Entries::Map(map) => {
+                map.next().map(|(key, value)| Item::Entry(key, value))
+            },
+            Entries::Fields(fields) => {
+                fields.next().map(|(key, value)| Item::Field(key, value))
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Entries::Values(items) => items.size_hint(),
+            Entries::Map(map) => map.size_hint(),
+            Entries::Fields(fields) => fields.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Entries<'_> {}
+
+/// Whether the diff mode puts a value on a single line
+///
+/// Scalars and options or newtypes around them have a single leaf,
+/// collections are always broken up so they can grow without reflowing.
+/// One element tuples only count with `tuples`, as
+/// `ValuePrinter::inline_wrapped_scalars` does.
+fn is_wrapped_scalar(mut value: &Value, tuples: bool) -> bool {
+    loop {
+        value = match value {
+            Value::List(_) | Value::Map(_) | Value::Struct(..) => return false,
+            Value::Option(Some(inner)) => inner,
+            Value::Tuple(items) if tuples => match items.as_slice() {
+                [inner] => inner,
+                _ => return false,
+            },
+            Value::Tuple(_) => return false,
+            Value::TupleStruct(_, items) => match items.as_slice() {
+                [inner] => inner,
+                _ => return false,
+            },
+            _ => return true,
+        };
+    }
+}
+
+/// Passes text on to an `io::Write`, keeping the error `fmt::Write` can't
+struct IoAdapter<'w, W: ?Sized> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<W> Write for IoAdapter<'_, W>
+where
+    W: io::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+/// Discards its input, but fails once more than the given length is written
+struct LimitedWriter(usize);
+
+impl Write for LimitedWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 = self.0.checked_sub(s.len()).ok_or(fmt::Error)?;
+        Ok(())
+    }
+}
+
+/// Shape of a value subtree, used by the auto mode
+#[derive(Copy, Clone, Default, Debug)]
+struct Statistics {
+    /// Nesting depth of collections, 0 for scalars
+    depth: usize,
+    /// Length of the longest string or identifier
+    longest_string: usize,
+}
+
+impl Statistics {
+    fn of(value: &Value) -> Self {
+        let mut result = Statistics::default();
+        // Values along with the number of collections around them
+        let mut pending = vec![(value, 0)];
+        while let Some((value, level)) = pending.pop() {
+            let inner = level + 1;
+            let collection = match value {
+                Value::Unit
+                | Value::Bool(_)
+                | Value::Char(_)
+                | Value::Number(_)
+                | Value::Type(_)
+                | Value::UnitStruct(_)
+                | Value::Option(None) => false,
+                Value::String(s) => {
+                    result.longest_string = result.longest_string.max(s.len());
+                    false
+                },
+                Value::Bytes(b) => {
+                    result.longest_string = result.longest_string.max(b.len());
+                    false
+                },
+                Value::List(items)
+                | Value::Tuple(items)
+                | Value::TupleStruct(_, items) => {
+                    pending.extend(items.iter().map(|item| (item, inner)));
+                    true
+                },
+                Value::Map(map) => {
+                    pending.extend(
+                        map.iter()
+                            .flat_map(|(k, v)| once(k).chain(once(v)))
+                            .map(|item| (item, inner)),
+                    );
+                    true
+                },
+                Value::Option(Some(value)) => {
+                    pending.push((value, inner));
+                    true
+                },
+                Value::Struct(_, fields) => {
+                    pending.extend(fields.values().map(|item| (item, inner)));
+                    result.longest_string = fields
+                        .keys()
+                        .map(|key| key.0.len())
+                        .fold(result.longest_string, usize::max);
+                    true
+                },
+            };
+
+            if collection {
+                result.depth = result.depth.max(inner);
+            }
+        }
+        result
+    }
+}
+
+/// Writes an identifier, quoting it if it would not parse as is
+fn write_identifier<W>(identifier: &Identifier, w: &mut W) -> fmt::Result
+where
+    W: Write,
+{
+    if identifier.is_valid() {
+        write!(w, "{}", identifier)
+    } else {
+        write!(w, "\"{}\"", escape_string(&identifier.0))
+    }
+}
+
 #[derive(Debug, Clone)]
 enum StrOrCharIterator<'lt> {
     Str(Chars<'lt>),
@@ -246,7 +1006,7 @@ fn escape_char_generic(input: char) -> EscapeResult {
     })
 }
 
-fn escape_char(c: char) -> String {
+pub(crate) fn escape_char(c: char) -> String {
     let iterator: StrOrCharIterator = escape_char_generic(c)
         .or_else(|c| match c {
             '\'' => Ok("\\'"),
@@ -256,7 +1016,48 @@ fn escape_char(c: char) -> String {
     iterator.collect()
 }
 
-fn escape_string(s: &str) -> String {
+/// Strings with at least this many backslashes and quotes are printed raw
+const RAW_STRING_MIN_ESCAPES: usize = 2;
+
+/// Quotes a string, as a raw string if that saves enough escapes
+///
+/// Raw strings are only used when the string contains no line breaks,
+/// tabs or null characters, so the output stays on a single line.
+pub(crate) fn quote_string(s: &str) -> String {
+    let escapes = s.chars().filter(|c| matches!(c, '\\' | '"')).count();
+    let needs_escapes = s.contains(&['\n', '\r', '\t', '\0'][..]);
+
+    if escapes < RAW_STRING_MIN_ESCAPES || needs_escapes {
+        return format!("\"{}\"", escape_string(s));
+    }
+
+    // One more `#` than follows any quote inside of the string
+    let hashes = s
+        .match_indices('"')
+        .map(|(index, _)| {
+            s[index + 1..].chars().take_while(|c| *c == '#').count() + 1
+        })
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(hashes);
+
+    format!("r{}\"{}\"{}", hashes, s, hashes)
+}
+
+/// Escapes everything but printable ASCII, like Rust byte strings
+pub(crate) fn escape_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match escape_char_generic(b as char) {
+            Ok(escaped) => escaped.to_string(),
+            Err(_) if b == b'"' => "\\\"".to_string(),
+            Err(c) if c == ' ' || c.is_ascii_graphic() => c.to_string(),
+            Err(_) => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+pub(crate) fn escape_string(s: &str) -> String {
     s.chars()
         .map(escape_char_generic)
         .map(|result| {
@@ -265,7 +1066,6 @@ fn escape_string(s: &str) -> String {
                 c => Err(c),
             })
         })
-        .map(StrOrCharIterator::from)
-        .flatten()
+        .flat_map(StrOrCharIterator::from)
         .collect()
 }
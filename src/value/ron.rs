@@ -0,0 +1,81 @@
+//! Conversion between values and RON text
+//!
+//! RON reads like the text format, but names structs and enum variants
+//! with a single identifier and has no generic arguments or type values.
+//! Writing RON therefore keeps only the last segment of a name, without
+//! generics: `Mode::Fast` becomes `Fast` and `Wrapper<u8>(1)` becomes
+//! `Wrapper(1)`. Type values like `[u8; 4]` are written as strings.
+//!
+//! RON text is read with the ron crate, which does not report names at all:
+//! structs become maps with string keys, tuples and tuple structs become
+//! lists and unit structs and variants become `()`. Typed data keeps its names when
+//! read with `ron::from_str` and converted with `Value::new`.
+
+use crate::{
+    error::Error,
+    value::{
+        types::{GenericIdentifier, TypeIdentifier},
+        Value,
+    },
+};
+
+/// Parses RON text into a value, see the module documentation for names
+pub fn from_ron(input: &str) -> Result<Value, Error> {
+    let value: ron::Value = ron::from_str(input)?;
+    Ok(Value::try_new(&value)?)
+}
+
+/// Writes a value as RON text, see the module documentation for names
+pub fn to_ron(value: &Value, pretty: bool) -> String {
+    let value = ron_value(value);
+    if pretty {
+        value.to_string_pretty()
+    } else {
+        value.to_string_compact()
+    }
+}
+
+/// The single segment RON name of a type path
+fn ron_name(identifier: &TypeIdentifier) -> TypeIdentifier {
+    TypeIdentifier {
+        segments: identifier
+            .segments
+            .last()
+            .map(|last| GenericIdentifier::from(last.identifier.clone()))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Converts names and types to what RON can express
+fn ron_value(value: &Value) -> Value {
+    let items = |items: &[Value]| items.iter().map(ron_value).collect();
+
+    match value {
+        Value::Type(t) => Value::String(t.to_string().into()),
+        Value::UnitStruct(identifier) => {
+            Value::UnitStruct(ron_name(identifier))
+        },
+        Value::List(list) => Value::List(items(list)),
+        Value::Tuple(tuple) => Value::Tuple(items(tuple)),
+        Value::Map(map) => Value::Map(
+            map.iter()
+                .map(|(key, value)| (ron_value(key), ron_value(value)))
+                .collect(),
+        ),
+        Value::Option(option) => Value::Option(
+            option.as_ref().map(|value| Box::new(ron_value(value))),
+        ),
+        Value::Struct(identifier, fields) => Value::Struct(
+            ron_name(identifier),
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), ron_value(value)))
+                .collect(),
+        ),
+        Value::TupleStruct(identifier, tuple) => {
+            Value::TupleStruct(ron_name(identifier), items(tuple))
+        },
+        other => other.clone(),
+    }
+}
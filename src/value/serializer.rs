@@ -1,4 +1,13 @@
-use crate::value::{Identifier, TypeIdentifier, Value};
+use crate::{
+    raw,
+    value::{
+        embed,
+        map::{Map, OrderedMap},
+        options::{SerializeOptions, UnitVariantStyle},
+        types::GenericIdentifier,
+        Identifier, TypeIdentifier, Value,
+    },
+};
 use serde::{
     ser::{
         SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
@@ -7,12 +16,66 @@ use serde::{
     Serialize, Serializer,
 };
 use std::{
-    collections::BTreeMap,
+    collections::BTreeSet,
     fmt::{Display, Formatter},
 };
 
+/// Serializes Rust types into a Value
+#[derive(Copy, Clone, Default)]
 pub struct ValueSerializer;
 
+impl ValueSerializer {
+    /// Applies settings like writing full type paths
+    pub fn with_options(
+        options: &SerializeOptions,
+    ) -> ValueSerializerWithOptions<'_> {
+        ValueSerializerWithOptions { options }
+    }
+}
+
+/// Serializes Rust types into a Value, see `ValueSerializer::with_options`
+#[derive(Copy, Clone)]
+pub struct ValueSerializerWithOptions<'options> {
+    options: &'options SerializeOptions,
+}
+
+static DEFAULT_OPTIONS: SerializeOptions = SerializeOptions {
+    type_paths: BTreeSet::new(),
+    root_type_path: false,
+    compact_representations: false,
+    unit_variants: UnitVariantStyle::Path,
+};
+
+impl<'options> ValueSerializerWithOptions<'options> {
+    /// The identifier of a type, with its full path if one is known
+    fn type_identifier(self, name: &'static str) -> TypeIdentifier {
+        let mut paths = self
+            .options
+            .type_paths
+            .iter()
+            .filter(|path| path.rsplit("::").next() == Some(name));
+        match (paths.next(), paths.next()) {
+            (Some(path), None) => TypeIdentifier {
+                segments: path
+                    .split("::")
+                    .map(GenericIdentifier::from)
+                    .collect(),
+            },
+            _ => name.into(),
+        }
+    }
+
+    fn variant_identifier(
+        self,
+        name: &'static str,
+        variant: &'static str,
+    ) -> TypeIdentifier {
+        let mut identifier = self.type_identifier(name);
+        identifier.segments.push(variant.into());
+        identifier
+    }
+}
+
 #[derive(Debug)]
 pub enum ValueSerializerError {
     Custom(String),
@@ -37,59 +100,67 @@ impl serde::ser::Error for ValueSerializerError {
     }
 }
 
-impl Serializer for ValueSerializer {
+impl<'options> Serializer for ValueSerializerWithOptions<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
-    type SerializeSeq = ValueSerializerSeq;
-    type SerializeTuple = ValueSerializerSeq;
-    type SerializeTupleStruct = ValueSerializerTupleStruct;
-    type SerializeTupleVariant = ValueSerializerTupleStruct;
-    type SerializeMap = ValueSerializerMap;
-    type SerializeStruct = ValueSerializerStruct;
-    type SerializeStructVariant = ValueSerializerStruct;
+    type SerializeSeq = ValueSerializerSeq<'options>;
+    type SerializeTuple = ValueSerializerSeq<'options>;
+    type SerializeTupleStruct = ValueSerializerTupleStruct<'options>;
+    type SerializeTupleVariant = ValueSerializerTupleStruct<'options>;
+    type SerializeMap = ValueSerializerMap<'options>;
+    type SerializeStruct = ValueSerializerStruct<'options>;
+    type SerializeStructVariant = ValueSerializerStruct<'options>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
         Ok(Value::Bool(v))
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Number(v.to_string()))
+        Ok(Value::Number(v.into()))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
@@ -97,25 +168,22 @@ impl Serializer for ValueSerializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(v.to_string()))
+        Ok(Value::String(v.into()))
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(&base64::encode(v))
+        Ok(Value::Bytes(v.to_vec()))
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         Ok(Value::Option(None))
     }
 
-    fn serialize_some<T: ?Sized>(
-        self,
-        value: &T,
-    ) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let inner = value.serialize(ValueSerializer)?;
+        let inner = value.serialize(self)?;
         Ok(Value::Option(Some(Box::new(inner))))
     }
 
@@ -127,7 +195,7 @@ impl Serializer for ValueSerializer {
         self,
         name: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Type(name.into()))
+        Ok(Value::UnitStruct(self.type_identifier(name)))
     }
 
     fn serialize_unit_variant(
@@ -136,22 +204,48 @@ impl Serializer for ValueSerializer {
         _: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Type((name, variant).into()))
+        Ok(match self.options.unit_variants {
+            UnitVariantStyle::Path => {
+                Value::UnitStruct(self.variant_identifier(name, variant))
+            },
+            UnitVariantStyle::Name => Value::UnitStruct(variant.into()),
+            UnitVariantStyle::Call => {
+                Value::TupleStruct(variant.into(), Vec::new())
+            },
+        })
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let inner = value.serialize(ValueSerializer)?;
-        Ok(Value::TupleStruct(name.into(), vec![inner]))
+        if name == embed::TOKEN {
+            if let Some(value) = embed::capture(value) {
+                return Ok(value);
+            }
+        }
+
+        let inner = value.serialize(self)?;
+
+        if name == raw::TOKEN {
+            return match inner {
+                Value::String(text) => Value::parse(&text).map_err(|e| {
+                    ValueSerializerError::Custom(format!("{:#}", e))
+                }),
+                _ => Err(ValueSerializerError::Custom(
+                    "Raw fragment is not a string".to_string(),
+                )),
+            };
+        }
+
+        Ok(Value::TupleStruct(self.type_identifier(name), vec![inner]))
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         name: &'static str,
         _: u32,
@@ -159,10 +253,10 @@ impl Serializer for ValueSerializer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let inner = value.serialize(ValueSerializer)?;
-        let identifier = (name, variant).into();
+        let inner = value.serialize(self)?;
+        let identifier = self.variant_identifier(name, variant);
         Ok(Value::TupleStruct(identifier, vec![inner]))
     }
 
@@ -171,6 +265,7 @@ impl Serializer for ValueSerializer {
         len: Option<usize>,
     ) -> Result<Self::SerializeSeq, Self::Error> {
         Ok(ValueSerializerSeq {
+            serializer: self,
             items: Vec::with_capacity(len.unwrap_or(0)),
         })
     }
@@ -180,6 +275,7 @@ impl Serializer for ValueSerializer {
         len: usize,
     ) -> Result<Self::SerializeTuple, Self::Error> {
         Ok(ValueSerializerSeq {
+            serializer: self,
             items: Vec::with_capacity(len),
         })
     }
@@ -190,7 +286,8 @@ impl Serializer for ValueSerializer {
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
         Ok(ValueSerializerTupleStruct {
-            identifier: name.into(),
+            serializer: self,
+            identifier: self.type_identifier(name),
             items: Vec::with_capacity(len),
         })
     }
@@ -203,7 +300,8 @@ impl Serializer for ValueSerializer {
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
         Ok(ValueSerializerTupleStruct {
-            identifier: (name, variant).into(),
+            serializer: self,
+            identifier: self.variant_identifier(name, variant),
             items: Vec::with_capacity(len),
         })
     }
@@ -213,7 +311,8 @@ impl Serializer for ValueSerializer {
         _: Option<usize>,
     ) -> Result<Self::SerializeMap, Self::Error> {
         Ok(ValueSerializerMap {
-            items: BTreeMap::new(),
+            serializer: self,
+            items: Map::new(),
             current_key: None,
             current_value: None,
         })
@@ -225,7 +324,8 @@ impl Serializer for ValueSerializer {
         _: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
         Ok(ValueSerializerStruct {
-            identifier: name.into(),
+            serializer: self,
+            identifier: self.type_identifier(name),
             items: Default::default(),
         })
     }
@@ -238,35 +338,142 @@ impl Serializer for ValueSerializer {
         _: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         Ok(ValueSerializerStruct {
-            identifier: (name, variant).into(),
+            serializer: self,
+            identifier: self.variant_identifier(name, variant),
             items: Default::default(),
         })
     }
 
-    fn collect_str<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn is_human_readable(&self) -> bool {
+        !self.options.compact_representations
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Display,
+        T: ?Sized + Display,
     {
         self.serialize_str(&value.to_string())
     }
 }
 
-pub struct ValueSerializerSeq {
-    items: Vec<Value>,
+/// Forwards to the serializer with the default options
+macro_rules! forward_to_default_options {
+    ($($method:ident($($arg:ident: $ty:ty),*) -> $ok:ident;)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<Self::$ok, Self::Error> {
+                ValueSerializer::with_options(&DEFAULT_OPTIONS)
+                    .$method($($arg),*)
+            }
+        )*
+    };
 }
 
-impl SerializeSeq for ValueSerializerSeq {
+impl Serializer for ValueSerializer {
     type Ok = Value;
     type Error = ValueSerializerError;
+    type SerializeSeq = ValueSerializerSeq<'static>;
+    type SerializeTuple = ValueSerializerSeq<'static>;
+    type SerializeTupleStruct = ValueSerializerTupleStruct<'static>;
+    type SerializeTupleVariant = ValueSerializerTupleStruct<'static>;
+    type SerializeMap = ValueSerializerMap<'static>;
+    type SerializeStruct = ValueSerializerStruct<'static>;
+    type SerializeStructVariant = ValueSerializerStruct<'static>;
+
+    forward_to_default_options! {
+        serialize_bool(v: bool) -> Ok;
+        serialize_i8(v: i8) -> Ok;
+        serialize_i16(v: i16) -> Ok;
+        serialize_i32(v: i32) -> Ok;
+        serialize_i64(v: i64) -> Ok;
+        serialize_i128(v: i128) -> Ok;
+        serialize_u8(v: u8) -> Ok;
+        serialize_u16(v: u16) -> Ok;
+        serialize_u32(v: u32) -> Ok;
+        serialize_u64(v: u64) -> Ok;
+        serialize_u128(v: u128) -> Ok;
+        serialize_f32(v: f32) -> Ok;
+        serialize_f64(v: f64) -> Ok;
+        serialize_char(v: char) -> Ok;
+        serialize_str(v: &str) -> Ok;
+        serialize_bytes(v: &[u8]) -> Ok;
+        serialize_none() -> Ok;
+        serialize_unit() -> Ok;
+        serialize_unit_struct(name: &'static str) -> Ok;
+        serialize_unit_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str
+        ) -> Ok;
+        serialize_seq(len: Option<usize>) -> SerializeSeq;
+        serialize_tuple(len: usize) -> SerializeTuple;
+        serialize_tuple_struct(
+            name: &'static str,
+            len: usize
+        ) -> SerializeTupleStruct;
+        serialize_tuple_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> SerializeTupleVariant;
+        serialize_map(len: Option<usize>) -> SerializeMap;
+        serialize_struct(name: &'static str, len: usize) -> SerializeStruct;
+        serialize_struct_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> SerializeStructVariant;
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ValueSerializer::with_options(&DEFAULT_OPTIONS).serialize_some(value)
+    }
 
-    fn serialize_element<T: ?Sized>(
-        &mut self,
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
         value: &T,
-    ) -> Result<(), Self::Error>
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ValueSerializer::with_options(&DEFAULT_OPTIONS)
+            .serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.items.push(value.serialize(ValueSerializer)?);
+        ValueSerializer::with_options(&DEFAULT_OPTIONS)
+            .serialize_newtype_variant(name, variant_index, variant, value)
+    }
+}
+
+pub struct ValueSerializerSeq<'options> {
+    serializer: ValueSerializerWithOptions<'options>,
+    items: Vec<Value>,
+}
+
+impl<'options> SerializeSeq for ValueSerializerSeq<'options> {
+    type Ok = Value;
+    type Error = ValueSerializerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.items.push(value.serialize(self.serializer)?);
         Ok(())
     }
 
@@ -275,16 +482,13 @@ impl SerializeSeq for ValueSerializerSeq {
     }
 }
 
-impl SerializeTuple for ValueSerializerSeq {
+impl<'options> SerializeTuple for ValueSerializerSeq<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_element<T: ?Sized>(
-        &mut self,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         SerializeSeq::serialize_element(self, value)
     }
@@ -294,23 +498,21 @@ impl SerializeTuple for ValueSerializerSeq {
     }
 }
 
-pub struct ValueSerializerTupleStruct {
+pub struct ValueSerializerTupleStruct<'options> {
+    serializer: ValueSerializerWithOptions<'options>,
     identifier: TypeIdentifier,
     items: Vec<Value>,
 }
 
-impl SerializeTupleStruct for ValueSerializerTupleStruct {
+impl<'options> SerializeTupleStruct for ValueSerializerTupleStruct<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.items.push(value.serialize(ValueSerializer)?);
+        self.items.push(value.serialize(self.serializer)?);
         Ok(())
     }
 
@@ -319,16 +521,13 @@ impl SerializeTupleStruct for ValueSerializerTupleStruct {
     }
 }
 
-impl SerializeTupleVariant for ValueSerializerTupleStruct {
+impl<'options> SerializeTupleVariant for ValueSerializerTupleStruct<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         SerializeTupleStruct::serialize_field(self, value)
     }
@@ -338,24 +537,25 @@ impl SerializeTupleVariant for ValueSerializerTupleStruct {
     }
 }
 
-pub struct ValueSerializerStruct {
+pub struct ValueSerializerStruct<'options> {
+    serializer: ValueSerializerWithOptions<'options>,
     identifier: TypeIdentifier,
-    items: BTreeMap<Identifier, Value>,
+    items: OrderedMap<Identifier, Value>,
 }
 
-impl SerializeStruct for ValueSerializerStruct {
+impl<'options> SerializeStruct for ValueSerializerStruct<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_field<T: ?Sized>(
+    fn serialize_field<T>(
         &mut self,
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let inner = value.serialize(ValueSerializer)?;
+        let inner = value.serialize(self.serializer)?;
         self.items.insert(key.into(), inner);
         Ok(())
     }
@@ -365,17 +565,17 @@ impl SerializeStruct for ValueSerializerStruct {
     }
 }
 
-impl SerializeStructVariant for ValueSerializerStruct {
+impl<'options> SerializeStructVariant for ValueSerializerStruct<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_field<T: ?Sized>(
+    fn serialize_field<T>(
         &mut self,
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         SerializeStruct::serialize_field(self, key, value)
     }
@@ -385,22 +585,23 @@ impl SerializeStructVariant for ValueSerializerStruct {
     }
 }
 
-pub struct ValueSerializerMap {
-    items: BTreeMap<Value, Value>,
+pub struct ValueSerializerMap<'options> {
+    serializer: ValueSerializerWithOptions<'options>,
+    items: Map<Value, Value>,
 
     current_key: Option<Value>,
     current_value: Option<Value>,
 }
 
-impl SerializeMap for ValueSerializerMap {
+impl<'options> SerializeMap for ValueSerializerMap<'options> {
     type Ok = Value;
     type Error = ValueSerializerError;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let key = key.serialize(ValueSerializer)?;
+        let key = key.serialize(self.serializer)?;
 
         match self.current_value.take() {
             Some(value) => {
@@ -414,14 +615,11 @@ impl SerializeMap for ValueSerializerMap {
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(
-        &mut self,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let value = value.serialize(ValueSerializer)?;
+        let value = value.serialize(self.serializer)?;
 
         match self.current_key.take() {
             Some(key) => {
@@ -0,0 +1,60 @@
+use crate::value::path::{Path, PathSegment};
+use std::{
+    collections::{btree_map, BTreeMap},
+    ops::Range,
+};
+
+/// Byte ranges of the values of a document, by their path
+///
+/// Filled by `Value::parse_with_spans`, so errors found after parsing
+/// can point at the text they are about. The value inside of `Some`
+/// shares the span of the option.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct Spans {
+    entries: BTreeMap<Path, Range<usize>>,
+}
+
+impl Spans {
+    pub fn new() -> Self {
+        Spans::default()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Range<usize>> {
+        self.entries.get(path).cloned()
+    }
+
+    pub fn insert(&mut self, path: Path, span: Range<usize>) {
+        self.entries.insert(path, span);
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, Path, Range<usize>> {
+        self.entries.iter()
+    }
+}
+
+/// Records the span of every value while a document is parsed
+#[derive(Default)]
+pub(crate) struct SpanCollector {
+    path: Path,
+    spans: Spans,
+}
+
+impl SpanCollector {
+    pub(crate) fn enter(&mut self, segment: PathSegment) {
+        self.path.push(segment);
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.path.pop();
+    }
+
+    /// Records the span of the value at the current path, unless the value
+    /// is nested in one at the same path
+    pub(crate) fn record(&mut self, span: Range<usize>) {
+        self.spans.entries.entry(self.path.clone()).or_insert(span);
+    }
+
+    pub(crate) fn finish(self) -> Spans {
+        self.spans
+    }
+}
@@ -0,0 +1,232 @@
+//! Reading a sequence of values one at a time, see `ValueStream`
+
+use crate::{error::Error, value::Value};
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read},
+};
+
+/// Reads the values of a sequence of documents lazily, see
+/// `Value::parse_all`
+///
+/// A value is parsed as soon as the line completing it was read, so
+/// files that are still being appended to can be followed. Errors are
+/// reported for the document they occur in, reading continues with the
+/// next one after a `---` line.
+pub struct ValueStream<R> {
+    reader: BufReader<R>,
+    /// Lines of the document being read
+    buffer: String,
+    /// Number of lines read so far
+    lines: usize,
+    /// Line the document being read starts at, starting at 1
+    start: usize,
+    /// Values of a finished document not returned yet
+    pending: VecDeque<Value>,
+    /// Tells when `buffer` may hold a complete value
+    scanner: Scanner,
+    /// Set once `buffer` failed to parse before its end, more lines
+    /// can not fix it
+    broken: bool,
+}
+
+impl<R> ValueStream<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        ValueStream {
+            reader: BufReader::new(reader),
+            buffer: String::new(),
+            lines: 0,
+            start: 1,
+            pending: VecDeque::new(),
+            scanner: Scanner::default(),
+            broken: false,
+        }
+    }
+
+    /// Parses the rest of the current document, None if it is empty
+    ///
+    /// A document can hold more values separated by `---` on the same
+    /// line, all but the first are kept in `pending`.
+    fn finish_document(&mut self) -> Option<Result<Value, Error>> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let start = self.start;
+        self.start = self.lines + 1;
+        self.scanner = Scanner::default();
+        self.broken = false;
+
+        match Value::parse_all(&buffer) {
+            Ok(values) => {
+                self.pending = values.into();
+                self.pending.pop_front().map(Ok)
+            },
+            Err(error) => Some(Err(Error::Document {
+                line: start,
+                error: Box::new(error.into()),
+            })),
+        }
+    }
+}
+
+impl<R> Iterator for ValueStream<R>
+where
+    R: Read,
+{
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.pending.pop_front() {
+            return Some(Ok(value));
+        }
+
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return self.finish_document(),
+                Ok(_) => self.lines += 1,
+                Err(error) => return Some(Err(error.into())),
+            }
+
+            if line.trim() == "---" {
+                match self.finish_document() {
+                    Some(result) => return Some(result),
+                    None => continue,
+                }
+            }
+
+            self.buffer.push_str(&line);
+            if self.broken {
+                continue;
+            }
+
+            // Only complete values are worth parsing
+            self.scanner.scan(&line);
+            if self.scanner.is_complete() {
+                match Value::parse(&self.buffer) {
+                    Ok(value) => {
+                        self.buffer.clear();
+                        self.start = self.lines + 1;
+                        return Some(Ok(value));
+                    },
+                    Err(error) => {
+                        self.broken =
+                            error.offset < self.buffer.trim_end().len();
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Where the lines read so far end, inside a string, a comment or code
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum ScanState {
+    Code,
+    String,
+    /// With the number of `#` that close it
+    RawString(usize),
+    BlockComment,
+}
+
+/// Follows delimiters, strings and comments line by line, so a document
+/// is only parsed once all its delimiters were closed
+#[derive(Copy, Clone, Debug)]
+struct Scanner {
+    state: ScanState,
+    depth: usize,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner {
+            state: ScanState::Code,
+            depth: 0,
+        }
+    }
+}
+
+impl Scanner {
+    fn is_complete(&self) -> bool {
+        self.state == ScanState::Code && self.depth == 0
+    }
+
+    fn scan(&mut self, line: &str) {
+        let bytes = line.as_bytes();
+        let at = |i: usize| bytes.get(i).copied();
+
+        let mut i = 0;
+        while i < bytes.len() {
+            match self.state {
+                ScanState::String => match bytes[i] {
+                    b'\\' => i += 1,
+                    b'"' => self.state = ScanState::Code,
+                    _ => {},
+                },
+                ScanState::RawString(hashes) => {
+                    let closes = bytes[i] == b'"'
+                        && bytes[i + 1..]
+                            .iter()
+                            .take_while(|&&b| b == b'#')
+                            .count()
+                            >= hashes;
+                    if closes {
+                        i += hashes;
+                        self.state = ScanState::Code;
+                    }
+                },
+                ScanState::BlockComment => {
+                    if bytes[i..].starts_with(b"*/") {
+                        i += 1;
+                        self.state = ScanState::Code;
+                    }
+                },
+                ScanState::Code => match bytes[i] {
+                    b'"' => self.state = ScanState::String,
+                    b'r' if !(i > 0 && is_identifier_byte(bytes[i - 1])) => {
+                        let hashes = bytes[i + 1..]
+                            .iter()
+                            .take_while(|&&b| b == b'#')
+                            .count();
+                        if at(i + 1 + hashes) == Some(b'"') {
+                            i += 1 + hashes;
+                            self.state = ScanState::RawString(hashes);
+                        }
+                    },
+                    // A char, unless it is a lifetime like `'a`
+                    b'\'' => {
+                        if at(i + 1) == Some(b'\\') {
+                            i += 3;
+                            while at(i).is_some_and(|b| b != b'\'') {
+                                i += 1;
+                            }
+                        } else if let Some(c) = line[i + 1..].chars().next() {
+                            if at(i + 1 + c.len_utf8()) == Some(b'\'') {
+                                i += 1 + c.len_utf8();
+                            }
+                        }
+                    },
+                    b'/' if at(i + 1) == Some(b'/') => break,
+                    b'/' if at(i + 1) == Some(b'*') => {
+                        i += 1;
+                        self.state = ScanState::BlockComment;
+                    },
+                    b'=' if at(i + 1) == Some(b'>') => i += 1,
+                    b'(' | b'[' | b'{' | b'<' => self.depth += 1,
+                    b')' | b']' | b'}' | b'>' => {
+                        self.depth = self.depth.saturating_sub(1)
+                    },
+                    _ => {},
+                },
+            }
+            i += 1;
+        }
+    }
+}
+
+fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
@@ -0,0 +1,41 @@
+//! Reading TOML documents into values, with the toml crate
+//!
+//! Tables become maps with string keys, arrays become lists.
+//! Dates and times are kept as strings in RFC 3339 notation,
+//! since the data model has no date type.
+
+use crate::{
+    error::Error,
+    value::{map::Map, Value},
+};
+
+/// Parses a TOML document into a map
+pub fn from_toml(input: &str) -> Result<Value, Error> {
+    let table: toml::Table = toml::from_str(input)?;
+    Ok(Value::from(toml::Value::Table(table)))
+}
+
+impl From<toml::Value> for Value {
+    fn from(value: toml::Value) -> Self {
+        match value {
+            toml::Value::String(s) => Value::String(s.into()),
+            toml::Value::Integer(v) => Value::Number(v.into()),
+            toml::Value::Float(v) => Value::Number(v.into()),
+            toml::Value::Boolean(b) => Value::Bool(b),
+            toml::Value::Datetime(datetime) => {
+                Value::String(datetime.to_string().into())
+            },
+            toml::Value::Array(items) => {
+                Value::List(items.into_iter().map(Value::from).collect())
+            },
+            toml::Value::Table(table) => Value::Map(
+                table
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (Value::String(key.into()), Value::from(value))
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+}
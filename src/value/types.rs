@@ -1,10 +1,28 @@
-use crate::value::parser::{parse_main_type, parse_main_type_identifier};
-use serde::export::Formatter;
-use std::fmt::Display;
+use crate::value::{
+    diagnostic::TypeParseError,
+    parser::{parse_main_type, parse_main_type_identifier},
+};
+use std::fmt::{Display, Formatter};
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct Identifier(pub String);
 
+impl Identifier {
+    /// Checks if this identifier can be written as is
+    ///
+    /// Identifiers that fail this check need to be quoted when printed
+    pub fn is_valid(&self) -> bool {
+        let mut chars = self.0.chars();
+
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {},
+            _ => return false,
+        }
+
+        chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
+
 impl Display for Identifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         Display::fmt(&self.0, f)
@@ -17,6 +35,12 @@ impl From<&str> for Identifier {
     }
 }
 
+impl From<String> for Identifier {
+    fn from(v: String) -> Self {
+        Identifier(v)
+    }
+}
+
 impl<T> PartialEq<T> for Identifier
 where
     T: AsRef<str>,
@@ -47,8 +71,14 @@ impl Display for GenericIdentifier {
 
 impl From<&str> for GenericIdentifier {
     fn from(v: &str) -> Self {
+        Identifier::from(v).into()
+    }
+}
+
+impl From<Identifier> for GenericIdentifier {
+    fn from(identifier: Identifier) -> Self {
         GenericIdentifier {
-            identifier: v.into(),
+            identifier,
             generics: None,
         }
     }
@@ -61,9 +91,40 @@ pub struct TypeIdentifier {
 }
 
 impl TypeIdentifier {
-    pub fn parse(input: &str) -> anyhow::Result<Self> {
+    pub fn parse(input: &str) -> Result<Self, TypeParseError> {
         parse_main_type_identifier(input)
     }
+
+    /// The name of the enum variant this path names, if it names one
+    ///
+    /// A path names a variant when the segment before the last one is a
+    /// type. Rust writes types in UpperCamelCase and modules in snake_case,
+    /// so `Mode::Fast` is a variant, while `config::Settings` and the full
+    /// paths of `SerializeOptions::type_paths` name structs.
+    pub fn variant(&self) -> Option<&Identifier> {
+        match self.segments.as_slice() {
+            [.., parent, variant] if is_type_name(&parent.identifier.0) => {
+                Some(&variant.identifier)
+            },
+            _ => None,
+        }
+    }
+
+    /// The name of a path of a single segment without generics, like the
+    /// map key `host` in `{ host: "localhost" }`
+    pub fn bare_name(&self) -> Option<&Identifier> {
+        match self.segments.as_slice() {
+            [segment] if segment.generics.is_none() => {
+                Some(&segment.identifier)
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Whether `name` is written like a type rather than a module
+pub(crate) fn is_type_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_uppercase())
 }
 
 impl Display for TypeIdentifier {
@@ -87,6 +148,14 @@ impl From<(&str, &str)> for TypeIdentifier {
     }
 }
 
+impl From<GenericIdentifier> for TypeIdentifier {
+    fn from(segment: GenericIdentifier) -> Self {
+        TypeIdentifier {
+            segments: vec![segment],
+        }
+    }
+}
+
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct Generics {
@@ -108,20 +177,26 @@ pub enum Type {
 }
 
 impl Type {
-    pub fn parse(input: &str) -> anyhow::Result<Self> {
+    pub fn parse(input: &str) -> Result<Self, TypeParseError> {
         parse_main_type(input)
     }
 
+    /// The path, unless this is an array or tuple type
+    pub fn as_type_identifier(&self) -> Option<&TypeIdentifier> {
+        match self {
+            Type::TypeIdentifier(identifier) => Some(identifier),
+            _ => None,
+        }
+    }
+
     /// If this is a TypeIdentifier, get the last segment
     ///
     /// Used for serde deserialize
     pub fn get_identifier(&self) -> Option<&Identifier> {
-        match self {
-            Type::TypeIdentifier(identifier) => {
-                identifier.segments.last().map(|i| &i.identifier)
-            },
-            _ => None,
-        }
+        self.as_type_identifier()?
+            .segments
+            .last()
+            .map(|i| &i.identifier)
     }
 }
 
@@ -130,17 +205,27 @@ impl Display for Type {
         match self {
             Type::TypeIdentifier(ident) => Display::fmt(ident, f),
             Type::Array { content, size } => {
-                write!(f, "[{} ; {}]", content, size)
+                write!(f, "[{}; {}]", content, size)
+            },
+            // A single type needs a comma to stay a tuple, as in `(u8,)`
+            Type::Tuple(tuple) if tuple.len() == 1 => {
+                write!(f, "({},)", tuple[0])
             },
             Type::Tuple(tuple) => {
                 write!(f, "(")?;
-                display_with_separator(f, &tuple, ", ")?;
+                display_with_separator(f, tuple, ", ")?;
                 write!(f, ")")
             },
         }
     }
 }
 
+impl From<TypeIdentifier> for Type {
+    fn from(identifier: TypeIdentifier) -> Self {
+        Type::TypeIdentifier(identifier)
+    }
+}
+
 impl From<&str> for Type {
     fn from(v: &str) -> Self {
         Self::TypeIdentifier(TypeIdentifier::from(v))
@@ -0,0 +1,56 @@
+//! Reading YAML documents into values, with the serde_yaml crate
+//!
+//! Mappings become maps and sequences become lists. Plain scalars are
+//! resolved like YAML 1.2 does: `null` and `~` become `None`, `true` and
+//! `false` booleans, numbers including `.inf` and `.nan` become numbers
+//! and everything else a string. Anchors and aliases are resolved, tagged
+//! nodes like `!Point [1, 2]` become maps with the tag as their only key.
+//! Only a single document is read.
+
+use crate::{
+    error::Error,
+    value::{map::Map, Value},
+};
+use serde_yaml::Value as YamlValue;
+
+/// Parses a YAML document
+pub fn from_yaml(input: &str) -> Result<Value, Error> {
+    let value: YamlValue = serde_yaml::from_str(input)?;
+    Ok(Value::from(value))
+}
+
+impl From<YamlValue> for Value {
+    fn from(value: YamlValue) -> Self {
+        match value {
+            YamlValue::Null => Value::Option(None),
+            YamlValue::Bool(b) => Value::Bool(b),
+            YamlValue::Number(n) => {
+                if let Some(v) = n.as_u64() {
+                    Value::Number(v.into())
+                } else if let Some(v) = n.as_i64() {
+                    Value::Number(v.into())
+                } else {
+                    Value::Number(n.as_f64().unwrap_or(f64::NAN).into())
+                }
+            },
+            YamlValue::String(s) => Value::String(s.into()),
+            YamlValue::Sequence(items) => {
+                Value::List(items.into_iter().map(Value::from).collect())
+            },
+            YamlValue::Mapping(mapping) => Value::Map(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| (Value::from(key), Value::from(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+            YamlValue::Tagged(tagged) => {
+                let mut map = Map::new();
+                map.insert(
+                    Value::String(tagged.tag.to_string().into()),
+                    Value::from(tagged.value),
+                );
+                Value::Map(map)
+            },
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! File access used by loaders and the directory migration
+//!
+//! Everything that touches files goes through a `Vfs`, so tests,
+//! WASM builds and applications with embedded assets can swap
+//! the real file system for `MemoryFs`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Kind of an entry returned by `Vfs::read_dir`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct VfsEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// A file system the crate can read from and write to
+pub trait Vfs: Send + Sync {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+
+    /// Direct children of the directory at `path`, sorted by path
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>>;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Whether a file or directory exists at `path`
+    fn exists(&self, path: &Path) -> bool {
+        self.read_dir(path).is_ok() || self.read_to_string(path).is_ok()
+    }
+
+    /// The path with all symbolic links resolved
+    ///
+    /// File systems without links return the path as it is.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+}
+
+/// The file system of the operating system
+#[derive(Copy, Clone, Default, Debug)]
+pub struct StdFs;
+
+impl Vfs for StdFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(VfsEntry {
+                    path: entry.path(),
+                    is_dir: entry.file_type()?.is_dir(),
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// A file system held in memory
+///
+/// Directories exist if they were created or contain a file.
+#[derive(Default, Debug)]
+pub struct MemoryFs {
+    files: RwLock<BTreeMap<PathBuf, String>>,
+    directories: RwLock<BTreeSet<PathBuf>>,
+}
+
+impl MemoryFs {
+    pub const fn new() -> Self {
+        MemoryFs {
+            files: RwLock::new(BTreeMap::new()),
+            directories: RwLock::new(BTreeSet::new()),
+        }
+    }
+
+    /// Adds a file, replacing any previous content
+    pub fn insert<P, S>(&self, path: P, contents: S)
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        self.files
+            .write()
+            .unwrap()
+            .insert(path.into(), contents.into());
+    }
+
+    pub fn with_file<P, S>(self, path: P, contents: S) -> Self
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        self.insert(path, contents);
+        self
+    }
+
+    pub fn get(&self, path: &Path) -> Option<String> {
+        self.files.read().unwrap().get(path).cloned()
+    }
+
+    /// Paths of all files, sorted
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files.read().unwrap().keys().cloned().collect()
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+impl Vfs for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.get(path).ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.insert(path, contents);
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let files = self.files.read().unwrap();
+        let directories = self.directories.read().unwrap();
+
+        let mut exists = directories.contains(path);
+        let mut entries = BTreeSet::new();
+
+        let paths = files
+            .keys()
+            .map(|file| (file, false))
+            .chain(directories.iter().map(|directory| (directory, true)));
+        for (descendant, is_dir) in paths {
+            let relative = match descendant.strip_prefix(path) {
+                Ok(relative) if relative != Path::new("") => relative,
+                _ => continue,
+            };
+            exists = true;
+
+            // Anything deeper is represented by its top directory
+            let mut components = relative.components();
+            let first = components.next().unwrap();
+            entries.insert(VfsEntry {
+                path: path.join(first),
+                is_dir: is_dir || components.next().is_some(),
+            });
+        }
+
+        if exists {
+            Ok(entries.into_iter().collect())
+        } else {
+            Err(not_found(path))
+        }
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.directories.write().unwrap().insert(path.to_path_buf());
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use typed_format::value::{anchors::Anchors, printer::ValuePrinter, Value};
+
+#[test]
+fn parse_anchors() {
+    let value = Value::parse(
+        r#"Services(
+            web: Service(limits: &limits Limits(cpu: 2, memory: 512), port: 80),
+            api: Service(limits: *limits, port: 8080),
+            workers: [*limits, &one 1, *one],
+        )"#,
+    )
+    .unwrap();
+
+    let expected = Value::parse(
+        r#"Services(
+            web: Service(limits: Limits(cpu: 2, memory: 512), port: 80),
+            api: Service(limits: Limits(cpu: 2, memory: 512), port: 8080),
+            workers: [Limits(cpu: 2, memory: 512), 1, 1],
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+
+    let error = Value::parse("[*missing]").unwrap_err();
+    assert!(
+        error.to_string().contains("Unknown anchor missing"),
+        "{}",
+        error
+    );
+    // Anchors are defined once their value is complete
+    assert!(Value::parse("&a [*a]").is_err());
+}
+
+#[test]
+fn print_anchors() {
+    let value = Value::parse(
+        r#"(
+            api: (limits: (cpu: 2, memory: 512), tags: ["a", "b"]),
+            db: (limits: (cpu: 2, memory: 512), tags: ["a", "b"]),
+            web: (limits: (cpu: 2, memory: 512), tags: ["c"]),
+            "not an identifier": [1, 2],
+            other: [1, 2],
+        )"#,
+    )
+    .unwrap();
+    let anchors = Anchors::detect(&value);
+    assert_eq!(anchors.len(), 3);
+
+    let mut text = String::new();
+    ValuePrinter::pretty()
+        .with_anchors(&anchors)
+        .write(&value, &mut text)
+        .unwrap();
+    assert_eq!(
+        text,
+        r#"(
+    api: &api (
+        limits: &limits (
+            cpu: 2,
+            memory: 512,
+        ),
+        tags: [
+            "a",
+            "b",
+        ],
+    ),
+    db: *api,
+    web: (
+        limits: *limits,
+        tags: [
+            "c",
+        ],
+    ),
+    "not an identifier": &anchor [
+        1,
+        2,
+    ],
+    other: *anchor,
+)"#
+    );
+    assert_eq!(Value::parse(&text).unwrap(), value);
+
+    for printer in &[
+        ValuePrinter::compact(),
+        ValuePrinter::auto(),
+        ValuePrinter::fit(),
+    ] {
+        let mut text = String::new();
+        printer
+            .with_anchors(&anchors)
+            .write(&value, &mut text)
+            .unwrap();
+        assert_eq!(Value::parse(&text).unwrap(), value, "{}", text);
+    }
+
+    let unique = Value::parse("[(a: 1), (a: 2)]").unwrap();
+    assert!(Anchors::detect(&unique).is_empty());
+}
@@ -0,0 +1,47 @@
+//! Generated values and types read back the way they are printed
+//!
+//! Run with `cargo test --features arbitrary`
+
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use typed_format::value::{types::Type, Value};
+
+/// Pseudo random bytes, the same ones for every run
+fn bytes(seed: u64) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..512)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn values() {
+    for seed in 0..2_000 {
+        let bytes = bytes(seed);
+        let value = Value::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+        for text in &[value.to_string_pretty(), value.to_string_compact()] {
+            let parsed = Value::parse(text).unwrap();
+            // NaN is not equal to itself
+            if parsed != value {
+                assert_eq!(parsed.to_string_pretty(), value.to_string_pretty());
+            }
+        }
+    }
+}
+
+#[test]
+fn types() {
+    for seed in 0..2_000 {
+        let bytes = bytes(seed);
+        let generated =
+            Type::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert_eq!(Type::parse(&generated.to_string()).unwrap(), generated);
+    }
+}
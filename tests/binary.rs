@@ -0,0 +1,92 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use typed_format::{from_bytes, to_bytes, to_writer_binary, value::Value};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Mode {
+    Fast,
+    Limited(u32),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Entry {
+    name: String,
+    mode: Mode,
+    offset: i64,
+    weights: Vec<f64>,
+    tags: BTreeMap<String, Option<char>>,
+    data: Vec<(u8, i128)>,
+}
+
+fn entry() -> Entry {
+    let mut tags = BTreeMap::new();
+    tags.insert("a".to_string(), Some('ä'));
+    tags.insert("b".to_string(), None);
+
+    Entry {
+        name: "cache".to_string(),
+        mode: Mode::Limited(10),
+        offset: -300,
+        weights: vec![1.5, -0.0, f64::INFINITY],
+        tags,
+        data: vec![(0, i128::MIN), (255, i128::MAX)],
+    }
+}
+
+#[test]
+fn serde_round_trip() {
+    let entry = entry();
+
+    let bytes = to_bytes(&entry).unwrap();
+    assert!(bytes.starts_with(b"TYFB"));
+    assert_eq!(from_bytes::<Entry>(&bytes).unwrap(), entry);
+
+    let mut written = Vec::new();
+    to_writer_binary(&mut written, &entry).unwrap();
+    assert_eq!(written, bytes);
+
+    assert_eq!(
+        from_bytes::<Mode>(&to_bytes(&Mode::Fast).unwrap()).unwrap(),
+        Mode::Fast
+    );
+}
+
+#[test]
+fn keeps_type_identifiers() {
+    let value = Value::parse(
+        r#"Wrapper<Vec<u8>, (i32, [u8; 4])>(
+            inner: path::Variant(b"\x00\xff", 'c', ()),
+            kind: [u8; 4],
+            unit: Unit::Variant,
+            map: {1: -1, 340282366920938463463374607431768211455: 1e300},
+        )"#,
+    )
+    .unwrap();
+
+    let bytes = value.to_bytes();
+    assert_eq!(Value::from_bytes(&bytes).unwrap(), value);
+    assert_eq!(
+        Value::from_bytes(&bytes).unwrap().to_string_compact(),
+        value.to_string_compact()
+    );
+}
+
+#[test]
+fn invalid_input() {
+    let bytes = Value::parse(r#"("a", [1, 2])"#).unwrap().to_bytes();
+
+    assert!(Value::from_bytes(b"").is_err());
+    assert!(Value::from_bytes(b"TYFB\x02\x00").is_err());
+    for end in 5..bytes.len() {
+        assert!(Value::from_bytes(&bytes[..end]).is_err());
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(Value::from_bytes(&trailing).is_err());
+
+    // A list claiming more items than there are bytes
+    assert!(Value::from_bytes(b"TYFB\x01\x0d\xff\xff\xff\xff\x0f").is_err());
+    // An unknown tag
+    assert!(Value::from_bytes(b"TYFB\x01\x7f").is_err());
+}
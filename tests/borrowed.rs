@@ -0,0 +1,56 @@
+use serde_derive::Deserialize;
+use std::{borrow::Cow, collections::BTreeMap};
+use typed_format::{from_str_borrowed, value::Value};
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Entry<'a> {
+    name: &'a str,
+    #[serde(borrow)]
+    note: Cow<'a, str>,
+    #[serde(borrow)]
+    tags: BTreeMap<&'a str, &'a [u8]>,
+}
+
+#[test]
+fn borrows_from_values() {
+    let value = Value::parse(
+        r#"Entry(name: "a\tb", note: "x", tags: {"k": b"\x00\x01"})"#,
+    )
+    .unwrap();
+    let entry: Entry = value.deserialize().unwrap();
+
+    assert_eq!(entry.name, "a\tb");
+    assert!(matches!(entry.note, Cow::Borrowed("x")));
+    assert_eq!(entry.tags["k"], &[0, 1]);
+}
+
+#[test]
+fn borrows_from_input() {
+    let input = r##"{"plain": "text", r"raw": r#"with "quotes""#}"##;
+    let map: BTreeMap<&str, &str> = from_str_borrowed(input).unwrap();
+    assert_eq!(map["plain"], "text");
+    assert_eq!(map["raw"], "with \"quotes\"");
+
+    // The strings are slices of the input
+    let range = input.as_bytes().as_ptr_range();
+    assert!(map
+        .iter()
+        .flat_map(|(key, value)| vec![key, value])
+        .all(|s| range.contains(&s.as_ptr())));
+
+    #[derive(Deserialize)]
+    struct Note<'a> {
+        #[serde(borrow)]
+        plain: Cow<'a, str>,
+        #[serde(borrow)]
+        escaped: Cow<'a, str>,
+    }
+    let note: Note =
+        from_str_borrowed(r#"Note(plain: "a", escaped: "\u00e4")"#).unwrap();
+    assert!(matches!(note.plain, Cow::Borrowed("a")));
+    assert!(matches!(note.escaped, Cow::Owned(ref s) if s == "ä"));
+
+    // Escaped strings can't be borrowed
+    let error = from_str_borrowed::<&str>(r#""a\nb""#).unwrap_err();
+    assert!(error.to_string().contains("borrowed string"), "{}", error);
+}
@@ -0,0 +1,153 @@
+//! The tyf command line tool
+//!
+//! Run with `cargo test --features cli`
+
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    process::{Command, Output},
+};
+use typed_format::value::{json, Value};
+
+fn tyf(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tyf"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn fmt_check_convert() {
+    let root = std::env::temp_dir()
+        .join(format!("typed_format_cli_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let config = root.join("config.tyf");
+    let broken = root.join("broken.tyf");
+    fs::write(&config, "Config(\n// The port\nport:80,tags:[\"a\"])").unwrap();
+    fs::write(&broken, "Config(port = 80)").unwrap();
+    let config = config.to_str().unwrap();
+    let broken = broken.to_str().unwrap();
+
+    assert_eq!(tyf(&["fmt", "--check", config]).status.code(), Some(1));
+    assert!(tyf(&["fmt", config]).status.success());
+    assert!(tyf(&["fmt", "--check", config]).status.success());
+    assert_eq!(
+        fs::read_to_string(config).unwrap(),
+        "Config(\n    // The port\n    port: 80,\n    tags: [\n        \"a\",\n    \
+         ],\n)\n"
+    );
+
+    // Fails like other errors, rather than panicking
+    let literal = root.join("literal.tyf");
+    fs::write(&literal, "Config(address: ip\"x\")").unwrap();
+    let literal = literal.to_str().unwrap();
+    assert_eq!(tyf(&["fmt", literal]).status.code(), Some(1));
+
+    assert!(tyf(&["check", config]).status.success());
+    let output = tyf(&["check", config, broken]);
+    assert_eq!(output.status.code(), Some(1));
+    let errors = String::from_utf8(output.stderr).unwrap();
+    assert!(errors.starts_with(broken), "{}", errors);
+    assert!(errors.contains("line 1, column 13"), "{}", errors);
+
+    let output = tyf(&["convert", config]);
+    assert!(output.status.success());
+    let converted =
+        json::from_json(&String::from_utf8(output.stdout).unwrap()).unwrap();
+    assert_eq!(
+        converted,
+        Value::parse(r#"{"port": 80, "tags": ["a"]}"#).unwrap()
+    );
+
+    let json = root.join("config.json");
+    let json = json.to_str().unwrap();
+    assert!(tyf(&["convert", "--to", "json", "--out", json, config])
+        .status
+        .success());
+    let output = tyf(&["convert", json]);
+    let round_trip = Value::parse(&String::from_utf8(output.stdout).unwrap());
+    assert_eq!(round_trip.unwrap(), converted);
+
+    assert_eq!(tyf(&["unknown"]).status.code(), Some(2));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn fmt_keeps_notation() {
+    let root = std::env::temp_dir()
+        .join(format!("typed_format_cli_notation_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    for (name, input, formatted) in &[
+        ("hex", "Config(mask:0xFF)", "Config(\n    mask: 0xFF,\n)\n"),
+        (
+            "separators",
+            "Config(count:1_000)",
+            "Config(\n    count: 1_000,\n)\n",
+        ),
+        (
+            "anchor",
+            "Config(base:&b [1],copy:*b)",
+            "Config(\n    base: &b [\n        1,\n    ],\n    copy: *b,\n)\n",
+        ),
+        (
+            "raw",
+            "Config(s:r#\"a\"#,t:Color :: Red)",
+            "Config(\n    s: r#\"a\"#,\n    t: Color::Red,\n)\n",
+        ),
+    ] {
+        let path = root.join(format!("{}.tyf", name));
+        fs::write(&path, input).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(tyf(&["fmt", "--check", path]).status.code(), Some(1));
+        assert_eq!(fs::read_to_string(path).unwrap(), *input);
+
+        assert!(tyf(&["fmt", path]).status.success());
+        assert_eq!(fs::read_to_string(path).unwrap(), *formatted);
+        assert!(tyf(&["fmt", "--check", path]).status.success());
+    }
+
+    // Map entries keep their text when they are sorted
+    let path = root.join("map.tyf");
+    fs::write(&path, "{zeta:1,\"alpha\":0x2}").unwrap();
+    let path = path.to_str().unwrap();
+    assert!(tyf(&["fmt", path]).status.success());
+    let formatted = if cfg!(feature = "preserve_order") {
+        "{\n    zeta: 1,\n    \"alpha\": 0x2,\n}\n"
+    } else {
+        "{\n    \"alpha\": 0x2,\n    zeta: 1,\n}\n"
+    };
+    assert_eq!(fs::read_to_string(path).unwrap(), formatted);
+
+    // Sorting would put the alias before its anchor
+    if !cfg!(feature = "preserve_order") {
+        let path = root.join("order.tyf");
+        let input = "{b:&x 1,a:*x}";
+        fs::write(&path, input).unwrap();
+        let path = path.to_str().unwrap();
+
+        let output = tyf(&["fmt", path]);
+        assert_eq!(output.status.code(), Some(1));
+        let errors = String::from_utf8(output.stderr).unwrap();
+        assert!(errors.contains("leaving the file as it is"), "{}", errors);
+        assert_eq!(fs::read_to_string(path).unwrap(), input);
+    }
+
+    // Text the printer writes the same way is formatted
+    let path = root.join("plain.tyf");
+    fs::write(&path, "Config(mask:255,count:1000)").unwrap();
+    let path = path.to_str().unwrap();
+    assert!(tyf(&["fmt", path]).status.success());
+    assert_eq!(
+        fs::read_to_string(path).unwrap(),
+        "Config(\n    mask: 255,\n    count: 1000,\n)\n"
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,124 @@
+use typed_format::value::{
+    comments::Comments, path::Path, printer::ValuePrinter, Value,
+};
+
+const DOCUMENT: &str = r#"// Server configuration
+Config(
+    // Where to listen
+    // on startup
+    address: "0.0.0.0", /* not "// a comment" */
+    ports: [
+        80,
+        /* Only with TLS */ 443,
+    ],
+    names: {"r#raw": 'r'},
+)
+// The end"#;
+
+fn path(path: &str) -> Path {
+    Path::parse(path).unwrap()
+}
+
+#[test]
+fn comments_are_accepted() {
+    let value = Value::parse(DOCUMENT).unwrap();
+    assert_eq!(
+        value,
+        Value::parse(r#"Config(address: "0.0.0.0", ports: [80, 443], names: {"r#raw": 'r'})"#)
+            .unwrap()
+    );
+
+    assert_eq!(
+        Value::parse("1 // trailing").unwrap(),
+        Value::parse("1").unwrap()
+    );
+}
+
+#[test]
+fn comments_are_attached() {
+    let (value, comments) = Value::parse_with_comments(DOCUMENT).unwrap();
+    assert_eq!(value, Value::parse(DOCUMENT).unwrap());
+
+    assert_eq!(
+        comments.get(&Path::root()),
+        &["Server configuration".to_string(), "The end".to_string()]
+    );
+    assert_eq!(
+        comments.get(&path("address")),
+        &["Where to listen".to_string(), "on startup".to_string()]
+    );
+    assert_eq!(
+        comments.get(&path("ports")),
+        &["not \"// a comment\"".to_string()]
+    );
+    assert_eq!(
+        comments.get(&path("ports[1]")),
+        &["Only with TLS".to_string()]
+    );
+    assert_eq!(comments.iter().count(), 4);
+}
+
+#[test]
+fn comments_are_printed() {
+    let (value, comments) = Value::parse_with_comments(DOCUMENT).unwrap();
+
+    let mut pretty = String::new();
+    ValuePrinter::pretty()
+        .with_comments(&comments)
+        .write(&value, &mut pretty)
+        .unwrap();
+    assert_eq!(
+        pretty,
+        r#"// Server configuration
+// The end
+Config(
+    // Where to listen
+    // on startup
+    address: "0.0.0.0",
+    // not "// a comment"
+    ports: [
+        80,
+        // Only with TLS
+        443,
+    ],
+    names: {
+        "r#raw": 'r',
+    },
+)"#
+    );
+    assert_eq!(
+        Value::parse_with_comments(&pretty).unwrap(),
+        (value.clone(), comments.clone())
+    );
+
+    let mut inline = String::new();
+    ValuePrinter::inline()
+        .with_comments(&comments)
+        .write(&value, &mut inline)
+        .unwrap();
+    assert_eq!(
+        Value::parse_with_comments(&inline).unwrap(),
+        (value.clone(), comments.clone())
+    );
+
+    let mut auto = String::new();
+    ValuePrinter::auto()
+        .with_comments(&comments)
+        .write(&value, &mut auto)
+        .unwrap();
+    assert!(auto.contains("// Only with TLS\n"), "{}", auto);
+}
+
+#[test]
+fn added_comments() {
+    let mut comments = Comments::new();
+    comments.add(path("[0]"), "first\nsecond");
+
+    let value = Value::parse("[1, 2]").unwrap();
+    let mut pretty = String::new();
+    ValuePrinter::pretty()
+        .with_comments(&comments)
+        .write(&value, &mut pretty)
+        .unwrap();
+    assert_eq!(pretty, "[\n    // first\n    // second\n    1,\n    2,\n]");
+}
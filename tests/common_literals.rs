@@ -0,0 +1,119 @@
+//! The `datetime"..."` and `uuid"..."` literals
+//!
+//! Run with `cargo test --features datetime,uuid`
+
+#![cfg(all(feature = "datetime", feature = "uuid"))]
+
+use serde_derive::{Deserialize, Serialize};
+use typed_format::{
+    literals::Literals,
+    value::{options::ParseOptions, printer::ValuePrinter, Value},
+};
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+struct Event {
+    #[serde(with = "typed_format::literals::uuid")]
+    id: String,
+    #[serde(with = "typed_format::literals::datetime")]
+    at: String,
+    /// Looks like a UUID, but is not written as one
+    name: String,
+}
+
+const PRINTED: &str = r#"Event(id:uuid"67e55044-10b1-426f-9247-bb680e5fe0c8",at:datetime"2023-01-01T12:30:00.250+01:00",name:"00000000-0000-0000-0000-000000000000",)"#;
+
+fn event() -> Event {
+    Event {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+        at: "2023-01-01T12:30:00.250+01:00".to_string(),
+        name: "00000000-0000-0000-0000-000000000000".to_string(),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let event = event();
+
+    assert_eq!(typed_format::to_string_compact(&event).unwrap(), PRINTED);
+    assert_eq!(typed_format::from_str::<Event>(PRINTED).unwrap(), event);
+    let pretty = typed_format::to_string_pretty(&event).unwrap();
+    assert_eq!(typed_format::from_str::<Event>(&pretty).unwrap(), event);
+
+    let mut written = Vec::new();
+    typed_format::to_writer(&mut written, &event).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), PRINTED);
+    let streamed: Event =
+        typed_format::from_reader(PRINTED.as_bytes()).unwrap();
+    assert_eq!(streamed, event);
+
+    // Tagged, so the literals print again and plain strings do not
+    let value = Value::parse(PRINTED).unwrap();
+    assert_eq!(value, Value::new(&event));
+    assert_eq!(value.to_string_compact(), PRINTED);
+
+    // Literals deserialize wherever a string is expected
+    assert_eq!(
+        typed_format::from_str::<Vec<String>>(
+            r#"[uuid"67e55044-10b1-426f-9247-bb680e5fe0c8"]"#
+        )
+        .unwrap(),
+        vec![event.id.clone()]
+    );
+    assert_eq!(
+        typed_format::from_reader::<_, String>(
+            &br#"datetime"2023-01-01T00:00:00Z""#[..]
+        )
+        .unwrap(),
+        "2023-01-01T00:00:00Z"
+    );
+}
+
+/// Without the literals, the tagged values are newtype structs
+#[test]
+fn without_literals() {
+    let literals = Literals::new();
+    let mut printed = String::new();
+    ValuePrinter::compact()
+        .with_literals(&literals)
+        .write(&Value::new(event()), &mut printed)
+        .unwrap();
+    assert!(
+        printed.starts_with(r#"Event(id:Uuid("67e55044"#),
+        "{}",
+        printed
+    );
+
+    let options = ParseOptions {
+        literals,
+        ..Default::default()
+    };
+    assert!(Value::parse_with(PRINTED, &options).is_err());
+    assert_eq!(
+        Value::parse_with(&printed, &options).unwrap(),
+        Value::new(event())
+    );
+}
+
+#[test]
+fn invalid_literals() {
+    let options = ParseOptions::default();
+
+    for valid in &[
+        r#"datetime"2023-01-01T00:00:00Z""#,
+        r#"datetime"2023-01-01T00:00:00""#,
+        r#"datetime"2023-12-31t23:59:60.5-08:00""#,
+        r#"uuid"67E55044-10B1-426F-9247-BB680E5FE0C8""#,
+    ] {
+        assert!(Value::parse_with(valid, &options).is_ok(), "{}", valid);
+    }
+    for invalid in &[
+        r#"datetime"2023-01-01""#,
+        r#"datetime"2023-13-01T00:00:00Z""#,
+        r#"datetime"2023-01-01T00:00:00.Z""#,
+        r#"datetime"2023-01-01T00:00:00+0100""#,
+        r#"uuid"67e55044-10b1-426f-9247""#,
+        r#"uuid"67e55044-10b1-426f-9247-bb680e5fe0cg""#,
+    ] {
+        assert!(Value::parse_with(invalid, &options).is_err(), "{}", invalid);
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use typed_format::value::{
+    compact::{CompactValue, ValueRef},
+    number::Number,
+    options::ParseOptions,
+    types::Identifier,
+    Value,
+};
+
+const DOCUMENT: &str = r#"Services(
+    web: Service(
+        name: "web",
+        limits: &limits Limits(cpu: 2, memory: 512),
+        ports: [80, 443, 0x1_0000_0000_0000_0000],
+        hosts: {"b": "web.example.com", a: None, "b": Some("other")},
+    ),
+    api: Service(
+        name: "api",
+        limits: *limits,
+        ports: [],
+        hosts: {[1, 2]: (), [1, 2]: 'x', Color::Red: b"\x00"},
+    ),
+    types: [<Vec<u8>>, [u8; 4], (1.5, -2)],
+)"#;
+
+#[test]
+fn parse() {
+    let compact = CompactValue::parse(DOCUMENT).unwrap();
+    let value = Value::parse(DOCUMENT).unwrap();
+    assert_eq!(compact.to_value(), value);
+    assert_eq!(CompactValue::from_value(&value).to_value(), value);
+
+    // Errors are reported like those of values
+    let input = "[(a: 1, b: {1: 2, 1: 3}), (a: 1, a: 2)]";
+    let rejecting = ParseOptions {
+        reject_duplicate_keys: true,
+        ..Default::default()
+    };
+    for (input, options) in &[
+        ("[1, (a: ]", &ParseOptions::default()),
+        (input, &rejecting),
+        ("(a: 1, a: 2)", &rejecting),
+    ] {
+        assert_eq!(
+            CompactValue::parse_with(input, options)
+                .unwrap_err()
+                .to_string(),
+            Value::parse_with(input, options).unwrap_err().to_string()
+        );
+    }
+    assert_eq!(
+        CompactValue::parse(input).unwrap().to_value(),
+        Value::parse(input).unwrap()
+    );
+
+    for input in &[
+        "()",
+        "Foo()",
+        "Foo ( /* empty */ )",
+        "Some ( [1, Some(\"a\")] ) // comment",
+        "a::B<u8>(x: 1, \"y z\": r#\"raw\"#)",
+        "[a::Unit, Unit, true, None, inf, -NaN, 1e3, 'c', <u8>]",
+        "{key: 1, \"key2\": &x [2], (1, 2): *x}",
+        "(a: Some(()), b: [], c: {}, d: ())",
+    ] {
+        assert_eq!(
+            CompactValue::parse(input).unwrap().to_value(),
+            Value::parse(input).unwrap(),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn read() {
+    let compact = CompactValue::parse(DOCUMENT).unwrap();
+
+    let mut services = match compact.root().get() {
+        ValueRef::Struct(name, fields) => {
+            assert_eq!(name.to_string(), "Services");
+            fields
+        },
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(services.len(), 3);
+
+    let (field, web) = services.next().unwrap();
+    assert_eq!(field, "web");
+    let fields = match web.get() {
+        ValueRef::Struct(_, fields) => fields.collect::<Vec<_>>(),
+        other => panic!("{:?}", other),
+    };
+    assert!(matches!(fields[0].1.get(), ValueRef::String("web")));
+
+    let ports = match fields[2].1.get() {
+        ValueRef::List(items) => {
+            items.map(|item| item.get()).collect::<Vec<_>>()
+        },
+        other => panic!("{:?}", other),
+    };
+    assert!(matches!(
+        ports.as_slice(),
+        [
+            ValueRef::Number(Number::U64(80)),
+            ValueRef::Number(Number::U64(443)),
+            ValueRef::Number(Number::U128(_)),
+        ]
+    ));
+
+    // Entries are kept in written order, a repeated key replaces the
+    // value in place
+    let hosts = match fields[3].1.get() {
+        ValueRef::Map(entries) => entries
+            .map(|(key, value)| (key.to_value(), value.to_value()))
+            .collect::<Vec<_>>(),
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(
+        hosts,
+        vec![
+            (Value::new("b"), Value::new(Some("other"))),
+            (Value::UnitStruct("a".into()), Value::Option(None)),
+        ]
+    );
+}
+
+#[test]
+fn shared_strings() {
+    let compact =
+        CompactValue::parse(r#"[(name: "a"), (name: "a"), "a"]"#).unwrap();
+
+    let strings = match compact.to_value() {
+        Value::List(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => s.clone(),
+                Value::Struct(_, fields) => {
+                    match &fields[&Identifier::from("name")] {
+                        Value::String(s) => s.clone(),
+                        other => panic!("{:?}", other),
+                    }
+                },
+                other => panic!("{:?}", other),
+            })
+            .collect::<Vec<_>>(),
+        other => panic!("{:?}", other),
+    };
+    assert!(Arc::ptr_eq(&strings[0], &strings[1]));
+    assert!(Arc::ptr_eq(&strings[0], &strings[2]));
+}
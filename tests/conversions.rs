@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use typed_format::value::{types::Identifier, Value};
+
+#[test]
+fn into_collections() {
+    let list = Value::parse("[1, 2]").unwrap().into_list().unwrap();
+    assert_eq!(list, vec![Value::new(1), Value::new(2)]);
+    assert_eq!(Value::parse("(1, 2)").unwrap().into_list().unwrap(), list);
+
+    let map = Value::parse("{\"a\": 1}").unwrap();
+    assert_eq!(
+        map.clone().into_map().unwrap(),
+        vec![(Value::new("a"), Value::new(1))].into_iter().collect()
+    );
+    let strings = map.into_string_map().unwrap();
+    assert_eq!(strings["a"], Value::new(1));
+
+    let fields = Value::parse("Point(x: 1, y: 2)")
+        .unwrap()
+        .into_struct_fields()
+        .unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[&Identifier::from("y")], Value::new(2));
+}
+
+#[test]
+fn kind_mismatch() {
+    let error = Value::parse("[1]").unwrap().into_map().unwrap_err();
+    assert_eq!(error.to_string(), "Expected a map, found a list");
+
+    assert!(Value::parse("Point(1, 2)").unwrap().into_list().is_err());
+    assert!(Value::parse("{1: 2}").unwrap().into_string_map().is_err());
+    assert!(Value::parse("{}").unwrap().into_struct_fields().is_err());
+}
+
+#[test]
+fn from_collections() {
+    let list: Value = (1..3).map(Value::new).collect();
+    assert_eq!(list, Value::parse("[1, 2]").unwrap());
+    assert_eq!(
+        Value::from(vec![Value::Unit]),
+        Value::parse("[()]").unwrap()
+    );
+
+    let mut strings = HashMap::new();
+    strings.insert("a".to_string(), Value::Unit);
+    assert_eq!(Value::from(strings), Value::parse("{\"a\": ()}").unwrap());
+
+    let map: Value = vec![(Value::new(1), Value::Bool(true))]
+        .into_iter()
+        .collect();
+    assert_eq!(map, Value::from(map.clone().into_map().unwrap()));
+    assert_eq!(map, Value::parse("{1: true}").unwrap());
+
+    let point = Value::from_struct_fields(
+        "Point",
+        vec![("x", Value::new(1)), ("y", Value::new(2))],
+    );
+    assert_eq!(point, Value::parse("Point(y: 2, x: 1)").unwrap());
+}
+
+#[test]
+fn standard_traits() {
+    let value: Value = "Point(x: 1, y: [2, 3])".parse().unwrap();
+    assert_eq!(value.to_string(), "Point(x: 1, y: [2, 3])");
+    assert_eq!(format!("{}", value), value.to_string_auto());
+    assert_eq!(value.to_string().parse::<Value>().unwrap(), value);
+
+    let error = "Point(x: )".parse::<Value>().unwrap_err();
+    assert_eq!((error.line, error.column), (1, 10));
+}
@@ -0,0 +1,160 @@
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use typed_format::{
+    de::{TextDeserializer, TextDeserializerError},
+    from_reader, from_str,
+};
+
+type Document = BTreeMap<String, Vec<Result<Option<(u8, char)>, String>>>;
+
+fn document() -> Document {
+    let mut document = BTreeMap::new();
+    document.insert(
+        "first".to_string(),
+        vec![Ok(Some((1, 'a'))), Ok(None), Err("failed".to_string())],
+    );
+    document.insert("second \"quoted\"".to_string(), vec![]);
+    document
+}
+
+#[test]
+fn matches_from_str() {
+    let document = document();
+
+    for text in &[
+        typed_format::to_string_pretty(&document).unwrap(),
+        typed_format::to_string_compact(&document).unwrap(),
+    ] {
+        let streamed: Document = from_reader(text.as_bytes()).unwrap();
+        assert_eq!(streamed, document);
+        assert_eq!(from_str::<Document>(text).unwrap(), document);
+    }
+}
+
+#[test]
+fn syntax() {
+    let text = r##"
+        // Comments are skipped
+        Point(
+            x: 1, /* block */
+            "y": -2,
+        )
+    "##;
+    let point: BTreeMap<String, i64> = from_reader(text.as_bytes()).unwrap();
+    assert_eq!(point["x"], 1);
+    assert_eq!(point["y"], -2);
+
+    let pair: (u8, String) =
+        from_reader(&b"Pair(1, r#\"raw \"\"#)"[..]).unwrap();
+    assert_eq!(pair, (1, "raw \"".to_string()));
+
+    let unit: () = from_reader(&b"()"[..]).unwrap();
+    assert_eq!(unit, ());
+
+    let escaped: (char, String) =
+        from_reader(&b"('\\n', \"\\u00e4\\t\\\"\")"[..]).unwrap();
+    assert_eq!(escaped, ('\n', "\u{e4}\t\"".to_string()));
+
+    let floats: Vec<f64> = from_reader(&b"[1.5, -2e3, 4]"[..]).unwrap();
+    assert_eq!(floats, vec![1.5, -2e3, 4.0]);
+
+    let bytes: Vec<Option<u8>> = from_reader(&b"[Some(1), None,]"[..]).unwrap();
+    assert_eq!(bytes, vec![Some(1), None]);
+}
+
+#[test]
+fn errors() {
+    let error = from_reader::<_, Vec<u8>>(&b"[1, 2\n 3]"[..]).unwrap_err();
+    match error {
+        TextDeserializerError::Syntax { line, .. } => assert_eq!(line, 2),
+        other => panic!("Unexpected error {:?}", other),
+    }
+
+    assert!(from_reader::<_, u8>(&b"1 2"[..]).is_err());
+
+    // Aliases need the whole document, which `from_str` has
+    let aliased = "[&a [1, 2], *a]";
+    let error = from_reader::<_, Vec<Vec<u8>>>(aliased.as_bytes()).unwrap_err();
+    match error {
+        TextDeserializerError::Alias { line, column } => {
+            assert_eq!((line, column), (1, 2))
+        },
+        other => panic!("Unexpected error {:?}", other),
+    }
+    assert_eq!(
+        from_str::<Vec<Vec<u8>>>(aliased).unwrap(),
+        vec![vec![1, 2], vec![1, 2]]
+    );
+    assert!(from_reader::<_, u8>(&b"300"[..]).is_err());
+    assert!(from_reader::<_, String>(&b"\"unterminated"[..]).is_err());
+
+    // Fails instead of overflowing the stack
+    let nested = "[".repeat(200_000);
+    let error =
+        from_reader::<_, serde::de::IgnoredAny>(nested.as_bytes()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Nesting exceeds the maximum depth of 128 at 1:129"
+    );
+    let generic =
+        format!("<{}u8{}>", "Vec<".repeat(20_000), ">".repeat(20_000));
+    assert!(
+        from_reader::<_, typed_format::value::Value>(generic.as_bytes())
+            .is_err()
+    );
+
+    let nested = format!("{}{}", "[".repeat(200), "]".repeat(200));
+    let mut deserializer =
+        TextDeserializer::new(nested.as_bytes()).max_depth(Some(200));
+    <serde::de::IgnoredAny as serde::Deserialize>::deserialize(
+        &mut deserializer,
+    )
+    .unwrap();
+    deserializer.end().unwrap();
+    assert!(from_reader::<_, serde::de::IgnoredAny>(nested.as_bytes()).is_err());
+}
+
+#[test]
+fn chunked_strings() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Text {
+        body: Vec<String>,
+        raw: Vec<String>,
+        first: (String,),
+        short: String,
+    }
+
+    let text = r###"Text(
+        body: "abcdefgh\u00e4ij",
+        raw: r##"ab"#cd"##,
+        first: "abcdef",
+        short: "xyz",
+    )"###;
+    let mut deserializer =
+        TextDeserializer::new(text.as_bytes()).max_string_length(3);
+    let chunked: Text =
+        serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    deserializer.end().unwrap();
+
+    assert_eq!(
+        chunked,
+        Text {
+            body: vec!["abc", "def", "gh", "\u{e4}i", "j"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            raw: vec!["ab\"".to_string(), "#cd".to_string()],
+            first: ("abc".to_string(),),
+            short: "xyz".to_string(),
+        }
+    );
+
+    let mut deserializer =
+        TextDeserializer::new(&b"\"abcd\""[..]).max_string_length(3);
+    let error = <String as serde::Deserialize>::deserialize(&mut deserializer)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "String exceeds the maximum length of 3 at 1:5"
+    );
+}
@@ -0,0 +1,194 @@
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{collections::BTreeMap, fmt};
+use typed_format::value::Value;
+
+/// Self-describing target, like `serde_json::Value`
+#[derive(PartialEq, Debug)]
+enum Any {
+    Unit,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F64(f64),
+    Char(char),
+    String(String),
+    Option(Option<Box<Any>>),
+    Seq(Vec<Any>),
+    Map(Vec<(Any, Any)>),
+}
+
+struct AnyVisitor;
+
+impl<'de> Visitor<'de> for AnyVisitor {
+    type Value = Any;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "anything")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Any, E> {
+        Ok(Any::Bool(v))
+    }
+    fn visit_i64<E>(self, v: i64) -> Result<Any, E> {
+        Ok(Any::I64(v))
+    }
+    fn visit_u64<E>(self, v: u64) -> Result<Any, E> {
+        Ok(Any::U64(v))
+    }
+    fn visit_i128<E>(self, v: i128) -> Result<Any, E> {
+        Ok(Any::I128(v))
+    }
+    fn visit_u128<E>(self, v: u128) -> Result<Any, E> {
+        Ok(Any::U128(v))
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<Any, E> {
+        Ok(Any::F64(v))
+    }
+    fn visit_char<E>(self, v: char) -> Result<Any, E> {
+        Ok(Any::Char(v))
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Any, E> {
+        Ok(Any::String(v.to_string()))
+    }
+    fn visit_none<E>(self) -> Result<Any, E> {
+        Ok(Any::Option(None))
+    }
+    fn visit_some<D>(self, d: D) -> Result<Any, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Any::Option(Some(Box::new(Any::deserialize(d)?))))
+    }
+    fn visit_unit<E>(self) -> Result<Any, E> {
+        Ok(Any::Unit)
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Any, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Any::Seq(items))
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Any, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Any::Map(entries))
+    }
+}
+
+impl<'de> Deserialize<'de> for Any {
+    fn deserialize<D>(d: D) -> Result<Any, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(AnyVisitor)
+    }
+}
+
+fn any(input: &str) -> Any {
+    Value::parse(input).unwrap().deserialize().unwrap()
+}
+
+#[test]
+fn every_variant() {
+    assert_eq!(any("()"), Any::Unit);
+    assert_eq!(any("true"), Any::Bool(true));
+    assert_eq!(any("'c'"), Any::Char('c'));
+    assert_eq!(any("\"text\""), Any::String("text".to_string()));
+
+    assert_eq!(any("5"), Any::U64(5));
+    assert_eq!(any("-5"), Any::I64(-5));
+    assert_eq!(any("0.5"), Any::F64(0.5));
+
+    assert_eq!(any("Name"), Any::String("Name".to_string()));
+    assert_eq!(any("Enum::Variant"), Any::String("Variant".to_string()));
+    match Value::parse("[u8; 4]").unwrap() {
+        Value::Type(t) => {
+            assert_eq!(any("[u8; 4]"), Any::String(t.to_string()))
+        },
+        other => panic!("Expected a type, found {:?}", other),
+    }
+
+    assert_eq!(any("None"), Any::Option(None));
+    assert_eq!(any("Some(1)"), Any::Option(Some(Box::new(Any::U64(1)))));
+
+    assert_eq!(any("[1, 2]"), Any::Seq(vec![Any::U64(1), Any::U64(2)]));
+    assert_eq!(any("(1, 'a')"), Any::Seq(vec![Any::U64(1), Any::Char('a')]));
+    assert_eq!(any("Pair(1, 2)"), Any::Seq(vec![Any::U64(1), Any::U64(2)]));
+
+    assert_eq!(
+        any("{1: true}"),
+        Any::Map(vec![(Any::U64(1), Any::Bool(true))])
+    );
+    assert_eq!(
+        any("Point(x: 1)"),
+        Any::Map(vec![(Any::String("x".to_string()), Any::U64(1))])
+    );
+
+    // Enum variants map their name to their contents
+    let variant = |contents| {
+        Any::Map(vec![(Any::String("Variant".to_string()), contents)])
+    };
+    assert_eq!(any("Enum::Variant(1)"), variant(Any::U64(1)));
+    assert_eq!(
+        any("Enum::Variant(1, 2)"),
+        variant(Any::Seq(vec![Any::U64(1), Any::U64(2)]))
+    );
+    assert_eq!(
+        any("Enum::Variant(x: 1)"),
+        variant(Any::Map(vec![(Any::String("x".to_string()), Any::U64(1))]))
+    );
+    let streamed: Any =
+        typed_format::from_reader("Enum::Variant(1, 2)".as_bytes()).unwrap();
+    assert_eq!(streamed, any("Enum::Variant(1, 2)"));
+}
+
+#[test]
+fn ignored_values() {
+    let input = r#"{"x": 1, "extra": [Some(Nested(a: {1: 'b'})), (), None]}"#;
+
+    let ignored: BTreeMap<String, IgnoredAny> =
+        Value::parse(input).unwrap().deserialize().unwrap();
+    assert_eq!(ignored.len(), 2);
+
+    let streamed: BTreeMap<String, IgnoredAny> =
+        typed_format::from_reader(input.as_bytes()).unwrap();
+    assert_eq!(streamed.len(), 2);
+}
+
+#[test]
+fn wide_integers() {
+    let input = "[18446744073709551616, -9223372036854775809]";
+    let expected = Any::Seq(vec![
+        Any::U128(u64::MAX as u128 + 1),
+        Any::I128(i64::MIN as i128 - 1),
+    ]);
+
+    assert_eq!(any(input), expected);
+    let streamed: Any = typed_format::from_reader(input.as_bytes()).unwrap();
+    assert_eq!(streamed, expected);
+
+    let values: Vec<i128> = vec![i128::MIN, -1, 0, i128::MAX];
+    let printed = typed_format::to_string_compact(&values).unwrap();
+    assert_eq!(
+        typed_format::from_str::<Vec<i128>>(&printed).unwrap(),
+        values
+    );
+    let streamed: Vec<i128> =
+        typed_format::from_reader(printed.as_bytes()).unwrap();
+    assert_eq!(streamed, values);
+    assert!(typed_format::from_str::<u128>("-1").is_err());
+}
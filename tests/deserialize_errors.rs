@@ -0,0 +1,157 @@
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use typed_format::value::{
+    deserializer::ValueDeserializerError, number::Number, path::Path, Value,
+};
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+enum Backend {
+    Local(Server),
+    Pool { servers: Vec<Server> },
+}
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct Config {
+    servers: Vec<Server>,
+    backends: BTreeMap<String, Backend>,
+    weights: Option<(u8, u8)>,
+}
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+#[serde(deny_unknown_fields)]
+struct Strict {
+    servers: Vec<Server>,
+    backends: BTreeMap<String, Backend>,
+    weights: Option<(u8, u8)>,
+}
+
+fn error(input: &str) -> String {
+    Value::parse(input)
+        .unwrap()
+        .deserialize::<Config>()
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn path_to_the_failing_value() {
+    assert_eq!(
+        error(
+            r#"Config(
+                servers: [
+                    Server(host: "a", port: 80),
+                    Server(host: "b", port: "8080"),
+                ],
+                backends: {},
+                weights: None,
+            )"#
+        ),
+        "servers[1].port: Expected Number, found String"
+    );
+    assert_eq!(
+        error(
+            r#"Config(
+                servers: [],
+                backends: {
+                    "main": Backend::Pool(servers: [Server(host: 1, port: 1)]),
+                },
+                weights: None,
+            )"#
+        ),
+        r#"backends{"main"}.servers[0].host: Expected String, found Number"#
+    );
+    assert_eq!(
+        error(
+            r#"Config(
+                servers: [],
+                backends: {"local": Backend::Local(Server(host: "c"))},
+                weights: None,
+            )"#
+        ),
+        r#"backends{"local"}[0]: missing field `port`"#
+    );
+    assert_eq!(
+        error("Config(servers: [], backends: {}, weights: Some((1, 300)))"),
+        "weights[1]: 300 does not fit the number type"
+    );
+}
+
+#[test]
+fn errors_at_the_root() {
+    let error = Value::parse("[1, 2]")
+        .unwrap()
+        .deserialize::<Config>()
+        .unwrap_err();
+    assert!(error.path().is_root());
+    assert_eq!(error.to_string(), "Expected Struct, found List");
+
+    let error = Value::parse("Config(servers: [Server(host: \"a\")])")
+        .unwrap()
+        .deserialize::<Config>()
+        .unwrap_err();
+    assert_eq!(error.path(), &Path::parse("servers[0]").unwrap());
+}
+
+#[test]
+fn error_variants() {
+    let deserialize = |input: &str| {
+        Value::parse(input)
+            .unwrap()
+            .deserialize::<Config>()
+            .unwrap_err()
+    };
+
+    match deserialize("Config(servers: [Server(host: 1, port: 1)])") {
+        ValueDeserializerError::TypeMismatch {
+            expected,
+            found,
+            path,
+        } => {
+            assert_eq!(expected, "String");
+            assert_eq!(found, "Number");
+            assert_eq!(path, Path::parse("servers[0].host").unwrap());
+        },
+        other => panic!("Expected a type mismatch, found {:?}", other),
+    }
+
+    let strict = Value::parse("Strict(servers: [], backends: {}, port: 1)")
+        .unwrap()
+        .deserialize::<Strict>()
+        .unwrap_err();
+    match strict {
+        ValueDeserializerError::UnknownField {
+            field, expected, ..
+        } => {
+            assert_eq!(field, "port");
+            assert_eq!(expected, &["servers", "backends", "weights"]);
+        },
+        other => panic!("Expected an unknown field, found {:?}", other),
+    }
+
+    match deserialize("Config(servers: [], weights: None)") {
+        ValueDeserializerError::MissingField { field, path } => {
+            assert_eq!(field, "backends");
+            assert!(path.is_root());
+        },
+        other => panic!("Expected a missing field, found {:?}", other),
+    }
+
+    match deserialize(
+        "Config(servers: [], backends: {}, weights: Some((-1, 1)))",
+    ) {
+        ValueDeserializerError::InvalidNumber { number, .. } => {
+            assert_eq!(number, Number::I64(-1));
+        },
+        other => panic!("Expected an invalid number, found {:?}", other),
+    }
+}
@@ -0,0 +1,122 @@
+use typed_format::value::{diagnostic::ParseError, Value};
+
+fn syntax_error(input: &str) -> ParseError {
+    Value::parse(input).unwrap_err()
+}
+
+/// The suggestions turn the input into the expected, valid document
+fn assert_fixed(input: &str, expected: &str) {
+    let error = syntax_error(input);
+    assert_eq!(error.fixed(), expected, "{}", error);
+    Value::parse(expected).unwrap();
+}
+
+#[test]
+fn equals_instead_of_colon() {
+    assert_fixed("Point(x = 1, y = 2)", "Point(x : 1, y : 2)");
+    assert_fixed("{1 => 2}", "{1 : 2}");
+
+    let error = syntax_error("Point(x = 1)");
+    assert_eq!(error.suggestions.len(), 1);
+    assert_eq!(error.suggestions[0].edits[0].span, 8..9);
+    assert!(error
+        .to_string()
+        .contains("help: use ':' instead of '=' (1:9)"));
+}
+
+#[test]
+fn missing_commas() {
+    assert_fixed("[1 2 3]", "[1, 2, 3]");
+    assert_fixed(
+        "Point(\n    a: \"x\"\n    b: 'y'\n)",
+        "Point(\n    a: \"x\",\n    b: 'y'\n)",
+    );
+    assert_fixed("[Some(1) None]", "[Some(1), None]");
+}
+
+#[test]
+fn unbalanced_delimiters() {
+    assert_fixed("[1, (2, 3]", "[1, (2, 3)]");
+    assert_fixed("[1, 2", "[1, 2]");
+    assert_fixed("(1, 2))", "(1, 2)");
+
+    // The help points at the insertion, the message at the opening
+    let error = syntax_error("[1, 2");
+    assert!(
+        error
+            .to_string()
+            .contains("help: close the '[' opened at 1:1 (1:6)"),
+        "{}",
+        error
+    );
+    let error = syntax_error("[\n  (1, 2,\n]");
+    assert!(
+        error
+            .to_string()
+            .contains("help: close the '(' opened at 2:3 (3:1)"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn json_style() {
+    assert_fixed("Point { \"x\": 1, \"y\": null }", "Point ( x: 1, y: None )");
+    assert_fixed("['abc']", "[\"abc\"]");
+}
+
+#[test]
+fn unterminated_strings() {
+    // Not a quoted field name, though it starts with a quote
+    let error = syntax_error("Point { \"ä");
+    assert!(error.to_string().contains("close the string"), "{}", error);
+}
+
+#[test]
+fn no_suggestions_for_valid_parts() {
+    let error = syntax_error("Foo<u8>(a: [1, 2], b: Bar::Baz) $");
+    assert!(error.suggestions.is_empty(), "{}", error);
+}
+
+#[test]
+fn location_and_excerpt() {
+    let error = syntax_error("Config(\n    name: \"a\",\n\tport: $,\n)");
+    assert_eq!(error.offset, 30);
+    assert_eq!((error.line, error.column), (3, 8));
+    assert_eq!(error.snippet(), "\tport: $,");
+    assert!(error.expected.iter().any(|rule| rule == "number"));
+    assert!(error.expected.iter().any(|rule| rule == "string"));
+    assert!(error.to_string().starts_with(
+        "expected identifier, generic_type, type_value, bool_true, bool_false"
+    ));
+    assert!(
+        error.to_string().ends_with(
+            ", anchored or alias at line 3, column 8\n  |\n3 | \tport: $,\n  | \t      ^"
+        ),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn operators_only_expected_with_exprs() {
+    let error = syntax_error("Foo(a = 1)");
+    assert_eq!(
+        error.expected.iter().any(|rule| rule == "operator"),
+        cfg!(feature = "exprs"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn invalid_values_are_located() {
+    let error = syntax_error("[\n    b\"abc\",\n    (1, ip\"::1\"),\n]");
+    assert!(error.expected.is_empty());
+    assert_eq!((error.line, error.column), (3, 9));
+    assert_eq!(
+        error.to_string(),
+        "Unknown literal prefix \"ip\" at line 3, column 9\n  \
+         |\n3 |     (1, ip\"::1\"),\n  |         ^"
+    );
+}
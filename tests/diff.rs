@@ -0,0 +1,87 @@
+use typed_format::value::{diff::Change, path::Path, Value};
+
+#[test]
+fn diff_values() {
+    let old = Value::parse(
+        r#"Config(
+            name: "server",
+            port: 80,
+            tags: ["a", "b", "c"],
+            limits: {"cpu": 2, "memory": 512},
+            tls: Some(Tls(cert: "old.pem")),
+        )"#,
+    )
+    .unwrap();
+    let new = Value::parse(
+        r#"Config(
+            name: "server",
+            port: 8080,
+            tags: ["a", "x"],
+            limits: {"cpu": 2, "disk": 10},
+            tls: Some(Tls(cert: "new.pem")),
+            host: "localhost",
+        )"#,
+    )
+    .unwrap();
+
+    assert!(old.diff(&old).is_empty());
+
+    let diff = old.diff(&new);
+    let paths = diff
+        .iter()
+        .map(|change| change.path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            r#"limits{"memory"}"#,
+            r#"limits{"disk"}"#,
+            "port",
+            "tags[1]",
+            "tags[2]",
+            "tls.cert",
+            "host",
+        ]
+    );
+
+    assert_eq!(
+        diff.changes[2],
+        Change::Changed {
+            path: Path::parse("port").unwrap(),
+            old: Value::parse("80").unwrap(),
+            new: Value::parse("8080").unwrap(),
+        }
+    );
+    assert_eq!(
+        diff.changes[4],
+        Change::Removed {
+            path: Path::parse("tags[2]").unwrap(),
+            value: Value::parse(r#""c""#).unwrap(),
+        }
+    );
+
+    assert_eq!(
+        diff.to_string(),
+        r#"- limits{"memory"}: 512
++ limits{"disk"}: 10
+- port: 80
++ port: 8080
+- tags[1]: "b"
++ tags[1]: "x"
+- tags[2]: "c"
+- tls.cert: "old.pem"
++ tls.cert: "new.pem"
++ host: "localhost"
+"#
+    );
+}
+
+#[test]
+fn diff_different_types() {
+    let old = Value::parse("A(x: 1)").unwrap();
+    let new = Value::parse("B(x: 1)").unwrap();
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff.to_string(), "- (root): A(x: 1)\n+ (root): B(x: 1)\n");
+}
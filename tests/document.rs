@@ -0,0 +1,209 @@
+use typed_format::{
+    document::{Document, EditError, Item},
+    value::Value,
+};
+
+const CONFIG: &str = r#"// Server configuration
+Config(
+    /* where to listen */
+    address: "localhost",
+    port: 8080, // default port
+    tags: [
+        "a",
+        "b",
+    ],
+    limits: {"requests": 10, "bytes": 4096},
+    array: [u8; 4],
+)
+// end
+"#;
+
+#[test]
+fn prints_back_unchanged() {
+    for input in &[
+        CONFIG,
+        "  [1,2 , 3 ]  ",
+        "Some ( ( a : \"quoted \\\" ]\", b: ')' ) )",
+        "Vec<u8>::Empty",
+        "{(1, 2): [], \"x\": ()}",
+        "Point(1, -2.5e3,)",
+        r##"[r"C:\dir", r#"a "b""#]"##,
+        "1 /*/ comment */",
+        "true0b::\ninf0x",
+        "[Foo :: /* path */ Bar, Vec<u8> ::Empty]",
+    ] {
+        let document = Document::parse(input).unwrap();
+        assert_eq!(document.to_string(), *input);
+        assert_eq!(document.to_value().unwrap(), Value::parse(input).unwrap());
+    }
+}
+
+#[cfg(all(feature = "datetime", feature = "uuid"))]
+#[test]
+fn prefixed_literals() {
+    let input = r#"Event(
+    id: uuid"67e55044-10b1-426f-9247-bb680e5fe0c8", // generated
+    at: [datetime"2023-01-01T00:00:00Z" ,datetime"2023-01-02T00:00:00Z"],
+)"#;
+    let mut document = Document::parse(input).unwrap();
+    assert_eq!(document.to_string(), input);
+    assert_eq!(document.to_value().unwrap(), Value::parse(input).unwrap());
+
+    document
+        .root_mut()
+        .set_field("name", &Value::String("release".into()))
+        .unwrap();
+    assert!(document.to_string().contains("// generated"));
+    assert_eq!(
+        document.to_value().unwrap().get("at").unwrap(),
+        &Value::parse(input).unwrap()["at"]
+    );
+}
+
+#[test]
+fn rejects_invalid_input() {
+    assert!(Document::parse("Config(a: 1").is_err());
+
+    // Too deep generics fail instead of overflowing the stack
+    let generic =
+        format!("<{}u8{}>", "Vec<".repeat(20_000), ">".repeat(20_000));
+    assert!(Document::parse(&generic).is_err());
+}
+
+#[test]
+fn set_existing_field() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    let root = document.root_mut();
+
+    root.set_field("port", &Value::Number(9090u64.into()))
+        .unwrap();
+    root.get_mut("limits")
+        .unwrap()
+        .set_field("bytes", &Value::Number(0u64.into()))
+        .unwrap();
+
+    assert_eq!(
+        document.to_string(),
+        CONFIG
+            .replace("8080", "9090")
+            .replace("\"bytes\": 4096", "\"bytes\": 0")
+    );
+}
+
+#[test]
+fn add_field() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    document
+        .root_mut()
+        .set_field("debug", &Value::Bool(true))
+        .unwrap();
+    document
+        .root_mut()
+        .get_mut("limits")
+        .unwrap()
+        .set_field("time", &Value::Number(5u64.into()))
+        .unwrap();
+
+    assert_eq!(
+        document.to_string(),
+        CONFIG
+            .replace(
+                "    array: [u8; 4],\n",
+                "    array: [u8; 4],\n    debug: true,\n"
+            )
+            .replace("\"bytes\": 4096", "\"bytes\": 4096, \"time\": 5")
+    );
+}
+
+#[test]
+fn push_item() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    let tags = document.root_mut().get_mut("tags").unwrap();
+    tags.push(&Value::String("c".into())).unwrap();
+    assert_eq!(tags.len(), 3);
+    assert_eq!(
+        tags.set_field("name", &Value::Unit),
+        Err(EditError::FieldNotAllowed {
+            key: "name".to_string(),
+            collection: "list",
+        })
+    );
+
+    assert_eq!(
+        document.to_string(),
+        CONFIG.replace("\"b\",\n", "\"b\",\n        \"c\",\n")
+    );
+
+    let mut document = Document::parse("[1]").unwrap();
+    document
+        .root_mut()
+        .push(&Value::Number(2u64.into()))
+        .unwrap();
+    assert_eq!(document.to_string(), "[1, 2]");
+
+    let mut document = Document::parse("Name()").unwrap();
+    document.root_mut().set_field("a", &Value::Unit).unwrap();
+    assert_eq!(document.to_string(), "Name(a: ())");
+}
+
+#[test]
+fn remove_field() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    let removed = document.root_mut().remove_field("address").unwrap();
+    assert_eq!(removed, Item::new(&Value::String("localhost".into())));
+
+    assert_eq!(
+        document.to_string(),
+        CONFIG.replace(
+            "\n    /* where to listen */\n    address: \"localhost\",",
+            ""
+        )
+    );
+}
+
+#[test]
+fn anchors_and_aliases() {
+    let input = r#"Config(
+    defaults: &base {"retries": 3},
+    primary: *base,
+    port: & p 80,
+    backup: [&b 1, *b],
+)"#;
+    let mut document = Document::parse(input).unwrap();
+    assert_eq!(document.to_string(), input);
+    assert_eq!(document.to_value().unwrap(), Value::parse(input).unwrap());
+
+    let defaults = document.root().get("defaults").unwrap();
+    assert_eq!(defaults.len(), 1);
+    assert_eq!(
+        defaults.get("retries").unwrap().to_value().unwrap(),
+        Value::Number(3u64.into())
+    );
+    assert_eq!(document.root().get("backup").unwrap().len(), 2);
+
+    document
+        .root_mut()
+        .get_mut("defaults")
+        .unwrap()
+        .set_field("retries", &Value::Number(5u64.into()))
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        input.replace("\"retries\": 3", "\"retries\": 5")
+    );
+}
+
+#[test]
+fn restore_notation() {
+    let input = r#"Config(mask: 0xFF, base: &b [1_000], copy: *b, name: r"x")"#;
+    let value = Value::parse(input).unwrap();
+    let mut printed = Document::parse(&value.to_string_pretty()).unwrap();
+    printed.restore_notation(&Document::parse(input).unwrap());
+
+    assert_eq!(
+        printed.to_string(),
+        "Config(\n    mask: 0xFF,\n    base: &b [\n        1_000,\n    ],\n    \
+         copy: *b,\n    name: r\"x\",\n)"
+    );
+    assert_eq!(printed.to_value().unwrap(), value);
+}
@@ -0,0 +1,103 @@
+use std::io::{self, Cursor, Read};
+use typed_format::value::{stream::ValueStream, Value};
+
+const LOG: &str = r#"Event(id: 1, kind: "start")
+// Written by the second worker
+Event(
+    id: 2,
+    kind: "tick",
+)
+
+---
+[1, 2]
+---
+"done"
+"#;
+
+fn expected() -> Vec<Value> {
+    vec![
+        Value::parse(r#"Event(id: 1, kind: "start")"#).unwrap(),
+        Value::parse(r#"Event(id: 2, kind: "tick")"#).unwrap(),
+        Value::parse("[1, 2]").unwrap(),
+        Value::parse(r#""done""#).unwrap(),
+    ]
+}
+
+#[test]
+fn parse_all() {
+    assert_eq!(Value::parse_all(LOG).unwrap(), expected());
+    assert_eq!(Value::parse_all("").unwrap(), vec![]);
+    assert_eq!(Value::parse_all("---\n1\n---\n").unwrap().len(), 1);
+    assert_eq!(Value::parse_all("1 --- 2").unwrap().len(), 2);
+
+    let error = Value::parse_all("1\n2 3").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 3));
+    assert!(error.message.contains("separated"), "{}", error);
+}
+
+#[test]
+fn value_stream() {
+    let values = ValueStream::new(Cursor::new(LOG))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values, expected());
+
+    let mut stream = ValueStream::new(Cursor::new("1\n(2,\n---\n3\n"));
+    assert_eq!(stream.next().unwrap().unwrap(), Value::parse("1").unwrap());
+
+    let error = stream.next().unwrap().unwrap_err();
+    assert!(
+        format!("{:#}", error).starts_with("Document at line 2"),
+        "{:#}",
+        error
+    );
+
+    assert_eq!(stream.next().unwrap().unwrap(), Value::parse("3").unwrap());
+    assert!(stream.next().is_none());
+
+    // Values separated on the same line are all read
+    let values = ValueStream::new(Cursor::new("1 --- 2\n3"))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values, Value::parse_all("1\n2\n3").unwrap());
+}
+
+/// Fails the read after the text, to see what was read before it
+struct Broken;
+
+impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("not written yet"))
+    }
+}
+
+#[test]
+fn value_stream_scans_lines() {
+    // Delimiters in strings, comments and chars do not count
+    let text = r##"[
+    "a ( string
+    over lines",
+    r#"raw " ]
+"#,
+    /* a ( comment
+    */ '(', '\'', '[',
+    <Wrapper<'a, u8>>,
+]
+"##;
+    let mut stream = ValueStream::new(text.as_bytes().chain(Broken));
+    assert_eq!(stream.next().unwrap().unwrap(), Value::parse(text).unwrap());
+    assert!(stream.next().unwrap().is_err());
+
+    // Long documents are parsed once, not once per line
+    let long = format!("[\n{}]\n", "    (1, \"(\"),\n".repeat(50_000));
+    let values = ValueStream::new(Cursor::new(&long))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 1);
+
+    let broken = format!("1 2\n{}---\n4\n", "3\n".repeat(20_000));
+    let mut stream = ValueStream::new(Cursor::new(&broken));
+    assert!(stream.next().unwrap().is_err());
+    assert_eq!(stream.next().unwrap().unwrap(), Value::parse("4").unwrap());
+    assert!(stream.next().is_none());
+}
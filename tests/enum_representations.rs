@@ -0,0 +1,236 @@
+pub mod round_trip;
+
+use round_trip::{all_asserts, assert_stream};
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Debug;
+use typed_format::{
+    de::from_reader,
+    from_str,
+    ser::TextSerializer,
+    value::{
+        options::{SerializeOptions, UnitVariantStyle},
+        printer::ValuePrinter,
+        Value,
+    },
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Inner {
+    x: i32,
+    list: Vec<char>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum External {
+    Unit,
+    Newtype(u32),
+    Tuple(u32, String),
+    Struct { a: u32, b: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+    Newtype(Inner),
+    Struct { a: u32, nested: External },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent {
+    Unit,
+    Newtype(u32),
+    Tuple(u32, String),
+    Struct { a: u32, b: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+enum Untagged {
+    Number(u32),
+    Pair(u32, String),
+    Struct { a: u32, b: Option<String> },
+    Inner(Inner),
+    External(External),
+}
+
+fn round_trips<T>(values: Vec<T>)
+where
+    T: serde::Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    for value in &values {
+        all_asserts(value);
+        assert_stream(value);
+    }
+    all_asserts(&values);
+    assert_stream(&values);
+}
+
+fn inner() -> Inner {
+    Inner {
+        x: -1,
+        list: vec!['a', 'b'],
+    }
+}
+
+#[test]
+fn externally_tagged() {
+    round_trips(vec![
+        External::Unit,
+        External::Newtype(1),
+        External::Tuple(1, "s".to_string()),
+        External::Struct {
+            a: 1,
+            b: Some("s".to_string()),
+        },
+    ]);
+}
+
+#[test]
+fn internally_tagged() {
+    round_trips(vec![
+        Internal::Unit,
+        Internal::Newtype(inner()),
+        Internal::Struct {
+            a: 1,
+            nested: External::Tuple(2, "s".to_string()),
+        },
+    ]);
+
+    let value = Value::new(Internal::Newtype(inner()));
+    assert_eq!(
+        value,
+        Value::parse(r#"Inner(type: "Newtype", x: -1, list: ['a', 'b'])"#)
+            .unwrap()
+    );
+}
+
+#[test]
+fn adjacently_tagged() {
+    round_trips(vec![
+        Adjacent::Unit,
+        Adjacent::Newtype(1),
+        Adjacent::Tuple(1, "s".to_string()),
+        Adjacent::Struct { a: 1, b: None },
+    ]);
+}
+
+#[test]
+fn untagged() {
+    round_trips(vec![
+        Untagged::Number(1),
+        Untagged::Pair(1, "s".to_string()),
+        Untagged::Struct {
+            a: 1,
+            b: Some("s".to_string()),
+        },
+        Untagged::Inner(inner()),
+        // Variants of nested enums keep their tag
+        Untagged::External(External::Newtype(1)),
+        Untagged::External(External::Tuple(1, "s".to_string())),
+        Untagged::External(External::Struct { a: 1, b: None }),
+    ]);
+}
+
+#[test]
+fn unit_variant_styles() {
+    let values = vec![External::Unit, External::Newtype(1)];
+    let styles = [
+        (
+            UnitVariantStyle::Path,
+            "[External::Unit,External::Newtype(1,),]",
+        ),
+        (UnitVariantStyle::Name, "[Unit,External::Newtype(1,),]"),
+        (UnitVariantStyle::Call, "[Unit(),External::Newtype(1,),]"),
+    ];
+
+    for (style, text) in styles.iter() {
+        let options = SerializeOptions {
+            unit_variants: *style,
+            ..Default::default()
+        };
+        let value = Value::try_new_with(&values, &options).unwrap();
+        assert_eq!(value.to_string_compact(), *text);
+
+        let mut printed = String::new();
+        ValuePrinter::compact()
+            .unit_variants(*style)
+            .write(&Value::new(&values), &mut printed)
+            .unwrap();
+        assert_eq!(printed, *text);
+
+        let mut written = Vec::new();
+        let mut serializer =
+            TextSerializer::compact(&mut written).unit_variants(*style);
+        serde::Serialize::serialize(&values, &mut serializer).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), *text);
+
+        assert_eq!(value.deserialize::<Vec<External>>().unwrap(), values);
+        assert_eq!(from_str::<Vec<External>>(text).unwrap(), values);
+        let read: Vec<External> = from_reader(text.as_bytes()).unwrap();
+        assert_eq!(read, values);
+    }
+
+    assert_eq!(
+        from_str::<External>("External::Unit()").unwrap(),
+        External::Unit
+    );
+    assert!(from_str::<External>("Unit(1)").is_err());
+    assert!(from_str::<External>("Unit(a: 1)").is_err());
+    assert!(from_reader::<_, External>("Unit(1)".as_bytes()).is_err());
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Config {
+    a: u32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+enum UntaggedConfig {
+    Flag(bool),
+    Config(Config),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "type")]
+enum InternalConfig {
+    A { c: Config, nested: External },
+}
+
+#[test]
+fn type_paths() {
+    type Values = (Vec<UntaggedConfig>, InternalConfig, Vec<Untagged>);
+    let values: Values = (
+        vec![
+            UntaggedConfig::Flag(true),
+            UntaggedConfig::Config(Config { a: 1 }),
+        ],
+        InternalConfig::A {
+            c: Config { a: 2 },
+            nested: External::Newtype(3),
+        },
+        vec![
+            Untagged::Inner(inner()),
+            Untagged::External(External::Struct { a: 4, b: None }),
+        ],
+    );
+    let options = SerializeOptions::default()
+        .with_type_path::<Config>()
+        .with_type_path::<Inner>()
+        .with_type_path::<External>();
+
+    let value = Value::try_new_with(&values, &options).unwrap();
+    let text = value.to_string_compact();
+    assert!(text.contains("enum_representations::Config(a:1,)"));
+    assert!(text.contains("enum_representations::External::Newtype(3,)"));
+
+    assert_eq!(value.deserialize::<Values>().unwrap(), values);
+    assert_eq!(from_str::<Values>(&text).unwrap(), values);
+    let read: Values = from_reader(text.as_bytes()).unwrap();
+    assert_eq!(read, values);
+    let owned: Values = serde::Deserialize::deserialize(value).unwrap();
+    assert_eq!(owned, values);
+}
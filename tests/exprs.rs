@@ -0,0 +1,82 @@
+//! Constant expressions in value position
+//!
+//! Run with `cargo test --features exprs`
+
+#![cfg(feature = "exprs")]
+
+use typed_format::value::{number::Number, options::ParseOptions, Value};
+
+fn number(value: &str) -> Number {
+    match Value::parse(value).unwrap() {
+        Value::Number(number) => number,
+        other => panic!("Expected a number, found {:?}", other),
+    }
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(number("1024 * 1024"), Number::from(1048576));
+    assert_eq!(number("1 + 2 * 3 - 4"), Number::from(3));
+    assert_eq!(number("(1 + 2) * 3"), Number::from(9));
+    assert_eq!(number("7 / 2 + 7 % 2"), Number::from(4));
+    assert_eq!(number("10 - 2 - 3"), Number::from(5));
+    assert_eq!(number("0x10 * -2"), Number::from(-32));
+    assert_eq!(number("7 / 2.0"), Number::from(3.5));
+    assert_eq!(
+        number("18446744073709551615 * 2"),
+        Number::from(36893488147419103230_u128)
+    );
+
+    let value =
+        Value::parse("Limits(size: 64 * 1024, names: [\"a\" + r\"\\b\"])")
+            .unwrap();
+    assert_eq!(
+        value.to_string_compact(),
+        r#"Limits(size:65536,names:["a\\b",],)"#
+    );
+
+    // Without an operator, values parse as before
+    assert_eq!(
+        Value::parse("(1)").unwrap(),
+        Value::Tuple(vec![Value::Number(1.into())])
+    );
+    assert_eq!(Value::parse("-1").unwrap(), Value::Number((-1).into()));
+}
+
+#[test]
+fn constants() {
+    let mut options = ParseOptions::default();
+    options
+        .constants
+        .insert("base".to_string(), Value::String("/srv".into()));
+    options
+        .constants
+        .insert("workers".to_string(), Value::Number(4.into()));
+
+    let value =
+        Value::parse_with(r#"(base + "/data", workers * 2, base)"#, &options)
+            .unwrap();
+    assert_eq!(value.to_string_compact(), r#"("/srv/data",8,base,)"#);
+
+    let error = Value::parse("prefix + \"x\"").unwrap_err();
+    assert!(format!("{:#}", error).contains("Unknown constant prefix"));
+}
+
+#[test]
+fn errors() {
+    let message =
+        |input: &str| format!("{:#}", Value::parse(input).unwrap_err());
+
+    assert!(message("1 / 0")
+        .contains("Could not evaluate `1 / 0`: Division by zero"));
+    assert!(message("170141183460469231731687303715884105727 + 1")
+        .contains("Integer overflow"));
+    assert!(message("\"a\" * 2").contains("Cannot apply * to \"a\" and 2"));
+    assert!(message("\"a\" - \"b\"").contains("Cannot apply -"));
+
+    let options = ParseOptions {
+        max_string_length: Some(3),
+        ..Default::default()
+    };
+    assert!(Value::parse_with("\"ab\" + \"cd\"", &options).is_err());
+}
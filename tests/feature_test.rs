@@ -3,6 +3,7 @@ pub mod round_trip;
 use maplit::*;
 use serde_derive::*;
 use std::collections::HashMap;
+use typed_format::value::{path::Path, printer::ValuePrinter, Value};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 enum Test {
@@ -38,3 +39,168 @@ fn value_round_trip() {
 
     round_trip::all_asserts(&values);
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+struct Unit;
+
+#[test]
+fn unit_struct_round_trip() {
+    assert_eq!(
+        Value::new(&Test::Baz),
+        Value::UnitStruct(("Test", "Baz").into())
+    );
+
+    round_trip::all_asserts(&Unit);
+    round_trip::all_asserts(&vec![Unit, Unit]);
+    round_trip::all_asserts(&(Test::Baz, Unit));
+}
+
+#[test]
+fn top_level_functions() {
+    let value = vec![Test::Foo(1), Test::Bar { bar: 2 }, Test::Baz];
+
+    let pretty = typed_format::to_string_pretty(&value).unwrap();
+    let compact = typed_format::to_string_compact(&value).unwrap();
+    assert_eq!(pretty, Value::new(&value).to_string_pretty());
+    assert!(!compact.contains('\n'));
+
+    for string in &[pretty, compact] {
+        let round_trip: Vec<Test> = typed_format::from_str(string).unwrap();
+        assert_eq!(round_trip, value);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+struct Meters(u32);
+
+#[test]
+fn omitted_names() {
+    let value = (
+        Point { x: 1, y: -2 },
+        Meters(5),
+        Unit,
+        vec![Test::Foo(1), Test::Bar { bar: 2 }, Test::Baz],
+    );
+    let original = Value::new(&value);
+
+    let mut terse = String::new();
+    ValuePrinter::compact()
+        .omit_names(true)
+        .write(&original, &mut terse)
+        .unwrap();
+    assert_eq!(terse, "((x:1,y:-2,),(5,),(),[Foo(1,),Bar(bar:2,),Baz,],)");
+
+    let parsed = Value::parse(&terse).unwrap();
+    assert_eq!(
+        parsed
+            .deserialize::<(Point, Meters, Unit, Vec<Test>)>()
+            .unwrap(),
+        value
+    );
+    assert_eq!(
+        parsed.retag::<(Point, Meters, Unit, Vec<Test>)>().unwrap(),
+        original
+    );
+}
+
+#[test]
+fn unknown_fields_are_skipped() {
+    let input = "Point(x: 1, y: 2, z: Some([Test::Foo(3), Test::Baz]))";
+
+    assert_eq!(
+        typed_format::from_str::<Point>(input).unwrap(),
+        Point { x: 1, y: 2 }
+    );
+    assert_eq!(
+        typed_format::from_reader::<_, Point>(input.as_bytes()).unwrap(),
+        Point { x: 1, y: 2 }
+    );
+}
+
+#[test]
+fn typed_keeps_unknown_fields() {
+    let input = "Point(x: 1, y: 2, label: \"origin\", extra: {\"a\": [1]})";
+
+    let mut point = typed_format::from_str_typed::<Point>(input).unwrap();
+    assert_eq!(*point, Point { x: 1, y: 2 });
+
+    let unknown = point.unknown_fields().keys().collect::<Vec<_>>();
+    assert_eq!(
+        unknown,
+        vec![&Path::root().field("extra"), &Path::root().field("label")]
+    );
+
+    point.x = 5;
+    let written = point.to_string_compact().unwrap();
+    let expected = input.replace("x: 1", "x: 5");
+    assert_eq!(
+        Value::parse(&written).unwrap(),
+        Value::parse(&expected).unwrap()
+    );
+    assert_eq!(point.original(), &Value::parse(input).unwrap());
+
+    point.clear_unknown_fields();
+    assert_eq!(point.to_string_compact().unwrap(), "Point(x:5,y:2,)");
+}
+
+#[test]
+fn typed_keeps_nested_unknown_fields() {
+    let input = "[Some(Point(x: 1, y: 2, z: 3)), None]";
+
+    let mut points =
+        typed_format::from_str_typed::<Vec<Option<Point>>>(input).unwrap();
+    assert_eq!(
+        points.unknown_fields().keys().collect::<Vec<_>>(),
+        vec![&Path::root().index(0).field("z")]
+    );
+
+    points[0].as_mut().unwrap().y = 7;
+    assert_eq!(
+        Value::parse(&points.to_string_compact().unwrap()).unwrap(),
+        Value::parse(&input.replace("y: 2", "y: 7")).unwrap()
+    );
+
+    // The struct holding the unknown field is gone, so is the field
+    points[0] = None;
+    assert_eq!(points.to_string_compact().unwrap(), "[None,None,]");
+}
+
+#[test]
+fn typed_unknown_fields_stay_with_their_items() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Server {
+        name: String,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Config {
+        servers: Vec<Server>,
+    }
+
+    let input = r#"Config(servers: [
+        Server(name: "a", secret: "A-ONLY"),
+        Server(name: "b"),
+    ])"#;
+    let mut config = typed_format::from_str_typed::<Config>(input).unwrap();
+
+    config.servers.remove(0);
+    assert_eq!(
+        config.to_string_compact().unwrap(),
+        r#"Config(servers:[Server(name:"b",),],)"#
+    );
+
+    // The same goes for added items, another one could take the index
+    let mut config = typed_format::from_str_typed::<Config>(input).unwrap();
+    config.servers.insert(
+        0,
+        Server {
+            name: "new".to_string(),
+        },
+    );
+    assert!(!config.to_string_compact().unwrap().contains("A-ONLY"));
+}
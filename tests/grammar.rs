@@ -0,0 +1,28 @@
+use typed_format::{
+    grammar::{rule, rules, rules_value, RuleKind},
+    value::Value,
+};
+
+#[test]
+fn rules_match_grammar() {
+    let value = rule("value").unwrap();
+    assert_eq!(value.kind, RuleKind::Silent);
+    assert!(value.children.contains(&"named_struct"));
+
+    assert_eq!(rule("number").unwrap().kind, RuleKind::Atomic);
+    assert!(rule("does_not_exist").is_none());
+
+    for it in rules() {
+        for child in it.children {
+            assert!(rule(child).is_some(), "{} is not a rule", child);
+        }
+    }
+}
+
+#[test]
+fn rules_as_value() {
+    let value = rules_value();
+    let string = value.to_string_pretty();
+
+    assert_eq!(Value::parse(&string).unwrap(), value);
+}
@@ -0,0 +1,72 @@
+use serde_derive::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use typed_format::{
+    de::TextDeserializer,
+    ser::TextSerializer,
+    value::{
+        options::{DeserializeOptions, SerializeOptions},
+        Value,
+    },
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Host {
+    address: IpAddr,
+}
+
+fn host() -> Host {
+    Host {
+        address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+    }
+}
+
+#[test]
+fn value_representations() {
+    let readable = Value::try_new(host()).unwrap();
+    assert_eq!(
+        readable,
+        Value::parse(r#"Host(address: "10.0.0.1")"#).unwrap()
+    );
+
+    let compact = Value::try_new_with(
+        &host(),
+        &SerializeOptions {
+            compact_representations: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        compact,
+        Value::parse("Host(address: IpAddr::V4((10, 0, 0, 1)))").unwrap()
+    );
+
+    let options = DeserializeOptions {
+        compact_representations: true,
+        ..Default::default()
+    };
+    assert_eq!(compact.deserialize_with::<Host>(&options).unwrap(), host());
+    assert!(compact.deserialize::<Host>().is_err());
+    assert!(readable.deserialize_with::<Host>(&options).is_err());
+}
+
+#[test]
+fn streaming_representations() {
+    let mut serializer =
+        TextSerializer::compact(Vec::new()).human_readable(false);
+    serde::Serialize::serialize(&host(), &mut serializer).unwrap();
+    let text = String::from_utf8(serializer.into_inner()).unwrap();
+    assert!(!text.contains("10.0.0.1"), "{}", text);
+
+    let mut deserializer =
+        TextDeserializer::new(text.as_bytes()).human_readable(false);
+    let read: Host =
+        serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    deserializer.end().unwrap();
+    assert_eq!(read, host());
+
+    let mut deserializer = TextDeserializer::new(text.as_bytes());
+    assert!(
+        <Host as serde::Deserialize>::deserialize(&mut deserializer).is_err()
+    );
+}
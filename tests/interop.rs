@@ -0,0 +1,147 @@
+//! Round trips a corpus of types through every format
+//!
+//! Run with `cargo test --features interop-tests`
+
+#![cfg(feature = "interop-tests")]
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+};
+use typed_format::{
+    interop::{check, CompatibilityReport, Fidelity, Format},
+    value::{json, Value},
+};
+
+fn assert_text_formats_exact(report: &CompatibilityReport) {
+    for format in &[Format::Text, Format::Stream] {
+        assert_eq!(
+            report.fidelity(*format),
+            Some(&Fidelity::Exact),
+            "{}",
+            report
+        );
+    }
+}
+
+/// Writes and reads `value` with serde_json itself
+fn serde_json_round_trip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let text = serde_json::to_string(value).unwrap();
+    assert_eq!(
+        serde_json::from_str::<T>(&text).unwrap(),
+        *value,
+        "{}",
+        text
+    );
+
+    // What the json format writes reads the same
+    let text = json::to_json(&Value::new(value), false);
+    assert_eq!(
+        serde_json::from_str::<T>(&text).unwrap(),
+        *value,
+        "{}",
+        text
+    );
+}
+
+/// Writes and reads `value` with the ron crate
+fn ron_round_trip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let text = ron::to_string(value).unwrap();
+    assert_eq!(ron::from_str::<T>(&text).unwrap(), *value, "{}", text);
+}
+
+#[test]
+fn lossless_everywhere() {
+    let mut map = HashMap::new();
+    map.insert("a".to_string(), vec![1]);
+
+    let reports = vec![
+        check(&-2i64),
+        check(&0.5f64),
+        check(&true),
+        check(&"text \" with \\ escapes\n".to_string()),
+        check(&vec![1u8, 2]),
+        check(&map),
+    ];
+
+    for report in &reports {
+        assert!(report.is_lossless(), "{}", report);
+    }
+
+    serde_json_round_trip(&-2i64);
+    serde_json_round_trip(&0.5f64);
+    serde_json_round_trip(&true);
+    serde_json_round_trip(&"text \" with \\ escapes\n".to_string());
+    serde_json_round_trip(&vec![1u8, 2]);
+    serde_json_round_trip(&map);
+
+    ron_round_trip(&-2i64);
+    ron_round_trip(&0.5f64);
+    ron_round_trip(&true);
+    ron_round_trip(&"text \" with \\ escapes\n".to_string());
+    ron_round_trip(&vec![1u8, 2]);
+    ron_round_trip(&map);
+}
+
+/// Types JSON cannot represent, the text formats have to keep them
+#[test]
+fn json_mismatches() {
+    let mut tuple_keys = BTreeMap::new();
+    tuple_keys.insert((1, 2), "a".to_string());
+    let results: Vec<Result<u8, String>> = vec![Ok(1), Err("e".to_string())];
+
+    let reports = vec![
+        check(&(1u8, 2u8)),
+        check(&tuple_keys),
+        check(&'x'),
+        check(&vec![Some(1), None]),
+        check(&results),
+        check(&f64::INFINITY),
+    ];
+
+    for report in &reports {
+        assert_text_formats_exact(report);
+        assert!(
+            !report.fidelity(Format::Json).unwrap().is_exact(),
+            "{}",
+            report
+        );
+        assert_eq!(report.mismatches().count(), 1);
+    }
+
+    // None and unit are both null
+    let report = check(&Some(()));
+    assert_text_formats_exact(&report);
+    assert!(matches!(
+        report.fidelity(Format::Json),
+        Some(Fidelity::Changed { .. })
+    ));
+
+    // RON keeps what JSON loses
+    ron_round_trip(&(1u8, 2u8));
+    ron_round_trip(&tuple_keys);
+    ron_round_trip(&'x');
+    ron_round_trip(&vec![Some(1), None]);
+    ron_round_trip(&results);
+    ron_round_trip(&f64::INFINITY);
+    ron_round_trip(&Some(()));
+}
+
+#[test]
+fn report_display() {
+    assert_eq!(
+        check(&Some(())).to_string(),
+        "core::option::Option<()>:
+    text: exact
+    stream: exact
+    json: changed from Some(()) to None
+"
+    );
+}
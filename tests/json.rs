@@ -0,0 +1,121 @@
+//! Conversions between values and JSON
+//!
+//! Run with `cargo test --features json`
+
+#![cfg(feature = "json")]
+
+use typed_format::value::{
+    json::{json_to_tyf, tyf_to_json, JsonOptions},
+    Value,
+};
+
+const CONFIG: &str = r#"Config(
+    name: "server",
+    mode: Mode::Fast,
+    origin: Point(1, 2),
+    limits: Some(Limits(connections: 10)),
+)"#;
+
+#[test]
+fn type_tag_round_trip() {
+    let options = JsonOptions::default().type_tag("$type");
+    let value = Value::parse(CONFIG).unwrap();
+
+    let json = value.to_json(&options);
+    assert_eq!(
+        json,
+        r#"{"$type":"Config","name":"server","mode":{"$type":"Mode::Fast"},"#
+            .to_string()
+            + r#""origin":{"$type":"Point","0":1,"1":2},"#
+            + r#""limits":{"$type":"Limits","connections":10}}"#
+    );
+
+    // `Some` is not visible in JSON
+    let expected =
+        Value::parse(&CONFIG.replace(
+            "Some(Limits(connections: 10))",
+            "Limits(connections: 10)",
+        ))
+        .unwrap();
+    assert_eq!(Value::from_json(&json, &options).unwrap(), expected);
+}
+
+#[test]
+fn without_type_tag() {
+    let options = JsonOptions::default();
+    let value = Value::parse(CONFIG).unwrap();
+
+    let json = value.to_json(&options);
+    assert_eq!(
+        json,
+        r#"{"name":"server","mode":"Fast","origin":[1,2],"limits":{"connections":10}}"#
+    );
+    assert_eq!(
+        Value::from_json(&json, &options).unwrap()["mode"],
+        Value::String("Fast".into())
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "preserve_order", ignore = "expects sorted entries")]
+fn text_conversions() {
+    let options = JsonOptions::default().type_tag("type");
+
+    let tyf = json_to_tyf(
+        r#"{"type": "User", "name": "alice", "groups": ["admin"]}"#,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        tyf,
+        "User(\n    groups: [\n        \"admin\",\n    ],\n    name: \"alice\",\n)"
+    );
+
+    let json = tyf_to_json(&tyf, &options.pretty(true)).unwrap();
+    assert_eq!(
+        json,
+        "{\n  \"type\": \"User\",\n  \"groups\": [\n    \"admin\"\n  ],\n  \"name\": \"alice\"\n}"
+    );
+
+    // Objects without the tag stay maps
+    let options = JsonOptions::default().type_tag("type");
+    assert_eq!(
+        Value::from_json(r#"{"a": 1}"#, &options).unwrap(),
+        Value::parse(r#"{"a": 1}"#).unwrap()
+    );
+    assert!(Value::from_json(r#"{"type": 1}"#, &options).is_err());
+    assert!(Value::from_json(r#"{"type": "T", "1": 1}"#, &options).is_err());
+}
+
+#[test]
+fn serde_json_values() {
+    let value = Value::parse(CONFIG).unwrap();
+    let json = serde_json::Value::from(&value);
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "name": "server",
+            "mode": "Fast",
+            "origin": [1, 2],
+            "limits": {"connections": 10},
+        })
+    );
+    assert_eq!(json, serde_json::to_value(&value).unwrap());
+
+    let value = Value::from(serde_json::json!({
+        "name": "server",
+        "ports": [80, -1, 0.5],
+        "proxy": null,
+    }));
+    assert_eq!(
+        value,
+        Value::parse(
+            r#"{"name": "server", "ports": [80, -1, 0.5], "proxy": None}"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        serde_json::Value::from(value),
+        serde_json::json!({"name": "server", "ports": [80, -1, 0.5], "proxy": null})
+    );
+}
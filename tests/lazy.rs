@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+use typed_format::{lazy::TyfLazy, vfs::MemoryFs};
+
+static EMBEDDED: TyfLazy<BTreeMap<String, Vec<u32>>> =
+    TyfLazy::embedded(r#"{"a": [1, 2], "b": []}"#);
+static INVALID: TyfLazy<Vec<u32>> = TyfLazy::embedded("[1, 2");
+static MISSING: TyfLazy<Vec<u32>> = TyfLazy::file("does/not/exist.tyf");
+
+static ASSETS: MemoryFs = MemoryFs::new();
+static FROM_ASSETS: TyfLazy<Vec<u32>> =
+    TyfLazy::file_in(&ASSETS, "assets/numbers.tyf");
+
+#[test]
+fn lazy_embedded() {
+    let value = EMBEDDED.get();
+    assert_eq!(value["a"], vec![1, 2]);
+    assert!(EMBEDDED.error().is_none());
+
+    // Every access returns the same instance
+    assert!(std::ptr::eq(value, EMBEDDED.get()));
+}
+
+#[test]
+fn lazy_errors() {
+    let error = INVALID.try_get().unwrap_err();
+    assert!(format!("{:#}", error).contains("Could not parse"));
+
+    let error = MISSING.try_get().unwrap_err();
+    assert!(format!("{:#}", error).contains("does/not/exist.tyf"));
+}
+
+#[test]
+fn lazy_file_in_vfs() {
+    ASSETS.insert("assets/numbers.tyf", "[1, 2, 3]");
+    assert_eq!(FROM_ASSETS.get(), &vec![1, 2, 3]);
+}
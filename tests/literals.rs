@@ -0,0 +1,124 @@
+use serde_derive::{Deserialize, Serialize};
+use std::net::IpAddr;
+use typed_format::{
+    literals::{LiteralSyntax, Literals, NewtypeLiteral},
+    value::{options::ParseOptions, printer::ValuePrinter, Value},
+};
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+struct Regex(String);
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+struct Rule {
+    pattern: Regex,
+    address: IpAddr,
+}
+
+/// Writes IP addresses, which serialize as strings, as `ip"..."`
+struct IpLiteral;
+
+impl LiteralSyntax for IpLiteral {
+    fn parse(&self, contents: &str) -> Result<Value, String> {
+        contents.parse::<IpAddr>().map_err(|e| e.to_string())?;
+        Ok(Value::String(contents.into()))
+    }
+
+    fn print(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) if s.parse::<IpAddr>().is_ok() => {
+                Some(s.to_string())
+            },
+            _ => None,
+        }
+    }
+}
+
+fn literals() -> Literals {
+    Literals::new()
+        .with("re", NewtypeLiteral::new("Regex"))
+        .unwrap()
+        .with("ip", IpLiteral)
+        .unwrap()
+}
+
+#[test]
+fn parse_and_print() {
+    let options = ParseOptions {
+        literals: literals(),
+        ..Default::default()
+    };
+    let input = r#"Rule(address: ip"10.0.0.1", pattern: re"^[a-z]+\"$")"#;
+
+    let value = Value::parse_with(input, &options).unwrap();
+    let rule: Rule = value.deserialize().unwrap();
+    assert_eq!(
+        rule,
+        Rule {
+            pattern: Regex("^[a-z]+\"$".to_string()),
+            address: "10.0.0.1".parse().unwrap(),
+        }
+    );
+
+    let mut printed = String::new();
+    ValuePrinter::compact()
+        .with_literals(&options.literals)
+        .write(&Value::new(&rule), &mut printed)
+        .unwrap();
+    assert_eq!(
+        printed,
+        r#"Rule(pattern:re"^[a-z]+\"$",address:ip"10.0.0.1",)"#
+    );
+
+    // Without the literals, the values print as usual
+    assert_eq!(
+        Value::new(&rule).to_string_compact(),
+        r#"Rule(pattern:Regex("^[a-z]+\"$",),address:"10.0.0.1",)"#
+    );
+}
+
+#[test]
+fn errors() {
+    let options = ParseOptions {
+        literals: literals(),
+        ..Default::default()
+    };
+
+    let error = Value::parse_with(r#"ip"10.0.0.300""#, &options)
+        .unwrap_err()
+        .to_string();
+    assert!(error.starts_with("Invalid ip\"...\" literal"), "{}", error);
+
+    assert!(Value::parse_with(r#"url"x""#, &options).is_err());
+    assert!(Value::parse(r#"re"x""#).is_err());
+
+    // Byte and raw strings keep their meaning
+    assert_eq!(
+        Value::parse_with(r#"b"x""#, &options).unwrap(),
+        Value::Bytes(b"x".to_vec())
+    );
+    assert!(Literals::new().with("b", IpLiteral).is_err());
+    assert!(Literals::new().with("not valid", IpLiteral).is_err());
+}
+
+#[test]
+fn checked_newtype() {
+    let literals = Literals::new()
+        .with(
+            "hex",
+            NewtypeLiteral::new("Hex").with_check(|s: &str| {
+                if s.chars().all(|c| c.is_ascii_hexdigit()) {
+                    Ok(())
+                } else {
+                    Err(format!("{:?} is not hexadecimal", s))
+                }
+            }),
+        )
+        .unwrap();
+    let options = ParseOptions {
+        literals,
+        ..Default::default()
+    };
+
+    assert!(Value::parse_with(r#"hex"c0ffee""#, &options).is_ok());
+    assert!(Value::parse_with(r#"hex"coffee""#, &options).is_err());
+}
@@ -0,0 +1,175 @@
+use serde_derive::Deserialize;
+use typed_format::{
+    loader::{LoadError, Loader},
+    value::Value,
+    vfs::MemoryFs,
+};
+
+#[derive(Deserialize, Debug, Eq, PartialEq)]
+struct Config {
+    server: Server,
+    plugins: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Eq, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[test]
+fn includes() {
+    let vfs = MemoryFs::new()
+        .with_file(
+            "config/main.tyf",
+            r#"Config(
+                server: include("server.tyf"),
+                plugins: [include("plugins/auth.tyf"), "cache"],
+            )"#,
+        )
+        .with_file(
+            "config/server.tyf",
+            r#"Server(host: "localhost", port: include("../shared/port.tyf"))"#,
+        )
+        .with_file("config/plugins/auth.tyf", r#""auth""#)
+        .with_file("shared/port.tyf", "8080");
+
+    let config: Config =
+        Loader::with_vfs(&vfs).load("config/main.tyf").unwrap();
+    assert_eq!(
+        config,
+        Config {
+            server: Server {
+                host: "localhost".to_string(),
+                port: 8080,
+            },
+            plugins: vec!["auth".to_string(), "cache".to_string()],
+        }
+    );
+
+    let error = Loader::with_vfs(&vfs)
+        .sandbox("config")
+        .load_value("config/main.tyf")
+        .unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("shared/port.tyf is outside of config"),
+        "{:#}",
+        error
+    );
+
+    let mut value = Value::parse(r#"[include("auth.tyf")]"#).unwrap();
+    Loader::with_vfs(&vfs)
+        .resolve(&mut value, "config/plugins")
+        .unwrap();
+    assert_eq!(value, Value::parse(r#"["auth"]"#).unwrap());
+}
+
+#[test]
+fn include_errors() {
+    let vfs = MemoryFs::new()
+        .with_file("a.tyf", r#"(b: include("b.tyf"))"#)
+        .with_file("b.tyf", r#"[include("./a.tyf")]"#)
+        .with_file("missing.tyf", r#"include("nothing.tyf")"#)
+        .with_file("invalid.tyf", r#"include(1)"#);
+    let loader = Loader::with_vfs(&vfs);
+
+    let error = loader.load_value("a.tyf").unwrap_err();
+    match &error {
+        LoadError::Included { file, error } => {
+            assert_eq!(file.to_str(), Some("a.tyf"));
+            assert!(matches!(**error, LoadError::Included { .. }));
+        },
+        other => panic!("Expected an include error, found {:?}", other),
+    }
+    let error = format!("{:#}", error);
+    assert!(
+        error.contains("Include cycle: a.tyf -> b.tyf -> a.tyf"),
+        "{}",
+        error
+    );
+
+    let error = format!("{:#}", loader.load_value("missing.tyf").unwrap_err());
+    assert!(error.contains("Could not read nothing.tyf"), "{}", error);
+
+    assert!(loader.load_value("invalid.tyf").is_err());
+}
+
+#[test]
+fn expand_variables() {
+    let vfs = MemoryFs::new()
+        .with_file(
+            "main.tyf",
+            r#"(
+                url: "http://${HOST}:${PORT:8080}/",
+                cost: "$$5 and $${HOST}",
+                nested: Some([{"key": "${HOST}"}]),
+                server: include("${NAME}.tyf"),
+            )"#,
+        )
+        .with_file("prod.tyf", r#"(user: "${USER:nobody}")"#)
+        .with_file("missing.tyf", r#"[1, "${MISSING}"]"#)
+        .with_file("broken.tyf", r#""${HOST""#);
+
+    let lookup = |name: &str| match name {
+        "HOST" => Some("example.com".to_string()),
+        "NAME" => Some("prod".to_string()),
+        _ => None,
+    };
+    let loader = Loader::with_vfs(&vfs).expand_variables(lookup);
+
+    assert_eq!(
+        loader.load_value("main.tyf").unwrap(),
+        Value::parse(
+            r#"(
+                url: "http://example.com:8080/",
+                cost: "$5 and ${HOST}",
+                nested: Some([{"key": "example.com"}]),
+                server: (user: "nobody"),
+            )"#
+        )
+        .unwrap()
+    );
+
+    let error = format!("{:#}", loader.load_value("missing.tyf").unwrap_err());
+    assert!(
+        error.contains("At [1]: Variable MISSING is not set"),
+        "{}",
+        error
+    );
+    assert!(loader.load_value("broken.tyf").is_err());
+
+    // Without expansion, strings are kept as they are
+    let value = Loader::with_vfs(&vfs).load_value("missing.tyf").unwrap();
+    assert_eq!(value, Value::parse(r#"[1, "${MISSING}"]"#).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn sandbox_symlinks() {
+    use std::{fs, os::unix::fs::symlink};
+
+    let root = std::env::temp_dir()
+        .join(format!("typed_format_sandbox_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("config")).unwrap();
+    fs::write(root.join("secret.tyf"), r#""secret""#).unwrap();
+    fs::write(root.join("config/port.tyf"), "8080").unwrap();
+    symlink(root.join("secret.tyf"), root.join("config/link.tyf")).unwrap();
+    symlink(root.join("config/port.tyf"), root.join("config/alias.tyf"))
+        .unwrap();
+
+    let loader = Loader::new().sandbox(root.join("config"));
+    let error = loader
+        .load_value(root.join("config/link.tyf"))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("is outside of"), "{}", error);
+
+    // Links that stay inside of the sandbox are followed
+    assert_eq!(
+        loader.load_value(root.join("config/alias.tyf")).unwrap(),
+        Value::new(8080)
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,90 @@
+use serde_derive::{Deserialize, Serialize};
+use std::io::{self, Cursor, Write};
+use typed_format::{
+    log::{ValueLogReader, ValueLogWriter},
+    Error,
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Event {
+    Started { worker: u32 },
+    Message(String),
+    Stopped,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Record {
+    n: u32,
+}
+
+/// Counts flushes, to check that every record is flushed
+#[derive(Default)]
+struct Journal {
+    bytes: Vec<u8>,
+    flushes: usize,
+}
+
+impl Write for Journal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn write_and_read_log() {
+    let events = vec![
+        Event::Started { worker: 1 },
+        Event::Message("two\nlines".to_string()),
+        Event::Stopped,
+    ];
+
+    let mut writer = ValueLogWriter::new(Journal::default());
+    for event in &events {
+        writer.write(event).unwrap();
+    }
+    let journal = writer.into_inner();
+    assert_eq!(journal.flushes, 3);
+
+    let text = String::from_utf8(journal.bytes).unwrap();
+    assert_eq!(text.lines().count(), 3);
+    assert!(text.ends_with('\n'));
+
+    let read = ValueLogReader::new(Cursor::new(text))
+        .collect::<Result<Vec<Event>, _>>()
+        .unwrap();
+    assert_eq!(read, events);
+}
+
+#[test]
+fn read_log_errors() {
+    let text = "Event::Stopped\nEvent::Message(5)\nEvent::Stopped\n";
+    let read = ValueLogReader::<_, Event>::new(Cursor::new(text))
+        .map(|result| result.is_ok())
+        .collect::<Vec<_>>();
+    assert_eq!(read, vec![true, false, true]);
+
+    // A torn record does not take the ones after it along
+    let text = "Rec(n: 1)\nRec(n:\nRec(n: 3)\n\n// comment\nRec(n: 4)\n";
+    let read = ValueLogReader::<_, Record>::new(Cursor::new(text))
+        .map(|result| {
+            result.map_err(|error| match error {
+                Error::Record { line, .. } => line,
+                other => panic!("Expected a record error, found {}", other),
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        read,
+        vec![
+            Ok(Record { n: 1 }),
+            Err(2),
+            Ok(Record { n: 3 }),
+            Ok(Record { n: 4 }),
+        ]
+    );
+}
@@ -0,0 +1,60 @@
+use typed_format::value::{
+    merge::{ListMerge, MergeStrategy},
+    Value,
+};
+
+const DEFAULTS: &str = r#"Config(
+    name: "service",
+    server: Server(host: "0.0.0.0", port: 80),
+    plugins: ["auth"],
+    env: {"LOG": "info", "HOME": "/"},
+    tls: Some(Tls(cert: "default.pem", verify: true)),
+)"#;
+
+const OVERRIDES: &str = r#"(
+    server: (port: 8080),
+    plugins: ["metrics"],
+    env: {"LOG": "debug"},
+    tls: Some((cert: "prod.pem")),
+    debug: true,
+)"#;
+
+#[test]
+fn merge_replacing_lists() {
+    let mut config = Value::parse(DEFAULTS).unwrap();
+    config.merge(&Value::parse(OVERRIDES).unwrap(), MergeStrategy::default());
+
+    let expected = Value::parse(
+        r#"Config(
+            name: "service",
+            server: Server(host: "0.0.0.0", port: 8080),
+            plugins: ["metrics"],
+            env: {"LOG": "debug", "HOME": "/"},
+            tls: Some(Tls(cert: "prod.pem", verify: true)),
+            debug: true,
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(config, expected);
+}
+
+#[test]
+fn merge_appending_lists() {
+    let strategy = MergeStrategy {
+        lists: ListMerge::Append,
+    };
+
+    let mut config = Value::parse(DEFAULTS).unwrap();
+    config.merge(&Value::parse(OVERRIDES).unwrap(), strategy);
+    assert_eq!(
+        config.get("plugins").unwrap(),
+        &Value::parse(r#"["auth", "metrics"]"#).unwrap()
+    );
+
+    // Values of other types and other names are replaced
+    config.merge(&Value::parse("(tls: None)").unwrap(), strategy);
+    assert_eq!(config.get("tls").unwrap(), &Value::Option(None));
+
+    config.merge(&Value::parse("Other(x: 1)").unwrap(), strategy);
+    assert_eq!(config, Value::parse("Other(x: 1)").unwrap());
+}
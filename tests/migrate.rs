@@ -0,0 +1,89 @@
+//! Converting file trees between formats
+//!
+//! Run with `cargo test --features json`
+
+#![cfg(feature = "json")]
+
+use std::{fs, path::Path};
+use typed_format::{
+    migrate::{migrate_tree, migrate_tree_in, FileFormat},
+    value::{json, Value},
+    vfs::{MemoryFs, Vfs},
+};
+
+#[test]
+fn migrate_json_tree() {
+    let root = std::env::temp_dir()
+        .join(format!("typed_format_migrate_{}", std::process::id()));
+    let input = root.join("input");
+    let output = root.join("output");
+    let _ = fs::remove_dir_all(&root);
+
+    fs::create_dir_all(input.join("nested")).unwrap();
+    fs::write(
+        input.join("nested/config.json"),
+        r#"{"name": "test", "values": [1, -2.5, true, null]}"#,
+    )
+    .unwrap();
+    fs::write(input.join("broken.json"), "{").unwrap();
+    fs::write(input.join("notes.txt"), "").unwrap();
+
+    let report =
+        migrate_tree(&input, &output, FileFormat::Json, FileFormat::Tyf)
+            .unwrap();
+
+    assert_eq!(report.converted.len(), 1);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.skipped, 1);
+
+    let converted =
+        fs::read_to_string(output.join("nested/config.tyf")).unwrap();
+    let original = json::from_json(
+        &fs::read_to_string(input.join("nested/config.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(Value::parse(&converted).unwrap(), original);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn migrate_in_memory() {
+    let vfs = MemoryFs::new()
+        .with_file("input/nested/config.json", r#"{"name": "test"}"#)
+        .with_file("input/broken.json", "{")
+        .with_file("input/notes.txt", "");
+
+    let report = migrate_tree_in(
+        &vfs,
+        Path::new("input"),
+        Path::new("output"),
+        FileFormat::Json,
+        FileFormat::Tyf,
+    )
+    .unwrap();
+
+    assert_eq!(report.converted.len(), 1);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.skipped, 1);
+
+    let converted = vfs
+        .read_to_string(Path::new("output/nested/config.tyf"))
+        .unwrap();
+    assert_eq!(
+        Value::parse(&converted).unwrap(),
+        json::from_json(r#"{"name": "test"}"#).unwrap()
+    );
+    assert!(vfs.read_dir(Path::new("missing")).is_err());
+}
+
+#[test]
+fn json_round_trip() {
+    let input = r#"{"a": [1, 2, {"b": "é\n"}], "c": false, "d": null}"#;
+    let value = json::from_json(input).unwrap();
+
+    for pretty in &[true, false] {
+        let output = json::to_json(&value, *pretty);
+        assert_eq!(json::from_json(&output).unwrap(), value);
+    }
+}
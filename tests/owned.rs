@@ -0,0 +1,110 @@
+use serde::{
+    de::{value::MapDeserializer, IntoDeserializer},
+    Deserialize as _,
+};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use typed_format::value::{
+    deserializer::ValueDeserializerError, path::Path, Value,
+};
+
+#[derive(Deserialize, PartialEq, Debug)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Line(i32, i32),
+    Rect { width: u32, height: u32 },
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Wrapper(Vec<u8>);
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Drawing {
+    name: String,
+    shapes: Vec<Shape>,
+    layers: BTreeMap<String, Option<(u8, char)>>,
+    data: Wrapper,
+    extra: Value,
+}
+
+const DRAWING: &str = r#"Drawing(
+    name: "sketch",
+    shapes: [
+        Shape::Point,
+        Shape::Circle(1.5),
+        Shape::Line(-1, 2),
+        Shape::Rect(width: 3, height: 4),
+    ],
+    layers: {"top": Some((1, 'a')), "bottom": None},
+    data: Wrapper([1, 2, 3]),
+    extra: Other<u8>(bytes: b"\x00\x01", list: [1, "two"]),
+)"#;
+
+#[test]
+fn same_as_borrowed() {
+    let value = Value::parse(DRAWING).unwrap();
+    let borrowed = value.deserialize::<Drawing>().unwrap();
+    let owned = Drawing::deserialize(value).unwrap();
+    assert_eq!(owned, borrowed);
+    assert_eq!(owned.extra, Value::parse(DRAWING).unwrap()["extra"]);
+}
+
+#[test]
+fn moves_nested_values() {
+    let value = Value::parse("Envelope(payload: b\"data\")").unwrap();
+    let bytes = match &value["payload"] {
+        Value::Bytes(bytes) => bytes.as_ptr(),
+        other => panic!("Expected bytes, found {:?}", other),
+    };
+
+    #[derive(Deserialize)]
+    struct Envelope {
+        payload: Value,
+    }
+    let envelope = Envelope::deserialize(value).unwrap();
+    match envelope.payload {
+        Value::Bytes(moved) => assert_eq!(moved.as_ptr(), bytes),
+        other => panic!("Expected bytes, found {:?}", other),
+    }
+}
+
+#[test]
+fn errors_have_paths() {
+    let input = DRAWING.replace("height: 4", "height: -4");
+    let value = Value::parse(&input).unwrap();
+
+    let borrowed = value.deserialize::<Drawing>().unwrap_err();
+    let owned = Drawing::deserialize(value).unwrap_err();
+    assert_eq!(owned.to_string(), borrowed.to_string());
+    assert_eq!(owned.path(), &Path::parse("shapes[3].height").unwrap());
+
+    let value = Value::parse("Drawing(name: 1)").unwrap();
+    match Drawing::deserialize(value).unwrap_err() {
+        ValueDeserializerError::TypeMismatch { found, path, .. } => {
+            assert_eq!(found, "Number");
+            assert_eq!(path, Path::parse("name").unwrap());
+        },
+        other => panic!("Expected a type mismatch, found {:?}", other),
+    }
+}
+
+#[test]
+fn into_deserializer() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Point {
+        x: i32,
+        y: Option<i32>,
+    }
+
+    let fields = vec![("x", Value::new(1)), ("y", Value::new(Some(2)))];
+    let deserializer: MapDeserializer<_, ValueDeserializerError> =
+        MapDeserializer::new(fields.into_iter());
+    assert_eq!(
+        Point::deserialize(deserializer).unwrap(),
+        Point { x: 1, y: Some(2) }
+    );
+
+    let value = Value::new([3, 4]).into_deserializer();
+    assert_eq!(<(u8, u8)>::deserialize(value).unwrap(), (3, 4));
+}
@@ -0,0 +1,81 @@
+//! Large lists and maps parsed on several threads
+//!
+//! Run with `cargo test --features rayon`
+
+#![cfg(feature = "rayon")]
+
+use typed_format::value::{options::ParseOptions, Value};
+
+/// Elements with commas and brackets in strings, chars, types and comments
+fn element(index: usize) -> String {
+    format!(
+        r##"Record(
+            id: {},
+            name: "name, with [brackets] and \"quotes\", {}",
+            raw: r#"{{,}}"#,
+            separator: ',',
+            quote: '\'',
+            kind: <Wrapper<'a, (u8, u16)>>, // trailing, comment
+            tags: [/* first, */ a, b],
+        )"##,
+        index, index
+    )
+}
+
+fn expected(index: usize) -> Value {
+    Value::parse(&element(index)).unwrap()
+}
+
+fn list(count: usize) -> String {
+    let elements = (0..count).map(element).collect::<Vec<_>>();
+    format!("[\n{},\n]", elements.join(",\n"))
+}
+
+#[test]
+fn large_list() {
+    let count = 4_000;
+    let value = Value::parse(&list(count)).unwrap();
+    assert_eq!(value, Value::List((0..count).map(expected).collect()));
+}
+
+#[test]
+fn large_map() {
+    let count = 4_000;
+    let entries = (0..count)
+        .map(|index| format!("key_{}: {}", index % 2_000, element(index)))
+        .collect::<Vec<_>>();
+    let input = format!("{{ {} }}", entries.join(", "));
+
+    // Later duplicates win
+    let value = Value::parse(&input).unwrap();
+    assert_eq!(value["key_0"], expected(2_000));
+
+    let options = ParseOptions {
+        reject_duplicate_keys: true,
+        ..Default::default()
+    };
+    let error = Value::parse_with(&input, &options).unwrap_err();
+    assert_eq!(error.message, "Duplicate key key_0");
+}
+
+#[test]
+fn anchors_across_parts() {
+    let input = format!("[&first {}, {}, *first]", element(0), list(4_000));
+    match Value::parse(&input).unwrap() {
+        Value::List(items) => assert_eq!(items[2], expected(0)),
+        other => panic!("Expected a list, found {:?}", other),
+    }
+}
+
+#[test]
+fn errors() {
+    let input = list(4_000).replace("id: 3000,", "id: 3000");
+    let error = Value::parse(&input).unwrap_err();
+    // Without expressions nothing can follow the value but a comma
+    if cfg!(feature = "exprs") {
+        assert_eq!(error.message, "expected operator");
+    } else {
+        assert_eq!(error.message, "unexpected input");
+    }
+    assert_eq!(error.line, 3000 * 9 + 4);
+}
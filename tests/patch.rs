@@ -0,0 +1,110 @@
+use typed_format::{
+    value::{
+        patch::{PatchOperation, ValuePatch},
+        path::Path,
+        Value,
+    },
+    Error,
+};
+
+const CONFIG: &str = r#"Config(
+    server: Server(host: "0.0.0.0", port: 80),
+    plugins: ["auth", "cache", "metrics", "trace"],
+    env: {"LOG": "info"},
+)"#;
+
+#[test]
+fn apply_patch() {
+    let patch = ValuePatch::parse(
+        r#"[
+            Add(path: "server.name", value: "api"),
+            Replace(path: "server.port", value: 8080),
+            Add(path: "plugins[1]", value: "rate_limit"),
+            Remove(path: "plugins[0]"),
+            Remove(path: "env{\"LOG\"}"),
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(patch.operations.len(), 5);
+    assert_eq!(
+        patch.operations[1],
+        PatchOperation::Replace {
+            path: Path::parse("server.port").unwrap(),
+            value: Value::parse("8080").unwrap(),
+        }
+    );
+
+    let mut config = Value::parse(CONFIG).unwrap();
+    config.apply_patch(&patch).unwrap();
+    let expected = Value::parse(
+        r#"Config(
+            server: Server(host: "0.0.0.0", port: 8080, name: "api"),
+            plugins: ["rate_limit", "cache", "metrics", "trace"],
+            env: {},
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(config, expected);
+
+    assert_eq!(ValuePatch::parse(&patch.to_string()).unwrap(), patch);
+}
+
+#[test]
+fn failed_patch_keeps_value() {
+    let original = Value::parse(CONFIG).unwrap();
+
+    for patch in &[
+        r#"[Replace(path: "server.port", value: 1), Remove(path: "missing")]"#,
+        r#"[Replace(path: "server.name", value: "api")]"#,
+        r#"[Add(path: "plugins[5]", value: "x")]"#,
+        r#"[Remove(path: "")]"#,
+    ] {
+        let mut config = original.clone();
+        let patch = ValuePatch::parse(patch).unwrap();
+        assert!(config.apply_patch(&patch).is_err());
+        assert_eq!(config, original);
+    }
+
+    assert!(ValuePatch::parse(r#"[Move(path: "a")]"#).is_err());
+    assert!(ValuePatch::parse(r#"[Add(path: "a")]"#).is_err());
+    assert!(ValuePatch::parse(r#"Remove(path: "a")"#).is_err());
+
+    match ValuePatch::parse(r#"[Remove(path: "a"), Add(path: 1)]"#) {
+        Err(Error::Invalid { path, .. }) => {
+            assert_eq!(path, Path::parse("[1].path").unwrap())
+        },
+        other => panic!("Expected an invalid operation, found {:?}", other),
+    }
+}
+
+#[test]
+fn diff_as_patch() {
+    let old = Value::parse(CONFIG).unwrap();
+    let new = Value::parse(
+        r#"Config(
+            server: Server(host: "localhost", port: 80, name: "api"),
+            plugins: ["auth"],
+            env: {"LOG": "debug", "HOME": "/"},
+        )"#,
+    )
+    .unwrap();
+
+    let patch = old.diff_as_patch(&new);
+    let mut patched = old.clone();
+    patched.apply_patch(&patch).unwrap();
+    assert_eq!(patched, new);
+
+    let removed = patch
+        .operations
+        .iter()
+        .filter_map(|operation| match operation {
+            PatchOperation::Remove { path } => Some(path.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(removed, vec!["plugins[3]", "plugins[2]", "plugins[1]"]);
+
+    let mut reverted = new.clone();
+    reverted.apply_patch(&new.diff_as_patch(&old)).unwrap();
+    assert_eq!(reverted, old);
+}
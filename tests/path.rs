@@ -0,0 +1,201 @@
+use typed_format::value::{path::Path, Value};
+
+const CONFIG: &str = r#"Config(
+    name: "server",
+    ports: [80, 443, 8080],
+    users: {"alice": User(admin: true, key: "secret")},
+    backup: Some(Backup(path: "/tmp", interval: 5)),
+)"#;
+
+#[test]
+fn path_display_and_parse() {
+    let path = Path::root()
+        .field("users")
+        .key(Value::String("alice".into()))
+        .field("admin");
+    assert_eq!(path.to_string(), r#"users{"alice"}.admin"#);
+    assert_eq!(Path::parse(&path.to_string()).unwrap(), path);
+
+    let path = Path::root().field("ports").index(1).field("with space");
+    assert_eq!(path.to_string(), r#"ports[1]."with space""#);
+    assert_eq!(Path::parse(&path.to_string()).unwrap(), path);
+
+    let path = Path::root().field("a\"b").field("c");
+    assert_eq!(path.to_string(), r#"."a\"b".c"#);
+    assert_eq!(Path::parse(&path.to_string()).unwrap(), path);
+
+    assert!(Path::parse("ports[").is_err());
+    assert!(Path::parse("a..b").is_err());
+}
+
+#[test]
+fn project() {
+    let value = Value::parse(CONFIG).unwrap();
+
+    let paths = ["ports[1]", "users.alice.admin", "backup.path"]
+        .iter()
+        .map(|p| Path::parse(p).unwrap())
+        .collect::<Vec<_>>();
+
+    let expected = Value::parse(
+        r#"Config(
+            ports: [443],
+            users: {"alice": User(admin: true)},
+            backup: Some(Backup(path: "/tmp")),
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(value.project(&paths), expected);
+
+    assert_eq!(value.project(&[Path::root()]), value);
+    assert_eq!(
+        value.project(&[Path::parse("name.missing").unwrap()]),
+        Value::Struct("Config".into(), Default::default())
+    );
+}
+
+#[test]
+fn find_all() {
+    let value = Value::parse(CONFIG).unwrap();
+
+    let strings = value
+        .find_all(|value| matches!(value, Value::String(_)))
+        .iter()
+        .map(Path::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        strings,
+        vec!["name", r#"users{"alice"}.key"#, "backup.path"]
+    );
+
+    let ports = value.find_all(|value| value.parse_number().is_some());
+    assert_eq!(ports.len(), 4);
+    assert_eq!(value.find_all(|_| true)[0], Path::root());
+}
+
+#[test]
+fn find_by_type() {
+    let value = Value::parse(
+        "[User(admin: true), auth::User(admin: false), Group(User), Users]",
+    )
+    .unwrap();
+
+    let paths = value
+        .find_by_type("User")
+        .iter()
+        .map(Path::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec!["[0]", "[1]", "[2][0]"]);
+
+    assert_eq!(
+        value.find_by_type("auth::User"),
+        vec![Path::root().index(1)]
+    );
+    assert_eq!(
+        Value::parse(CONFIG).unwrap().find_by_type("Backup"),
+        vec![Path::root().field("backup")]
+    );
+    assert!(value.find_by_type("Missing").is_empty());
+}
+
+#[test]
+fn get_and_index() {
+    let value = Value::parse(CONFIG).unwrap();
+
+    assert_eq!(value.get("name"), Some(&Value::String("server".into())));
+    assert_eq!(value["ports"][1], Value::Number(443u64.into()));
+    assert_eq!(value["users"]["alice"]["admin"], Value::Bool(true));
+    let alice = Value::String("alice".into());
+    assert_eq!(
+        value["users"][&alice]["key"],
+        value["users"]["alice"]["key"]
+    );
+    // Options are looked through
+    assert_eq!(value["backup"]["interval"], Value::Number(5u64.into()));
+
+    assert_eq!(value.get("missing"), None);
+    assert_eq!(value["ports"].get(3), None);
+    assert_eq!(value["name"].get(0), None);
+
+    let map = Value::parse("{1: \"one\"}").unwrap();
+    assert_eq!(map[1], Value::String("one".into()));
+}
+
+#[test]
+#[should_panic(expected = "No value at [3]")]
+fn index_out_of_range() {
+    let value = Value::parse(CONFIG).unwrap();
+    let _ = &value["ports"][3];
+}
+
+#[test]
+fn pointer() {
+    let value = Value::parse(CONFIG).unwrap();
+
+    assert_eq!(
+        value.pointer("ports[2]"),
+        Some(&Value::Number(8080u64.into()))
+    );
+    assert_eq!(
+        value.pointer(r#"users{"alice"}.key"#),
+        Some(&Value::String("secret".into()))
+    );
+    assert_eq!(
+        value.pointer("backup.path"),
+        Some(&Value::String("/tmp".into()))
+    );
+    assert_eq!(value.pointer(""), Some(&value));
+    assert_eq!(value.pointer("ports[9]"), None);
+    assert_eq!(value.pointer("ports[x"), None);
+
+    let path = Path::parse("users.alice.admin").unwrap();
+    assert_eq!(value.get_path(&path), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn edit() {
+    let mut value = Value::parse(CONFIG).unwrap();
+
+    value["ports"][0] = Value::Number(8000u64.into());
+    *value.get_mut("name").unwrap() = Value::String("proxy".into());
+    value["ports"].push(Value::Number(9090u64.into())).unwrap();
+    assert_eq!(value["ports"].remove(1), Some(Value::Number(443u64.into())));
+    assert_eq!(value["ports"].remove(9), None);
+
+    let old = value["users"]["alice"]
+        .insert("admin", Value::Bool(false))
+        .unwrap();
+    assert_eq!(old, Some(Value::Bool(true)));
+    value["users"]
+        .insert("bob", Value::parse("User(admin: false)").unwrap())
+        .unwrap();
+    // Options are looked through
+    value.insert("backup", Value::Option(None)).unwrap();
+    assert!(value["backup"].take().is_none());
+    assert_eq!(
+        value["users"]["alice"].remove("key"),
+        Some(Value::String("secret".into()))
+    );
+
+    let expected = Value::parse(
+        r#"Config(
+            name: "proxy",
+            ports: [8000, 8080, 9090],
+            users: {
+                "alice": User(admin: false),
+                "bob": User(admin: false),
+            },
+            backup: None,
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+
+    let mut option = Value::parse("Some(1)").unwrap();
+    assert_eq!(option.take(), Some(Value::Number(1u64.into())));
+    assert_eq!(option, Value::Option(None));
+
+    assert!(value["name"].push(Value::Unit).is_err());
+    assert!(value["ports"].insert("x", Value::Unit).is_err());
+    assert!(value.insert(0, Value::Unit).is_err());
+}
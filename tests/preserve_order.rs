@@ -0,0 +1,58 @@
+//! Maps and struct fields in insertion order
+//!
+//! Run with `cargo test --features preserve_order`
+
+#![cfg(feature = "preserve_order")]
+
+use serde_derive::Serialize;
+use typed_format::value::{map::OrderedMap, Value};
+
+#[derive(Serialize)]
+struct Server {
+    port: u16,
+    host: String,
+}
+
+#[test]
+fn written_order() {
+    let text = r#"{"zeta":1,"alpha":2,"mid":Point(y:3,x:4,),}"#;
+    let value = Value::parse(text).unwrap();
+    assert_eq!(value.to_string_compact(), text);
+
+    // The order does not matter for comparisons
+    let sorted =
+        Value::parse(r#"{"alpha":2,"mid":Point(x:4,y:3),"zeta":1}"#).unwrap();
+    assert_eq!(value, sorted);
+
+    let server = Value::new(&Server {
+        port: 80,
+        host: "localhost".to_string(),
+    });
+    assert_eq!(
+        server.to_string_compact(),
+        r#"Server(port:80,host:"localhost",)"#
+    );
+}
+
+#[test]
+fn ordered_map() {
+    let mut map = OrderedMap::new();
+    map.insert("c", 1);
+    map.insert("a", 2);
+    map.insert("b", 3);
+    assert_eq!(map.insert("a", 4), Some(2));
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec!["c", "a", "b"]);
+
+    assert_eq!(map.remove("c"), Some(1));
+    *map.entry("d").or_insert(0) += 5;
+    *map.entry("a").or_insert(0) += 5;
+    assert_eq!(
+        map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+        vec![("a", 9), ("b", 3), ("d", 5)]
+    );
+    assert_eq!(map["b"], 3);
+
+    let reversed = map.clone().into_iter().rev().collect::<OrderedMap<_, _>>();
+    assert_eq!(reversed, map);
+    assert_eq!(reversed.keys().next(), Some(&"d"));
+}
@@ -0,0 +1,114 @@
+use typed_format::{
+    de::{
+        from_reader_with_cancellation, from_reader_with_progress,
+        TextDeserializerError,
+    },
+    progress::{CancellationToken, Progress, ProgressReporter},
+    ser::{to_writer_with_cancellation, TextSerializerError},
+    value::{options::ParseOptions, Value},
+};
+
+const INPUT: &str = "[(1, 2), (3, 4), (5, 6)]";
+
+#[test]
+fn value_reports_top_level_elements() {
+    let mut reports = Vec::new();
+    let options = ParseOptions::default();
+    let progress =
+        ProgressReporter::new().on_progress(|progress| reports.push(progress));
+
+    let value = Value::parse_with_progress(INPUT, &options, progress).unwrap();
+    assert_eq!(value, Value::parse(INPUT).unwrap());
+
+    // One report per element, then one for the whole document
+    assert_eq!(reports.len(), 4);
+    assert_eq!(reports[0].bytes, "[(1, 2)".len());
+    assert_eq!(
+        reports[3],
+        Progress {
+            bytes: INPUT.len(),
+            nodes: 10,
+        }
+    );
+    assert!(reports.windows(2).all(|w| w[0].nodes <= w[1].nodes));
+}
+
+#[test]
+fn value_cancellation() {
+    let token = CancellationToken::new();
+    let mut reports = 0;
+    let options = ParseOptions::default();
+    let progress = ProgressReporter::new()
+        .cancellation(token.clone())
+        .on_progress(|_| {
+            reports += 1;
+            token.cancel();
+        });
+
+    let error =
+        Value::parse_with_progress(INPUT, &options, progress).unwrap_err();
+    assert!(matches!(error, typed_format::Error::Cancelled));
+    assert_eq!(reports, 1);
+}
+
+#[test]
+fn reader_reports_top_level_elements() {
+    let mut reports = Vec::new();
+    let progress =
+        ProgressReporter::new().on_progress(|progress| reports.push(progress));
+
+    let value: Vec<(u8, u8)> =
+        from_reader_with_progress(INPUT.as_bytes(), progress).unwrap();
+    assert_eq!(value, vec![(1, 2), (3, 4), (5, 6)]);
+
+    assert_eq!(reports.len(), 4);
+    assert_eq!(reports.last().unwrap().bytes, INPUT.len());
+    assert!(reports.windows(2).all(|w| w[0].bytes <= w[1].bytes));
+    assert!(reports.windows(2).all(|w| w[0].nodes <= w[1].nodes));
+}
+
+#[test]
+fn reader_cancellation() {
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let progress = ProgressReporter::new().cancellation(token);
+    let error = from_reader_with_progress::<_, Vec<(u8, u8)>>(
+        INPUT.as_bytes(),
+        progress,
+    )
+    .unwrap_err();
+    assert!(matches!(error, TextDeserializerError::Cancelled));
+}
+
+#[test]
+fn reader_cancellation_inside_nested_values() {
+    // Checked before every item, not only between top level ones
+    let checks = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = checks.clone();
+    let token = CancellationToken::from_fn(move || {
+        counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed) >= 2
+    });
+
+    let error = from_reader_with_cancellation::<_, Vec<Vec<u8>>>(
+        "[[1, 2, 3]]".as_bytes(),
+        token,
+    )
+    .unwrap_err();
+    assert!(matches!(error, TextDeserializerError::Cancelled));
+    assert_eq!(checks.load(std::sync::atomic::Ordering::Relaxed), 3);
+}
+
+#[test]
+fn writer_cancellation() {
+    let token = CancellationToken::new();
+    let mut output = Vec::new();
+    to_writer_with_cancellation(&mut output, &vec![1, 2, 3], token.clone())
+        .unwrap();
+    assert_eq!(output, b"[1,2,3,]");
+
+    token.cancel();
+    let error = to_writer_with_cancellation(Vec::new(), &vec![1, 2, 3], token)
+        .unwrap_err();
+    assert!(matches!(error, TextSerializerError::Cancelled));
+}
@@ -0,0 +1,189 @@
+//! Printed values read back unchanged and print the same text again
+
+use proptest::{collection::vec, option, prelude::*};
+use std::sync::Arc;
+use typed_format::value::{
+    map::{Map, OrderedMap},
+    number::Number,
+    types::{GenericIdentifier, Generics, Identifier, Type, TypeIdentifier},
+    Value,
+};
+
+/// Names that are read as something else than a type
+const KEYWORDS: &[&str] = &["true", "false", "None", "Some", "inf", "NaN"];
+
+/// Strings full of characters that are escaped when printed
+fn string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<String>(),
+        "[\"'\\\\\n\r\t\u{0}\u{1b}\u{7f}\u{200b}\u{fffd}éあ😀 a{}#]{0,16}",
+    ]
+}
+
+/// Floats other than NaN, which is not equal to itself
+fn float() -> impl Strategy<Value = f64> {
+    use proptest::num::f64::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+fn number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        any::<i64>().prop_map(Number::I64),
+        any::<u64>().prop_map(Number::U64),
+        any::<i128>().prop_map(Number::I128),
+        any::<u128>().prop_map(Number::U128),
+        prop_oneof![
+            Just(Number::I128(i128::MIN)),
+            Just(Number::U128(u128::MAX)),
+            Just(Number::F64(f64::MAX)),
+            Just(Number::F64(f64::MIN_POSITIVE)),
+        ],
+        float().prop_map(Number::F64),
+    ]
+}
+
+/// A name that is read as a type, like `Foo_1`
+fn name() -> impl Strategy<Value = Identifier> {
+    "[A-Za-z_][A-Za-z0-9_]{0,8}"
+        .prop_filter("keywords are not types", |name| {
+            !KEYWORDS.contains(&name.as_str())
+        })
+        .prop_map(Identifier)
+}
+
+fn path() -> impl Strategy<Value = TypeIdentifier> {
+    vec(name(), 1..4).prop_map(|names| TypeIdentifier {
+        segments: names
+            .into_iter()
+            .map(|identifier| GenericIdentifier {
+                identifier,
+                generics: None,
+            })
+            .collect(),
+    })
+}
+
+fn generic_type() -> impl Strategy<Value = Type> {
+    path()
+        .prop_map(Type::TypeIdentifier)
+        .prop_recursive(3, 8, 3, |inner| {
+            prop_oneof![
+                (inner.clone(), any::<u32>()).prop_map(|(content, size)| {
+                    Type::Array {
+                        content: Box::new(content),
+                        size: size.to_string(),
+                    }
+                }),
+                vec(inner.clone(), 0..3).prop_map(Type::Tuple),
+                (path(), vec(inner, 1..3)).prop_map(|(mut path, types)| {
+                    let last = path.segments.last_mut().unwrap();
+                    last.generics = Some(Generics { types });
+                    path.into()
+                }),
+            ]
+        })
+}
+
+fn type_identifier() -> impl Strategy<Value = TypeIdentifier> {
+    (path(), option::of(vec(generic_type(), 1..3))).prop_map(
+        |(mut path, generics)| {
+            let last = path.segments.last_mut().unwrap();
+            last.generics = generics.map(|types| Generics { types });
+            path
+        },
+    )
+}
+
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Unit),
+        any::<bool>().prop_map(Value::Bool),
+        any::<char>().prop_map(Value::Char),
+        string().prop_map(|string| Value::String(Arc::from(string))),
+        vec(any::<u8>(), 0..16).prop_map(Value::Bytes),
+        number().prop_map(Value::Number),
+        Just(Value::Option(None)),
+        type_identifier().prop_map(Value::UnitStruct),
+        generic_type().prop_map(Value::Type),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    scalar().prop_recursive(6, 64, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::List),
+            vec(inner.clone(), 1..4).prop_map(Value::Tuple),
+            inner
+                .clone()
+                .prop_map(|value| Value::Option(Some(Box::new(value)))),
+            (type_identifier(), vec(inner.clone(), 0..4))
+                .prop_map(|(name, values)| Value::TupleStruct(name, values)),
+            (
+                type_identifier(),
+                vec((string().prop_map(Identifier), inner.clone()), 1..4),
+            )
+                .prop_map(|(name, fields)| {
+                    let mut map = OrderedMap::new();
+                    for (field, value) in fields {
+                        map.insert(field, value);
+                    }
+                    Value::Struct(name, map)
+                }),
+            vec((inner.clone(), inner), 0..4).prop_map(|entries| {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    map.insert(key, value);
+                }
+                Value::Map(map)
+            }),
+        ]
+    })
+}
+
+/// `value` wrapped in a container for each of `levels`
+fn nest(value: Value, levels: &[u8]) -> Value {
+    levels.iter().fold(value, |value, level| match level % 4 {
+        0 => Value::List(vec![value]),
+        1 => Value::Tuple(vec![Value::Unit, value]),
+        2 => Value::Option(Some(Box::new(value))),
+        _ => {
+            let mut map = Map::new();
+            map.insert(Value::new("key"), value);
+            Value::Map(map)
+        },
+    })
+}
+
+proptest! {
+    #[test]
+    fn pretty_round_trips(value in value()) {
+        let text = value.to_string_pretty();
+        let parsed = Value::parse(&text).unwrap();
+        prop_assert_eq!(&parsed, &value);
+        prop_assert_eq!(parsed.to_string_pretty(), text);
+    }
+
+    #[test]
+    fn compact_round_trips(value in value()) {
+        let text = value.to_string_compact();
+        let parsed = Value::parse(&text).unwrap();
+        prop_assert_eq!(&parsed, &value);
+        prop_assert_eq!(parsed.to_string_compact(), text);
+    }
+
+    #[test]
+    fn compact_reprinted_pretty(value in value()) {
+        let compact = Value::parse(&value.to_string_compact()).unwrap();
+        let pretty = compact.to_string_pretty();
+        prop_assert_eq!(&pretty, &value.to_string_pretty());
+        prop_assert_eq!(Value::parse(&pretty).unwrap(), value);
+    }
+
+    #[test]
+    fn deep_nesting(value in scalar(), levels in vec(any::<u8>(), 0..100)) {
+        let value = nest(value, &levels);
+        for text in &[value.to_string_pretty(), value.to_string_compact()] {
+            prop_assert_eq!(&Value::parse(text).unwrap(), &value);
+        }
+    }
+}
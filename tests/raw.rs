@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use typed_format::{from_reader, from_str, value::Value, RawFragment};
+
+const DOCUMENT: &str = r#"{
+    "name": "host",
+    // Interpreted by the plugin later on
+    "plugin": Config(
+        retries: 3,
+        targets: [Some(Vec<u8>::Empty), None],
+        label: "a \"label\"",
+    ),
+}"#;
+
+type Document = BTreeMap<String, RawFragment>;
+
+fn plugin() -> Value {
+    Value::parse(
+        r#"Config(
+            retries: 3,
+            targets: [Some(Vec<u8>::Empty), None],
+            label: "a \"label\"",
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn capture() {
+    let parsed: Document = from_str(DOCUMENT).unwrap();
+    assert_eq!(parsed["name"].as_str(), "\"host\"");
+    assert_eq!(parsed["plugin"].parse().unwrap(), plugin());
+
+    let streamed: Document = from_reader(DOCUMENT.as_bytes()).unwrap();
+    assert_eq!(
+        streamed["plugin"].as_str(),
+        r##"Config( retries: 3, targets: [Some(Vec<u8>::Empty), None], label: r#"a "label""#, )"##
+    );
+    assert_eq!(streamed["plugin"].parse().unwrap(), plugin());
+    assert_eq!(streamed["name"].deserialize::<String>().unwrap(), "host");
+}
+
+#[test]
+fn separated_tokens() {
+    let streamed: Document = from_reader(
+        r#"{"a": [1, /* two */ 2], "b": Some(Mode::Fast)}"#.as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(streamed["a"].as_str(), "[1, 2]");
+    assert_eq!(streamed["b"].as_str(), "Some(Mode::Fast)");
+
+    // Tokens the grammar doesn't allow next to each other stay apart
+    for input in &[
+        r#"{"a": [1 2]}"#,
+        r#"{"a": [true false]}"#,
+        r#"{"a": [Foo Bar]}"#,
+        r#"{"a": (a: 1 2)}"#,
+    ] {
+        assert!(
+            from_reader::<_, Document>(input.as_bytes()).is_err(),
+            "{}",
+            input
+        );
+        assert!(from_str::<Document>(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn write_through() {
+    let mut document = Document::new();
+    document.insert(
+        "plugin".to_string(),
+        RawFragment::from_string("Config( retries: 3 )".to_string()).unwrap(),
+    );
+
+    let mut text = Vec::new();
+    typed_format::to_writer(&mut text, &document).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "{\"plugin\":Config( retries: 3 ),}"
+    );
+
+    let value = Value::new(&document);
+    let mut expected = BTreeMap::new();
+    expected.insert(
+        Value::String("plugin".into()),
+        Value::parse("Config(retries: 3)").unwrap(),
+    );
+    assert_eq!(value, Value::from(expected));
+
+    assert!(RawFragment::from_string("Config(".to_string()).is_err());
+}
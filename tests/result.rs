@@ -0,0 +1,45 @@
+use typed_format::value::Value;
+
+#[test]
+fn constructors_match_serde() {
+    let ok: Result<u8, String> = Ok(1);
+    let err: Result<u8, String> = Err("failed".to_string());
+
+    assert_eq!(Value::new(&ok), Value::ok(Value::Number(1u64.into())));
+    assert_eq!(Value::new(&err), Value::err(Value::String("failed".into())));
+}
+
+#[test]
+fn round_trip() {
+    let values: Vec<Result<(u8, char), Option<String>>> =
+        vec![Ok((1, 'a')), Err(None), Err(Some("failed".to_string()))];
+
+    for value in &values {
+        let printed = Value::new(value);
+        for string in &[printed.to_string_pretty(), printed.to_string_compact()]
+        {
+            let parsed = Value::parse(string).unwrap();
+            assert_eq!(parsed, printed);
+            assert_eq!(
+                parsed
+                    .deserialize::<Result<(u8, char), Option<String>>>()
+                    .unwrap(),
+                *value
+            );
+        }
+    }
+}
+
+#[test]
+fn as_result() {
+    let ok = Value::parse("Ok(5)").unwrap();
+    assert_eq!(ok.as_result(), Some(Ok(&Value::Number(5u64.into()))));
+    assert_eq!(ok.deserialize::<Result<u8, ()>>().unwrap(), Ok(5));
+
+    let err = Value::parse("Result::Err(\"failed\")").unwrap();
+    assert_eq!(err.as_result(), Some(Err(&Value::String("failed".into()))));
+
+    assert_eq!(Value::parse("Other(5)").unwrap().as_result(), None);
+    assert_eq!(Value::parse("Ok(1, 2)").unwrap().as_result(), None);
+    assert_eq!(Value::Unit.as_result(), None);
+}
@@ -0,0 +1,97 @@
+//! Conversions between values and RON text
+//!
+//! Run with `cargo test --features ron`
+
+#![cfg(feature = "ron")]
+
+use serde_derive::{Deserialize, Serialize};
+use typed_format::value::{
+    ron::{from_ron, to_ron},
+    Value,
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Mode {
+    Fast,
+    Slow { delay: u32 },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Config {
+    name: String,
+    modes: Vec<Mode>,
+    limit: Option<(u8, char)>,
+}
+
+#[test]
+fn names_become_single_segments() {
+    let value = Value::parse(
+        "[Mode::Fast, Wrapper<u8>(1), outer::Inner(a: Vec<u8>, b: [u8; 4]), (b: 2)]",
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_ron(&value, false),
+        r#"[Fast,Wrapper(1,),Inner(a:Vec,b:"[u8; 4]",),(b:2,),]"#
+    );
+}
+
+#[test]
+fn written_ron_reads_with_ron() {
+    let config = Config {
+        name: "server".to_string(),
+        modes: vec![Mode::Fast, Mode::Slow { delay: 5 }],
+        limit: Some((3, 'x')),
+    };
+    let value = Value::new(&config);
+
+    let ron = to_ron(&value, true);
+    assert!(ron.contains("Slow(\n"), "{}", ron);
+    assert_eq!(ron::from_str::<Config>(&ron).unwrap(), config);
+    assert_eq!(
+        ron::from_str::<Config>(&to_ron(&value, false)).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn reads_ron() {
+    let value = from_ron(
+        r##"// A RON document
+        Config(
+            name: r#"raw"#,
+            modes: [Fast, Slow(delay: 5)],
+            limit: Some((3, 'x')),
+        )"##,
+    )
+    .unwrap();
+
+    // The ron crate reads no names
+    assert_eq!(
+        value,
+        Value::parse(
+            r#"{
+                "name": "raw",
+                "modes": [(), {"delay": 5}],
+                "limit": Some([3, 'x']),
+            }"#
+        )
+        .unwrap()
+    );
+
+    let written = ron::to_string(&Config {
+        name: "server".to_string(),
+        modes: vec![],
+        limit: None,
+    })
+    .unwrap();
+    assert_eq!(
+        from_ron(&written).unwrap(),
+        Value::parse(r#"{"name": "server", "modes": [], "limit": None}"#)
+            .unwrap()
+    );
+
+    let value = from_ron("#![enable(implicit_some)]\n(a: 1)").unwrap();
+    assert_eq!(value, Value::parse(r#"{"a": 1}"#).unwrap());
+    assert!(from_ron("(a: 1").is_err());
+}
@@ -1,10 +1,12 @@
-use serde::{de::DeserializeOwned, export::fmt::Debug, Serialize};
-use std::path::Path;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use std::path::PathBuf;
 use typed_format::value::Value;
 
-pub fn test_output_path() -> &'static Path {
-    let path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/test_output"));
-    let _ = std::fs::create_dir_all(path);
+/// A scratch directory for the last serialized output, to inspect failures
+pub fn test_output_path() -> PathBuf {
+    let path = std::env::temp_dir().join("typed_format_test_output");
+    let _ = std::fs::create_dir_all(&path);
     path
 }
 
@@ -48,7 +50,7 @@ where
 {
     std::fs::write(test_output_path().join("parse.tyf"), string).unwrap();
 
-    let parsed_value = Value::parse(&string).unwrap();
+    let parsed_value = Value::parse(string).unwrap();
 
     assert_eq!(*value, parsed_value);
 
@@ -65,3 +67,17 @@ where
     assert_string_compact(t);
     assert_string_pretty(t);
 }
+
+/// Writes T with the streaming serializer and reads it back with the
+/// streaming deserializer, then checks if they are equal
+pub fn assert_stream<T>(t: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let mut bytes = Vec::new();
+    typed_format::to_writer(&mut bytes, t).unwrap();
+
+    let streamed: T = typed_format::from_reader(bytes.as_slice()).unwrap();
+
+    assert_eq!(*t, streamed);
+}
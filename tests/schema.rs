@@ -0,0 +1,237 @@
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use std::{any::type_name, collections::BTreeMap, fmt::Debug};
+use typed_format::{
+    schema::{generate, Fields, GenerateOptions, Schema, XorShiftRng},
+    value::{types::Type, Value},
+};
+
+/// Generates documents for T and checks that they deserialize as T
+fn assert_generates<T>()
+where
+    T: DeserializeOwned + Debug,
+{
+    let t = Type::parse(type_name::<T>()).unwrap();
+    let mut rng = XorShiftRng::new(42);
+    let options = GenerateOptions::default();
+
+    for _ in 0..32 {
+        let value = generate(&t, &mut rng, &options);
+
+        let parsed = Value::parse(&value.to_string_compact()).unwrap();
+        assert_eq!(parsed, value);
+
+        let _: T = value.deserialize().unwrap();
+    }
+}
+
+#[test]
+fn generate_primitives() {
+    assert_generates::<u8>();
+    assert_generates::<i64>();
+    assert_generates::<f32>();
+    assert_generates::<bool>();
+    assert_generates::<String>();
+    assert_generates::<char>();
+}
+
+#[test]
+fn generate_collections() {
+    assert_generates::<Vec<(u8, Option<String>)>>();
+    assert_generates::<BTreeMap<String, [i16; 3]>>();
+    assert_generates::<Option<Option<Vec<Vec<u32>>>>>();
+}
+
+#[test]
+fn generate_deterministic() {
+    let t = Type::parse("Vec<(u64, String)>").unwrap();
+    let options = GenerateOptions {
+        collection_length: 3..4,
+        ..Default::default()
+    };
+
+    let a = generate(&t, &mut XorShiftRng::new(7), &options);
+    let b = generate(&t, &mut XorShiftRng::new(7), &options);
+    assert_eq!(a, b);
+
+    match a {
+        Value::List(items) => assert_eq!(items.len(), 3),
+        other => panic!("Expected list, found {:?}", other),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+enum Shape {
+    Circle { radius: f64 },
+    Square(f32),
+    Empty,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Drawing {
+    name: String,
+    layer: Option<u8>,
+    shapes: Vec<Shape>,
+    offset: (i32, i32),
+    tags: BTreeMap<String, bool>,
+}
+
+#[test]
+fn trace_shape() {
+    let drawing = Drawing {
+        name: "sketch".to_string(),
+        layer: None,
+        shapes: vec![Shape::Square(1.0), Shape::Empty],
+        offset: (0, 0),
+        tags: BTreeMap::new(),
+    };
+
+    let schema = Schema::trace(&drawing).unwrap();
+    assert_eq!(schema.to_type(), Type::from("Drawing"));
+
+    let fields = match &schema {
+        Schema::Struct {
+            fields: Fields::Named(fields),
+            ..
+        } => fields,
+        other => panic!("Expected struct, found {:?}", other),
+    };
+    assert_eq!(fields["name"], Schema::Primitive("String"));
+    assert_eq!(fields["layer"].to_type().to_string(), "Option<Value>");
+    assert_eq!(fields["offset"].to_type().to_string(), "(i32, i32)");
+    assert_eq!(
+        fields["tags"].to_type().to_string(),
+        "BTreeMap<Value, Value>"
+    );
+
+    assert_eq!(
+        schema.to_value().to_string_compact(),
+        "Drawing(layer:Option(Value,),name:String,offset:(i32,i32,),\
+         shapes:Vec([Shape::Empty,Shape::Square(f32,),],),\
+         tags:BTreeMap(Value,Value,),)"
+    );
+}
+
+#[test]
+fn trace_merges_samples() {
+    let samples = vec![
+        Drawing {
+            name: "a".to_string(),
+            layer: Some(3),
+            shapes: vec![Shape::Circle { radius: 1.0 }],
+            offset: (1, 2),
+            tags: BTreeMap::new(),
+        },
+        Drawing {
+            name: "b".to_string(),
+            layer: None,
+            shapes: vec![Shape::Empty],
+            offset: (3, 4),
+            tags: vec![("visible".to_string(), true)].into_iter().collect(),
+        },
+    ];
+
+    let schema = Schema::trace_all(&samples).unwrap();
+    let value = schema.to_value();
+
+    assert_eq!(
+        value.to_string_compact(),
+        "Drawing(layer:Option(u8,),name:String,offset:(i32,i32,),\
+         shapes:Vec([Shape::Circle(radius:f64,),Shape::Empty,],),\
+         tags:BTreeMap(String,bool,),)"
+    );
+    assert_eq!(Value::parse(&value.to_string_pretty()).unwrap(), value);
+}
+
+#[test]
+fn validate_shape() {
+    let samples = vec![
+        Drawing {
+            name: "a".to_string(),
+            layer: Some(1),
+            shapes: vec![Shape::Circle { radius: 1.0 }, Shape::Square(2.0)],
+            offset: (0, 0),
+            tags: vec![("visible".to_string(), true)].into_iter().collect(),
+        },
+        Drawing {
+            name: "b".to_string(),
+            layer: None,
+            shapes: vec![Shape::Empty],
+            offset: (0, 0),
+            tags: BTreeMap::new(),
+        },
+    ];
+    let schema = Schema::trace_all(&samples).unwrap();
+
+    let valid = Value::parse(
+        r#"Drawing(
+            name: "c",
+            offset: (-1, 1),
+            shapes: [Shape::Empty, Shape::Circle(radius: 3)],
+            tags: {},
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(schema.validate(&valid), vec![]);
+    let _: Drawing = valid.deserialize().unwrap();
+
+    let invalid = Value::parse(
+        r#"Drawing(
+            name: 5,
+            layer: Some(300),
+            shapes: [Shape::Circle(radius: 1.5), Shape::Hexagon, Shape::Square("big")],
+            tags: {"hidden": ()},
+        )"#,
+    )
+    .unwrap();
+    let errors = schema
+        .validate(&invalid)
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        errors,
+        vec![
+            "layer: 300 does not fit u8",
+            "name: Expected String, found Number",
+            "offset: Missing field offset",
+            "shapes[1]: Unknown variant Shape::Hexagon",
+            "shapes[2][0]: Expected f32, found String",
+            r#"tags{"hidden"}: Expected bool, found Unit"#,
+        ]
+    );
+}
+
+#[test]
+fn generate_from_schema() {
+    let samples = vec![
+        Drawing {
+            name: "a".to_string(),
+            layer: Some(3),
+            shapes: vec![Shape::Circle { radius: 1.0 }, Shape::Square(2.0)],
+            offset: (1, 2),
+            tags: vec![("visible".to_string(), true)].into_iter().collect(),
+        },
+        Drawing {
+            name: "b".to_string(),
+            layer: None,
+            shapes: vec![Shape::Empty],
+            offset: (3, 4),
+            tags: BTreeMap::new(),
+        },
+    ];
+    let schema = Schema::trace_all(&samples).unwrap();
+    let mut rng = XorShiftRng::new(42);
+    let options = GenerateOptions::default();
+
+    for _ in 0..32 {
+        let value = schema.generate(&mut rng, &options);
+        assert_eq!(schema.validate(&value), vec![]);
+
+        let parsed = Value::parse(&value.to_string_compact()).unwrap();
+        assert_eq!(parsed, value);
+
+        let _: Drawing = value.deserialize().unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use std::{fmt, marker::PhantomData};
+use typed_format::{
+    from_str_seed,
+    value::{path::Path, Value},
+};
+
+/// Replaces the strings of a list with their index in `names`
+struct Interner<'a> {
+    names: &'a mut Vec<String>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for Interner<'a> {
+    type Value = Vec<usize>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for Interner<'a> {
+    type Value = Vec<usize>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of names")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut indices = Vec::new();
+        while let Some(name) = seq.next_element::<String>()? {
+            let index = match self.names.iter().position(|n| *n == name) {
+                Some(index) => index,
+                None => {
+                    self.names.push(name);
+                    self.names.len() - 1
+                },
+            };
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+}
+
+#[test]
+fn value_with_seed() {
+    let mut names = Vec::new();
+
+    let value = Value::parse(r#"["a", "b", "a"]"#).unwrap();
+    let indices = value.deserialize_seed(Interner { names: &mut names });
+    assert_eq!(indices.unwrap(), [0, 1, 0]);
+
+    let value = Value::parse(r#"["c", "b"]"#).unwrap();
+    let indices = value.deserialize_seed(Interner { names: &mut names });
+    assert_eq!(indices.unwrap(), [2, 1]);
+    assert_eq!(names, ["a", "b", "c"]);
+
+    let value = Value::parse(r#"["a", 1]"#).unwrap();
+    let error = value
+        .deserialize_seed(Interner { names: &mut names })
+        .unwrap_err();
+    assert_eq!(error.path(), &Path::parse("[1]").unwrap());
+}
+
+#[test]
+fn str_with_seed() {
+    let mut names = Vec::new();
+    let indices =
+        from_str_seed(r#"["x", "y", "x"]"#, Interner { names: &mut names });
+    assert_eq!(indices.unwrap(), [0, 1, 0]);
+    assert_eq!(names, ["x", "y"]);
+
+    // Strings without escapes are borrowed from the input
+    let input = String::from(r#"["plain", "text"]"#);
+    let words = from_str_seed(&input, PhantomData::<Vec<&str>>).unwrap();
+    assert_eq!(words, ["plain", "text"]);
+
+    assert!(from_str_seed("[", PhantomData::<Vec<&str>>).is_err());
+}
@@ -0,0 +1,66 @@
+use maplit::*;
+use typed_format::{
+    ser::{to_string_into, with_buffer},
+    to_writer, to_writer_pretty,
+    value::Value,
+};
+
+fn write<T>(value: &T, pretty: bool) -> String
+where
+    T: serde::Serialize,
+{
+    let mut buffer = Vec::new();
+    if pretty {
+        to_writer_pretty(&mut buffer, value).unwrap();
+    } else {
+        to_writer(&mut buffer, value).unwrap();
+    }
+    String::from_utf8(buffer).unwrap()
+}
+
+#[test]
+fn matches_printer() {
+    let value = btreemap! {
+        "first".to_string() => vec![Some((1, 'a')), None],
+        "second \"quoted\"".to_string() => vec![],
+        "third".to_string() => vec![Some((-3, '\n'))],
+    };
+
+    assert_eq!(write(&value, true), Value::new(&value).to_string_pretty());
+    assert_eq!(write(&value, false), Value::new(&value).to_string_compact());
+}
+
+#[test]
+fn reparse() {
+    let value = (
+        vec![1u8, 2, 3],
+        Some("text"),
+        (),
+        btreemap! { 1 => true, 2 => false },
+    );
+
+    for pretty in &[true, false] {
+        let parsed = Value::parse(&write(&value, *pretty)).unwrap();
+        assert_eq!(parsed, Value::new(&value));
+    }
+}
+
+#[test]
+fn reused_buffers() {
+    let mut buffer = String::from("prefix ");
+    to_string_into(&mut buffer, &(1, "a")).unwrap();
+    to_string_into(&mut buffer, &vec![true]).unwrap();
+    assert_eq!(buffer, "prefix (1,\"a\",)[true,]");
+
+    let lengths = (0..3)
+        .map(|i| with_buffer(&vec![i; i], |text| text.len()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lengths, vec![2, 4, 6]);
+
+    let nested = with_buffer(&1, |outer| {
+        let inner = with_buffer(&2, str::to_string).unwrap();
+        format!("{} {}", outer, inner)
+    })
+    .unwrap();
+    assert_eq!(nested, "1 2");
+}
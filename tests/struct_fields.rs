@@ -0,0 +1,459 @@
+use serde_derive::{Deserialize, Serialize};
+use typed_format::{
+    de::{from_reader, TextDeserializer},
+    from_str,
+    value::{
+        options::{DeserializeOptions, SerializeOptions, TypeNameCheck},
+        printer::ValuePrinter,
+        serializer::ValueSerializer,
+        Value,
+    },
+};
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+struct Point {
+    #[serde(rename = "horizontal")]
+    x: i32,
+    #[serde(rename = "vertical")]
+    y: i32,
+}
+
+fn point(input: &str) -> Point {
+    Value::parse(input).unwrap().deserialize().unwrap()
+}
+
+#[test]
+fn fields_by_index() {
+    let expected = Point { x: 1, y: 2 };
+
+    assert_eq!(point("Point(horizontal: 1, vertical: 2)"), expected);
+    assert_eq!(point(r#"{"vertical": 2, "horizontal": 1}"#), expected);
+    assert_eq!(point("{1: 2, 0: 1}"), expected);
+    assert_eq!(point("(1, 2)"), expected);
+    assert_eq!(point("Point(1, 2)"), expected);
+
+    let error = Value::parse("{0: 1, 1: 2, 2: 3}")
+        .unwrap()
+        .deserialize::<Point>()
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("expected field index"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn unknown_fields() {
+    let input = r#"Point(horizontal: 1, depth: 5, vertical: 2)"#;
+    let value = Value::parse(input).unwrap();
+
+    let error = value.deserialize::<Point>().unwrap_err();
+    assert!(
+        error.to_string().contains("unknown field `depth`"),
+        "{}",
+        error
+    );
+
+    let options = DeserializeOptions {
+        ignore_unknown_fields: true,
+        ..Default::default()
+    };
+    let expected = Point { x: 1, y: 2 };
+    assert_eq!(value.deserialize_with::<Point>(&options).unwrap(), expected);
+
+    let map = Value::parse(r#"{"horizontal": 1, 7: 0, "vertical": 2, "z": 3}"#)
+        .unwrap();
+    assert_eq!(map.deserialize_with::<Point>(&options).unwrap(), expected);
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Service {
+    name: String,
+    port: Option<u16>,
+    #[serde(default = "default_level")]
+    level: u8,
+    #[serde(default)]
+    tags: Vec<String>,
+    backup: Option<Option<String>>,
+}
+
+fn default_level() -> u8 {
+    3
+}
+
+#[test]
+fn missing_fields() {
+    let expected = Service {
+        name: "web".to_string(),
+        port: None,
+        level: 3,
+        tags: vec![],
+        backup: None,
+    };
+    for input in &[r#"Service(name: "web")"#, r#"{"name": "web"}"#] {
+        let value = Value::parse(input).unwrap();
+        assert_eq!(value.deserialize::<Service>().unwrap(), expected);
+        assert_eq!(from_str::<Service>(input).unwrap(), expected);
+        let read: Service = from_reader(input.as_bytes()).unwrap();
+        assert_eq!(read, expected);
+    }
+
+    let error = Value::parse("Service(port: None)")
+        .unwrap()
+        .deserialize::<Service>()
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("missing field `name`"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn implicit_some() {
+    let value = Value::parse(r#"Service(name: "web", port: 80, backup: "db")"#)
+        .unwrap();
+    let error = value.deserialize::<Service>().unwrap_err();
+    assert!(error.to_string().contains("Expected Option"), "{}", error);
+
+    let options = DeserializeOptions {
+        implicit_some: true,
+        ..Default::default()
+    };
+    let service = value.deserialize_with::<Service>(&options).unwrap();
+    assert_eq!(service.port, Some(80));
+    assert_eq!(service.backup, Some(Some("db".to_string())));
+
+    // Written options are still read as usual
+    let value = Value::parse(
+        r#"Service(name: "web", port: Some(80), backup: Some(None))"#,
+    )
+    .unwrap();
+    let service = value.deserialize_with::<Service>(&options).unwrap();
+    assert_eq!((service.port, service.backup), (Some(80), Some(None)));
+
+    let mut text = String::new();
+    let printer = ValuePrinter::inline().implicit_some(true);
+    printer.write(&value, &mut text).unwrap();
+    assert_eq!(
+        text,
+        r#"Service(name: "web", port: 80, backup: Some(None))"#
+    );
+
+    for backup in &[None, Some(None), Some(Some("db".to_string()))] {
+        let service = Service {
+            name: "web".to_string(),
+            port: Some(80),
+            level: 1,
+            tags: vec!["a".to_string()],
+            backup: backup.clone(),
+        };
+        let mut text = String::new();
+        printer.write(&Value::new(&service), &mut text).unwrap();
+
+        let value = Value::parse(&text).unwrap();
+        assert_eq!(
+            value.deserialize_with::<Service>(&options).unwrap(),
+            service
+        );
+
+        let mut deserializer =
+            TextDeserializer::new(text.as_bytes()).implicit_some(true);
+        let read: Service =
+            serde::Deserialize::deserialize(&mut deserializer).unwrap();
+        deserializer.end().unwrap();
+        assert_eq!(read, service);
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct ClientConfig {
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Meters(u32);
+
+#[test]
+fn type_names() {
+    let server = Value::parse("ServerConfig(port: 80)").unwrap();
+    let client = Value::parse("config::ClientConfig(port: 80)").unwrap();
+    let unnamed = Value::parse("(port: 80)").unwrap();
+    let expected = ClientConfig { port: 80 };
+
+    // Not checked by default
+    assert_eq!(server.deserialize::<ClientConfig>().unwrap(), expected);
+
+    let last_segment = DeserializeOptions {
+        type_names: TypeNameCheck::LastSegment,
+        ..Default::default()
+    };
+    let error = server
+        .deserialize_with::<ClientConfig>(&last_segment)
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Expected ClientConfig, found ServerConfig"),
+        "{}",
+        error
+    );
+    assert_eq!(
+        client
+            .deserialize_with::<ClientConfig>(&last_segment)
+            .unwrap(),
+        expected
+    );
+    assert_eq!(
+        unnamed
+            .deserialize_with::<ClientConfig>(&last_segment)
+            .unwrap(),
+        expected
+    );
+
+    let exact = DeserializeOptions {
+        type_names: TypeNameCheck::Exact,
+        ..Default::default()
+    };
+    assert!(client.deserialize_with::<ClientConfig>(&exact).is_err());
+
+    let meters = Value::parse("Meters(5)").unwrap();
+    let feet = Value::parse("Feet(5)").unwrap();
+    assert_eq!(
+        meters.deserialize_with::<Meters>(&exact).unwrap(),
+        Meters(5)
+    );
+    assert!(feet.deserialize_with::<Meters>(&exact).is_err());
+}
+
+#[test]
+fn type_paths() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Mode {
+        Fast,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(rename = "config::Settings")]
+    struct Settings {
+        mode: Mode,
+        port: Meters,
+    }
+
+    let settings = Settings {
+        mode: Mode::Fast,
+        port: Meters(80),
+    };
+    // Renamed types keep their name, since it differs from the Rust name
+    let options = SerializeOptions {
+        root_type_path: true,
+        ..Default::default()
+    }
+    .with_type_path::<Mode>();
+    let value = Value::try_new_with(&settings, &options).unwrap();
+
+    let module = module_path!();
+    assert_eq!(
+        value.to_string_compact(),
+        format!(
+            "config::Settings(mode:{}::type_paths::Mode::Fast,port:Meters(80,),)",
+            module
+        )
+    );
+    let value = Value::try_new_with(&Meters(80), &options).unwrap();
+    assert_eq!(
+        value.to_string_compact(),
+        format!("{}::Meters(80,)", module)
+    );
+
+    let check = |type_names, input: &str| {
+        Value::parse(input)
+            .unwrap()
+            .deserialize_with::<Settings>(&DeserializeOptions {
+                type_names,
+                ..Default::default()
+            })
+            .is_ok()
+    };
+    let input = "app::config::Settings(mode: Fast, port: 80)";
+    assert!(check(TypeNameCheck::LastSegment, input));
+    assert!(check(TypeNameCheck::Suffix, input));
+    assert!(!check(TypeNameCheck::Exact, input));
+    assert!(check(
+        TypeNameCheck::Exact,
+        "config::Settings(mode: Fast, port: 80)"
+    ));
+    assert!(check(
+        TypeNameCheck::LastSegment,
+        "other::Settings(mode: Fast, port: 80)"
+    ));
+    assert!(!check(
+        TypeNameCheck::Suffix,
+        "other::Settings(mode: Fast, port: 80)"
+    ));
+}
+
+mod client {
+    #[derive(serde_derive::Serialize)]
+    pub struct Config(pub u8);
+}
+
+mod server {
+    #[derive(serde_derive::Serialize)]
+    pub struct Config(pub u8);
+}
+
+#[test]
+fn ambiguous_type_paths() {
+    let options = SerializeOptions::default()
+        .with_type_path::<client::Config>()
+        .with_type_path::<Meters>();
+    let value = Value::try_new_with(&client::Config(1), &options).unwrap();
+    assert_eq!(
+        value.to_string_compact(),
+        format!("{}::client::Config(1,)", module_path!())
+    );
+
+    // Both are named Config, so neither gets its path
+    let options = options.with_type_path::<server::Config>();
+    let value =
+        Value::try_new_with(&(client::Config(1), Meters(2)), &options).unwrap();
+    assert_eq!(
+        value.to_string_compact(),
+        format!("(Config(1,),{}::Meters(2,),)", module_path!())
+    );
+
+    // Without options, as a unit struct
+    let value = serde::Serialize::serialize(&Meters(3), ValueSerializer);
+    assert_eq!(value.unwrap().to_string_compact(), "Meters(3,)");
+}
+
+#[test]
+#[cfg_attr(feature = "preserve_order", ignore = "expects sorted entries")]
+fn bare_keys() {
+    let value = Value::parse("{ horizontal: 1, vertical: 2 }").unwrap();
+    let keys = value
+        .clone()
+        .into_map()
+        .unwrap()
+        .into_keys()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            Value::UnitStruct("horizontal".into()),
+            Value::UnitStruct("vertical".into()),
+        ]
+    );
+    assert_eq!(Value::parse(&value.to_string_compact()).unwrap(), value);
+    assert_eq!(value["horizontal"], Value::parse("1").unwrap());
+    assert_eq!(value.deserialize::<Point>().unwrap(), Point { x: 1, y: 2 });
+
+    let map: std::collections::BTreeMap<String, i32> =
+        value.deserialize().unwrap();
+    assert_eq!(map["vertical"], 2);
+    let map: std::collections::BTreeMap<&str, i32> =
+        typed_format::from_str_borrowed("{ a: 1, /* b */ b : 2 }").unwrap();
+    assert_eq!(map["b"], 2);
+
+    // Keywords and paths are still values
+    let keywords = "{ true: 1, None: 2, Color::Red: 3 }";
+    let value = Value::parse(keywords).unwrap();
+    let keys = value.into_map().unwrap().into_keys().collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            Value::Bool(true),
+            Value::parse("Color::Red").unwrap(),
+            Value::Option(None),
+        ]
+    );
+    let streamed: Value = from_reader(keywords.as_bytes()).unwrap();
+    assert_eq!(streamed, Value::parse(keywords).unwrap());
+}
+
+#[test]
+fn bare_keys_streamed() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct S {
+        a: u32,
+        b: String,
+        m: std::collections::BTreeMap<String, i32>,
+    }
+
+    let input = r#"S(a: 1_000, b: "x", m: {k: 1, /* l */ l : 2})"#;
+    let expected = S {
+        a: 1_000,
+        b: "x".to_string(),
+        m: vec![("k".to_string(), 1), ("l".to_string(), 2)]
+            .into_iter()
+            .collect(),
+    };
+    assert_eq!(from_reader::<_, S>(input.as_bytes()).unwrap(), expected);
+    assert_eq!(from_str::<S>(input).unwrap(), expected);
+
+    let point = "{ horizontal: 1, vertical: 2 }";
+    assert_eq!(
+        from_reader::<_, Point>(point.as_bytes()).unwrap(),
+        Point { x: 1, y: 2 }
+    );
+    assert_eq!(
+        from_reader::<_, Value>(point.as_bytes()).unwrap(),
+        Value::parse(point).unwrap()
+    );
+}
+
+#[test]
+fn anonymous_newtypes() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Meters(u32);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Route {
+        length: Meters,
+        detour: Option<Meters>,
+    }
+
+    let expected = Route {
+        length: Meters(5),
+        detour: Some(Meters(2)),
+    };
+    for input in &[
+        "Route(length: Meters(5), detour: Some(Meters(2)))",
+        "(length: (5), detour: Some((2)))",
+        "(length: 5, detour: Some(2))",
+    ] {
+        let route: Route = Value::parse(input).unwrap().deserialize().unwrap();
+        assert_eq!(route, expected, "{}", input);
+    }
+}
+
+#[test]
+fn declaration_order() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Server {
+        port: u16,
+        host: String,
+        aliases: Vec<String>,
+    }
+
+    let server = Server {
+        port: 80,
+        host: "localhost".to_string(),
+        aliases: vec![],
+    };
+    let value = Value::new(&server);
+    assert_eq!(
+        value.to_string_pretty(),
+        "Server(\n    port: 80,\n    host: \"localhost\",\n    aliases: [],\n)"
+    );
+
+    // Parsed fields keep the order they were written in
+    let text = r#"Server(host:"localhost",aliases:[],port:80,)"#;
+    let parsed = Value::parse(text).unwrap();
+    assert_eq!(parsed.to_string_compact(), text);
+    assert_eq!(parsed, value);
+    assert_eq!(parsed.deserialize::<Server>().unwrap(), server);
+}
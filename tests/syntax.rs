@@ -0,0 +1,857 @@
+use maplit::*;
+use std::sync::Arc;
+use typed_format::value::{
+    compact::CompactValue,
+    number::Number,
+    options::{ParseOptions, DEFAULT_MAX_DEPTH},
+    printer::ValuePrinter,
+    types::{Identifier, TypeIdentifier},
+    Value,
+};
+
+/// Prints the value in both modes and parses it back
+fn assert_reparse(value: &Value) {
+    for string in &[value.to_string_pretty(), value.to_string_compact()] {
+        let parsed = Value::parse(string).unwrap();
+        assert_eq!(*value, parsed);
+    }
+}
+
+#[test]
+fn quoted_identifiers() {
+    let value = Value::Struct(
+        "Test".into(),
+        btreemap! {
+            Identifier::from("valid_name") => Value::Unit,
+            Identifier::from("with space") => Value::Char('a'),
+            Identifier::from("\"quoted\"") => Value::Unit,
+            Identifier::from("0digit") => Value::Unit,
+            Identifier::from("") => Value::Unit,
+        }
+        .into_iter()
+        .collect(),
+    );
+
+    assert!(value.to_string_compact().contains("valid_name:"));
+    assert!(value.to_string_compact().contains("\"with space\":"));
+
+    assert_reparse(&value);
+}
+
+#[test]
+fn auto_layout() {
+    let short = Value::List(vec![
+        Value::Char('a'),
+        Value::Unit,
+        Value::Tuple(vec![Value::Unit, Value::Char('b')]),
+    ]);
+    assert_eq!(short.to_string_auto(), "['a', (), ((), 'b')]");
+
+    let long = Value::Tuple(vec![short.clone(); 10]);
+    let string = long.to_string_auto();
+    assert!(string.starts_with("(\n    ['a', (), ((), 'b')],\n"));
+    assert_eq!(Value::parse(&string).unwrap(), long);
+}
+
+#[test]
+fn diff_layout() {
+    let value = Value::parse(
+        r#"Config(
+            name: Some("server"),
+            port: Port(8080),
+            tags: ["a"],
+            pair: (Some(1),),
+            limits: {(1, 2): Some([3])},
+        )"#,
+    )
+    .unwrap();
+    let string = value.to_string_diff();
+    assert_eq!(
+        string,
+        r#"Config(
+    name: Some("server"),
+    port: Port(8080),
+    tags: [
+        "a",
+    ],
+    pair: (
+        Some(1),
+    ),
+    limits: {
+        (1, 2): Some(
+            [
+                3,
+            ]
+        ),
+    },
+)"#
+    );
+    assert_eq!(Value::parse(&string).unwrap(), value);
+
+    // Growing a collection only adds lines
+    let grown = Value::parse(r#"Config(tags: ["a", "b"])"#).unwrap();
+    let before = Value::parse(r#"Config(tags: ["a"])"#).unwrap();
+    let lines = |value: &Value| {
+        value
+            .to_string_diff()
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let mut expected = lines(&before);
+    expected.insert(3, "        \"b\",".to_string());
+    assert_eq!(lines(&grown), expected);
+}
+
+#[test]
+fn fit_layout() {
+    let value = Value::parse(
+        r#"Scene(
+            origin: (1, 2, 3),
+            background: Color(r: 1, g: 2, b: 3),
+            objects: [
+                Object(name: "a rather long name", position: (10, 20, 30), visible: true),
+                Object(name: "short", position: (0, 0, 0), visible: false),
+            ],
+            lookup: {"an even longer key than before to push things": [1, 2, 3, 4, 5, 6]},
+        )"#,
+    )
+    .unwrap();
+    let string = value.to_string_fit();
+    assert_eq!(
+        string,
+        r#"Scene(
+    origin: (1, 2, 3),
+    background: Color(r: 1, g: 2, b: 3),
+    objects: [
+        Object(
+            name: "a rather long name",
+            position: (10, 20, 30),
+            visible: true,
+        ),
+        Object(name: "short", position: (0, 0, 0), visible: false),
+    ],
+    lookup: {
+        "an even longer key than before to push things": [1, 2, 3, 4, 5, 6],
+    },
+)"#
+    );
+    assert_eq!(Value::parse(&string).unwrap(), value);
+
+    // The key and the comma count towards the width
+    let width = |width| {
+        let mut string = String::new();
+        ValuePrinter::fit()
+            .max_inline_width(Some(width))
+            .write(&value, &mut string)
+            .unwrap();
+        string
+    };
+    assert!(width(76).contains("\n        \"an even longer key than before to push things\": [1, 2, 3, 4, 5, 6],\n"));
+    assert!(width(75).contains("\n        \"an even longer key than before to push things\": [\n            1,\n"));
+    assert_eq!(width(1000).lines().count(), 1);
+}
+
+#[test]
+fn wrapped_scalars() {
+    let value = Value::parse(
+        r#"Config(
+            name: Some("server"),
+            port: Port(8080),
+            pair: (Some(1),),
+            nested: Some(Some(Meters(5))),
+            list: Some([1]),
+        )"#,
+    )
+    .unwrap();
+    assert!(value.to_string_pretty().contains("name: Some(\n"));
+
+    let mut string = String::new();
+    ValuePrinter::pretty()
+        .inline_wrapped_scalars(true)
+        .write(&value, &mut string)
+        .unwrap();
+    assert_eq!(
+        string,
+        r#"Config(
+    name: Some("server"),
+    port: Port(8080),
+    pair: (Some(1)),
+    nested: Some(Some(Meters(5))),
+    list: Some(
+        [
+            1,
+        ]
+    ),
+)"#
+    );
+    assert_eq!(Value::parse(&string).unwrap(), value);
+}
+
+#[test]
+fn printer_options() {
+    let value = Value::parse(
+        r#"Config(
+            name: "web",
+            ports: [80, 443],
+            routes: {"/": Route(methods: ["GET"], target: (1, 2))},
+        )"#,
+    )
+    .unwrap();
+    let print = |printer: ValuePrinter| {
+        let mut string = String::new();
+        printer.write(&value, &mut string).unwrap();
+        string
+    };
+
+    assert_eq!(
+        print(
+            ValuePrinter::pretty()
+                .indentation("\t")
+                .newline("\r\n")
+                .trailing_commas(false)
+                .max_inline_width(Some(10))
+                .field_colon(" = ")
+                .map_colon(" => ")
+        ),
+        "Config(\r\n\tname = \"web\",\r\n\tports = [80, 443],\r\n\troutes = {\r\n\
+         \t\t\"/\" => Route(\r\n\t\t\tmethods = [\"GET\"],\r\n\t\t\ttarget = (1, 2)\r\n\
+         \t\t)\r\n\t}\r\n)"
+    );
+    assert_eq!(
+        print(ValuePrinter::pretty().inline_depth(Some(1))),
+        r#"Config(
+    name: "web",
+    ports: [80, 443],
+    routes: {"/": Route(methods: ["GET"], target: (1, 2))},
+)"#
+    );
+    assert_eq!(
+        print(ValuePrinter::compact().trailing_commas(false)),
+        r#"Config(name:"web",ports:[80,443],routes:{"/":Route(methods:["GET"],target:(1,2))})"#
+    );
+
+    // Only the custom colons leave the syntax
+    for printer in [
+        ValuePrinter::pretty().max_inline_width(Some(40)),
+        ValuePrinter::pretty().inline_depth(Some(2)),
+        ValuePrinter::compact().trailing_commas(false),
+    ] {
+        assert_eq!(Value::parse(&print(printer)).unwrap(), value);
+    }
+}
+
+#[test]
+fn io_printer() {
+    let value =
+        Value::parse(r#"Config(name: "web", ports: [80, 443])"#).unwrap();
+
+    let mut bytes = Vec::new();
+    ValuePrinter::pretty().write_io(&value, &mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), value.to_string_pretty());
+
+    // Errors of the stream are passed on as they are
+    let mut full = [0u8; 8];
+    let error = ValuePrinter::pretty()
+        .write_io(&value, &mut &mut full[..])
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+    assert_eq!(&full, b"Config(\n");
+}
+
+#[test]
+fn length_limits() {
+    let options = ParseOptions {
+        max_string_length: Some(5),
+        max_identifier_length: Some(3),
+        ..Default::default()
+    };
+
+    assert!(Value::parse_with(r#"["12345", "\n\n"]"#, &options).is_ok());
+    assert!(Value::parse_with(r#""123456""#, &options).is_err());
+    assert!(Value::parse_with(r#""1234\n""#, &options).is_ok());
+    assert!(Value::parse_with(r#""1234\n\n""#, &options).is_err());
+    assert!(Value::parse_with(r#""\n12345""#, &options).is_err());
+
+    assert!(Value::parse_with("Foo(bar: ())", &options).is_ok());
+    assert!(Value::parse_with("Foo(long: ())", &options).is_err());
+    assert!(Value::parse_with(r#"Foo("long": ())"#, &options).is_err());
+    assert!(Value::parse_with("Long", &options).is_err());
+}
+
+#[test]
+fn size_and_depth_limits() {
+    let options = ParseOptions {
+        max_input_size: Some(16),
+        max_depth: Some(3),
+        ..Default::default()
+    };
+
+    assert!(Value::parse_with("[[[1]], (2,)]", &options).is_ok());
+    let error = Value::parse_with("[[[1]], ([{}],)]", &options).unwrap_err();
+    assert_eq!(error.offset, 10);
+    assert_eq!(error.message, "Nesting exceeds the maximum depth of 3");
+
+    // Delimiters in strings and comments do not count
+    assert!(Value::parse_with("[\"[[[\"] // ((", &options).is_ok());
+
+    let error =
+        Value::parse_with("\"12345678901234567\"", &options).unwrap_err();
+    assert_eq!(error.message, "Input of 19 bytes exceeds the maximum of 16");
+
+    // Nesting far beyond what the parser could handle fails cleanly
+    let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+    let options = ParseOptions {
+        max_depth: Some(64),
+        ..Default::default()
+    };
+    assert!(Value::parse_with(&deep, &options).is_err());
+}
+
+#[test]
+fn deep_nesting() {
+    // Limited by default, instead of overflowing the stack
+    let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+    assert!(Value::parse(&nested(DEFAULT_MAX_DEPTH)).is_ok());
+    let error = Value::parse(&nested(100_000)).unwrap_err();
+    assert_eq!(
+        error.message,
+        format!("Nesting exceeds the maximum depth of {}", DEFAULT_MAX_DEPTH)
+    );
+
+    let options = ParseOptions {
+        max_depth: None,
+        ..Default::default()
+    };
+    assert!(Value::parse_with(&nested(200), &options).is_ok());
+
+    // Generics nest as deep as delimiters
+    let generic =
+        |depth| format!("<{}u8{}>", "Vec<".repeat(depth), ">".repeat(depth));
+    assert!(Value::parse(&generic(DEFAULT_MAX_DEPTH - 1)).is_ok());
+    let error = Value::parse(&generic(20_000)).unwrap_err();
+    assert_eq!(
+        error.message,
+        format!("Nesting exceeds the maximum depth of {}", DEFAULT_MAX_DEPTH)
+    );
+    assert!(typed_format::from_str::<Value>(&generic(20_000)).is_err());
+
+    // Every delimiter counts, up to the maximum and not further
+    let shapes = [
+        ("[", "]"),
+        ("Some(", ")"),
+        ("{0: ", "}"),
+        ("Foo(a: ", ")"),
+        ("(0, ", ")"),
+        ("a::B<u8>(", ")"),
+    ];
+    for (open, close) in &shapes {
+        for (inner, inner_depth) in &[("()", 1), ("<(u8, [u8; 1])>", 3)] {
+            let nested = |depth| {
+                format!(
+                    "{}{}{}",
+                    open.repeat(depth),
+                    inner,
+                    close.repeat(depth)
+                )
+            };
+            let depth = DEFAULT_MAX_DEPTH - inner_depth;
+            let input = nested(depth);
+            assert!(Value::parse(&input).is_ok(), "{}", input);
+            assert!(CompactValue::parse(&input).is_ok(), "{}", input);
+
+            let input = nested(depth + 1);
+            for error in &[
+                Value::parse(&input).unwrap_err(),
+                CompactValue::parse(&input).unwrap_err(),
+            ] {
+                assert_eq!(
+                    error.message,
+                    format!(
+                        "Nesting exceeds the maximum depth of {}",
+                        DEFAULT_MAX_DEPTH
+                    ),
+                    "{}",
+                    input
+                );
+            }
+        }
+    }
+
+    // Printing needs no stack for nesting
+    let depth = 2_000;
+    let mut value = Value::List(vec![]);
+    for _ in 1..depth {
+        value = Value::List(vec![value]);
+    }
+    for printer in &[
+        ValuePrinter::compact(),
+        ValuePrinter::pretty(),
+        ValuePrinter::auto(),
+        ValuePrinter::diff(),
+        ValuePrinter::fit(),
+    ] {
+        let mut text = String::new();
+        printer.write(&value, &mut text).unwrap();
+        assert_eq!(text.matches('[').count(), depth);
+        assert_eq!(text.matches(']').count(), depth);
+    }
+}
+
+#[test]
+fn lenient_parsing() {
+    let input = r#"["C:\data", b"\q", "\u00e4"]"#;
+    let error = Value::parse(input).unwrap_err();
+    assert_eq!(error.message, "Unknown escape character 'd'");
+    assert_eq!(error.offset, 1);
+
+    let options = ParseOptions {
+        allow_unknown_escapes: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        Value::parse_with(input, &options).unwrap(),
+        Value::List(vec![
+            Value::new("C:\\data"),
+            Value::Bytes(b"\\q".to_vec()),
+            Value::new("\u{e4}"),
+        ])
+    );
+    // A char can not hold the backslash and the escaped character
+    assert!(Value::parse_with(r"'\d'", &options).is_err());
+
+    let input = "Config(port: 80)\n---\nnot: [a value";
+    assert!(Value::parse(input).is_err());
+    let options = ParseOptions {
+        allow_trailing_garbage: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        Value::parse_with(input, &options).unwrap(),
+        Value::parse("Config(port: 80)").unwrap()
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    let input = "{\n    \"a\": 1,\n    \"a\": 2,\n}";
+    assert_eq!(Value::parse(input).unwrap()["a"], Value::new(2));
+
+    let options = ParseOptions {
+        reject_duplicate_keys: true,
+        ..Default::default()
+    };
+    let error = Value::parse_with(input, &options).unwrap_err();
+    assert_eq!(error.message, "Duplicate key \"a\"");
+    assert_eq!((error.line, error.column), (3, 5));
+
+    let error =
+        Value::parse_with("[Foo(a: 1, b: 2, a: 3)]", &options).unwrap_err();
+    assert_eq!(error.message, "Duplicate field a");
+    assert_eq!(error.offset, 17);
+
+    assert!(Value::parse_with("{1: Foo(a: 1), 2: Foo(a: 1)}", &options).is_ok());
+}
+
+#[test]
+fn dedup_strings() {
+    let input = r#"["same", "same", "other"]"#;
+
+    let strings =
+        |options: &ParseOptions| match Value::parse_with(input, options)
+            .unwrap()
+        {
+            Value::List(items) => items
+                .into_iter()
+                .map(|it| match it {
+                    Value::String(s) => s,
+                    other => panic!("Expected string, found {:?}", other),
+                })
+                .collect::<Vec<Arc<str>>>(),
+            other => panic!("Expected list, found {:?}", other),
+        };
+
+    let shared = strings(&ParseOptions {
+        dedup_strings: true,
+        ..Default::default()
+    });
+    assert!(Arc::ptr_eq(&shared[0], &shared[1]));
+    assert!(!Arc::ptr_eq(&shared[0], &shared[2]));
+
+    let separate = strings(&ParseOptions::default());
+    assert_eq!(separate, shared);
+    assert!(!Arc::ptr_eq(&separate[0], &separate[1]));
+}
+
+#[test]
+fn omitted_names() {
+    let value = Value::Tuple(vec![
+        Value::Struct(
+            "Point".into(),
+            btreemap! { Identifier::from("x") => Value::Number(1u64.into()) }
+                .into_iter()
+                .collect(),
+        ),
+        Value::TupleStruct("Meters".into(), vec![Value::Number(5u64.into())]),
+        Value::UnitStruct("Marker".into()),
+        Value::UnitStruct(("Enum", "Variant").into()),
+        Value::ok(Value::Unit),
+    ]);
+
+    let mut terse = String::new();
+    ValuePrinter::inline()
+        .omit_names(true)
+        .write(&value, &mut terse)
+        .unwrap();
+    assert_eq!(terse, "((x: 1), (5), (), Variant, Ok(()))");
+
+    let parsed = Value::parse(&terse).unwrap();
+    assert_eq!(
+        parsed,
+        Value::Tuple(vec![
+            Value::Struct(
+                TypeIdentifier { segments: vec![] },
+                btreemap! { Identifier::from("x") => Value::Number(1u64.into()) }
+                .into_iter()
+                .collect(),
+            ),
+            Value::Tuple(vec![Value::Number(5u64.into())]),
+            Value::Unit,
+            Value::UnitStruct("Variant".into()),
+            Value::TupleStruct("Ok".into(), vec![Value::Unit]),
+        ])
+    );
+    let result = Value::parse("Ok(())").unwrap();
+    assert_eq!(
+        result.retag::<Result<(), ()>>().unwrap(),
+        Value::ok(Value::Unit)
+    );
+}
+
+#[test]
+fn number_literals() {
+    let input = "[0xFF, -0x10, 0o77, 0b1010, 1_000_000, 1_0.5, 0xffff_ffff]";
+
+    let value = Value::parse(input).unwrap();
+    assert_eq!(
+        value.to_string_compact(),
+        "[255,-16,63,10,1000000,10.5,4294967295,]"
+    );
+
+    let numbers: (u8, i32, u16, u8, u32, f64, u32) =
+        typed_format::from_str(&input.replace('[', "(").replace(']', ")"))
+            .unwrap();
+    assert_eq!(numbers, (255, -16, 63, 10, 1_000_000, 10.5, 0xffff_ffff));
+
+    let streamed: Vec<f64> =
+        typed_format::from_reader(input.as_bytes()).unwrap();
+    assert_eq!(
+        streamed,
+        vec![255.0, -16.0, 63.0, 10.0, 1_000_000.0, 10.5, 4_294_967_295.0]
+    );
+
+    assert!(typed_format::from_str::<u8>("0x100").is_err());
+    assert!(Value::parse("0xG").is_err());
+    assert!(Value::parse("0b102").is_err());
+    #[cfg(feature = "json")]
+    assert_eq!(
+        typed_format::value::json::to_json(&value, false),
+        "[255,-16,63,10,1000000,10.5,4294967295]"
+    );
+}
+
+#[test]
+fn special_floats() {
+    let floats = vec![f64::INFINITY, f64::NEG_INFINITY, 1.5e10, -2.5e-3];
+
+    let printed = Value::try_new(&floats).unwrap();
+    assert_reparse(&printed);
+    assert_eq!(printed.deserialize::<Vec<f64>>().unwrap(), floats);
+
+    let string = typed_format::to_string_compact(&floats).unwrap();
+    assert_eq!(typed_format::from_str::<Vec<f64>>(&string).unwrap(), floats);
+    let streamed: Vec<f64> =
+        typed_format::from_reader(string.as_bytes()).unwrap();
+    assert_eq!(streamed, floats);
+
+    let nan: f32 = typed_format::from_str("NaN").unwrap();
+    assert!(nan.is_nan());
+    let nan: f64 = typed_format::from_reader("NaN".as_bytes()).unwrap();
+    assert!(nan.is_nan());
+
+    #[cfg(feature = "json")]
+    {
+        let value = Value::parse("[inf, -inf, NaN, 1.5e10, 1E-3]").unwrap();
+        assert_eq!(
+            typed_format::value::json::to_json(&value, false),
+            "[null,null,null,15000000000.0,0.001]"
+        );
+    }
+
+    // Names that only start like a special float are still types
+    assert_eq!(
+        Value::parse("info").unwrap(),
+        Value::UnitStruct("info".into())
+    );
+    assert!(Value::parse("inf").unwrap().deserialize::<u32>().is_err());
+}
+
+#[test]
+#[cfg_attr(feature = "preserve_order", ignore = "expects sorted entries")]
+fn structured_numbers() {
+    let numbers = Value::parse(
+        "[0, -1, 18446744073709551616, -9223372036854775809, 1.0, 0x7f]",
+    )
+    .unwrap();
+    assert_eq!(
+        numbers,
+        Value::List(vec![
+            Value::Number(Number::U64(0)),
+            Value::Number(Number::I64(-1)),
+            Value::Number(Number::U128(1 << 64)),
+            Value::Number(Number::I128(-(1 << 63) - 1)),
+            Value::Number(Number::F64(1.0)),
+            Value::Number(Number::U64(127)),
+        ])
+    );
+    assert_reparse(&numbers);
+
+    // Integers are compared by value, not by variant
+    assert_eq!(Number::I64(5), Number::U64(5));
+    assert_ne!(Number::U64(1), Number::F64(1.0));
+    assert_eq!(Number::from(-3i8).as_i64(), Some(-3));
+    assert_eq!(Number::from(-3i8).as_u64(), None);
+    assert_eq!(Number::F64(2.5).as_i64(), None);
+    assert_eq!(Number::from(0.1f32).to_string(), "0.1");
+
+    let big: (u128, i128) = typed_format::from_str(
+        "(340282366920938463463374607431768211455, \
+          -170141183460469231731687303715884105728)",
+    )
+    .unwrap();
+    assert_eq!(big, (u128::MAX, i128::MIN));
+    assert_eq!(
+        typed_format::to_string_compact(&big).unwrap(),
+        "(340282366920938463463374607431768211455,\
+          -170141183460469231731687303715884105728,)"
+    );
+
+    // Map keys are ordered by value
+    let map = Value::parse("{10: (), 9: (), -1.5: (), 100: ()}").unwrap();
+    assert_eq!(map.to_string_compact(), "{-1.5:(),9:(),10:(),100:(),}");
+}
+
+#[test]
+fn raw_strings() {
+    let value = Value::parse(
+        r####"[r"C:\Users", r#"say "hi""#, r##"a "# b"##, r"", rust]"####,
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        Value::List(vec![
+            Value::String(r"C:\Users".into()),
+            Value::String(r#"say "hi""#.into()),
+            Value::String(r##"a "# b"##.into()),
+            Value::String("".into()),
+            Value::UnitStruct("rust".into()),
+        ])
+    );
+
+    let strings = vec![
+        r"C:\Program Files\app".to_string(),
+        r"^\d+\.\d+$".to_string(),
+        r##"a "quote" "# hash"##.to_string(),
+        r"one \ backslash".to_string(),
+        "line\nbreak \\ \\".to_string(),
+    ];
+    let printed = typed_format::to_string_compact(&strings).unwrap();
+    assert_eq!(
+        printed,
+        r###"[r"C:\Program Files\app",r"^\d+\.\d+$",r##"a "quote" "# hash"##,"one \\ backslash","line\nbreak \\ \\",]"###
+    );
+    assert_reparse(&Value::new(&strings));
+
+    let mut streamed = Vec::new();
+    typed_format::to_writer(&mut streamed, &strings).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), printed);
+    let read: Vec<String> =
+        typed_format::from_reader(printed.as_bytes()).unwrap();
+    assert_eq!(read, strings);
+
+    let options = ParseOptions {
+        max_string_length: Some(3),
+        ..Default::default()
+    };
+    assert!(Value::parse_with(r#"r"abcd""#, &options).is_err());
+}
+
+/// Bytes that serialize with `serialize_bytes` instead of as a sequence
+#[derive(Debug, Eq, PartialEq)]
+struct Blob(Vec<u8>);
+
+impl serde::Serialize for Blob {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Blob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct BlobVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BlobVisitor {
+            type Value = Blob;
+
+            fn expecting(
+                &self,
+                formatter: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Blob, E> {
+                Ok(Blob(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Blob, E> {
+                Ok(Blob(v))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BlobVisitor)
+    }
+}
+
+#[test]
+fn byte_strings() {
+    let bytes = Blob(b"a\"b\\c\0\xff\n".to_vec());
+    let printed = r#"b"a\"b\\c\0\xff\n""#;
+
+    let value = Value::new(&bytes);
+    assert_eq!(value, Value::Bytes(bytes.0.clone()));
+    assert_eq!(value.to_string_compact(), printed);
+    assert_eq!(Value::parse(printed).unwrap(), value);
+    assert_reparse(&value);
+    assert_eq!(value.deserialize::<Blob>().unwrap(), bytes);
+
+    assert_eq!(typed_format::to_string_compact(&bytes).unwrap(), printed);
+    let read: Blob = typed_format::from_str(printed).unwrap();
+    assert_eq!(read, bytes);
+
+    // Plain lists of u8 are accepted too, strings are not decoded
+    let read: Blob = typed_format::from_str("[1, 2, 255]").unwrap();
+    assert_eq!(read, Blob(vec![1, 2, 255]));
+    let list = Value::parse("[1, 2, 255]").unwrap();
+    assert_eq!(list.deserialize::<Blob>().unwrap(), Blob(vec![1, 2, 255]));
+    assert!(typed_format::from_str::<Blob>("\"AQI=\"").is_err());
+    assert!(Value::parse("\"AQI=\"")
+        .unwrap()
+        .deserialize::<Blob>()
+        .is_err());
+
+    assert!(Value::parse("b\"\\xzz\"").is_err());
+    assert!(Value::parse("b\"ä\"").is_err());
+    let error = Value::parse("b\"a\\tb\\x00ä\"").unwrap_err();
+    assert_eq!(error.message, "Non ASCII character 'ä' in byte string");
+    assert!(typed_format::from_str::<Blob>("b\"ä\"").is_err());
+
+    #[cfg(feature = "json")]
+    assert_eq!(
+        typed_format::value::json::to_json(&Value::Bytes(vec![1, 2]), false),
+        "[1,2]"
+    );
+}
+
+#[test]
+#[cfg(not(feature = "exprs"))]
+fn expressions_need_feature() {
+    let error = Value::parse("[1024 * 1024]").unwrap_err();
+    assert!(format!("{:#}", error).contains("need the exprs feature"));
+}
+
+#[test]
+fn grammar_corner_cases() {
+    // Whitespace ends an identifier, even one of a single character
+    assert!(Value::parse("Foo Bar(1)").is_err());
+    assert_eq!(
+        Value::parse("Some(\n    r // comment\n)").unwrap(),
+        Value::Option(Some(Box::new(Value::UnitStruct("r".into()))))
+    );
+    // Keywords are whole words
+    assert_eq!(
+        Value::parse("[trueish, Nonesuch, false_, inform]").unwrap(),
+        Value::List(
+            ["trueish", "Nonesuch", "false_", "inform"]
+                .iter()
+                .map(|name| Value::UnitStruct((*name).into()))
+                .collect()
+        )
+    );
+    // A comment can not separate a bare key from its colon
+    assert_eq!(
+        Value::parse("{a /* key */ : 1}").unwrap(),
+        Value::Map(
+            btreemap! {
+                Value::UnitStruct("a".into()) => Value::new(1),
+            }
+            .into_iter()
+            .collect()
+        )
+    );
+    assert_eq!(
+        Value::parse("Some (1)").unwrap(),
+        Value::Option(Some(Box::new(Value::new(1))))
+    );
+
+    let input = "[\n  Foo(a: 1),\n  Foo(a: 1 b: 2),\n]";
+    let error = Value::parse(input).unwrap_err();
+    if cfg!(feature = "exprs") {
+        assert_eq!(error.message, "expected operator");
+    } else {
+        assert_eq!(error.message, "unexpected input");
+    }
+    assert_eq!((error.line, error.column), (3, 12));
+}
+
+/// Inputs the hand-written parser rejects are parsed again with pest, which
+/// needs the comments, spans and borrowed strings collected once more
+#[test]
+fn fallback_keeps_collectors() {
+    for input in &["ip\"x\"", "*x", "[1, *x]"] {
+        assert!(Value::parse_with_comments(input).is_err());
+        assert!(Value::parse_with_spans(input).is_err());
+        assert!(typed_format::from_str_borrowed::<Value>(input).is_err());
+        assert!(typed_format::from_str_seed(
+            input,
+            std::marker::PhantomData::<Value>
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "exprs")]
+    {
+        let input = r#""a" + "b""#;
+        let expected = Value::String("ab".into());
+        assert_eq!(Value::parse_with_comments(input).unwrap().0, expected);
+        assert_eq!(Value::parse_with_spans(input).unwrap().0, expected);
+        assert_eq!(
+            typed_format::from_str_borrowed::<Value>(input).unwrap(),
+            expected
+        );
+        assert_eq!(
+            typed_format::from_str_seed(
+                input,
+                std::marker::PhantomData::<Value>
+            )
+            .unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading TOML documents
+//!
+//! Run with `cargo test --features toml`
+
+#![cfg(feature = "toml")]
+
+use typed_format::value::Value;
+
+const CARGO: &str = r#"
+# A manifest
+[package]
+name = "typed_format"
+version = "0.1.0"
+authors = [
+    "Dario Bartussek",  # trailing comment
+    'literal \n',
+]
+edition = 2018
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+pest.version = "2.1"
+
+[[bin]]
+name = "tyf"
+path = "src/bin/tyf.rs"
+
+[[bin]]
+name = "other"
+
+[profile.release]
+lto = true
+opt-level = 0x3
+ratio = +1.5e3
+released = 1979-05-27 07:32:00Z
+notes = """
+Multiple \
+    lines
+"""
+"#;
+
+#[test]
+fn reads_tables() {
+    let value = Value::from_toml_str(CARGO).unwrap();
+
+    assert_eq!(
+        value["package"],
+        Value::parse(
+            r#"{
+                "name": "typed_format",
+                "version": "0.1.0",
+                "authors": ["Dario Bartussek", "literal \\n"],
+                "edition": 2018,
+            }"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        value["dependencies"],
+        Value::parse(
+            r#"{
+                "serde": {"version": "1.0", "features": ["derive"]},
+                "pest": {"version": "2.1"},
+            }"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        value["bin"],
+        Value::parse(
+            r#"[
+                {"name": "tyf", "path": "src/bin/tyf.rs"},
+                {"name": "other"},
+            ]"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        value["profile"]["release"],
+        Value::parse(
+            r#"{
+                "lto": true,
+                "opt-level": 3,
+                "ratio": 1500.0,
+                "released": "1979-05-27T07:32:00Z",
+                "notes": "Multiple lines\n",
+            }"#
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn errors() {
+    assert!(Value::from_toml_str("a = 1\na = 2").is_err());
+    assert!(Value::from_toml_str("a = 1 b = 2").is_err());
+    assert!(Value::from_toml_str("a = \"open").is_err());
+    assert!(Value::from_toml_str("a = 1\n[a]").is_err());
+    assert!(Value::from_toml_str("a = what").is_err());
+}
@@ -0,0 +1,107 @@
+//! Values written to and read from other formats, here JSON
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use typed_format::{from_str, value::Value};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Line(i32, i32),
+    Rect { width: u32, height: u32 },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Id(u32);
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Drawing {
+    id: Id,
+    shapes: Vec<Shape>,
+    layers: BTreeMap<String, Option<(u8, char)>>,
+}
+
+#[test]
+fn mapping() {
+    let value = Value::parse(
+        r#"Config(
+            string: "a",
+            unit_variant: Mode::Fast,
+            struct_variant: Shape::Rect(w: 1, h: 2),
+            tuple_variant: Shape::Line(1, 2),
+            newtype_variant: Shape::Circle(1.5),
+            tuple_struct: Pair(1, 2),
+            newtype_struct: Id(5),
+            unit_struct: Marker,
+            path_struct: app::Point(x: 1),
+            path_unit_struct: app::Marker,
+            type: <Vec<u8>>,
+            option: Some(None),
+            bytes: b"ab",
+            tuple: (1, 'c'),
+            map: {1: "x"},
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&value).unwrap(),
+        json!({
+            "string": "a",
+            "unit_variant": "Fast",
+            "struct_variant": {"Rect": {"w": 1, "h": 2}},
+            "tuple_variant": {"Line": [1, 2]},
+            "newtype_variant": {"Circle": 1.5},
+            "tuple_struct": [1, 2],
+            "newtype_struct": 5,
+            "unit_struct": null,
+            "path_struct": {"x": 1},
+            "path_unit_struct": null,
+            "type": "Vec<u8>",
+            "option": null,
+            "bytes": [97, 98],
+            "tuple": [1, "c"],
+            "map": {"1": "x"},
+        })
+    );
+}
+
+#[test]
+fn rust_types_read_the_same() {
+    let text = r#"Drawing(
+        id: Id(7),
+        shapes: [
+            Shape::Point,
+            Shape::Circle(1.5),
+            Shape::Line(-1, 2),
+            Shape::Rect(width: 3, height: 4),
+        ],
+        layers: {"top": Some((1, 'a')), "bottom": None},
+    )"#;
+
+    let json = serde_json::to_string(&Value::parse(text).unwrap()).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Drawing>(&json).unwrap(),
+        from_str::<Drawing>(text).unwrap()
+    );
+}
+
+#[test]
+fn embedded_values() {
+    #[derive(Serialize)]
+    struct Settings {
+        name: &'static str,
+        extra: Value,
+    }
+
+    let settings = Settings {
+        name: "server",
+        extra: Value::parse("Plugin(level: Level::High)").unwrap(),
+    };
+    assert_eq!(
+        serde_json::to_value(&settings).unwrap(),
+        json!({"name": "server", "extra": {"level": "High"}})
+    );
+}
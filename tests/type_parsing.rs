@@ -1,5 +1,8 @@
 use std::{any::type_name, borrow::Cow};
-use typed_format::value::types::Type;
+use typed_format::value::{
+    types::{GenericIdentifier, Identifier, Type, TypeIdentifier},
+    Value,
+};
 
 fn parse_type<T>()
 where
@@ -62,3 +65,110 @@ pub fn parse_tuple() {
 pub fn parse_lifetime() {
     parse_type::<Cow<'static, str>>();
 }
+
+#[test]
+pub fn arrays_and_tuples_print_like_type_name() {
+    fn check<T>()
+    where
+        T: ?Sized,
+    {
+        let name = type_name::<T>();
+        assert_eq!(Type::parse(name).unwrap().to_string(), name);
+    }
+
+    check::<[usize; 42]>();
+    check::<[[usize; 1]; 42]>();
+    check::<(usize, usize)>();
+    check::<(usize,)>();
+    check::<()>();
+    check::<Vec<([u8; 2], (u8,))>>();
+
+    match Type::parse("[(u8, u16); 3]").unwrap() {
+        Type::Array { content, size } => {
+            assert_eq!(*content, Type::Tuple(vec!["u8".into(), "u16".into()]));
+            assert_eq!(size, "3");
+        },
+        other => panic!("Expected an array, got {:?}", other),
+    }
+}
+
+#[test]
+pub fn parse_nested_generics() {
+    fn generics(segment: &GenericIdentifier) -> Vec<String> {
+        segment
+            .generics
+            .as_ref()
+            .unwrap()
+            .types
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    let t = Type::parse("Foo<Bar, Baz<Qux>>").unwrap();
+    let identifier = match &t {
+        Type::TypeIdentifier(identifier) => identifier,
+        other => panic!("Expected a type identifier, got {:?}", other),
+    };
+    assert_eq!(generics(&identifier.segments[0]), vec!["Bar", "Baz<Qux>"]);
+
+    let inner = TypeIdentifier::parse("Baz<Qux>").unwrap();
+    assert_eq!(generics(&inner.segments[0]), vec!["Qux"]);
+
+    // Trailing commas are accepted, the printed form drops them
+    assert_eq!(
+        Type::parse("Foo<Bar, Baz,>").unwrap().to_string(),
+        "Foo<Bar, Baz>"
+    );
+}
+
+#[test]
+pub fn lifetimes_are_dropped() {
+    let t = Type::parse("Cow<'_, str>").unwrap();
+    assert_eq!(t.to_string(), "Cow<str>");
+
+    let t = Type::parse("Ref<'static>").unwrap();
+    assert_eq!(t, Type::parse("Ref").unwrap());
+}
+
+#[test]
+pub fn parse_nested_std_generics() {
+    parse_type::<std::collections::HashMap<String, Vec<Option<u32>>>>();
+    parse_type::<Result<Vec<(u8, [u16; 2])>, Box<String>>>();
+}
+
+#[test]
+pub fn conversions() {
+    let segment =
+        GenericIdentifier::from(Identifier::from("Config".to_string()));
+    let t = Type::from(TypeIdentifier::from(segment));
+    assert_eq!(t, Type::from("Config"));
+    assert_eq!(t.as_type_identifier(), Some(&"Config".into()));
+    assert_eq!(t.get_identifier(), Some(&"Config".into()));
+
+    let array = Type::parse("[u8; 4]").unwrap();
+    assert_eq!(array.as_type_identifier(), None);
+    assert_eq!(Value::from(array.clone()), Value::Type(array));
+}
+
+#[test]
+pub fn errors() {
+    let error = Type::parse("Vec<u8").unwrap_err();
+    assert_eq!(error.name(), "Vec<u8");
+    assert_eq!(error.error.offset, 6);
+    assert_eq!(
+        error.to_string(),
+        "Invalid type `Vec<u8`: expected generics at column 7"
+    );
+
+    let error = TypeIdentifier::parse("[u8; 4]").unwrap_err();
+    assert_eq!(error.error.offset, 0);
+
+    // Limited like the nesting of values, instead of overflowing the stack
+    let nested = format!("{}u8{}", "Vec<".repeat(5_000), ">".repeat(5_000));
+    let error = Type::parse(&nested).unwrap_err();
+    assert_eq!(
+        error.error.message,
+        "Nesting exceeds the maximum depth of 128"
+    );
+}
@@ -0,0 +1,117 @@
+use std::{any::type_name, borrow::Cow};
+use typed_format::value::{
+    printer::ValuePrinter,
+    types::{Type, TypeIdentifier},
+    Value,
+};
+
+/// Prints the type of T as a value in every layout and parses it back
+fn round_trip<T>() -> String
+where
+    T: ?Sized,
+{
+    let value = Value::Type(Type::parse(type_name::<T>()).unwrap());
+
+    for printer in &[
+        ValuePrinter::compact(),
+        ValuePrinter::pretty(),
+        ValuePrinter::auto(),
+    ] {
+        let mut text = String::new();
+        printer.write(&value, &mut text).unwrap();
+        assert_eq!(Value::parse(&text).unwrap(), value, "{}", text);
+    }
+
+    // Nested in other values, too
+    let list = Value::List(vec![value.clone(), value.clone()]);
+    assert_eq!(Value::parse(&list.to_string_pretty()).unwrap(), list);
+
+    value.to_string_compact()
+}
+
+#[test]
+pub fn print_usize() {
+    assert_eq!(round_trip::<usize>(), "<usize>");
+}
+
+#[test]
+pub fn print_struct() {
+    struct Test;
+
+    assert!(round_trip::<Test>().ends_with("::Test>"));
+}
+
+#[test]
+pub fn print_vec() {
+    assert_eq!(round_trip::<Vec<usize>>(), "<alloc::vec::Vec<usize>>");
+}
+
+#[test]
+pub fn print_vec_of_array() {
+    round_trip::<Vec<[usize; 42]>>();
+}
+
+#[test]
+pub fn print_vec_of_tuple() {
+    round_trip::<Vec<(usize, usize)>>();
+}
+
+#[test]
+pub fn print_array() {
+    assert_eq!(round_trip::<[usize; 42]>(), "[usize; 42]");
+}
+
+#[test]
+pub fn print_nested_array() {
+    assert_eq!(round_trip::<[[usize; 1]; 42]>(), "[[usize; 1]; 42]");
+}
+
+#[test]
+pub fn print_array_of_tuples() {
+    assert_eq!(round_trip::<[(u8, u16); 2]>(), "[(u8, u16); 2]");
+}
+
+#[test]
+pub fn print_tuple() {
+    assert_eq!(round_trip::<(usize, usize)>(), "<(usize, usize)>");
+}
+
+#[test]
+pub fn print_small_tuples() {
+    assert_eq!(round_trip::<()>(), "<()>");
+    assert_eq!(round_trip::<(u8,)>(), "<(u8,)>");
+}
+
+#[test]
+pub fn print_str() {
+    assert_eq!(round_trip::<str>(), "<str>");
+}
+
+#[test]
+pub fn print_cow() {
+    round_trip::<Cow<str>>();
+}
+
+#[test]
+pub fn print_nested_std_generics() {
+    round_trip::<std::collections::HashMap<String, Vec<Option<u32>>>>();
+    round_trip::<Result<Vec<(u8, [u16; 2])>, Box<String>>>();
+}
+
+#[test]
+pub fn types_and_unit_structs() {
+    // Without brackets, paths are unit structs and tuples hold values
+    assert_eq!(
+        Value::parse("Vec<u8>").unwrap(),
+        Value::UnitStruct(TypeIdentifier::parse("Vec<u8>").unwrap())
+    );
+    assert_eq!(
+        Value::parse("<Vec<u8>>").unwrap(),
+        Value::Type(Type::parse("Vec<u8>").unwrap())
+    );
+    assert!(matches!(
+        Value::parse("(u8, u16)").unwrap(),
+        Value::Tuple(_)
+    ));
+    assert!(Value::parse("<1>").is_err());
+}
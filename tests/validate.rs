@@ -0,0 +1,139 @@
+use typed_format::{
+    validate::{PathExists, Rules, Validators},
+    value::{path::Path, Value},
+    vfs::MemoryFs,
+};
+
+const CONFIG: &str = r#"Config(
+    admin: "admin@example.com",
+    homepage: "not a url",
+    servers: [
+        Server(address: "10.0.0.1", url: "https://example.com/api"),
+        Server(address: "10.0.0.300", url: Some("ftp:/missing")),
+    ],
+    contact: None,
+    backup: 5,
+)"#;
+
+fn rules(rules: &[(&str, &str)]) -> Rules {
+    rules
+        .iter()
+        .map(|(path, format)| (Path::parse(path).unwrap(), format.to_string()))
+        .collect()
+}
+
+#[test]
+fn reports_all_errors_with_spans() {
+    let rules = rules(&[
+        ("admin", "email"),
+        ("homepage", "url"),
+        ("servers[0].address", "ip"),
+        ("servers[0].url", "url"),
+        ("servers[1].address", "ip"),
+        ("servers[1].url", "url"),
+        ("contact", "email"),
+        ("backup", "email"),
+        ("missing", "email"),
+    ]);
+
+    let errors = Validators::new().validate_str(CONFIG, &rules).unwrap();
+    let paths = errors
+        .iter()
+        .map(|error| error.path.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec!["backup", "homepage", "servers[1].address", "servers[1].url"]
+    );
+
+    assert_eq!(errors[0].message, "Expected a string");
+    let span = errors[2].span.clone().unwrap();
+    assert_eq!(&CONFIG[span], "\"10.0.0.300\"");
+    let span = errors[3].span.clone().unwrap();
+    assert_eq!(&CONFIG[span], "Some(\"ftp:/missing\")");
+    assert!(errors[1]
+        .to_string()
+        .starts_with("homepage: \"not a url\" is not"));
+
+    let value = Value::parse(CONFIG).unwrap();
+    let without_spans = Validators::new().validate(&value, &rules);
+    assert_eq!(without_spans.len(), 4);
+    assert!(without_spans.iter().all(|error| error.span.is_none()));
+}
+
+#[test]
+fn custom_formats() {
+    static FILES: MemoryFs = MemoryFs::new();
+    FILES.insert("/etc/app.conf", "");
+
+    let validators = Validators::empty()
+        .with("lowercase", |value: &str| {
+            if value.chars().any(char::is_uppercase) {
+                Err(format!("{:?} has uppercase letters", value))
+            } else {
+                Ok(())
+            }
+        })
+        .with("path-exists", PathExists(&FILES));
+
+    let value =
+        Value::parse(r#"("Name", "/etc/app.conf", "/etc/other", "a@b.c")"#)
+            .unwrap();
+    let errors = validators.validate(
+        &value,
+        &rules(&[
+            ("[0]", "lowercase"),
+            ("[1]", "path-exists"),
+            ("[2]", "path-exists"),
+            ("[3]", "email"),
+        ]),
+    );
+
+    let messages = errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![
+            "\"Name\" has uppercase letters",
+            "/etc/other does not exist",
+            "Unknown format \"email\"",
+        ]
+    );
+}
+
+#[test]
+fn parse_spans() {
+    let input = "{\"a\": [1, Some(2)]}";
+    let (_, spans) = Value::parse_with_spans(input).unwrap();
+
+    let text = |path: Path| &input[spans.get(&path).unwrap()];
+    assert_eq!(text(Path::root()), input);
+    let list = Path::root().key(Value::String("a".into()));
+    assert_eq!(text(list.clone()), "[1, Some(2)]");
+    assert_eq!(text(list.clone().index(0)), "1");
+    assert_eq!(text(list.index(1)), "Some(2)");
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_formats() {
+    let validators = Validators::empty()
+        .with("slug", regex::Regex::new("^[a-z-]+$").unwrap());
+
+    let errors = validators
+        .validate_str(
+            r#"["hello-world", "Hello World"]"#,
+            &rules(&[("[0]", "slug"), ("[1]", "slug")]),
+        )
+        .unwrap();
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>(),
+        vec![r#"[1]: "Hello World" does not match ^[a-z-]+$ at 16..29"#]
+    );
+}
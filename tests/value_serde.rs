@@ -0,0 +1,116 @@
+use maplit::btreemap;
+use serde::de::{value::MapDeserializer, IntoDeserializer};
+use serde_derive::{Deserialize, Serialize};
+use typed_format::{
+    from_reader, from_str, to_string_compact, to_string_pretty, to_writer,
+    value::Value,
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Settings {
+    name: String,
+    extra: Value,
+}
+
+fn settings() -> Settings {
+    Settings {
+        name: "server".to_string(),
+        extra: Value::parse(
+            r#"Plugin(
+                level: Level::High,
+                tags: ["a", "b"],
+                retry: Some(Retry(3, 1.5)),
+            )"#,
+        )
+        .unwrap(),
+    }
+}
+
+#[test]
+fn embedded_in_struct() {
+    let settings = settings();
+
+    let text = to_string_compact(&settings).unwrap();
+    assert_eq!(
+        text,
+        "Settings(name:\"server\",extra:Plugin(level:Level::High,\
+         tags:[\"a\",\"b\",],retry:Some(Retry(3,1.5,)),),)"
+    );
+    assert_eq!(from_str::<Settings>(&text).unwrap(), settings);
+    assert_eq!(
+        from_reader::<_, Settings>(text.as_bytes()).unwrap(),
+        settings
+    );
+
+    let pretty = to_string_pretty(&settings).unwrap();
+    assert_eq!(from_str::<Settings>(&pretty).unwrap(), settings);
+}
+
+#[test]
+fn streamed_malformed() {
+    let value: Value = from_reader("[1, /* two */ 2]".as_bytes()).unwrap();
+    assert_eq!(value, Value::parse("[1, 2]").unwrap());
+
+    for input in &["[1 2]", "Foo(a: 1 2)", "[Some(1) None]", "(a b)"] {
+        assert!(
+            from_reader::<_, Value>(input.as_bytes()).is_err(),
+            "{}",
+            input
+        );
+        assert!(from_str::<Value>(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn streaming_serializer() {
+    let settings = settings();
+
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, &settings).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    assert_eq!(from_str::<Settings>(&text).unwrap(), settings);
+
+    let mut bytes = Vec::new();
+    typed_format::to_writer_pretty(&mut bytes, &settings).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    assert!(
+        text.contains("\n    extra: Plugin(\n        level"),
+        "{}",
+        text
+    );
+    assert_eq!(from_str::<Settings>(&text).unwrap(), settings);
+}
+
+#[test]
+fn value_itself() {
+    let value = settings().extra;
+
+    assert_eq!(Value::new(&value), value);
+    assert_eq!(Value::new(&value).deserialize::<Value>().unwrap(), value);
+    assert_eq!(from_str::<Value>("()").unwrap(), Value::Unit);
+}
+
+#[test]
+fn from_other_formats() {
+    let entries = btreemap! {
+        "name" => "server",
+        "mode" => "fast",
+    };
+    let deserializer: MapDeserializer<_, serde::de::value::Error> =
+        MapDeserializer::new(entries.into_iter());
+
+    let value =
+        <Value as serde::Deserialize>::deserialize(deserializer).unwrap();
+    assert_eq!(
+        value,
+        Value::parse(r#"{"mode": "fast", "name": "server"}"#).unwrap()
+    );
+
+    let deserializer: serde::de::value::U64Deserializer<
+        serde::de::value::Error,
+    > = 5u64.into_deserializer();
+    assert_eq!(
+        <Value as serde::Deserialize>::deserialize(deserializer).unwrap(),
+        Value::new(5u64)
+    );
+}
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+use typed_format::vfs::{MemoryFs, Vfs, VfsEntry};
+
+#[test]
+fn memory_read_dir() {
+    let vfs = MemoryFs::new()
+        .with_file("root/b.tyf", "()")
+        .with_file("root/a/deep/c.tyf", "()");
+    vfs.create_dir_all(Path::new("root/empty")).unwrap();
+
+    let entry = |path: &str, is_dir| VfsEntry {
+        path: PathBuf::from(path),
+        is_dir,
+    };
+    assert_eq!(
+        vfs.read_dir(Path::new("root")).unwrap(),
+        vec![
+            entry("root/a", true),
+            entry("root/b.tyf", false),
+            entry("root/empty", true),
+        ]
+    );
+    assert_eq!(vfs.read_dir(Path::new("root/empty")).unwrap(), vec![]);
+    assert!(vfs.read_dir(Path::new("root/missing")).is_err());
+}
+
+#[test]
+fn memory_read_write() {
+    let vfs = MemoryFs::new();
+    assert!(vfs.read_to_string(Path::new("a.tyf")).is_err());
+
+    vfs.write(Path::new("a.tyf"), "[1]").unwrap();
+    assert_eq!(vfs.read_to_string(Path::new("a.tyf")).unwrap(), "[1]");
+    assert_eq!(vfs.files(), vec![PathBuf::from("a.tyf")]);
+}
@@ -0,0 +1,90 @@
+//! Reading YAML documents
+//!
+//! Run with `cargo test --features yaml`
+
+#![cfg(feature = "yaml")]
+
+use typed_format::value::Value;
+
+const COMPOSE: &str = r#"---
+# A compose file
+version: "3.8"
+services:
+  web:
+    image: nginx:latest
+    ports:
+      - "80:80"
+      - 443
+    environment: {DEBUG: true, LEVEL: ~}
+    command: [nginx, -g, 'daemon off;']
+  db:
+    image: postgres  # the database
+    volumes:
+    - data:/var/lib/postgresql
+    healthcheck:
+      - name: ready
+        interval: 1.5
+      - name: it's up
+script: |
+  echo one
+    echo two
+summary: >-
+  folded
+  text
+
+  next
+empty:
+ratio: .inf
+"#;
+
+#[test]
+fn reads_documents() {
+    let value = Value::from_yaml_str(COMPOSE).unwrap();
+
+    let expected = Value::parse(
+        r#"{
+            "version": "3.8",
+            "services": {
+                "web": {
+                    "image": "nginx:latest",
+                    "ports": ["80:80", 443],
+                    "environment": {"DEBUG": true, "LEVEL": None},
+                    "command": ["nginx", "-g", "daemon off;"],
+                },
+                "db": {
+                    "image": "postgres",
+                    "volumes": ["data:/var/lib/postgresql"],
+                    "healthcheck": [
+                        {"name": "ready", "interval": 1.5},
+                        {"name": "it's up"},
+                    ],
+                },
+            },
+            "script": "echo one\n  echo two\n",
+            "summary": "folded text\nnext",
+            "empty": None,
+            "ratio": inf,
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn scalars_and_errors() {
+    assert_eq!(Value::from_yaml_str("").unwrap(), Value::Option(None));
+    assert_eq!(
+        Value::from_yaml_str("- 0x10\n- -2\n- 1_000\n- \"a\\tb\"\n- 'it''s'")
+            .unwrap(),
+        Value::parse(r#"[16, -2, "1_000", "a\tb", "it's"]"#).unwrap()
+    );
+    assert_eq!(
+        Value::from_yaml_str("a: &anchor 1\nb: *anchor\nc: !Point [1, 2]")
+            .unwrap(),
+        Value::parse(r#"{"a": 1, "b": 1, "c": {"!Point": [1, 2]}}"#).unwrap()
+    );
+
+    assert!(Value::from_yaml_str("a: 1\na: 2").is_err());
+    assert!(Value::from_yaml_str("a: 1\n---\nb: 2").is_err());
+    assert!(Value::from_yaml_str("a: 1\n   b: 2").is_err());
+}